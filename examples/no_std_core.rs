@@ -0,0 +1,60 @@
+//! The in-memory core without the `std` feature.
+//!
+//! ```text
+//! cargo run --example no_std_core --no-default-features --features alloc
+//! ```
+//!
+//! In that configuration the library is `#![no_std]`: no files, no journal,
+//! no wall clock. This binary still runs on the host so it can print, but
+//! every databank call below is part of the alloc-only core. Ids come from
+//! the deterministic `new_at` constructors, and entry ids from the tick.
+
+use databank_rs::{codec, BankCluster, BankConfig, BankId, BankRef, EdgeType, Temperature};
+use ternary_signal::Signal;
+
+fn vector(pattern: &[i8]) -> Vec<Signal> {
+    pattern
+        .iter()
+        .map(|&p| if p == 0 { Signal::ZERO } else { Signal::new_raw(p, 200, 1) })
+        .collect()
+}
+
+fn main() {
+    let config = BankConfig {
+        vector_width: 4,
+        ..BankConfig::default()
+    };
+    let mut cluster = BankCluster::new();
+
+    let visual = BankId::new_at("occipital.v4", 0, 1);
+    let semantic = BankId::new_at("temporal.semantic", 0, 1);
+
+    let jar = cluster
+        .get_or_create(visual, "occipital.v4".into(), config.clone())
+        .expect("new bank names are unique")
+        .insert(vector(&[1, 0, -1, 1]), Temperature::Hot, 10)
+        .expect("width matches");
+    let container = cluster
+        .get_or_create(semantic, "temporal.semantic".into(), config)
+        .expect("new bank names are unique")
+        .insert(vector(&[1, 1, 0, 0]), Temperature::Hot, 11)
+        .expect("width matches");
+
+    let jar = BankRef { bank: visual, entry: jar };
+    let container = BankRef { bank: semantic, entry: container };
+    cluster
+        .link(jar, container, EdgeType::IsA, 200, 12)
+        .expect("source entry exists");
+
+    for hit in cluster.query_by_prefix("occipital.", &vector(&[1, 0, -1, 0]), 3) {
+        println!("{} {} score={}", hit.bank_name, hit.entry_id, hit.score);
+    }
+    for target in cluster.traverse(jar, EdgeType::IsA, 2) {
+        println!("jar IsA {target}");
+    }
+
+    // The `.bank` byte codec works without std; only file I/O is gated.
+    let bytes = codec::encode(cluster.get(visual).expect("bank exists")).expect("encodes");
+    let restored = codec::decode(&bytes).expect("decodes");
+    println!("{}: {} bytes, {} entries", restored.name, bytes.len(), restored.len());
+}
@@ -0,0 +1,173 @@
+/*
+ * databank-rs C ABI. Build the library with
+ *
+ *     cargo rustc --release --features ffi --crate-type staticlib
+ *
+ * Generated from src/ffi.rs by cbindgen; do not edit by hand. Regenerate
+ * with (expanding the `ffi` feature needs a nightly toolchain)
+ *
+ *     RUSTUP_TOOLCHAIN=nightly cbindgen --config cbindgen.toml -o include/databank.h
+ */
+
+#ifndef DATABANK_H
+#define DATABANK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Success.
+ */
+#define DATABANK_OK 0
+
+/**
+ * A Rust panic was caught at the boundary. The handle should be freed and
+ * not used again.
+ */
+#define DATABANK_ERR_PANIC -1
+
+/**
+ * Mask selecting the category of a returned error code, a
+ * `DatabankErrorCode` value. The low byte is a category-specific sub-code.
+ */
+#define DATABANK_ERROR_CATEGORY_MASK 65280
+
+/**
+ * Stable error categories for FFI and firmware status registers.
+ *
+ * A full error code is `category << 8 | sub_code`. The category values
+ * below are part of the public contract and must never be renumbered;
+ * new categories take the next unused value.
+ */
+enum DatabankErrorCode
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint16_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  DatabankErrorCode_VectorWidthMismatch = 256,
+  DatabankErrorCode_BankFull = 512,
+  DatabankErrorCode_EntryNotFound = 768,
+  DatabankErrorCode_EdgeLimitReached = 1024,
+  DatabankErrorCode_BankNotFound = 1280,
+  DatabankErrorCode_Io = 1536,
+  DatabankErrorCode_Codec = 1792,
+  DatabankErrorCode_InvalidConfig = 2048,
+  DatabankErrorCode_Parse = 2304,
+  DatabankErrorCode_ChecksumMismatch = 2560,
+  DatabankErrorCode_SlotNotBound = 2816,
+  DatabankErrorCode_InvalidOperand = 3072,
+  DatabankErrorCode_Journal = 3328,
+  DatabankErrorCode_EntryCorrupted = 3584,
+  DatabankErrorCode_DuplicateBankName = 3840,
+  DatabankErrorCode_EntryLeased = 4096,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum DatabankErrorCode DatabankErrorCode;
+#else
+typedef uint16_t DatabankErrorCode;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+/**
+ * Multi-bank manager -- the brain's distributed representational memory.
+ *
+ * Each region owns one or more banks in the cluster. The cluster provides
+ * cross-bank operations (linking, traversal) and batch persistence.
+ *
+ * Banks are indexed by both BankId and name for flexible lookup.
+ */
+typedef struct DatabankCluster DatabankCluster;
+
+/**
+ * Every category, in code order.
+ */
+#define DatabankErrorCode_ALL { DatabankErrorCode_VectorWidthMismatch, DatabankErrorCode_BankFull, DatabankErrorCode_EntryNotFound, DatabankErrorCode_EdgeLimitReached, DatabankErrorCode_BankNotFound, DatabankErrorCode_Io, DatabankErrorCode_Codec, DatabankErrorCode_InvalidConfig, DatabankErrorCode_Parse, DatabankErrorCode_ChecksumMismatch, DatabankErrorCode_SlotNotBound, DatabankErrorCode_InvalidOperand, DatabankErrorCode_Journal, DatabankErrorCode_EntryCorrupted, DatabankErrorCode_DuplicateBankName, DatabankErrorCode_EntryLeased, }
+
+
+
+
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Load every `.bank` file in `dir` into a new cluster.
+ *
+ * Returns an owned handle, or null on failure (see
+ * [`databank_last_error`]). Free the handle with
+ * [`databank_cluster_free`].
+ *
+ * # Safety
+ *
+ * `dir` must be null or a valid NUL-terminated string.
+ */
+DatabankCluster *databank_cluster_load_dir(const char *dir);
+
+/**
+ * Query the bank named `bank_name` with `query_len` signal currents.
+ *
+ * On entry `*out_len` is the capacity of `out_results` in `int32_t`s; on
+ * success it is the number written, `1 + 3 * count`. A buffer of
+ * `1 + 3 * top_k` is always large enough; a smaller one fails with
+ * `InvalidOperand` and `*out_len` set to the required length.
+ *
+ * # Safety
+ *
+ * `handle` must come from [`databank_cluster_load_dir`] and not be freed.
+ * `bank_name` must be a valid NUL-terminated string, `query` must point to
+ * `query_len` readable values, `out_results` to `*out_len` writable
+ * values, and `out_len` must be valid for reads and writes.
+ */
+int32_t databank_cluster_query(const DatabankCluster *handle,
+                               const char *bank_name,
+                               const int32_t *query,
+                               size_t query_len,
+                               uint32_t top_k,
+                               int32_t *out_results,
+                               size_t *out_len);
+
+/**
+ * Insert a vector of `vector_len` signal currents into the bank named
+ * `bank_name` at `temperature` (0 = Hot .. 4 = Set) and `tick`.
+ *
+ * On success the new entry id is written to `out_id` as `(high, low)`.
+ *
+ * # Safety
+ *
+ * `handle` must come from [`databank_cluster_load_dir`] and not be freed
+ * or used concurrently. `bank_name` must be a valid NUL-terminated string,
+ * `vector` must point to `vector_len` readable values, and `out_id` must
+ * be null or point to two writable values.
+ */
+int32_t databank_cluster_write(DatabankCluster *handle,
+                               const char *bank_name,
+                               const int32_t *vector,
+                               size_t vector_len,
+                               uint8_t temperature,
+                               uint64_t tick,
+                               int32_t *out_id);
+
+/**
+ * Free a cluster handle. Null is ignored. Unsaved changes are discarded.
+ *
+ * # Safety
+ *
+ * `handle` must be null or come from [`databank_cluster_load_dir`], and
+ * must not be used afterwards.
+ */
+void databank_cluster_free(DatabankCluster *handle);
+
+/**
+ * Message for the most recent failure on the calling thread, or null if
+ * nothing has failed yet. The string stays valid until the next failing
+ * call on the same thread.
+ */
+const char *databank_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DATABANK_H */
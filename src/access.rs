@@ -46,7 +46,7 @@ impl BankAccess for ClusterBankAccess<'_> {
         let bank_id = self.slot_map.resolve(bank_slot)?;
         let bank = self.cluster.get(bank_id)?;
         let signals = bridge::i32_to_packed_signals(query);
-        let results = bank.query_sparse(&signals, top_k);
+        let results = bank.query_lenient(&signals, top_k);
         Some(
             results
                 .iter()
@@ -85,9 +85,7 @@ impl BankAccess for ClusterBankAccess<'_> {
             return;
         };
         let entry_id = bridge::i32_pair_to_entry_id(entry_id_high, entry_id_low);
-        if let Some(entry) = bank.get_mut(entry_id) {
-            entry.touch(self.tick);
-        }
+        let _ = bank.touch_entry(entry_id, self.tick);
     }
 
     fn delete(&mut self, bank_slot: u8, entry_id_high: i32, entry_id_low: i32) -> bool {
@@ -114,7 +112,7 @@ mod tests {
             vector_width: 4,
             ..BankConfig::default()
         };
-        cluster.get_or_create(bank_id, "test.access".to_string(), config);
+        cluster.get_or_create(bank_id, "test.access".to_string(), config).unwrap();
         let mut slot_map = BankSlotMap::new();
         slot_map.bind(0, bank_id);
         (cluster, slot_map, bank_id)
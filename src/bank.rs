@@ -1,12 +1,198 @@
-use std::collections::HashMap;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::cmp::Reverse;
+use serde::{Deserialize, Serialize};
 use ternary_signal::Signal;
 
+use crate::cluster::{fuse_results, ClusterQueryResult};
 use crate::entry::BankEntry;
 use crate::error::{DataBankError, Result};
-use crate::index::VectorIndex;
+use crate::index::{QueryFilter, QueryPath, QueryTrace, RecallReport, VectorIndex};
 use crate::ivf::{IndexType, IvfIndex};
-use crate::similarity::QueryResult;
-use crate::types::{BankConfig, BankId, BankRef, Edge, EdgeType, EntryId, Temperature};
+#[cfg(feature = "metrics")]
+use crate::metrics::{BankMetrics, MetricsRecorder};
+use crate::observer::BankObserver;
+use crate::prelude::*;
+use crate::quantize::Codebook;
+use crate::similarity::{
+    explain_similarity, sparse_cosine_similarity, sparse_cosine_similarity_weighted,
+    MatchExplanation, QueryResult,
+};
+use crate::snapshot::BankSnapshot;
+use crate::store::EntryStore;
+use crate::types::{
+    BankConfig, BankId, BankRef, Edge, EdgeOverflow, EdgeType, EntryId, EntryStorage,
+    EvictionMode, QueryWidth, Temperature,
+};
+
+/// Callback that fills derived fields on each new entry; see
+/// [`DataBank::set_insert_hook`].
+pub type InsertHook = Box<dyn Fn(&mut BankEntry) + Send + Sync>;
+
+/// Entry-map cost of one entry beyond the entry itself: its EntryId key and
+/// the hash table's control byte.
+const MAP_SLOT_BYTES: usize = core::mem::size_of::<EntryId>() + 1;
+
+/// Lowest-scoring entries `EvictionMode::NearDuplicatesFirst` checks for a
+/// near-duplicate before falling back to the score. Bounds eviction to a
+/// fixed number of index queries however large the bank is.
+const NEAR_DUPLICATE_SCAN: usize = 16;
+
+/// Number of buckets in [`EdgeStats::weight_buckets`]. Each bucket spans
+/// 256 / WEIGHT_BUCKETS weight values.
+pub const WEIGHT_BUCKETS: usize = 8;
+
+/// Edge structure summary for a bank: per-type counts and a coarse
+/// weight histogram.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EdgeStats {
+    /// Total number of outgoing edges across all entries.
+    pub total: usize,
+    /// Edge count per EdgeType. Types with no edges are absent.
+    pub by_type: HashMap<EdgeType, usize>,
+    /// Edge count per weight range: bucket i covers weights
+    /// `[i * 32, i * 32 + 31]`.
+    pub weight_buckets: [usize; WEIGHT_BUCKETS],
+}
+
+/// Log-line prefix naming a bank: `bank 'temporal.semantic' (<id>)`.
+/// Every log site that concerns one bank starts with this so warnings in a
+/// multi-bank system can be attributed to their region.
+pub(crate) struct BankLabel<'a> {
+    pub name: &'a str,
+    pub id: BankId,
+}
+
+impl core::fmt::Display for BankLabel<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "bank '{}' ({})", self.name, self.id)
+    }
+}
+
+/// A bank's persistence bookkeeping, for flush schedulers and telemetry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistenceState {
+    /// Whether the bank has unsaved changes.
+    pub dirty: bool,
+    /// Mutations since the last persistence flush.
+    pub mutations_since_persist: u32,
+    /// Tick of the last persistence flush.
+    pub last_persist_tick: u64,
+    /// Size a default-options `.bank` encode would produce, computed from
+    /// entry shapes without encoding.
+    pub approx_encoded_bytes: usize,
+    /// Entries removed since the last compaction, see
+    /// [`BankConfig::auto_compact_percent`].
+    pub removals_since_compact: u32,
+}
+
+/// Why an entry left the bank, as recorded in the eviction log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvictionReason {
+    /// Evicted by `insert` to stay within `max_entries` or `max_bytes`.
+    Capacity,
+    /// Evicted by an explicit `evict_n` call.
+    Explicit,
+    /// Expired at the end of its lifetime.
+    Expired,
+}
+
+/// One eviction, kept when `BankConfig::audit_evictions` is non-zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvictionRecord {
+    pub entry_id: EntryId,
+    /// Tick the eviction happened at.
+    pub tick: u64,
+    /// The entry's eviction score at that tick (lower evicts first).
+    pub eviction_score: i64,
+    pub temperature: Temperature,
+    pub access_count: u32,
+    pub debug_tag: Option<String>,
+    pub reason: EvictionReason,
+}
+
+/// Proof of a held entry lease, see [`DataBank::lease`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LeaseToken {
+    pub entry: EntryId,
+    pub owner: u32,
+}
+
+/// Mutable access to one entry, from [`DataBank::get_mut`]. Derefs to the
+/// [`BankEntry`]; on drop, a changed temperature updates the bank's
+/// per-temperature counts, a changed size its byte estimate and an added or
+/// cleared TTL its TTL count, and any change to a checksummed field counts
+/// as a mutation of the bank.
+pub struct EntryMut<'a> {
+    entry: &'a mut BankEntry,
+    temp_counts: &'a mut [u32; 5],
+    was: Temperature,
+    bytes: &'a mut usize,
+    was_bytes: usize,
+    ttl_entries: &'a mut usize,
+    had_ttl: bool,
+    was_checksums: (u32, u32),
+    mutations: &'a mut u32,
+    generation: &'a mut u64,
+    dirty: &'a mut bool,
+}
+
+impl core::ops::Deref for EntryMut<'_> {
+    type Target = BankEntry;
+
+    fn deref(&self) -> &BankEntry {
+        self.entry
+    }
+}
+
+impl core::ops::DerefMut for EntryMut<'_> {
+    fn deref_mut(&mut self) -> &mut BankEntry {
+        self.entry
+    }
+}
+
+impl Drop for EntryMut<'_> {
+    fn drop(&mut self) {
+        self.entry.refresh_meta_checksum();
+        let now = self.entry.temperature;
+        if now != self.was {
+            self.temp_counts[self.was as usize] -= 1;
+            self.temp_counts[now as usize] += 1;
+        }
+        *self.bytes = *self.bytes - self.was_bytes + entry_bytes(self.entry);
+        match (self.had_ttl, self.entry.expires_at_tick.is_some()) {
+            (false, true) => *self.ttl_entries += 1,
+            (true, false) => *self.ttl_entries -= 1,
+            _ => {}
+        }
+        if (self.entry.checksum, self.entry.meta_checksum) != self.was_checksums {
+            *self.mutations = self.mutations.saturating_add(1);
+            *self.generation += 1;
+            *self.dirty = true;
+        }
+    }
+}
+
+/// Point-in-time summary of a bank's size and shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BankStats {
+    /// Number of entries currently stored.
+    pub entries: usize,
+    /// Entry capacity, or None for unlimited banks (`max_entries == 0`).
+    pub capacity: Option<u32>,
+    /// Total outgoing edges across all entries.
+    pub edges: usize,
+    /// Entries exempt from demotion and eviction, see [`DataBank::pin`].
+    pub pinned: usize,
+    /// Entry count per temperature, see [`DataBank::temperature_counts`].
+    pub temperatures: [u32; 5],
+    /// Entry count per namespace, omitting empty namespaces.
+    pub namespaces: BTreeMap<u16, usize>,
+    /// Fixed vector width.
+    pub vector_width: u16,
+    /// Estimated memory footprint, see [`DataBank::approx_bytes`].
+    pub approx_bytes: usize,
+}
 
 /// A single databank -- one region's representational memory.
 ///
@@ -26,19 +212,58 @@ pub struct DataBank {
     /// Human-readable name for debugging (e.g. "temporal.semantic").
     pub name: String,
     /// All entries, indexed by EntryId.
-    entries: HashMap<EntryId, BankEntry>,
+    entries: EntryStore,
     /// Next sequence counter for EntryId generation.
     next_seq: u32,
     /// Vector similarity index.
     vector_index: Box<dyn VectorIndex>,
     /// Reverse edge index: "who points to me?"
     reverse_edges: HashMap<EntryId, Vec<(BankRef, EdgeType)>>,
+    /// Content hash -> entries with that exact vector. Only maintained when
+    /// `config.exact_match_fast_path` is set.
+    content_hashes: HashMap<u64, Vec<EntryId>>,
     /// Mutations since last persistence flush.
     mutations_since_persist: u32,
     /// Tick of last persistence flush.
     last_persist_tick: u64,
     /// Whether the bank has unsaved changes.
     dirty: bool,
+    /// Bumped on every mutation; never reset.
+    generation: u64,
+    /// Entries removed since the last `compact`.
+    removals_since_compact: u32,
+    /// Set on copies made by [`fork`](Self::fork), which are never flushed.
+    /// Not persisted.
+    forked: bool,
+    /// Entry count per temperature, indexed by discriminant. Kept in step
+    /// by every path that adds, removes or re-temperatures an entry.
+    temp_counts: [u32; 5],
+    /// Running [`approx_bytes`](Self::approx_bytes) less the struct itself,
+    /// kept in step by every path that resizes an entry, reverse-edge list
+    /// or content-hash bucket, so byte budgets cost O(1) per insert.
+    bytes: usize,
+    /// Entries with an expiry tick, kept in step by every path that adds,
+    /// removes or re-times an entry, so TTL-free banks skip the TTL filter.
+    ttl_entries: usize,
+    /// Generation of the newest snapshot known to be persisted.
+    persisted_generation: u64,
+    /// Per-entry event hook. Not persisted.
+    observer: Option<Arc<dyn BankObserver>>,
+    /// Fills derived fields on each new entry. Not persisted.
+    insert_hook: Option<InsertHook>,
+    /// Most recent evictions, oldest first, at most
+    /// `config.audit_evictions` long. Not persisted.
+    eviction_log: Vec<EvictionRecord>,
+    /// Leased entries: id -> (owner, tick the lease expires at). Not
+    /// persisted.
+    leases: HashMap<EntryId, (u32, u64)>,
+    /// Codebook reused by quantized encodes, see
+    /// [`train_codebook`](Self::train_codebook). Only persisted inside
+    /// quantized files.
+    codebook: Option<Codebook>,
+    /// Runtime counters. Not persisted.
+    #[cfg(feature = "metrics")]
+    metrics: MetricsRecorder,
 }
 
 impl DataBank {
@@ -47,31 +272,164 @@ impl DataBank {
     /// Uses IVF indexing by default (k=64, nprobe=8). Override via
     /// `BankConfig::index_type` for specific needs.
     pub fn new(id: BankId, name: String, config: BankConfig) -> Self {
-        let vector_index = create_index(&config.index_type);
+        let vector_index = create_index(&config);
         Self {
             id,
+            entries: EntryStore::new(config.storage),
             config,
             name,
-            entries: HashMap::new(),
             next_seq: 0,
             vector_index,
             reverse_edges: HashMap::new(),
+            content_hashes: HashMap::new(),
             mutations_since_persist: 0,
             last_persist_tick: 0,
             dirty: false,
+            generation: 0,
+            removals_since_compact: 0,
+            forked: false,
+            persisted_generation: 0,
+            temp_counts: [0; 5],
+            bytes: 0,
+            ttl_entries: 0,
+            observer: None,
+            insert_hook: None,
+            eviction_log: Vec::new(),
+            leases: HashMap::new(),
+            codebook: None,
+            #[cfg(feature = "metrics")]
+            metrics: MetricsRecorder::default(),
         }
     }
 
+    /// Register an observer for this bank's entry events, replacing any
+    /// existing one. See [`BankObserver`] for the callback contract.
+    pub fn set_observer(&mut self, observer: Arc<dyn BankObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Remove and return the registered observer, if any.
+    pub fn take_observer(&mut self) -> Option<Arc<dyn BankObserver>> {
+        self.observer.take()
+    }
+
+    /// Install a hook run on every entry `insert` creates, after the entry
+    /// is built and before it is indexed, replacing any existing hook.
+    ///
+    /// The hook may set any field (confidence, debug_tag, temperature, even
+    /// the vector). The entry's `id` and `origin` are restored afterwards,
+    /// the checksum is recomputed, and the insert fails with
+    /// `VectorWidthMismatch` if the hook changed the vector's width.
+    pub fn set_insert_hook(&mut self, hook: InsertHook) {
+        self.insert_hook = Some(hook);
+    }
+
+    /// Remove and return the insert hook, if any.
+    pub fn take_insert_hook(&mut self) -> Option<InsertHook> {
+        self.insert_hook.take()
+    }
+
     /// Insert a new entry into the bank.
     ///
     /// The vector must match the bank's configured `vector_width`.
     /// If the bank is at capacity, the lowest-scoring entry is evicted first.
+    /// Unlimited banks (`max_entries == 0`) skip the capacity check entirely.
     pub fn insert(
         &mut self,
         vector: Vec<Signal>,
         temperature: Temperature,
         tick: u64,
     ) -> Result<EntryId> {
+        self.insert_in_namespace(vector, temperature, tick, 0)
+    }
+
+    /// Like [`insert`](Self::insert), but the entry belongs to `namespace`,
+    /// so queries filtered with [`QueryFilter::in_namespace`] and
+    /// [`clear_namespace`](Self::clear_namespace) can scope to one context
+    /// (e.g. one conversation) without a bank per context.
+    ///
+    /// Eviction and capacity still span the whole bank. The insert hook
+    /// sees the namespace already set.
+    pub fn insert_in_namespace(
+        &mut self,
+        vector: Vec<Signal>,
+        temperature: Temperature,
+        tick: u64,
+        namespace: u16,
+    ) -> Result<EntryId> {
+        self.insert_entry(vector, temperature, tick, namespace, None)
+    }
+
+    /// Like [`insert`](Self::insert), but the entry expires `ttl` ticks
+    /// after `tick`: [`query_sparse_at`](Self::query_sparse_at) skips it
+    /// from then on and [`expire_entries`](Self::expire_entries) removes it.
+    /// Until removed it still counts toward capacity.
+    pub fn insert_ttl(
+        &mut self,
+        vector: Vec<Signal>,
+        temperature: Temperature,
+        tick: u64,
+        ttl: u64,
+    ) -> Result<EntryId> {
+        self.insert_entry(vector, temperature, tick, 0, Some(tick.saturating_add(ttl)))
+    }
+
+    /// Whether an insert of `vector` at `tick` would pass the width check
+    /// and find room, evicting if it has to. The insert hook and byte
+    /// budget are not consulted.
+    #[cfg(feature = "std")]
+    pub(crate) fn has_room_for(&self, vector: &[Signal], tick: u64) -> bool {
+        vector.len() == self.config.vector_width as usize
+            && self.config.capacity().is_none_or(|capacity| {
+                self.entries.len() < capacity as usize
+                    || !self.eviction_candidates(1, tick).is_empty()
+            })
+    }
+
+    /// Insert a copy of `entry`, e.g. one migrated from another bank. Its
+    /// vector, temperature, created tick, TTL and namespace go through the
+    /// usual insert path (width check, hook, eviction); its edges, origin,
+    /// pin, confidence and access history are then carried over. The copy
+    /// gets a fresh id and no debug tag. An entry with more edges than
+    /// this bank allows is refused with `EdgeLimitReached` before anything
+    /// changes.
+    pub(crate) fn insert_copy(&mut self, entry: &BankEntry) -> Result<EntryId> {
+        let max = self.config.max_edges_per_entry;
+        if max > 0 && entry.edges.len() > max as usize {
+            return Err(DataBankError::EdgeLimitReached { max });
+        }
+        let id = self.insert_entry(
+            entry.vector.clone(),
+            entry.temperature,
+            entry.created_tick,
+            entry.namespace,
+            entry.expires_at_tick,
+        )?;
+        for edge in &entry.edges {
+            self.add_edge(id, *edge)?;
+        }
+        if entry.pinned {
+            self.pin(id)?;
+        }
+        self.set_confidence(id, entry.confidence)?;
+        if let Some(mut copy) = self.get_mut(id) {
+            copy.origin = entry.origin;
+            copy.access_count = entry.access_count;
+            copy.last_accessed_tick = entry.last_accessed_tick;
+        }
+        Ok(id)
+    }
+
+    /// Insert with every property a journal record can carry.
+    pub(crate) fn insert_entry(
+        &mut self,
+        vector: Vec<Signal>,
+        temperature: Temperature,
+        tick: u64,
+        namespace: u16,
+        expires_at_tick: Option<u64>,
+    ) -> Result<EntryId> {
+        self.compact_if_due();
         // Validate vector width
         if vector.len() != self.config.vector_width as usize {
             return Err(DataBankError::VectorWidthMismatch {
@@ -80,26 +438,70 @@ impl DataBank {
             });
         }
 
-        // Evict if at capacity
-        if self.entries.len() >= self.config.max_entries as usize {
-            self.evict_lowest(tick);
+        // Without std there is no wall clock; the tick orders ids instead.
+        #[cfg(feature = "std")]
+        let id = EntryId::new(self.next_seq);
+        #[cfg(not(feature = "std"))]
+        let id = EntryId::new_at(tick, self.next_seq);
+        let mut entry = BankEntry::new(id, vector, self.id, temperature, tick);
+        entry.namespace = namespace;
+        entry.expires_at_tick = expires_at_tick;
+        if let Some(hook) = &self.insert_hook {
+            hook(&mut entry);
+            entry.id = id;
+            entry.origin = self.id;
+            if entry.vector.len() != self.config.vector_width as usize {
+                return Err(DataBankError::VectorWidthMismatch {
+                    expected: self.config.vector_width,
+                    got: entry.vector.len() as u16,
+                });
+            }
+            entry.checksum = entry.compute_checksum();
+        }
+
+        // Evict only once the hook has run, so a rejected entry costs nothing
+        // and the byte budget sees the vector that will actually be stored.
+        if let Some(capacity) = self.config.capacity() {
+            // Evict if at capacity
+            if self.entries.len() >= capacity as usize {
+                self.evict_lowest(tick);
+            }
+
+            // Still full after eviction? (shouldn't happen, but be safe)
+            if self.entries.len() >= capacity as usize {
+                return Err(DataBankError::BankFull { capacity });
+            }
         }
 
-        // Still full after eviction? (shouldn't happen, but be safe)
-        if self.entries.len() >= self.config.max_entries as usize {
-            return Err(DataBankError::BankFull {
-                capacity: self.config.max_entries,
-            });
+        if let Some(max_bytes) = self.config.max_bytes {
+            let needed = entry_bytes(&entry);
+            while self.approx_bytes() + needed > max_bytes {
+                if !self.evict_lowest(tick) {
+                    return Err(DataBankError::BankOverBudget {
+                        max_bytes,
+                        used: self.approx_bytes(),
+                        needed,
+                    });
+                }
+            }
         }
 
-        let id = EntryId::new(self.next_seq);
+        entry.refresh_meta_checksum();
         self.next_seq = self.next_seq.wrapping_add(1);
 
-        let entry = BankEntry::new(id, vector.clone(), self.id, temperature, tick);
-        self.vector_index.insert(id, &vector);
+        self.vector_index.insert(id, &entry.vector);
+        self.remember_content_hash(id, &entry.vector);
+        self.temp_counts[entry.temperature as usize] += 1;
+        self.bytes += entry_bytes(&entry);
+        self.ttl_entries += usize::from(entry.expires_at_tick.is_some());
         self.entries.insert(id, entry);
 
         self.mark_mutated();
+        #[cfg(feature = "metrics")]
+        self.metrics.record_insert();
+        if let Some(observer) = &self.observer {
+            observer.on_insert(self.id, id);
+        }
         Ok(id)
     }
 
@@ -109,200 +511,1434 @@ impl DataBank {
     }
 
     /// Get a mutable reference to an entry by ID.
-    pub fn get_mut(&mut self, id: EntryId) -> Option<&mut BankEntry> {
-        self.entries.get_mut(&id)
+    ///
+    /// The returned guard derefs to the entry. A temperature changed through
+    /// it is reflected in [`count_by_temperature`](Self::count_by_temperature)
+    /// when the guard drops, and a changed entry marks the bank dirty.
+    pub fn get_mut(&mut self, id: EntryId) -> Option<EntryMut<'_>> {
+        let entry = self.entries.get_mut(&id)?;
+        Some(EntryMut {
+            was: entry.temperature,
+            was_bytes: entry_bytes(entry),
+            had_ttl: entry.expires_at_tick.is_some(),
+            was_checksums: (entry.checksum, entry.meta_checksum),
+            entry,
+            temp_counts: &mut self.temp_counts,
+            bytes: &mut self.bytes,
+            ttl_entries: &mut self.ttl_entries,
+            mutations: &mut self.mutations_since_persist,
+            generation: &mut self.generation,
+            dirty: &mut self.dirty,
+        })
+    }
+
+    /// Remove an entry from the map, the temperature counts, the byte
+    /// estimate and the TTL count only; the caller handles the index, hashes, edges and
+    /// notifications.
+    fn take_entry(&mut self, id: EntryId) -> Option<BankEntry> {
+        let entry = self.entries.remove(&id)?;
+        self.temp_counts[entry.temperature as usize] -= 1;
+        self.bytes -= entry_bytes(&entry);
+        self.ttl_entries -= usize::from(entry.expires_at_tick.is_some());
+        self.removals_since_compact = self.removals_since_compact.saturating_add(1);
+        Some(entry)
+    }
+
+    /// Move one entry's count from `from` to `to`.
+    fn retemper(&mut self, from: Temperature, to: Temperature) {
+        self.temp_counts[from as usize] -= 1;
+        self.temp_counts[to as usize] += 1;
     }
 
     /// Remove an entry by ID, returning it if it existed.
+    ///
+    /// A leased entry is not removed (None is returned) until its lease is
+    /// released or pruned as expired by a ticked call such as
+    /// [`evict_n`](Self::evict_n) or [`expire_leases`](Self::expire_leases);
+    /// this method has no tick to judge expiry by.
     pub fn remove(&mut self, id: EntryId) -> Option<BankEntry> {
-        if let Some(entry) = self.entries.remove(&id) {
+        if self.leases.contains_key(&id) {
+            return None;
+        }
+        if let Some(entry) = self.take_entry(id) {
             self.vector_index.remove(id);
-            self.reverse_edges.remove(&id);
+            self.drop_reverse_list(id);
+            self.forget_content_hash(id, &entry.vector);
             self.mark_mutated();
+            #[cfg(feature = "metrics")]
+            self.metrics.record_remove();
+            if let Some(observer) = &self.observer {
+                observer.on_remove(self.id, id);
+            }
             Some(entry)
         } else {
             None
         }
     }
 
+    /// Remove an entry on behalf of lease `owner` at `current_tick`.
+    ///
+    /// Unlike [`remove`](Self::remove), the owner's own lease and any lease
+    /// expired at `current_tick` do not block removal; the lease is dropped
+    /// with the entry. Fails with `EntryLeased` (naming the holder) while
+    /// another owner's lease is unexpired and with `EntryNotFound` for an
+    /// unknown id.
+    pub fn remove_as(&mut self, id: EntryId, owner: u32, current_tick: u64) -> Result<BankEntry> {
+        if !self.entries.contains_key(&id) {
+            return Err(DataBankError::EntryNotFound { id });
+        }
+        if let Some(&(holder, _)) = self.leases.get(&id) {
+            if holder != owner && self.is_leased(id, current_tick) {
+                return Err(DataBankError::EntryLeased { id, owner: holder });
+            }
+            self.leases.remove(&id);
+        }
+        self.remove(id).ok_or(DataBankError::EntryNotFound { id })
+    }
+
+    /// Remove every entry in `namespace`, pinned and leased ones included,
+    /// when its context is torn down. Returns the removed ids in ascending
+    /// order.
+    ///
+    /// Each removal goes through [`remove`](Self::remove), so indexes and
+    /// observers stay consistent. Journal the ids as a `BatchEvict`, or use
+    /// [`BankCluster::clear_namespace`], which does.
+    ///
+    /// [`BankCluster::clear_namespace`]: crate::cluster::BankCluster::clear_namespace
+    pub fn clear_namespace(&mut self, namespace: u16) -> Vec<EntryId> {
+        let mut ids: Vec<EntryId> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.namespace == namespace)
+            .map(|(&id, _)| id)
+            .collect();
+        ids.sort_unstable();
+        for &id in &ids {
+            self.leases.remove(&id);
+            self.remove(id);
+        }
+        ids
+    }
+
     /// Query the bank for entries most similar to the given vector.
     ///
     /// Uses sparse cosine similarity with the full s = p x m x k equation.
     /// Only non-zero query dimensions participate. This IS pattern completion:
     /// a partial cue activates the full stored patterns that best match.
-    pub fn query_sparse(&self, query: &[Signal], top_k: usize) -> Vec<QueryResult> {
-        self.vector_index.query(query, &self.entries, top_k)
+    ///
+    /// With `exact_match_fast_path` enabled, a query byte-identical to at
+    /// least `top_k` stored vectors returns `top_k` of them (smallest ids
+    /// first) at score 256 without scanning. With fewer identical vectors
+    /// the query scans as usual, so the results still fill `top_k`.
+    ///
+    /// Returns `VectorWidthMismatch` if the query is not exactly the bank's
+    /// width; a wider query would otherwise be silently truncated. Use
+    /// [`query_sparse_with`](Self::query_sparse_with) to compare over the
+    /// shorter length instead.
+    pub fn query_sparse(&self, query: &[Signal], top_k: usize) -> Result<Vec<QueryResult>> {
+        self.query_sparse_with(query, top_k, QueryWidth::Strict)
     }
 
-    /// Add a directed edge from one entry to another.
-    pub fn add_edge(&mut self, from: EntryId, edge: Edge) -> Result<()> {
-        let max = self.config.max_edges_per_entry;
-        let entry = self
-            .entries
-            .get_mut(&from)
-            .ok_or(DataBankError::EntryNotFound { id: from })?;
-        entry.add_edge(edge, max)?;
-
-        // Update reverse index: the target now has a back-pointer
-        self.reverse_edges
-            .entry(edge.target.entry)
-            .or_default()
-            .push((
-                BankRef {
-                    bank: self.id,
-                    entry: from,
-                },
-                edge.edge_type,
-            ));
+    /// Like [`query_sparse`](Self::query_sparse), with the width check
+    /// chosen by `width`. [`QueryWidth::Lenient`] never fails.
+    pub fn query_sparse_with(
+        &self,
+        query: &[Signal],
+        top_k: usize,
+        width: QueryWidth,
+    ) -> Result<Vec<QueryResult>> {
+        if width == QueryWidth::Strict {
+            self.check_query_width(query)?;
+        }
+        Ok(self.query_lenient(query, top_k))
+    }
 
-        self.mark_mutated();
+    fn check_query_width(&self, query: &[Signal]) -> Result<()> {
+        let expected = self.config.vector_width;
+        if query.len() != expected as usize {
+            return Err(DataBankError::VectorWidthMismatch {
+                expected,
+                got: query.len().min(u16::MAX as usize) as u16,
+            });
+        }
         Ok(())
     }
 
-    /// Get edges from a specific entry.
-    pub fn edges_from(&self, id: EntryId) -> &[Edge] {
-        self.entries
-            .get(&id)
-            .map(|e| e.edges.as_slice())
-            .unwrap_or(&[])
+    /// [`query_sparse`](Self::query_sparse) without the width check, for
+    /// the cluster and bridge paths that have always compared over the
+    /// shorter length.
+    pub(crate) fn query_lenient(&self, query: &[Signal], top_k: usize) -> Vec<QueryResult> {
+        let mut out = Vec::new();
+        self.query_sparse_into(query, top_k, &mut out);
+        out
     }
 
-    /// Get reverse edges pointing to an entry in this bank.
-    pub fn reverse_edges(&self, id: EntryId) -> &[(BankRef, EdgeType)] {
-        self.reverse_edges
-            .get(&id)
-            .map(|v| v.as_slice())
-            .unwrap_or(&[])
+    /// Like [`query_sparse`](Self::query_sparse), but clears and fills the
+    /// caller's buffer instead of allocating a new one. Hot loops issuing
+    /// many queries per tick keep one buffer and pay for its growth once.
+    /// Lenient about the query width.
+    pub fn query_sparse_into(&self, query: &[Signal], top_k: usize, out: &mut Vec<QueryResult>) {
+        self.query_impl(query, top_k, out, None, None);
     }
 
-    /// Evict the entry with the lowest eviction score.
-    fn evict_lowest(&mut self, current_tick: u64) {
-        let lowest = self
-            .entries
-            .iter()
-            .min_by_key(|(_, entry)| entry.eviction_score(current_tick))
-            .map(|(&id, _)| id);
+    /// Like [`query_sparse`](Self::query_sparse), but records which path
+    /// served the query, how many candidates were scored, how many IVF
+    /// buckets were probed, and the elapsed microseconds in `trace`
+    /// (overwritten).
+    pub fn query_sparse_traced(
+        &self,
+        query: &[Signal],
+        top_k: usize,
+        trace: &mut QueryTrace,
+    ) -> Vec<QueryResult> {
+        *trace = QueryTrace::default();
+        let mut out = Vec::new();
+        self.query_impl(query, top_k, &mut out, Some(trace), None);
+        out
+    }
 
-        if let Some(id) = lowest {
-            self.entries.remove(&id);
-            self.vector_index.remove(id);
-            self.reverse_edges.remove(&id);
-            log::debug!("evicted entry {:?} from bank {:?}", id, self.id);
-        }
+    /// Measure this bank's index against an exact scan, see
+    /// [`evaluate_recall`](crate::index::evaluate_recall).
+    pub fn evaluate_recall(&self, samples: usize, top_k: usize, seed: u64) -> RecallReport {
+        let index = self.vector_index.as_ref();
+        crate::index::evaluate_recall(index, &self.entries, samples, top_k, seed)
     }
 
-    /// Check whether the bank should be flushed to disk.
-    pub fn should_persist(&self, current_tick: u64) -> bool {
-        if !self.dirty {
-            return false;
-        }
-        let ticks_since = current_tick.saturating_sub(self.last_persist_tick);
-        self.config
-            .should_persist(self.mutations_since_persist, ticks_since)
+    /// Like [`query_sparse`](Self::query_sparse), but only entries matching
+    /// `filter` are scored, e.g. "what was encoded between ticks 10,000 and
+    /// 12,000 that resembles this cue". Skips the exact-match fast path.
+    pub fn query_sparse_filtered(
+        &self,
+        query: &[Signal],
+        top_k: usize,
+        filter: &QueryFilter,
+    ) -> Vec<QueryResult> {
+        let mut out = Vec::new();
+        self.query_impl(query, top_k, &mut out, None, Some(filter));
+        out
     }
 
-    /// Mark the bank as persisted, resetting mutation counters.
-    pub fn mark_persisted(&mut self, tick: u64) {
-        self.mutations_since_persist = 0;
-        self.last_persist_tick = tick;
-        self.dirty = false;
+    /// Like [`query_sparse`](Self::query_sparse), but entries whose TTL has
+    /// run out at `current_tick` are skipped, whether or not
+    /// [`expire_entries`](Self::expire_entries) has removed them yet.
+    ///
+    /// Banks without any TTL entries (a running count, so the check is
+    /// O(1)) take the unfiltered path; otherwise this is a filtered query
+    /// and skips the exact-match fast path.
+    pub fn query_sparse_at(
+        &self,
+        query: &[Signal],
+        top_k: usize,
+        current_tick: u64,
+    ) -> Result<Vec<QueryResult>> {
+        if self.ttl_entries == 0 {
+            return self.query_sparse(query, top_k);
+        }
+        self.check_query_width(query)?;
+        let filter = QueryFilter::default().with_live_at(current_tick);
+        Ok(self.query_sparse_filtered(query, top_k, &filter))
     }
 
-    /// Whether the bank has unsaved changes.
-    pub fn is_dirty(&self) -> bool {
-        self.dirty
+    /// Shared body of the query methods. Without a trace or filter the only
+    /// added cost is the `Option` checks.
+    // Without std the trace is not reused for timing after the index call.
+    #[cfg_attr(not(feature = "std"), allow(clippy::needless_option_as_deref))]
+    fn query_impl(
+        &self,
+        query: &[Signal],
+        top_k: usize,
+        out: &mut Vec<QueryResult>,
+        mut trace: Option<&mut QueryTrace>,
+        filter: Option<&QueryFilter>,
+    ) {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("query", bank = %self.name, id = %self.id, top_k).entered();
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        #[cfg(feature = "std")]
+        let traced_at = trace.is_some().then(std::time::Instant::now);
+        if let Some(filter) = filter {
+            self.vector_index
+                .query_filtered_into(query, &self.entries, top_k, filter, out);
+        } else if self.exact_matches_into(query, top_k, out) {
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.path = QueryPath::ExactMatch;
+                trace.candidates_scanned = out.len();
+            }
+        } else if let Some(trace) = trace.as_deref_mut() {
+            self.vector_index
+                .query_traced(query, &self.entries, top_k, out, trace);
+        } else {
+            self.vector_index.query_into(query, &self.entries, top_k, out);
+        }
+        #[cfg(feature = "std")]
+        if let (Some(trace), Some(at)) = (trace, traced_at) {
+            trace.micros = at.elapsed().as_micros().min(u64::MAX as u128) as u64;
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.record_query(started.elapsed());
     }
 
-    /// Number of entries in the bank.
-    pub fn len(&self) -> usize {
-        self.entries.len()
+    /// Like [`query_sparse`](Self::query_sparse), but entries whose
+    /// confidence is below `min_confidence` are skipped before scoring, so
+    /// unreliable memories cost nothing and can never be recalled.
+    ///
+    /// Always a linear scan: the vector index has no notion of confidence.
+    /// Equal scores rank the smaller id first. Returns
+    /// `VectorWidthMismatch` for a query that is not the bank's width.
+    pub fn query_sparse_min_confidence(
+        &self,
+        query: &[Signal],
+        top_k: usize,
+        min_confidence: u8,
+    ) -> Result<Vec<QueryResult>> {
+        self.check_query_width(query)?;
+        if top_k == 0 {
+            return Ok(Vec::new());
+        }
+        let mut out: Vec<QueryResult> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.confidence >= min_confidence)
+            .map(|(&entry_id, entry)| QueryResult {
+                entry_id,
+                score: self.config.metric.score(query, &entry.vector),
+            })
+            .collect();
+        out.sort_unstable_by_key(|r| (Reverse(r.score), r.entry_id));
+        out.truncate(top_k);
+        Ok(out)
     }
 
-    /// Whether the bank has no entries.
-    pub fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+    /// Like [`query_sparse`](Self::query_sparse), with each dimension's
+    /// contribution scaled by `weights`, e.g. to favour shape over colour.
+    /// Dimensions past the end of `weights` weigh 1 and negative weights
+    /// count as 0; see [`sparse_cosine_similarity_weighted`].
+    ///
+    /// Always a linear scan: the vector index ranks by unweighted score.
+    pub fn query_sparse_dim_weighted(
+        &self,
+        query: &[Signal],
+        top_k: usize,
+        weights: &[i32],
+    ) -> Result<Vec<QueryResult>> {
+        self.check_query_width(query)?;
+        if top_k == 0 {
+            return Ok(Vec::new());
+        }
+        let mut out: Vec<QueryResult> = self
+            .entries
+            .iter()
+            .map(|(&entry_id, entry)| QueryResult {
+                entry_id,
+                score: sparse_cosine_similarity_weighted(query, &entry.vector, weights),
+            })
+            .collect();
+        out.sort_unstable_by_key(|r| (Reverse(r.score), r.entry_id));
+        out.truncate(top_k);
+        Ok(out)
     }
 
-    /// Get the bank's configuration.
-    pub fn config(&self) -> &BankConfig {
-        &self.config
+    /// Why `entry_id` scores what it does against `query`: each active query
+    /// dimension's contribution, which support and which conflict, and the
+    /// parts of the final score. A debugging aid for recall, not a hot path.
+    pub fn explain_match(&self, query: &[Signal], entry_id: EntryId) -> Result<MatchExplanation> {
+        let entry = self
+            .entries
+            .get(&entry_id)
+            .ok_or(DataBankError::EntryNotFound { id: entry_id })?;
+        Ok(explain_similarity(query, &entry.vector))
     }
 
-    /// Get an iterator over all entries.
-    pub fn entries(&self) -> impl Iterator<Item = (&EntryId, &BankEntry)> {
-        self.entries.iter()
+    /// The `top_k` entries *least* similar to `query`, lowest score first.
+    /// Opposite-polarity entries come before orthogonal ones. Used for
+    /// negative sampling in contrastive learning.
+    ///
+    /// Always a linear scan: the vector index only ranks nearest-first.
+    pub fn query_least_similar(&self, query: &[Signal], top_k: usize) -> Vec<QueryResult> {
+        if top_k == 0 {
+            return Vec::new();
+        }
+        let mut out: Vec<QueryResult> = self.score_all(query).collect();
+        out.sort_unstable_by_key(|r| (r.score, r.entry_id));
+        out.truncate(top_k);
+        out
     }
 
-    /// Get the next sequence counter (for codec restore).
-    pub(crate) fn next_seq(&self) -> u32 {
-        self.next_seq
+    /// The `top_k` nearest entries scoring strictly below `ceiling`,
+    /// highest score first: "something similar, but not this".
+    ///
+    /// Always a linear scan, like [`query_least_similar`](Self::query_least_similar).
+    pub fn query_below(&self, query: &[Signal], ceiling: i32, top_k: usize) -> Vec<QueryResult> {
+        if top_k == 0 {
+            return Vec::new();
+        }
+        let mut out: Vec<QueryResult> =
+            self.score_all(query).filter(|r| r.score < ceiling).collect();
+        out.sort_unstable_by_key(|r| (Reverse(r.score), r.entry_id));
+        out.truncate(top_k);
+        out
     }
 
-    /// Get the mutations since persist (for codec restore).
-    pub(crate) fn mutations_since_persist(&self) -> u32 {
-        self.mutations_since_persist
+    /// "Like `cue`, but not like `inhibit`": the `top_k` entries ranked by
+    /// `cos(cue, v) - inhibit_weight * cos(inhibit, v) / 255`, highest first.
+    ///
+    /// Both similarities are the usual sparse cosine, so only the non-zero
+    /// dimensions of each vector participate. `inhibit_weight` 0 reduces to
+    /// plain cue ranking; 255 subtracts the inhibitor match in full.
+    ///
+    /// Always a linear scan: the index cannot rank on a difference of scores.
+    pub fn query_contrastive(
+        &self,
+        cue: &[Signal],
+        inhibit: &[Signal],
+        inhibit_weight: u8,
+        top_k: usize,
+    ) -> Vec<QueryResult> {
+        if top_k == 0 {
+            return Vec::new();
+        }
+        let weight = inhibit_weight as i32;
+        let mut out: Vec<QueryResult> = self
+            .entries
+            .iter()
+            .map(|(&entry_id, entry)| {
+                let excite = sparse_cosine_similarity(cue, &entry.vector);
+                let suppress = sparse_cosine_similarity(inhibit, &entry.vector);
+                QueryResult {
+                    entry_id,
+                    score: excite - weight * suppress / 255,
+                }
+            })
+            .collect();
+        out.sort_unstable_by_key(|r| (Reverse(r.score), r.entry_id));
+        out.truncate(top_k);
+        out
     }
 
-    /// Get the last persist tick (for codec restore).
-    pub(crate) fn last_persist_tick(&self) -> u64 {
-        self.last_persist_tick
+    /// Score every entry against `query`, in map order.
+    fn score_all<'a>(&'a self, query: &'a [Signal]) -> impl Iterator<Item = QueryResult> + 'a {
+        self.entries.iter().map(|(&entry_id, entry)| QueryResult {
+            entry_id,
+            score: self.config.metric.score(query, &entry.vector),
+        })
     }
 
-    /// Get the reverse edges map (for codec).
-    #[allow(dead_code)]
-    pub(crate) fn reverse_edges_map(&self) -> &HashMap<EntryId, Vec<(BankRef, EdgeType)>> {
-        &self.reverse_edges
+    /// Like [`query_sparse`](Self::query_sparse), but each result carries this
+    /// bank's id and name so it can be merged with [`BankCluster::query_all`]
+    /// output without manual tagging.
+    ///
+    /// `normalized_score` is z-scored within this bank, as the cluster does
+    /// when this is the only bank queried.
+    ///
+    /// [`BankCluster::query_all`]: crate::cluster::BankCluster::query_all
+    pub fn query_sparse_tagged(&self, query: &[Signal], top_k: usize) -> Vec<ClusterQueryResult> {
+        fuse_results(vec![(self.id, self.name.clone(), self.query_lenient(query, top_k))])
     }
 
-    /// Restore bank state from decoded fields (used by codec).
-    pub(crate) fn restore(
-        id: BankId,
-        name: String,
-        config: BankConfig,
-        entries: HashMap<EntryId, BankEntry>,
-        reverse_edges: HashMap<EntryId, Vec<(BankRef, EdgeType)>>,
-        next_seq: u32,
-        mutations_since_persist: u32,
-        last_persist_tick: u64,
-    ) -> Self {
-        let mut vector_index = create_index(&config.index_type);
-        vector_index.rebuild(&entries);
+    /// Deep copy for speculative mutation, e.g. imagining outcomes and
+    /// throwing them away.
+    ///
+    /// The fork keeps the id, name, config, entries, edges, leases and
+    /// persistence counters, and rebuilds its own index. It never reports
+    /// [`should_persist`](Self::should_persist), so cluster flushes cannot
+    /// write it over the original's file; save it explicitly through
+    /// [`codec`](crate::codec) to keep it. The observer and insert hook are
+    /// not carried over.
+    pub fn fork(&self) -> DataBank {
+        let mut vector_index = create_index(&self.config);
+        vector_index.rebuild(&self.entries);
         Self {
-            id,
-            config,
-            name,
-            entries,
-            next_seq,
+            id: self.id,
+            config: self.config.clone(),
+            name: self.name.clone(),
+            entries: self.entries.clone(),
+            next_seq: self.next_seq,
             vector_index,
-            reverse_edges,
-            mutations_since_persist,
-            last_persist_tick,
-            dirty: false,
+            reverse_edges: self.reverse_edges.clone(),
+            content_hashes: self.content_hashes.clone(),
+            mutations_since_persist: self.mutations_since_persist,
+            last_persist_tick: self.last_persist_tick,
+            dirty: self.dirty,
+            generation: self.generation,
+            removals_since_compact: self.removals_since_compact,
+            forked: true,
+            temp_counts: self.temp_counts,
+            bytes: self.bytes,
+            ttl_entries: self.ttl_entries,
+            persisted_generation: self.persisted_generation,
+            observer: None,
+            insert_hook: None,
+            eviction_log: self.eviction_log.clone(),
+            leases: self.leases.clone(),
+            codebook: self.codebook.clone(),
+            #[cfg(feature = "metrics")]
+            metrics: MetricsRecorder::default(),
         }
     }
 
-    /// Promote an entry's temperature. Returns Ok(true) if promoted.
-    pub fn promote_entry(&mut self, id: EntryId) -> Result<bool> {
-        let entry = self.entries.get_mut(&id)
-            .ok_or(DataBankError::EntryNotFound { id })?;
-        let promoted = entry.promote();
-        if promoted {
-            self.mark_mutated();
-        }
-        Ok(promoted)
+    /// Whether this bank is a [`fork`](Self::fork).
+    pub fn is_forked(&self) -> bool {
+        self.forked
     }
 
-    /// Demote an entry's temperature. Returns Ok(true) if demoted.
-    pub fn demote_entry(&mut self, id: EntryId) -> Result<bool> {
+    /// Take an immutable snapshot of the bank's current entries.
+    ///
+    /// Reads served from the snapshot are unaffected by later mutations of
+    /// the live bank. Cloning the snapshot is O(1).
+    pub fn snapshot(&self) -> BankSnapshot {
+        BankSnapshot::from_entries(self.id, self.config.metric, self.entries.iter())
+    }
+
+    /// Pattern completion that synthesizes rather than selects: the
+    /// score-weighted average of the top-k matches for `query`.
+    ///
+    /// Each dimension is `sum(score_i * current_i) / sum(score_i)` over
+    /// results with a positive score, computed in i64 and converted back
+    /// with `Signal::from_current`. Returns an all-zero vector of the bank's
+    /// width when nothing matches positively.
+    pub fn evoke(&self, query: &[Signal], top_k: usize) -> Vec<Signal> {
+        let width = self.config.vector_width as usize;
+        let mut sums = vec![0i64; width];
+        let mut total_weight: i64 = 0;
+
+        for r in self.query_lenient(query, top_k) {
+            if r.score <= 0 {
+                continue;
+            }
+            let Some(entry) = self.entries.get(&r.entry_id) else {
+                continue;
+            };
+            let weight = r.score as i64;
+            total_weight += weight;
+            for (sum, s) in sums.iter_mut().zip(&entry.vector) {
+                *sum += weight * s.current() as i64;
+            }
+        }
+
+        if total_weight == 0 {
+            return vec![Signal::ZERO; width];
+        }
+        sums.into_iter()
+            .map(|sum| Signal::from_current((sum / total_weight) as i32))
+            .collect()
+    }
+
+    /// Fast-path lookup of stored vectors identical to `query`, written to
+    /// `out` (cleared first), smallest ids first. Returns false, leaving
+    /// `out` empty, when the fast path is disabled or fewer than `top_k`
+    /// vectors match: the scan must then fill the remaining slots.
+    fn exact_matches_into(
+        &self,
+        query: &[Signal],
+        top_k: usize,
+        out: &mut Vec<QueryResult>,
+    ) -> bool {
+        out.clear();
+        if !self.config.exact_match_fast_path || top_k == 0 {
+            return false;
+        }
+        // A zero vector scores 0 under cosine, never 256
+        if query.iter().all(|s| s.current() == 0) {
+            return false;
+        }
+        let Some(ids) = self.content_hashes.get(&vector_hash(query)) else {
+            return false;
+        };
+        out.extend(
+            ids.iter()
+                .filter(|&id| {
+                    // Guard against hash collisions and vectors mutated in place
+                    self.entries
+                        .get(id)
+                        .is_some_and(|e| e.vector.as_slice() == query)
+                })
+                .map(|&entry_id| QueryResult {
+                    entry_id,
+                    score: 256,
+                }),
+        );
+        if out.len() < top_k {
+            out.clear();
+            return false;
+        }
+        out.sort_unstable_by_key(|r| r.entry_id);
+        out.truncate(top_k);
+        true
+    }
+
+    /// Add an entry to the content-hash map, if it is maintained.
+    fn remember_content_hash(&mut self, id: EntryId, vector: &[Signal]) {
+        if !self.config.exact_match_fast_path {
+            return;
+        }
+        let ids = self.content_hashes.entry(vector_hash(vector)).or_default();
+        let before = if ids.is_empty() { 0 } else { hash_bucket_bytes(ids) };
+        ids.push(id);
+        self.bytes = self.bytes - before + hash_bucket_bytes(ids);
+    }
+
+    /// Drop an entry from the content-hash map.
+    fn forget_content_hash(&mut self, id: EntryId, vector: &[Signal]) {
+        if !self.config.exact_match_fast_path {
+            return;
+        }
+        let hash = vector_hash(vector);
+        if let Some(ids) = self.content_hashes.get_mut(&hash) {
+            let before = hash_bucket_bytes(ids);
+            ids.retain(|&eid| eid != id);
+            if ids.is_empty() {
+                self.content_hashes.remove(&hash);
+                self.bytes -= before;
+            } else {
+                self.bytes = self.bytes - before + hash_bucket_bytes(ids);
+            }
+        }
+    }
+
+    /// Rebuild the content-hash map from the current entries, then recount
+    /// the byte estimate.
+    fn rebuild_content_hashes(&mut self) {
+        self.content_hashes.clear();
+        if self.config.exact_match_fast_path {
+            for (&id, entry) in &self.entries {
+                self.content_hashes
+                    .entry(vector_hash(&entry.vector))
+                    .or_default()
+                    .push(id);
+            }
+        }
+        self.bytes = self.recount_bytes();
+    }
+
+    /// Drop the reverse-edge list of `id`, e.g. once the entry is removed.
+    fn drop_reverse_list(&mut self, id: EntryId) {
+        if let Some(list) = self.reverse_edges.remove(&id) {
+            self.bytes -= reverse_list_bytes(&list);
+        }
+    }
+
+    /// Add a directed edge from one entry to another.
+    ///
+    /// When the entry is at `max_edges_per_entry`, the bank's
+    /// [`EdgeOverflow`] policy decides whether to fail or displace an
+    /// existing edge (whose reverse-index entry is removed with it). Under
+    /// `EvictWeakest` an edge weaker than every held edge fails with
+    /// `EdgeLimitReached`, leaving the entry unchanged.
+    pub fn add_edge(&mut self, from: EntryId, edge: Edge) -> Result<()> {
+        self.compact_if_due();
+        let max = self.config.max_edges_per_entry;
+        let policy = self.config.edge_overflow;
+        let entry = self
+            .entries
+            .get_mut(&from)
+            .ok_or(DataBankError::EntryNotFound { id: from })?;
+        let entry_before = entry_bytes(entry);
+
+        if max > 0 && entry.edges.len() >= max as usize && policy != EdgeOverflow::Reject {
+            let victim = match policy {
+                EdgeOverflow::EvictWeakest => entry
+                    .edges
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, e)| (e.weight, e.created_tick))
+                    .map(|(i, _)| i),
+                EdgeOverflow::EvictOldest => entry
+                    .edges
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, e)| (e.created_tick, e.weight))
+                    .map(|(i, _)| i),
+                EdgeOverflow::Reject => None,
+            };
+            if let Some(i) = victim {
+                if policy == EdgeOverflow::EvictWeakest && edge.weight < entry.edges[i].weight {
+                    // Weaker than everything we hold: keep the strongest N
+                    return Err(DataBankError::EdgeLimitReached { max });
+                }
+                let evicted = entry.edges.remove(i);
+                let source = BankRef {
+                    bank: self.id,
+                    entry: from,
+                };
+                if let Some(back) = self.reverse_edges.get_mut(&evicted.target.entry) {
+                    let before = reverse_list_bytes(back);
+                    if let Some(j) = back
+                        .iter()
+                        .position(|&(r, t)| r == source && t == evicted.edge_type)
+                    {
+                        back.remove(j);
+                    }
+                    if back.is_empty() {
+                        self.reverse_edges.remove(&evicted.target.entry);
+                        self.bytes -= before;
+                    }
+                }
+            }
+        }
+        let added = entry.add_edge(edge, max);
+        self.bytes = self.bytes - entry_before + entry_bytes(entry);
+        added?;
+
+        // Update reverse index: the target now has a back-pointer
+        let back = self.reverse_edges.entry(edge.target.entry).or_default();
+        let before = if back.is_empty() { 0 } else { reverse_list_bytes(back) };
+        back.push((
+            BankRef {
+                bank: self.id,
+                entry: from,
+            },
+            edge.edge_type,
+        ));
+        self.bytes = self.bytes - before + reverse_list_bytes(back);
+
+        self.mark_mutated();
+        if let Some(observer) = &self.observer {
+            observer.on_edge_added(self.id, from, &edge);
+        }
+        Ok(())
+    }
+
+    /// Get edges from a specific entry.
+    pub fn edges_from(&self, id: EntryId) -> &[Edge] {
+        self.entries
+            .get(&id)
+            .map(|e| e.edges.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Get reverse edges pointing to an entry in this bank.
+    pub fn reverse_edges(&self, id: EntryId) -> &[(BankRef, EdgeType)] {
+        self.reverse_edges
+            .get(&id)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Strip every edge in this bank that points at `target`, finding the
+    /// sources through the reverse index rather than scanning all entries.
+    /// Returns the number of edges removed.
+    pub fn remove_all_edges_to(&mut self, target: BankRef) -> usize {
+        let Some(back) = self.reverse_edges.get(&target.entry) else {
+            return 0;
+        };
+        let mut sources: Vec<EntryId> = back.iter().map(|(r, _)| r.entry).collect();
+        sources.sort_unstable();
+        sources.dedup();
+
+        let bank = self.id;
+        let mut stripped: Vec<(BankRef, EdgeType)> = Vec::new();
+        for source in sources {
+            let Some(entry) = self.entries.get_mut(&source) else {
+                continue;
+            };
+            let before = stripped.len();
+            stripped.extend(
+                entry
+                    .edges
+                    .iter()
+                    .filter(|e| e.target == target)
+                    .map(|e| (BankRef { bank, entry: source }, e.edge_type)),
+            );
+            if stripped.len() > before {
+                let was = entry_bytes(entry);
+                entry.remove_edges_to(target);
+                self.bytes = self.bytes - was + entry_bytes(entry);
+            }
+        }
+        if stripped.is_empty() {
+            return 0;
+        }
+
+        // The same entry id may be targeted in another bank, so drop only
+        // the back-pointers for edges actually removed.
+        if let Some(back) = self.reverse_edges.get_mut(&target.entry) {
+            for pair in &stripped {
+                if let Some(j) = back.iter().position(|p| p == pair) {
+                    back.remove(j);
+                }
+            }
+            if back.is_empty() {
+                self.drop_reverse_list(target.entry);
+            }
+        }
+        self.mark_mutated();
+        stripped.len()
+    }
+
+    /// Iterate every outgoing edge in the bank, paired with its source entry.
+    pub fn all_edges(&self) -> impl Iterator<Item = (EntryId, &Edge)> {
+        self.entries
+            .iter()
+            .flat_map(|(&id, e)| e.edges.iter().map(move |edge| (id, edge)))
+    }
+
+    /// Per-entry `(id, in_degree, out_degree)`, sorted by EntryId.
+    ///
+    /// Out-degree is the entry's edge list length. In-degree comes from the
+    /// reverse index, which only records edges held by this bank, so it
+    /// counts incoming edges from this bank's own entries. Use
+    /// [`BankCluster::most_connected`](crate::cluster::BankCluster::most_connected)
+    /// for in-degree across banks.
+    pub fn degree_summary(&self) -> Vec<(EntryId, usize, usize)> {
+        let mut degrees: Vec<(EntryId, usize, usize)> = self
+            .entries
+            .iter()
+            .map(|(&id, entry)| (id, self.reverse_edges(id).len(), entry.edges.len()))
+            .collect();
+        degrees.sort_unstable_by_key(|&(id, _, _)| id);
+        degrees
+    }
+
+    /// Summarize the bank's edges: counts per EdgeType and a coarse weight
+    /// histogram, computed in one pass over `all_edges`.
+    pub fn edge_histogram(&self) -> EdgeStats {
+        let mut stats = EdgeStats::default();
+        let bucket_width = 256 / WEIGHT_BUCKETS;
+        for (_, edge) in self.all_edges() {
+            stats.total += 1;
+            *stats.by_type.entry(edge.edge_type).or_insert(0) += 1;
+            stats.weight_buckets[edge.weight as usize / bucket_width] += 1;
+        }
+        stats
+    }
+
+    /// Evict the entry with the lowest eviction score.
+    ///
+    /// Returns false if nothing was evictable.
+    fn evict_lowest(&mut self, current_tick: u64) -> bool {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("evict", bank = %self.name, id = %self.id).entered();
+        let Some(id) = self.eviction_victim(current_tick) else {
+            return false;
+        };
+
+        if let Some(entry) = self.take_entry(id) {
+            self.forget_content_hash(id, &entry.vector);
+            self.record_eviction(entry, current_tick, EvictionReason::Capacity);
+        }
+        self.leases.remove(&id);
+        self.vector_index.remove(id);
+        self.drop_reverse_list(id);
+        log::debug!("{}: evicted entry {}", self.label(), id);
+        #[cfg(feature = "metrics")]
+        self.metrics.record_evictions(1);
+        if let Some(observer) = &self.observer {
+            observer.on_evict(self.id, id);
+        }
+        true
+    }
+
+    /// The entry `evict_lowest` removes under the configured
+    /// [`EvictionMode`]. Pinned and leased entries are never chosen.
+    fn eviction_victim(&self, current_tick: u64) -> Option<EntryId> {
+        let unpinned = self
+            .entries
+            .iter()
+            .filter(|(&id, entry)| !entry.pinned && !self.is_leased(id, current_tick));
+        let min_similarity = match self.config.eviction_mode {
+            EvictionMode::Score => {
+                return unpinned
+                    .map(|(&id, entry)| (entry.eviction_score(current_tick), id))
+                    .min()
+                    .map(|(_, id)| id);
+            }
+            EvictionMode::NearDuplicatesFirst { min_similarity } => min_similarity,
+        };
+
+        let mut candidates: Vec<(i64, EntryId)> = unpinned
+            .map(|(&id, entry)| (entry.eviction_score(current_tick), id))
+            .collect();
+        if candidates.len() > NEAR_DUPLICATE_SCAN {
+            candidates.select_nth_unstable(NEAR_DUPLICATE_SCAN);
+            candidates.truncate(NEAR_DUPLICATE_SCAN);
+        }
+        candidates.sort_unstable();
+
+        // The nearest hit is usually the candidate itself; ask for one more.
+        let mut nearest = Vec::with_capacity(2);
+        candidates
+            .iter()
+            .find(|&&(_, id)| {
+                self.vector_index
+                    .query_into(&self.entries[&id].vector, &self.entries, 2, &mut nearest);
+                nearest
+                    .iter()
+                    .any(|r| r.entry_id != id && r.score >= min_similarity)
+            })
+            .or(candidates.first())
+            .map(|&(_, id)| id)
+    }
+
+    /// Append an eviction to the audit log, dropping the oldest record when
+    /// full. Returns immediately when auditing is off.
+    fn record_eviction(&mut self, entry: BankEntry, tick: u64, reason: EvictionReason) {
+        let cap = self.config.audit_evictions;
+        if cap == 0 {
+            return;
+        }
+        if self.eviction_log.len() >= cap {
+            let excess = self.eviction_log.len() + 1 - cap;
+            self.eviction_log.drain(..excess);
+        }
+        self.eviction_log.push(EvictionRecord {
+            entry_id: entry.id,
+            tick,
+            eviction_score: entry.eviction_score(tick),
+            temperature: entry.temperature,
+            access_count: entry.access_count,
+            debug_tag: entry.debug_tag,
+            reason,
+        });
+    }
+
+    /// Recent evictions, oldest first. Empty unless
+    /// `BankConfig::audit_evictions` is non-zero.
+    pub fn eviction_log(&self) -> &[EvictionRecord] {
+        &self.eviction_log
+    }
+
+    /// Check whether the bank should be flushed to disk. Always false for a
+    /// [`fork`](Self::fork).
+    pub fn should_persist(&self, current_tick: u64) -> bool {
+        if !self.dirty || self.forked {
+            return false;
+        }
+        let ticks_since = current_tick.saturating_sub(self.last_persist_tick);
+        self.config
+            .should_persist(self.mutations_since_persist, ticks_since)
+    }
+
+    /// Mark the bank as persisted, resetting mutation counters.
+    pub fn mark_persisted(&mut self, tick: u64) {
+        self.mutations_since_persist = 0;
+        self.last_persist_tick = tick;
+        self.dirty = false;
+        self.persisted_generation = self.generation;
+        #[cfg(feature = "metrics")]
+        self.metrics.record_flush();
+    }
+
+    /// Mark a snapshot taken after `mutations` mutations as persisted.
+    ///
+    /// Mutations made after the snapshot was taken keep the bank dirty, so a
+    /// write that completes late never hides newer changes.
+    pub fn mark_persisted_through(&mut self, tick: u64, mutations: u32) {
+        self.mutations_since_persist = self.mutations_since_persist.saturating_sub(mutations);
+        self.last_persist_tick = tick;
+        self.dirty = self.mutations_since_persist > 0;
+        #[cfg(feature = "metrics")]
+        self.metrics.record_flush();
+    }
+
+    /// Mark a snapshot taken at `generation` (see
+    /// [`generation`](Self::generation)) after `mutations` mutations as
+    /// persisted.
+    ///
+    /// The bank stays dirty unless no mutation happened since the snapshot,
+    /// even if the mutation counter was reset in between by a synchronous
+    /// flush. A snapshot older than one already persisted is ignored.
+    pub fn mark_generation_persisted(&mut self, tick: u64, generation: u64, mutations: u32) {
+        if generation < self.persisted_generation {
+            return;
+        }
+        self.persisted_generation = generation;
+        self.last_persist_tick = tick;
+        if generation >= self.generation {
+            self.mutations_since_persist = 0;
+            self.dirty = false;
+        } else {
+            self.mutations_since_persist =
+                self.mutations_since_persist.saturating_sub(mutations).max(1);
+            self.dirty = true;
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.record_flush();
+    }
+
+    /// Generation of the newest snapshot known to be persisted.
+    #[cfg(feature = "std")]
+    pub(crate) fn persisted_generation(&self) -> u64 {
+        self.persisted_generation
+    }
+
+    /// Mutation generation: increases by one on every mutation and is never
+    /// reset, so a snapshot tagged with it can tell whether the bank changed
+    /// since. Starts at 0 for new and loaded banks.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Snapshot of this bank's runtime counters.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> BankMetrics {
+        self.metrics.snapshot()
+    }
+
+    /// Live counters, for cluster-side recording (flush timing, journal).
+    #[cfg(feature = "metrics")]
+    pub(crate) fn metrics_recorder(&self) -> &MetricsRecorder {
+        &self.metrics
+    }
+
+    /// Mutations since the last persistence flush.
+    pub fn mutations_since_persist(&self) -> u32 {
+        self.mutations_since_persist
+    }
+
+    /// Whether the bank has unsaved changes.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Snapshot of the bank's persistence bookkeeping.
+    pub fn persistence_state(&self) -> PersistenceState {
+        PersistenceState {
+            dirty: self.dirty,
+            mutations_since_persist: self.mutations_since_persist,
+            last_persist_tick: self.last_persist_tick,
+            approx_encoded_bytes: crate::codec::encoded_len(self),
+            removals_since_compact: self.removals_since_compact,
+        }
+    }
+
+    /// Prefix for log lines about this bank.
+    pub(crate) fn label(&self) -> BankLabel<'_> {
+        BankLabel {
+            name: &self.name,
+            id: self.id,
+        }
+    }
+
+    /// Number of entries in the bank.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the bank has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the bank's configuration.
+    pub fn config(&self) -> &BankConfig {
+        &self.config
+    }
+
+    /// Change the bank's name, marking it dirty so the next flush writes it
+    /// under the new one. Only the cluster renames banks, since it indexes
+    /// them by name.
+    pub(crate) fn rename(&mut self, name: String) {
+        self.name = name;
+        self.mark_mutated();
+    }
+
+    /// Move the entries to another storage backend. Decoded banks start on
+    /// the default backend, since `.bank` files don't record it.
+    pub fn set_storage(&mut self, storage: EntryStorage) {
+        self.config.storage = storage;
+        let entries = core::mem::take(&mut self.entries);
+        self.entries = entries.into_storage(storage);
+    }
+
+    /// Summarize the bank's size and shape.
+    pub fn stats(&self) -> BankStats {
+        BankStats {
+            entries: self.entries.len(),
+            capacity: self.config.capacity(),
+            edges: self.entries.values().map(|e| e.edges.len()).sum(),
+            pinned: self.entries.values().filter(|e| e.pinned).count(),
+            temperatures: self.temp_counts,
+            namespaces: self.entries.values().fold(BTreeMap::new(), |mut counts, e| {
+                *counts.entry(e.namespace).or_insert(0) += 1;
+                counts
+            }),
+            vector_width: self.config.vector_width,
+            approx_bytes: self.approx_bytes(),
+        }
+    }
+
+    /// Entries currently at `temperature`. O(1): maintained incrementally.
+    pub fn count_by_temperature(&self, temperature: Temperature) -> u32 {
+        self.temp_counts[temperature as usize]
+    }
+
+    /// Entry count per temperature, indexed by discriminant
+    /// (`counts[Temperature::Set as usize]`). O(1).
+    pub fn temperature_counts(&self) -> [u32; 5] {
+        self.temp_counts
+    }
+
+    /// The per-temperature counts from a full pass over the entries.
+    fn recount_temperatures(&self) -> [u32; 5] {
+        let mut counts = [0u32; 5];
+        for entry in self.entries.values() {
+            counts[entry.temperature as usize] += 1;
+        }
+        counts
+    }
+
+    /// Per dimension, how many entries hold a non-zero signal there.
+    ///
+    /// Zero counts are dead dimensions; counts near `len()` are ones every
+    /// entry relies on. One pass over the entries.
+    pub fn dimension_activity(&self) -> Vec<u32> {
+        let mut counts = vec![0u32; self.config.vector_width as usize];
+        for entry in self.entries.values() {
+            for (count, signal) in counts.iter_mut().zip(&entry.vector) {
+                if signal.current() != 0 {
+                    *count += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// Every vector as one row-major `[n, width]` matrix of signal
+    /// currents (the register convention, p x m x k), rows in ascending
+    /// [`EntryId`] order. Returns the matrix, the id of each row, and the
+    /// width.
+    pub fn to_dense_matrix(&self) -> (Vec<i32>, Vec<EntryId>, usize) {
+        let width = self.config.vector_width as usize;
+        let mut ids: Vec<EntryId> = self.entries.keys().copied().collect();
+        ids.sort_unstable();
+        let mut matrix = Vec::with_capacity(ids.len() * width);
+        for id in &ids {
+            matrix.extend(self.entries[id].vector.iter().map(|s| s.current()));
+        }
+        (matrix, ids, width)
+    }
+
+    /// Estimated bytes held by the bank.
+    ///
+    /// The model sums, per entry, [`BankEntry::approx_bytes`] plus the
+    /// entry map's key and control byte; per reverse-edge list, its key,
+    /// `Vec` header, and elements; per content-hash bucket, likewise; plus
+    /// the `DataBank` struct itself. Hash-map spare capacity, allocator
+    /// headers, and vector index internals are not counted, so this is a
+    /// lower bound that tracks growth rather than an exact RSS figure.
+    ///
+    /// The total is kept up to date as the bank changes, so this is O(1).
+    pub fn approx_bytes(&self) -> usize {
+        core::mem::size_of::<Self>() + self.bytes
+    }
+
+    /// Sum the [`approx_bytes`](Self::approx_bytes) model from scratch,
+    /// less the struct itself.
+    fn recount_bytes(&self) -> usize {
+        let entries: usize = self.entries.values().map(entry_bytes).sum();
+        let reverse: usize = self.reverse_edges.values().map(reverse_list_bytes).sum();
+        let hashes: usize = self.content_hashes.values().map(hash_bucket_bytes).sum();
+        entries + reverse + hashes
+    }
+
+    /// Get an iterator over all entries.
+    pub fn entries(&self) -> impl Iterator<Item = (&EntryId, &BankEntry)> {
+        self.entries.iter()
+    }
+
+    /// The entry map itself, for index training outside the bank.
+    pub(crate) fn entry_map(&self) -> &EntryStore {
+        &self.entries
+    }
+
+    /// Centroids of the bank's vector index, empty until it is trained.
+    pub(crate) fn index_centroids(&self) -> &[Vec<i32>] {
+        self.vector_index.centroids()
+    }
+
+    /// Train a quantization codebook (see [`Codebook::train`]) and keep it,
+    /// so encodes with [`EncodeOptions::quantize`] reuse it instead of
+    /// running k-means on every save. A bank decoded from a quantized file
+    /// keeps that file's codebook.
+    ///
+    /// Retrain once the contents drift: a stale codebook stays correct, it
+    /// only needs more corrections per vector.
+    ///
+    /// [`EncodeOptions::quantize`]: crate::codec::EncodeOptions::quantize
+    pub fn train_codebook(&mut self) -> &Codebook {
+        let codebook = Codebook::train(self);
+        self.codebook.insert(codebook)
+    }
+
+    /// The kept quantization codebook, if there is one that fits the
+    /// bank's width and, unless the bank is empty, has prototypes.
+    pub fn codebook(&self) -> Option<&Codebook> {
+        self.codebook.as_ref().filter(|c| {
+            c.width() == self.config.vector_width as usize
+                && (!c.is_empty() || self.entries.is_empty())
+        })
+    }
+
+    /// Keep `codebook` for later quantized encodes (decode of a quantized
+    /// file).
+    pub(crate) fn keep_codebook(&mut self, codebook: Codebook) {
+        self.codebook = Some(codebook);
+    }
+
+    /// Get the next sequence counter (for codec restore).
+    pub(crate) fn next_seq(&self) -> u32 {
+        self.next_seq
+    }
+
+    /// Get the last persist tick (for codec restore).
+    pub(crate) fn last_persist_tick(&self) -> u64 {
+        self.last_persist_tick
+    }
+
+    /// Get the reverse edges map (for codec).
+    #[allow(dead_code)]
+    pub(crate) fn reverse_edges_map(&self) -> &HashMap<EntryId, Vec<(BankRef, EdgeType)>> {
+        &self.reverse_edges
+    }
+
+    /// Restore bank state from decoded fields (used by codec).
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn restore(
+        id: BankId,
+        name: String,
+        config: BankConfig,
+        entries: HashMap<EntryId, BankEntry>,
+        reverse_edges: HashMap<EntryId, Vec<(BankRef, EdgeType)>>,
+        next_seq: u32,
+        mutations_since_persist: u32,
+        last_persist_tick: u64,
+    ) -> Self {
+        let entries = EntryStore::from(entries).into_storage(config.storage);
+        let mut vector_index = create_index(&config);
+        vector_index.rebuild(&entries);
+        let mut bank = Self {
+            id,
+            config,
+            name,
+            entries,
+            next_seq,
+            vector_index,
+            reverse_edges,
+            content_hashes: HashMap::new(),
+            mutations_since_persist,
+            last_persist_tick,
+            dirty: false,
+            generation: 0,
+            removals_since_compact: 0,
+            forked: false,
+            persisted_generation: 0,
+            temp_counts: [0; 5],
+            bytes: 0,
+            ttl_entries: 0,
+            observer: None,
+            insert_hook: None,
+            eviction_log: Vec::new(),
+            leases: HashMap::new(),
+            codebook: None,
+            #[cfg(feature = "metrics")]
+            metrics: MetricsRecorder::default(),
+        };
+        bank.rebuild_content_hashes();
+        bank.temp_counts = bank.recount_temperatures();
+        bank.ttl_entries = bank.entries.values().filter(|e| e.expires_at_tick.is_some()).count();
+        bank
+    }
+
+    /// Record an access to an entry: bump its access count and set its
+    /// last-accessed tick.
+    pub fn touch_entry(&mut self, id: EntryId, tick: u64) -> Result<()> {
+        let entry = self.entries.get_mut(&id)
+            .ok_or(DataBankError::EntryNotFound { id })?;
+        entry.touch(tick);
+        self.mark_mutated();
+        Ok(())
+    }
+
+    /// Promote an entry's temperature. Returns Ok(true) if promoted.
+    pub fn promote_entry(&mut self, id: EntryId) -> Result<bool> {
         let entry = self.entries.get_mut(&id)
             .ok_or(DataBankError::EntryNotFound { id })?;
+        let from = entry.temperature;
+        let promoted = entry.promote();
+        let to = entry.temperature;
+        if promoted {
+            self.retemper(from, to);
+            self.mark_mutated();
+            if let Some(observer) = &self.observer {
+                observer.on_promote(self.id, id, to);
+            }
+        }
+        Ok(promoted)
+    }
+
+    /// Demote an entry's temperature. Returns Ok(true) if demoted.
+    pub fn demote_entry(&mut self, id: EntryId) -> Result<bool> {
+        let entry = self.entries.get_mut(&id)
+            .ok_or(DataBankError::EntryNotFound { id })?;
+        let from = entry.temperature;
         let demoted = entry.demote();
+        let to = entry.temperature;
         if demoted {
+            self.retemper(from, to);
             self.mark_mutated();
+            if let Some(observer) = &self.observer {
+                observer.on_demote(self.id, id, to);
+            }
         }
         Ok(demoted)
     }
 
+    /// Pin an entry as an innate prior: it will not be demoted or evicted.
+    /// Returns Ok(true) if the entry was not already pinned.
+    pub fn pin(&mut self, id: EntryId) -> Result<bool> {
+        self.set_pinned(id, true)
+    }
+
+    /// Release a pinned entry back to normal lifecycle rules.
+    /// Returns Ok(true) if the entry was pinned.
+    pub fn unpin(&mut self, id: EntryId) -> Result<bool> {
+        self.set_pinned(id, false)
+    }
+
+    fn set_pinned(&mut self, id: EntryId, pinned: bool) -> Result<bool> {
+        let entry = self.entries.get_mut(&id)
+            .ok_or(DataBankError::EntryNotFound { id })?;
+        if entry.pinned == pinned {
+            return Ok(false);
+        }
+        entry.pinned = pinned;
+        entry.refresh_meta_checksum();
+        self.mark_mutated();
+        Ok(true)
+    }
+
+    /// Set an entry's temperature directly, as an explicit promotion or
+    /// demotion. Returns Ok(true) if it changed; a pinned entry is never
+    /// moved to a hotter tier, and Ok(false) is returned instead.
+    pub fn set_temperature(&mut self, id: EntryId, temperature: Temperature) -> Result<bool> {
+        let entry = self.entries.get_mut(&id)
+            .ok_or(DataBankError::EntryNotFound { id })?;
+        let from = entry.temperature;
+        if from == temperature || (entry.pinned && temperature.level() < from.level()) {
+            return Ok(false);
+        }
+        entry.temperature = temperature;
+        entry.refresh_meta_checksum();
+        self.retemper(from, temperature);
+        self.mark_mutated();
+        if let Some(observer) = &self.observer {
+            if temperature.level() > from.level() {
+                observer.on_promote(self.id, id, temperature);
+            } else {
+                observer.on_demote(self.id, id, temperature);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Set an entry's confidence score.
+    pub fn set_confidence(&mut self, id: EntryId, confidence: u8) -> Result<()> {
+        let entry = self.entries.get_mut(&id)
+            .ok_or(DataBankError::EntryNotFound { id })?;
+        if entry.confidence != confidence {
+            entry.confidence = confidence;
+            entry.refresh_meta_checksum();
+            self.mark_mutated();
+        }
+        Ok(())
+    }
+
+    /// Set or clear an entry's debug label.
+    pub fn set_debug_tag(&mut self, id: EntryId, tag: Option<String>) -> Result<()> {
+        let entry = self.entries.get_mut(&id)
+            .ok_or(DataBankError::EntryNotFound { id })?;
+        if entry.debug_tag != tag {
+            let was = entry_bytes(entry);
+            entry.debug_tag = tag;
+            self.bytes = self.bytes - was + entry_bytes(entry);
+            self.mark_mutated();
+        }
+        Ok(())
+    }
+
+    /// Replace an entry's vector, keeping its id, edges and metadata. The
+    /// checksum, vector index and exact-match hashes follow the new vector.
+    pub fn set_vector(&mut self, id: EntryId, vector: Vec<Signal>) -> Result<()> {
+        if vector.len() != self.config.vector_width as usize {
+            return Err(DataBankError::VectorWidthMismatch {
+                expected: self.config.vector_width,
+                got: vector.len() as u16,
+            });
+        }
+        let entry = self.entries.get_mut(&id)
+            .ok_or(DataBankError::EntryNotFound { id })?;
+        let was = entry_bytes(entry);
+        let old = core::mem::replace(&mut entry.vector, vector);
+        entry.refresh_checksum();
+        self.bytes = self.bytes - was + entry_bytes(entry);
+        let vector = entry.vector.clone();
+        self.forget_content_hash(id, &old);
+        self.vector_index.remove(id);
+        self.vector_index.insert(id, &vector);
+        self.remember_content_hash(id, &vector);
+        self.mark_mutated();
+        Ok(())
+    }
+
+    /// Lease an entry to `owner` for `ttl_ticks` ticks from `current_tick`,
+    /// protecting a read-modify-write sequence: until the lease expires or
+    /// is released, [`remove`](Self::remove) and eviction skip the entry.
+    ///
+    /// Leasing again as the same owner renews the lease. Fails with
+    /// `EntryLeased` while another owner's lease is unexpired and with
+    /// `EntryNotFound` for an unknown id. Leases are not persisted.
+    pub fn lease(
+        &mut self,
+        id: EntryId,
+        owner: u32,
+        ttl_ticks: u64,
+        current_tick: u64,
+    ) -> Result<LeaseToken> {
+        if !self.entries.contains_key(&id) {
+            return Err(DataBankError::EntryNotFound { id });
+        }
+        if let Some(&(holder, _)) = self.leases.get(&id) {
+            if holder != owner && self.is_leased(id, current_tick) {
+                return Err(DataBankError::EntryLeased { id, owner: holder });
+            }
+        }
+        self.leases
+            .insert(id, (owner, current_tick.saturating_add(ttl_ticks)));
+        Ok(LeaseToken { entry: id, owner })
+    }
+
+    /// Release a lease. Returns false if `token`'s owner no longer holds it.
+    pub fn release(&mut self, token: LeaseToken) -> bool {
+        match self.leases.get(&token.entry) {
+            Some(&(holder, _)) if holder == token.owner => {
+                self.leases.remove(&token.entry);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Owner of `id`'s lease and the tick it expires at, expired or not.
+    pub fn lease_holder(&self, id: EntryId) -> Option<(u32, u64)> {
+        self.leases.get(&id).copied()
+    }
+
+    /// Whether `id` is under a lease that has not expired at `current_tick`.
+    pub fn is_leased(&self, id: EntryId, current_tick: u64) -> bool {
+        self.leases
+            .get(&id)
+            .is_some_and(|&(_, expires)| current_tick < expires)
+    }
+
+    /// Drop leases that have expired at `current_tick`. Returns how many.
+    pub fn expire_leases(&mut self, current_tick: u64) -> usize {
+        let before = self.leases.len();
+        self.leases.retain(|_, &mut (_, expires)| current_tick < expires);
+        before - self.leases.len()
+    }
+
     /// Batch promote all eligible entries. Returns count promoted.
     pub fn consolidation_pass(
         &mut self,
@@ -317,8 +1953,14 @@ impl DataBank {
         let mut count = 0;
         for id in eligible {
             if let Some(entry) = self.entries.get_mut(&id) {
+                let from = entry.temperature;
                 if entry.promote() {
+                    self.temp_counts[from as usize] -= 1;
+                    self.temp_counts[entry.temperature as usize] += 1;
                     count += 1;
+                    if let Some(observer) = &self.observer {
+                        observer.on_promote(self.id, id, entry.temperature);
+                    }
                 }
             }
         }
@@ -337,8 +1979,14 @@ impl DataBank {
         let mut count = 0;
         for id in eligible {
             if let Some(entry) = self.entries.get_mut(&id) {
+                let from = entry.temperature;
                 if entry.demote() {
+                    self.temp_counts[from as usize] -= 1;
+                    self.temp_counts[entry.temperature as usize] += 1;
                     count += 1;
+                    if let Some(observer) = &self.observer {
+                        observer.on_demote(self.id, id, entry.temperature);
+                    }
                 }
             }
         }
@@ -348,52 +1996,290 @@ impl DataBank {
         count
     }
 
+    /// The ids [`evict_n`](Self::evict_n) would evict at `current_tick`, in
+    /// eviction order, without removing anything.
+    pub fn eviction_candidates(&self, count: usize, current_tick: u64) -> Vec<EntryId> {
+        let mut scored: Vec<(i64, EntryId)> = self.entries.iter()
+            .filter(|(&id, e)| !e.pinned && !self.is_leased(id, current_tick))
+            .map(|(&id, e)| (e.eviction_score(current_tick), id))
+            .collect();
+        // Ties go to the oldest (smallest) id, whatever the map's order
+        scored.sort_unstable();
+        scored.into_iter().take(count).map(|(_, id)| id).collect()
+    }
+
     /// Evict lowest-scoring entries. Returns count evicted.
+    /// Equal scores evict the oldest (smallest id) first, so the same bank
+    /// state always loses the same entries.
+    /// Pinned entries and entries under an unexpired lease are never
+    /// evicted; expired leases are pruned first.
     pub fn evict_n(&mut self, count: usize, current_tick: u64) -> usize {
-        let mut scored: Vec<(EntryId, i64)> = self.entries.iter()
-            .map(|(&id, e)| (id, e.eviction_score(current_tick)))
-            .collect();
-        scored.sort_by_key(|&(_, score)| score);
-        let to_evict = scored.iter().take(count).map(|&(id, _)| id).collect::<Vec<_>>();
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("evict_n", bank = %self.name, id = %self.id, count).entered();
+        self.expire_leases(current_tick);
+        let to_evict = self.eviction_candidates(count, current_tick);
         let mut evicted = 0;
         for id in to_evict {
-            if self.entries.remove(&id).is_some() {
+            if let Some(entry) = self.take_entry(id) {
+                self.forget_content_hash(id, &entry.vector);
                 self.vector_index.remove(id);
-                self.reverse_edges.remove(&id);
+                self.drop_reverse_list(id);
+                self.record_eviction(entry, current_tick, EvictionReason::Explicit);
                 evicted += 1;
+                if let Some(observer) = &self.observer {
+                    observer.on_evict(self.id, id);
+                }
             }
         }
         if evicted > 0 {
+            log::debug!("{}: evicted {} entries", self.label(), evicted);
+            #[cfg(feature = "metrics")]
+            self.metrics.record_evictions(evicted as u64);
             self.mark_mutated();
         }
         evicted
     }
 
+    /// The ids [`expire_entries`](Self::expire_entries) would remove at
+    /// `current_tick`, in ascending order, without removing anything.
+    pub fn expiry_candidates(&self, current_tick: u64) -> Vec<EntryId> {
+        let mut expired: Vec<EntryId> = self
+            .entries
+            .iter()
+            .filter(|(&id, e)| e.is_expired(current_tick) && !self.is_leased(id, current_tick))
+            .map(|(&id, _)| id)
+            .collect();
+        expired.sort_unstable();
+        expired
+    }
+
+    /// Remove every entry whose TTL has run out at `current_tick`, recording
+    /// each as [`EvictionReason::Expired`]. Pinned entries never expire (see
+    /// [`BankEntry::is_expired`]); entries under an unexpired lease are kept
+    /// until released.
+    /// Returns the removed ids in ascending order.
+    pub fn expire_entries(&mut self, current_tick: u64) -> Vec<EntryId> {
+        self.expire_leases(current_tick);
+        let expired = self.expiry_candidates(current_tick);
+        for &id in &expired {
+            if let Some(entry) = self.take_entry(id) {
+                self.forget_content_hash(id, &entry.vector);
+                self.vector_index.remove(id);
+                self.drop_reverse_list(id);
+                self.record_eviction(entry, current_tick, EvictionReason::Expired);
+                if let Some(observer) = &self.observer {
+                    observer.on_evict(self.id, id);
+                }
+            }
+        }
+        if !expired.is_empty() {
+            log::debug!("{}: expired {} entries", self.label(), expired.len());
+            #[cfg(feature = "metrics")]
+            self.metrics.record_evictions(expired.len() as u64);
+            self.mark_mutated();
+        }
+        expired
+    }
+
+    /// Verify one entry's vector and metadata against their stored CRC32s.
+    ///
+    /// Returns `EntryNotFound` if the id is unknown, `EntryCorrupted` (with
+    /// both checksums) if the vector was damaged, and
+    /// `EntryMetadataCorrupted` if its edges or lifecycle fields were.
+    pub fn validate_entry(&self, id: EntryId) -> Result<()> {
+        let entry = self
+            .entries
+            .get(&id)
+            .ok_or(DataBankError::EntryNotFound { id })?;
+        let actual = entry.compute_checksum();
+        if actual != entry.checksum {
+            return Err(DataBankError::EntryCorrupted {
+                bank: self.id,
+                entry: id,
+                expected: entry.checksum,
+                actual,
+            });
+        }
+        let actual = entry.compute_meta_checksum();
+        if actual != entry.meta_checksum {
+            return Err(DataBankError::EntryMetadataCorrupted {
+                bank: self.id,
+                entry: id,
+                expected: entry.meta_checksum,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// [`validate_entry`](Self::validate_entry) for every entry: one error
+    /// per damaged entry, in id order. Empty if the bank is intact.
+    pub fn validate_all(&self) -> Vec<DataBankError> {
+        let mut ids: Vec<EntryId> = self.entries.keys().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter().filter_map(|id| self.validate_entry(id).err()).collect()
+    }
+
+    /// Migrate every entry to a new vector width.
+    ///
+    /// Growing pads each vector with `pad`; shrinking truncates (and logs a
+    /// warning, since the dropped dimensions are lost). Checksums, the
+    /// config, and the similarity index are all updated. Rejects the
+    /// migration before touching anything if `new_width` is zero or any
+    /// entry fails its checksum — migrating would otherwise bless corrupted
+    /// data with a fresh checksum.
+    pub fn migrate_width(&mut self, new_width: u16, pad: Signal) -> Result<()> {
+        let old_width = self.config.vector_width;
+        if new_width == 0 {
+            return Err(DataBankError::VectorWidthMismatch {
+                expected: old_width,
+                got: new_width,
+            });
+        }
+        if new_width == old_width {
+            return Ok(());
+        }
+        for &id in self.entries.keys() {
+            self.validate_entry(id)?;
+        }
+
+        if new_width < old_width {
+            log::warn!(
+                "{}: shrinking vector width {} -> {}, truncating {} entries",
+                self.label(),
+                old_width,
+                new_width,
+                self.entries.len()
+            );
+        }
+
+        for entry in self.entries.values_mut() {
+            entry.vector.resize(new_width as usize, pad);
+            entry.checksum = entry.compute_checksum();
+        }
+        self.config.vector_width = new_width;
+        self.vector_index.rebuild(&self.entries);
+        self.rebuild_content_hashes();
+        self.mark_mutated();
+        Ok(())
+    }
+
+    /// Shift every stored tick from one logical-clock frame into another.
+    ///
+    /// Tick `old_reference` in the saved frame maps to `new_reference` in the
+    /// new one; entry created/accessed/expiry ticks, edge ticks, and the last
+    /// persist tick all move by the same offset, so relative recency (and
+    /// therefore eviction order) and remaining TTLs are preserved across a
+    /// clock reset. Ticks
+    /// that would fall below zero clamp to 0.
+    pub fn rebase_ticks(&mut self, old_reference: u64, new_reference: u64) {
+        if old_reference == new_reference {
+            return;
+        }
+        let shift = |t: u64| {
+            if new_reference >= old_reference {
+                t.saturating_add(new_reference - old_reference)
+            } else {
+                t.saturating_sub(old_reference - new_reference)
+            }
+        };
+        for entry in self.entries.values_mut() {
+            entry.created_tick = shift(entry.created_tick);
+            entry.last_accessed_tick = shift(entry.last_accessed_tick);
+            entry.expires_at_tick = entry.expires_at_tick.map(shift);
+            for edge in &mut entry.edges {
+                edge.created_tick = shift(edge.created_tick);
+            }
+            entry.refresh_meta_checksum();
+        }
+        self.last_persist_tick = shift(self.last_persist_tick);
+        self.mark_mutated();
+    }
+
     /// Compact internal data structures after mass eviction.
     pub fn compact(&mut self) {
         self.vector_index.rebuild(&self.entries);
+        self.rebuild_content_hashes();
         // Clean up reverse edges pointing to removed entries
-        let valid_ids: std::collections::HashSet<EntryId> = self.entries.keys().copied().collect();
+        let valid_ids: HashSet<EntryId> = self.entries.keys().copied().collect();
         self.reverse_edges.retain(|id, _| valid_ids.contains(id));
+        self.bytes = self.recount_bytes();
+        self.removals_since_compact = 0;
     }
 
-    fn mark_mutated(&mut self) {
-        self.mutations_since_persist = self.mutations_since_persist.saturating_add(1);
-        self.dirty = true;
-    }
+    /// Run the compaction `auto_compact_percent` asks for, if removals have
+    /// reached it. Called at the start of a mutation so eviction loops never
+    /// pay for it inline.
+    fn compact_if_due(&mut self) {
+        let Some(threshold) = self.config.auto_compact_threshold() else {
+            return;
+        };
+        if self.removals_since_compact >= threshold {
+            log::debug!(
+                "{}: auto-compacting after {} removals",
+                self.label(),
+                self.removals_since_compact
+            );
+            self.compact();
+        }
+    }
+
+    fn mark_mutated(&mut self) {
+        self.mutations_since_persist = self.mutations_since_persist.saturating_add(1);
+        self.generation += 1;
+        self.dirty = true;
+    }
+}
+
+/// xxh3 hash of a vector's raw signal bytes (polarity, magnitude, multiplier).
+fn vector_hash(vector: &[Signal]) -> u64 {
+    let mut bytes = Vec::with_capacity(vector.len() * 3);
+    for s in vector {
+        bytes.push(s.polarity as u8);
+        bytes.push(s.magnitude);
+        bytes.push(s.multiplier);
+    }
+    xxhash_rust::xxh3::xxh3_64(&bytes)
+}
+
+/// Estimated bytes of one entry in the entry map: its key and control byte
+/// plus [`BankEntry::approx_bytes`].
+fn entry_bytes(entry: &BankEntry) -> usize {
+    MAP_SLOT_BYTES + entry.approx_bytes()
+}
+
+/// Estimated bytes of one content-hash bucket: its key, control byte, `Vec`
+/// header and elements.
+fn hash_bucket_bytes(ids: &Vec<EntryId>) -> usize {
+    core::mem::size_of::<u64>()
+        + 1
+        + core::mem::size_of::<Vec<EntryId>>()
+        + ids.capacity() * core::mem::size_of::<EntryId>()
+}
+
+/// Estimated bytes for one reverse-edge list, including its map slot.
+fn reverse_list_bytes(list: &Vec<(BankRef, EdgeType)>) -> usize {
+    MAP_SLOT_BYTES
+        + core::mem::size_of::<Vec<(BankRef, EdgeType)>>()
+        + list.capacity() * core::mem::size_of::<(BankRef, EdgeType)>()
 }
 
-/// Create a VectorIndex from the config's IndexType.
-fn create_index(index_type: &IndexType) -> Box<dyn VectorIndex> {
-    match index_type {
-        IndexType::BruteForce => Box::new(crate::index::BruteForceIndex),
-        IndexType::Ivf { k, nprobe } => Box::new(IvfIndex::new(*k, *nprobe)),
+/// Create a VectorIndex from the config's IndexType, scoring with its
+/// metric.
+fn create_index(config: &BankConfig) -> Box<dyn VectorIndex> {
+    match config.index_type {
+        IndexType::BruteForce => Box::new(crate::index::BruteForceIndex::new(config.metric)),
+        IndexType::Ivf { k, nprobe } => {
+            Box::new(IvfIndex::new(k, nprobe).with_metric(config.metric))
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::similarity::Metric;
 
     fn make_config(width: u16) -> BankConfig {
         BankConfig {
@@ -445,50 +2331,850 @@ mod tests {
     }
 
     #[test]
-    fn eviction_on_capacity() {
-        let mut bank = make_bank(); // max_entries = 10
-        for i in 0..10 {
-            bank.insert(make_vector(8), Temperature::Hot, i).unwrap();
-        }
-        assert_eq!(bank.len(), 10);
-        // 11th insert triggers eviction
-        let result = bank.insert(make_vector(8), Temperature::Hot, 100);
-        assert!(result.is_ok());
-        assert_eq!(bank.len(), 10); // still 10 after eviction + insert
+    fn eviction_on_capacity() {
+        let mut bank = make_bank(); // max_entries = 10
+        for i in 0..10 {
+            bank.insert(make_vector(8), Temperature::Hot, i).unwrap();
+        }
+        assert_eq!(bank.len(), 10);
+        // 11th insert triggers eviction
+        let result = bank.insert(make_vector(8), Temperature::Hot, 100);
+        assert!(result.is_ok());
+        assert_eq!(bank.len(), 10); // still 10 after eviction + insert
+    }
+
+    #[test]
+    fn unlimited_bank_never_evicts() {
+        let config = BankConfig {
+            max_entries: 0,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "test.archive".into(), config);
+        for i in 0..5000 {
+            bank.insert(make_vector(8), Temperature::Hot, i).unwrap();
+        }
+        assert_eq!(bank.len(), 5000);
+        assert_eq!(bank.stats().capacity, None);
+
+        // Explicit eviction still works
+        assert_eq!(bank.evict_n(10, 6000), 10);
+        assert_eq!(bank.len(), 4990);
+    }
+
+    #[test]
+    fn query_sparse_returns_results() {
+        let mut bank = make_bank();
+        let v = make_vector(8);
+        bank.insert(v.clone(), Temperature::Hot, 0).unwrap();
+
+        let results = bank.query_sparse(&v, 1).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].score > 200); // should be near-identical match
+    }
+
+    #[test]
+    fn query_sparse_rejects_wrong_width_unless_lenient() {
+        let mut bank = make_bank();
+        let v = make_vector(8);
+        let id = bank.insert(v.clone(), Temperature::Hot, 0).unwrap();
+
+        let mut wide = v.clone();
+        wide.push(Signal::new_raw(1, 255, 1));
+        match bank.query_sparse(&wide, 1) {
+            Err(DataBankError::VectorWidthMismatch { expected: 8, got: 9 }) => {}
+            other => panic!("expected VectorWidthMismatch, got {:?}", other),
+        }
+        assert!(bank.query_sparse(&v[..4], 1).is_err());
+
+        let lenient = bank.query_sparse_with(&wide, 1, QueryWidth::Lenient).unwrap();
+        assert_eq!(lenient[0].entry_id, id);
+        assert_eq!(bank.query_sparse_tagged(&wide, 1)[0].entry_id, id);
+    }
+
+    #[test]
+    fn exact_match_fast_path_returns_max_score() {
+        let config = BankConfig {
+            exact_match_fast_path: true,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "test.exact".into(), config);
+        let target = make_vector(8);
+        let mut other = make_vector(8);
+        other[0] = Signal::new_raw(-1, 50, 1);
+        bank.insert(other, Temperature::Hot, 0).unwrap();
+        let id = bank.insert(target.clone(), Temperature::Hot, 0).unwrap();
+
+        assert!(bank.exact_matches_into(&target, 1, &mut Vec::new()), "fast path should hit");
+        let results = bank.query_sparse(&target, 1).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry_id, id);
+        assert_eq!(results[0].score, 256);
+
+        // Fewer identical vectors than top_k: the scan fills the rest
+        assert!(!bank.exact_matches_into(&target, 5, &mut Vec::new()));
+        let results = bank.query_sparse(&target, 5).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!((results[0].entry_id, results[0].score), (id, 256));
+        let twin = bank.insert(target.clone(), Temperature::Hot, 0).unwrap();
+        let mut hits = Vec::new();
+        assert!(bank.exact_matches_into(&target, 2, &mut hits));
+        assert_eq!(hits.iter().map(|r| r.entry_id).collect::<Vec<_>>(), [id, twin]);
+
+        // Removal clears the hash entry; query falls back to scanning
+        bank.remove(id);
+        bank.remove(twin);
+        assert!(!bank.exact_matches_into(&target, 1, &mut Vec::new()));
+    }
+
+    #[test]
+    fn query_sparse_into_reuses_buffer_capacity() {
+        let mut bank = DataBank::new(BankId::from_raw(1), "scratch".into(), make_config(8));
+        for i in 0..8u8 {
+            let vector = (0..8)
+                .map(|d| Signal::new_raw(if d % 2 == 0 { 1 } else { -1 }, i * 20 + d + 1, 1))
+                .collect();
+            bank.insert(vector, Temperature::Hot, 0).unwrap();
+        }
+        let query = make_vector(8);
+        let expected: Vec<_> = bank
+            .query_sparse(&query, 5)
+            .unwrap()
+            .iter()
+            .map(|r| (r.entry_id, r.score))
+            .collect();
+
+        let mut scratch = Vec::new();
+        let mut capacity = 0;
+        for _ in 0..4 {
+            bank.query_sparse_into(&query, 5, &mut scratch);
+            assert!(scratch.capacity() >= capacity);
+            capacity = scratch.capacity();
+            let got: Vec<_> = scratch.iter().map(|r| (r.entry_id, r.score)).collect();
+            assert_eq!(got, expected);
+        }
+        let ptr = scratch.as_ptr();
+        bank.query_sparse_into(&query, 5, &mut scratch);
+        assert_eq!(scratch.as_ptr(), ptr, "buffer should not be reallocated");
+    }
+
+    #[test]
+    fn query_trace_reports_path_and_candidates() {
+        let mut bank = make_bank();
+        for i in 0..5 {
+            bank.insert(make_vector(8), Temperature::Hot, i).unwrap();
+        }
+        let mut trace = QueryTrace::default();
+        let results = bank.query_sparse_traced(&make_vector(8), 2, &mut trace);
+        assert_eq!(results.len(), 2);
+        // A fresh IVF bank has no centroids until its index is rebuilt
+        assert_eq!(trace.path, QueryPath::IvfFallback);
+        assert!(trace.is_fallback());
+        assert_eq!(trace.candidates_scanned, 5);
+        assert_eq!(trace.buckets_probed, 0);
+
+        let config = BankConfig {
+            exact_match_fast_path: true,
+            ..make_config(8)
+        };
+        let mut exact = DataBank::new(BankId::from_raw(9), "exact".into(), config);
+        exact.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        exact.query_sparse_traced(&make_vector(8), 1, &mut trace);
+        assert_eq!(trace.path, QueryPath::ExactMatch);
+        assert_eq!(trace.candidates_scanned, 1);
+    }
+
+    #[test]
+    fn min_confidence_query_skips_unreliable_entries() {
+        let mut bank = make_bank();
+        let mut ids = Vec::new();
+        for confidence in [10u8, 100, 200] {
+            let id = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+            bank.get_mut(id).unwrap().confidence = confidence;
+            ids.push(id);
+        }
+
+        // Identical vectors tie on score, so the smaller id ranks first
+        let results = bank.query_sparse_min_confidence(&make_vector(8), 10, 100).unwrap();
+        let found: Vec<EntryId> = results.iter().map(|r| r.entry_id).collect();
+        assert_eq!(found, vec![ids[1], ids[2]]);
+        assert!(results.iter().all(|r| r.score > 250));
+        let top = bank.query_sparse_min_confidence(&make_vector(8), 1, 0).unwrap();
+        assert_eq!(top[0].entry_id, ids[0]);
+
+        assert_eq!(bank.query_sparse_min_confidence(&make_vector(8), 10, 201).unwrap().len(), 0);
+        assert_eq!(bank.query_sparse_min_confidence(&make_vector(8), 10, 0).unwrap().len(), 3);
+        assert!(matches!(
+            bank.query_sparse_min_confidence(&make_vector(9), 10, 0),
+            Err(DataBankError::VectorWidthMismatch { expected: 8, got: 9 })
+        ));
+    }
+
+    #[test]
+    fn insert_hook_sets_derived_fields() {
+        let mut bank = make_bank();
+        bank.set_insert_hook(Box::new(|entry: &mut BankEntry| {
+            entry.confidence = 200;
+            entry.debug_tag = Some(format!("t{}", entry.created_tick));
+            entry.id = EntryId::from_raw(0); // restored by insert
+        }));
+
+        let first = bank.insert(make_vector(8), Temperature::Hot, 7).unwrap();
+        let second = bank.insert(make_vector(8), Temperature::Hot, 9).unwrap();
+        for (id, tag) in [(first, "t7"), (second, "t9")] {
+            let entry = bank.get(id).unwrap();
+            assert_eq!(entry.confidence, 200);
+            assert_eq!(entry.debug_tag.as_deref(), Some(tag));
+            assert_eq!(entry.id, id);
+            assert!(entry.validate());
+        }
+
+        // A hook that breaks the width fails the insert and adds nothing
+        bank.set_insert_hook(Box::new(|entry: &mut BankEntry| {
+            entry.vector.pop();
+        }));
+        assert!(bank.insert(make_vector(8), Temperature::Hot, 10).is_err());
+        assert_eq!(bank.len(), 2);
+
+        assert!(bank.take_insert_hook().is_some());
+        let plain = bank.insert(make_vector(8), Temperature::Hot, 11).unwrap();
+        assert_eq!(bank.get(plain).unwrap().confidence, 128);
+    }
+
+    #[test]
+    fn created_range_filter_is_inclusive_on_both_index_paths() {
+        for index_type in [IndexType::BruteForce, IndexType::Ivf { k: 2, nprobe: 2 }] {
+            let config = BankConfig {
+                index_type,
+                ..make_config(8)
+            };
+            let mut bank = DataBank::new(BankId::from_raw(1), "episodic".into(), config);
+            let mut ids = Vec::new();
+            for tick in [5_000, 10_000, 11_000, 12_000, 15_000] {
+                ids.push(bank.insert(make_vector(8), Temperature::Hot, tick).unwrap());
+            }
+            bank.get_mut(ids[2]).unwrap().temperature = Temperature::Cold;
+            // Gives the IVF index centroids so its bucket path is exercised
+            bank.compact();
+
+            let filter = QueryFilter::created_between(10_000, 12_000);
+            let mut hits: Vec<EntryId> = bank
+                .query_sparse_filtered(&make_vector(8), 10, &filter)
+                .iter()
+                .map(|r| r.entry_id)
+                .collect();
+            hits.sort();
+            assert_eq!(hits, ids[1..4]);
+
+            let cold = filter.with_temperatures([Temperature::Cold]);
+            let hits = bank.query_sparse_filtered(&make_vector(8), 10, &cold);
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].entry_id, ids[2]);
+
+            let empty = QueryFilter::created_between(12_001, 14_999);
+            assert!(bank.query_sparse_filtered(&make_vector(8), 10, &empty).is_empty());
+        }
+    }
+
+    #[test]
+    fn leased_entry_survives_eviction_until_expiry() {
+        let mut bank = make_bank(); // max_entries = 10
+        let vector = make_vector(8);
+        let leased = bank.insert(vector.clone(), Temperature::Cold, 0).unwrap();
+        for tick in 1..10 {
+            bank.insert(vector.clone(), Temperature::Hot, tick).unwrap();
+        }
+        let token = bank.lease(leased, 1, 20, 10).unwrap();
+        assert!(matches!(
+            bank.lease(leased, 2, 5, 11),
+            Err(DataBankError::EntryLeased { owner: 1, .. })
+        ));
+
+        // The coldest entry would be the victim, but its lease protects it
+        // from capacity pressure, explicit eviction, and removal alike
+        bank.insert(vector.clone(), Temperature::Hot, 12).unwrap();
+        assert_eq!(bank.evict_n(3, 15), 3);
+        assert!(bank.remove(leased).is_none());
+        assert!(bank.get(leased).is_some());
+
+        // Expired at tick 30: evictable again, and another owner may lease
+        assert!(bank.is_leased(leased, 29));
+        assert!(!bank.is_leased(leased, 30));
+        assert!(bank.lease(leased, 2, 5, 30).is_ok());
+        assert!(!bank.release(token));
+        assert!(bank.evict_n(10, 35) > 0);
+        assert!(bank.get(leased).is_none());
+        assert_eq!(bank.lease_holder(leased), None);
+    }
+
+    #[test]
+    fn temperature_counters_track_every_mutation_path() {
+        let config = BankConfig {
+            max_entries: 6,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "temps".into(), config);
+        let check = |bank: &DataBank| {
+            assert_eq!(bank.temperature_counts(), bank.recount_temperatures());
+            assert_eq!(bank.temperature_counts().iter().sum::<u32>() as usize, bank.len());
+        };
+        let mut ids = Vec::new();
+        for (tick, t) in [Temperature::Hot, Temperature::Hot, Temperature::Warm, Temperature::Cool]
+            .into_iter()
+            .enumerate()
+        {
+            ids.push(bank.insert(make_vector(8), t, tick as u64).unwrap());
+        }
+        check(&bank);
+        assert_eq!(bank.count_by_temperature(Temperature::Hot), 2);
+
+        // promote / demote, single and batched
+        assert!(bank.promote_entry(ids[0]).unwrap());
+        assert!(bank.demote_entry(ids[3]).unwrap());
+        check(&bank);
+        bank.get_mut(ids[1]).unwrap().touch(5);
+        assert_eq!(bank.consolidation_pass(100, 1, 0), 1);
+        check(&bank);
+        bank.get_mut(ids[2]).unwrap().confidence = 0;
+        assert_eq!(bank.demotion_pass(10), 1);
+        check(&bank);
+
+        // direct edits through the guard
+        bank.get_mut(ids[0]).unwrap().temperature = Temperature::Set;
+        check(&bank);
+        assert_eq!(bank.count_by_temperature(Temperature::Set), 1);
+
+        // removal paths: remove, namespace clear, TTL expiry, capacity and
+        // explicit eviction
+        bank.remove(ids[3]).unwrap();
+        check(&bank);
+        let ns = bank
+            .insert_in_namespace(make_vector(8), Temperature::Cold, 6, 3)
+            .unwrap();
+        assert_eq!(bank.clear_namespace(3), [ns]);
+        check(&bank);
+        bank.insert_ttl(make_vector(8), Temperature::Warm, 7, 1).unwrap();
+        assert_eq!(bank.expire_entries(8).len(), 1);
+        check(&bank);
+        for tick in 10..16 {
+            bank.insert(make_vector(8), Temperature::Hot, tick).unwrap();
+        }
+        assert_eq!(bank.len(), 6);
+        check(&bank);
+        assert_eq!(bank.evict_n(2, 20), 2);
+        check(&bank);
+
+        // restore through the codec recomputes the same counts
+        let decoded = crate::codec::decode(&crate::codec::encode(&bank).unwrap()).unwrap();
+        assert_eq!(decoded.temperature_counts(), bank.temperature_counts());
+        assert_eq!(decoded.stats().temperatures, bank.temperature_counts());
+    }
+
+    #[test]
+    fn ttl_entry_is_skipped_after_expiry_and_purged() {
+        let config = BankConfig {
+            audit_evictions: 4,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "ttl".into(), config);
+        let v = make_vector(8);
+        let durable = bank.insert(v.clone(), Temperature::Hot, 0).unwrap();
+        let transient = bank.insert_ttl(v.clone(), Temperature::Hot, 0, 10).unwrap();
+        assert_eq!(bank.get(transient).unwrap().expires_at_tick, Some(10));
+
+        let ids = |results: Vec<QueryResult>| -> Vec<EntryId> {
+            let mut ids: Vec<_> = results.iter().map(|r| r.entry_id).collect();
+            ids.sort_unstable();
+            ids
+        };
+        assert_eq!(ids(bank.query_sparse_at(&v, 5, 9).unwrap()), [durable, transient]);
+        assert_eq!(ids(bank.query_sparse_at(&v, 5, 10).unwrap()), [durable]);
+        assert!(bank.query_sparse_at(&v[..4], 5, 10).is_err());
+
+        assert!(bank.expire_entries(9).is_empty());
+        assert_eq!(bank.expire_entries(10), [transient]);
+        assert!(bank.get(transient).is_none());
+        assert_eq!(bank.len(), 1);
+        assert_eq!(bank.eviction_log()[0].reason, EvictionReason::Expired);
+        assert!(bank.expire_entries(100).is_empty());
+        assert_eq!(bank.ttl_entries, 0);
+    }
+
+    #[test]
+    fn pinned_ttl_entry_outlives_its_expiry() {
+        let mut bank = make_bank();
+        let v = make_vector(8);
+        let prior = bank.insert_ttl(v.clone(), Temperature::Hot, 0, 10).unwrap();
+        bank.pin(prior).unwrap();
+
+        assert!(bank.expire_entries(20).is_empty());
+        assert_eq!(bank.query_sparse_at(&v, 5, 20).unwrap()[0].entry_id, prior);
+
+        // Unpinned, the TTL it still carries applies again
+        bank.unpin(prior).unwrap();
+        assert!(bank.query_sparse_at(&v, 5, 20).unwrap().is_empty());
+        assert_eq!(bank.expire_entries(20), [prior]);
+    }
+
+    #[test]
+    fn dense_matrix_rows_follow_entry_id_order() {
+        let mut bank = make_bank();
+        assert_eq!(bank.to_dense_matrix(), (Vec::new(), Vec::new(), 8));
+        let second_row = vec![Signal::new_raw(-1, 5, 2); 8];
+        let first = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        let second = bank.insert(second_row, Temperature::Hot, 1).unwrap();
+
+        let (matrix, ids, width) = bank.to_dense_matrix();
+        assert_eq!(width, 8);
+        assert_eq!(ids, [first, second]);
+        assert_eq!(matrix.len(), 16);
+        assert_eq!(&matrix[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(&matrix[8..], [-10; 8]);
+    }
+
+    #[test]
+    fn dimension_activity_counts_non_zero_signals() {
+        let mut bank = make_bank();
+        for i in 0..5 {
+            let mut v = make_vector(8);
+            v[7] = if i < 2 { Signal::new_raw(-1, 3, 1) } else { Signal::ZERO };
+            v[6] = Signal::new_raw(1, 0, 1); // zero magnitude is inactive
+            bank.insert(v, Temperature::Hot, 0).unwrap();
+        }
+        assert_eq!(bank.dimension_activity(), [5, 5, 5, 5, 5, 5, 0, 2]);
+    }
+
+    #[test]
+    fn namespaces_scope_queries_stats_and_teardown() {
+        let mut bank = make_bank();
+        let shared = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        let a: Vec<EntryId> = (0..2)
+            .map(|t| bank.insert_in_namespace(make_vector(8), Temperature::Hot, t, 1).unwrap())
+            .collect();
+        let b = bank.insert_in_namespace(make_vector(8), Temperature::Hot, 0, 2).unwrap();
+        assert_eq!(bank.get(shared).unwrap().namespace, 0);
+        assert_eq!(bank.get(b).unwrap().namespace, 2);
+
+        let mut hits: Vec<EntryId> = bank
+            .query_sparse_filtered(&make_vector(8), 10, &QueryFilter::in_namespace(1))
+            .iter()
+            .map(|r| r.entry_id)
+            .collect();
+        hits.sort();
+        assert_eq!(hits, a);
+        let b_only = QueryFilter::created_between(0, 0).with_namespace(2);
+        let hits = bank.query_sparse_filtered(&make_vector(8), 10, &b_only);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entry_id, b);
+
+        let counts: Vec<(u16, usize)> = bank.stats().namespaces.into_iter().collect();
+        assert_eq!(counts, vec![(0, 1), (1, 2), (2, 1)]);
+
+        bank.pin(a[0]).unwrap();
+        assert_eq!(bank.clear_namespace(1), a);
+        assert!(bank.clear_namespace(1).is_empty());
+        assert_eq!(bank.len(), 2);
+        assert!(!bank.stats().namespaces.contains_key(&1));
+    }
+
+    #[test]
+    fn near_duplicate_eviction_keeps_the_unique_entry() {
+        let unique: Vec<Signal> = (0..8)
+            .map(|i| Signal::new_raw(if i % 2 == 0 { 1 } else { -1 }, 90, 1))
+            .collect();
+        let fill = |mode| {
+            let config = BankConfig {
+                max_entries: 4,
+                eviction_mode: mode,
+                ..make_config(8)
+            };
+            let mut bank = DataBank::new(BankId::from_raw(1), "dedup".into(), config);
+            // The unique entry is the oldest, so it has the lowest score
+            let unique_id = bank.insert(unique.clone(), Temperature::Hot, 0).unwrap();
+            for tick in 1..=3 {
+                bank.insert(make_vector(8), Temperature::Hot, tick).unwrap();
+            }
+            let mut fresh = make_vector(8);
+            fresh.reverse();
+            bank.insert(fresh, Temperature::Hot, 4).unwrap();
+            (bank, unique_id)
+        };
+
+        let (bank, unique_id) = fill(EvictionMode::Score);
+        assert!(bank.get(unique_id).is_none());
+
+        let (bank, unique_id) = fill(EvictionMode::NearDuplicatesFirst { min_similarity: 250 });
+        assert!(bank.get(unique_id).is_some());
+        assert_eq!(bank.len(), 4);
+        let copies = bank
+            .entries()
+            .filter(|(_, e)| e.vector == make_vector(8))
+            .count();
+        assert_eq!(copies, 2);
+    }
+
+    #[test]
+    fn contrastive_query_suppresses_inhibitor_matches() {
+        let mut bank = make_bank();
+        let on = Signal::new_raw(1, 100, 1);
+        let cue = [on, on, on, on, Signal::ZERO, Signal::ZERO, Signal::ZERO, Signal::ZERO];
+        let inhibit = [Signal::ZERO, Signal::ZERO, Signal::ZERO, Signal::ZERO, on, on, on, on];
+
+        // Matches the cue perfectly, but also the inhibitor
+        let both = bank.insert(vec![on; 8], Temperature::Hot, 0).unwrap();
+        // Matches the cue on half its dimensions, nothing of the inhibitor
+        let mut partial = vec![Signal::ZERO; 8];
+        partial[0] = on;
+        partial[1] = on;
+        let partial = bank.insert(partial, Temperature::Hot, 0).unwrap();
+
+        let plain = bank.query_contrastive(&cue, &inhibit, 0, 2);
+        assert_eq!(plain[0].entry_id, both);
+        assert_eq!(plain[0].score, 256);
+
+        let results = bank.query_contrastive(&cue, &inhibit, 200, 2);
+        let order: Vec<EntryId> = results.iter().map(|r| r.entry_id).collect();
+        assert_eq!(order, vec![partial, both]);
+        assert_eq!(results[1].score, 256 - 200);
+
+        let full = bank.query_contrastive(&cue, &inhibit, 255, 2);
+        assert_eq!(full[1].score, 0);
+        assert!(bank.query_contrastive(&cue, &inhibit, 255, 0).is_empty());
+    }
+
+    #[test]
+    fn least_similar_ranks_opposite_polarity_first() {
+        let mut bank = make_bank();
+        let query = make_vector(8);
+        let opposite: Vec<Signal> = query
+            .iter()
+            .map(|s| Signal::new_raw(-s.polarity, s.magnitude, s.multiplier))
+            .collect();
+        let mut near = query.clone();
+        near[7] = opposite[7];
+        let same = bank.insert(query.clone(), Temperature::Hot, 0).unwrap();
+        let near = bank.insert(near, Temperature::Hot, 0).unwrap();
+        let opposite = bank.insert(opposite, Temperature::Hot, 0).unwrap();
+
+        let least = bank.query_least_similar(&query, 3);
+        let order: Vec<EntryId> = least.iter().map(|r| r.entry_id).collect();
+        assert_eq!(order, vec![opposite, near, same]);
+        assert!(least[0].score < 0);
+
+        // Below the identical entry's score: the near miss ranks first
+        let below = bank.query_below(&query, least[2].score, 5);
+        let order: Vec<EntryId> = below.iter().map(|r| r.entry_id).collect();
+        assert_eq!(order, vec![near, opposite]);
+        assert!(bank.query_below(&query, -256, 5).is_empty());
+        assert!(bank.query_least_similar(&query, 0).is_empty());
+    }
+
+    #[test]
+    fn query_sparse_tagged_carries_bank_identity() {
+        let mut bank = make_bank();
+        let v = make_vector(8);
+        let mut other = make_vector(8);
+        other[0] = Signal::new_raw(-1, 200, 1);
+        let id = bank.insert(v.clone(), Temperature::Hot, 0).unwrap();
+        bank.insert(other, Temperature::Hot, 0).unwrap();
+
+        let plain = bank.query_sparse(&v, 5).unwrap();
+        let tagged = bank.query_sparse_tagged(&v, 5);
+        assert_eq!(tagged.len(), plain.len());
+        assert_eq!(tagged[0].entry_id, id);
+        for (t, p) in tagged.iter().zip(&plain) {
+            assert_eq!(t.bank_id, bank.id);
+            assert_eq!(t.bank_name, "test.bank");
+            assert_eq!(t.entry_id, p.entry_id);
+            assert_eq!(t.score, p.score);
+        }
+        assert!(tagged[0].normalized_score >= tagged[1].normalized_score);
+    }
+
+    #[test]
+    fn evoke_blends_between_stored_patterns() {
+        let config = BankConfig {
+            index_type: crate::ivf::IndexType::BruteForce,
+            ..make_config(4)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "test.evoke".into(), config);
+        let sig = |m: u8| Signal::new_raw(1, m, 1);
+        let a = vec![sig(200), sig(20), sig(100), sig(10)];
+        let b = vec![sig(20), sig(200), sig(100), sig(10)];
+        bank.insert(a.clone(), Temperature::Hot, 0).unwrap();
+        bank.insert(b.clone(), Temperature::Hot, 0).unwrap();
+
+        // Cue equidistant from both patterns
+        let cue = vec![sig(110), sig(110), Signal::ZERO, Signal::ZERO];
+        let evoked = bank.evoke(&cue, 2);
+        assert_eq!(evoked.len(), 4);
+        for i in 0..4 {
+            let lo = a[i].current().min(b[i].current());
+            let hi = a[i].current().max(b[i].current());
+            let v = evoked[i].current();
+            assert!(lo <= v && v <= hi, "dim {i}: {v} not in [{lo}, {hi}]");
+        }
+        // Strictly between on the dimensions where the patterns differ
+        assert!(evoked[0].current() > 20 && evoked[0].current() < 200);
+        assert!(evoked[1].current() > 20 && evoked[1].current() < 200);
+
+        // Nothing to evoke from an empty bank
+        let empty = DataBank::new(BankId::from_raw(2), "empty".into(), make_config(4));
+        assert!(empty.evoke(&cue, 2).iter().all(|s| s.current() == 0));
+    }
+
+    #[test]
+    fn migrate_width_pads_and_keeps_queries_working() {
+        let mut bank = DataBank::new(BankId::from_raw(1), "test.migrate".into(), make_config(4));
+        let v = make_vector(4);
+        let id = bank.insert(v.clone(), Temperature::Hot, 0).unwrap();
+
+        bank.migrate_width(8, Signal::ZERO).unwrap();
+        assert_eq!(bank.config().vector_width, 8);
+        let entry = bank.get(id).unwrap();
+        assert_eq!(entry.vector.len(), 8);
+        assert_eq!(&entry.vector[..4], v.as_slice());
+        assert!(entry.validate());
+
+        // Old 4-wide cue padded with zeros still recalls the entry
+        let mut cue = v.clone();
+        cue.resize(8, Signal::ZERO);
+        let results = bank.query_sparse(&cue, 1).unwrap();
+        assert_eq!(results[0].entry_id, id);
+        assert!(results[0].score > 250);
+
+        // New inserts must use the new width
+        assert!(bank.insert(make_vector(4), Temperature::Hot, 1).is_err());
+        assert!(bank.insert(make_vector(8), Temperature::Hot, 1).is_ok());
+    }
+
+    #[test]
+    fn migrate_width_rejects_corrupt_entries() {
+        let mut bank = DataBank::new(BankId::from_raw(1), "test.migrate".into(), make_config(4));
+        let id = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        bank.get_mut(id).unwrap().vector[0] = Signal::new_raw(-1, 9, 1);
+        assert!(matches!(
+            bank.migrate_width(8, Signal::ZERO),
+            Err(DataBankError::EntryCorrupted { entry, .. }) if entry == id
+        ));
+        assert_eq!(bank.config().vector_width, 4);
+        assert_eq!(bank.get(id).unwrap().vector.len(), 4);
+    }
+
+    #[test]
+    fn validate_entry_reports_both_checksums() {
+        let mut bank = make_bank();
+        let id = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        assert!(bank.validate_entry(id).is_ok());
+        assert!(matches!(
+            bank.validate_entry(EntryId::from_raw(1)),
+            Err(DataBankError::EntryNotFound { .. })
+        ));
+
+        let stored = bank.get(id).unwrap().checksum;
+        bank.get_mut(id).unwrap().vector[3] = Signal::new_raw(-1, 77, 2);
+        let actual = bank.get(id).unwrap().compute_checksum();
+        match bank.validate_entry(id) {
+            Err(DataBankError::EntryCorrupted {
+                bank: b,
+                entry,
+                expected,
+                actual: got,
+            }) => {
+                assert_eq!(b, bank.id);
+                assert_eq!(entry, id);
+                assert_eq!(expected, stored);
+                assert_eq!(got, actual);
+            }
+            other => panic!("expected EntryCorrupted, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn mutating_paths_keep_metadata_checksums_current() {
+        let mut bank = make_bank();
+        let a = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        let b = bank.insert(make_vector(8), Temperature::Hot, 1).unwrap();
+        let edge = Edge {
+            edge_type: EdgeType::RelatedTo,
+            target: BankRef { bank: bank.id, entry: b },
+            weight: 9,
+            created_tick: 2,
+        };
+        bank.add_edge(a, edge).unwrap();
+        bank.set_temperature(a, Temperature::Cool).unwrap();
+        bank.set_confidence(a, 40).unwrap();
+        bank.pin(b).unwrap();
+        bank.get_mut(b).unwrap().touch(7);
+        bank.get_mut(a).unwrap().promote();
+        bank.rebase_ticks(0, 100);
+        assert!(bank.validate_all().is_empty());
+
+        bank.entries.get_mut(&a).unwrap().edges[0].weight = 200;
+        match bank.validate_entry(a) {
+            Err(DataBankError::EntryMetadataCorrupted { entry, expected, actual, .. }) => {
+                assert_eq!(entry, a);
+                assert_ne!(expected, actual);
+            }
+            other => panic!("expected EntryMetadataCorrupted, got {other:?}"),
+        }
+        assert_eq!(bank.validate_all().len(), 1);
+    }
+
+    #[test]
+    fn add_edge_and_retrieve() {
+        let mut bank = make_bank();
+        let id1 = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+
+        let target = BankRef {
+            bank: BankId::from_raw(2),
+            entry: EntryId::from_raw(999),
+        };
+        let edge = Edge {
+            edge_type: EdgeType::RelatedTo,
+            target,
+            weight: 200,
+            created_tick: 0,
+        };
+        bank.add_edge(id1, edge).unwrap();
+
+        let edges = bank.edges_from(id1);
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].edge_type, EdgeType::RelatedTo);
+        assert_eq!(edges[0].weight, 200);
+    }
+
+    fn overflow_bank(policy: EdgeOverflow) -> (DataBank, EntryId) {
+        let config = BankConfig {
+            max_edges_per_entry: 3,
+            edge_overflow: policy,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "test.overflow".into(), config);
+        let id = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        // Weights 50, 200, 120 created at ticks 1, 2, 3
+        for (n, weight) in [50u8, 200, 120].into_iter().enumerate() {
+            bank.add_edge(id, overflow_edge(n as u64, weight, n as u64 + 1)).unwrap();
+        }
+        (bank, id)
+    }
+
+    fn overflow_edge(target: u64, weight: u8, created_tick: u64) -> Edge {
+        Edge {
+            edge_type: EdgeType::RelatedTo,
+            target: BankRef {
+                bank: BankId::from_raw(1),
+                entry: EntryId::from_raw(1000 + target),
+            },
+            weight,
+            created_tick,
+        }
+    }
+
+    fn weights(bank: &DataBank, id: EntryId) -> Vec<u8> {
+        let mut w: Vec<u8> = bank.edges_from(id).iter().map(|e| e.weight).collect();
+        w.sort_unstable();
+        w
+    }
+
+    #[test]
+    fn edge_overflow_reject_errors_at_limit() {
+        let (mut bank, id) = overflow_bank(EdgeOverflow::Reject);
+        let result = bank.add_edge(id, overflow_edge(9, 255, 10));
+        assert!(matches!(result, Err(DataBankError::EdgeLimitReached { max: 3 })));
+        assert_eq!(weights(&bank, id), vec![50, 120, 200]);
+    }
+
+    #[test]
+    fn edge_overflow_evict_weakest_keeps_strongest() {
+        let (mut bank, id) = overflow_bank(EdgeOverflow::EvictWeakest);
+        let weakest = EntryId::from_raw(1000);
+
+        bank.add_edge(id, overflow_edge(9, 180, 10)).unwrap();
+        assert_eq!(weights(&bank, id), vec![120, 180, 200]);
+        // Reverse index for the displaced edge is gone
+        assert!(bank.reverse_edges(weakest).is_empty());
+        assert_eq!(bank.reverse_edges(EntryId::from_raw(1009)).len(), 1);
+
+        // A weaker edge than everything held is refused
+        let result = bank.add_edge(id, overflow_edge(10, 5, 11));
+        assert!(matches!(result, Err(DataBankError::EdgeLimitReached { max: 3 })));
+        assert_eq!(weights(&bank, id), vec![120, 180, 200]);
+        assert!(bank.reverse_edges(EntryId::from_raw(1010)).is_empty());
+    }
+
+    #[test]
+    fn edge_overflow_evict_oldest_drops_first_created() {
+        let (mut bank, id) = overflow_bank(EdgeOverflow::EvictOldest);
+        bank.add_edge(id, overflow_edge(9, 10, 10)).unwrap();
+        // The tick-1 edge (weight 50) is gone even though the new one is weaker
+        assert_eq!(weights(&bank, id), vec![10, 120, 200]);
+        assert!(bank.reverse_edges(EntryId::from_raw(1000)).is_empty());
     }
 
     #[test]
-    fn query_sparse_returns_results() {
+    fn remove_all_edges_to_strips_every_source() {
         let mut bank = make_bank();
-        let v = make_vector(8);
-        bank.insert(v.clone(), Temperature::Hot, 0).unwrap();
+        let ids: Vec<EntryId> = (0..4)
+            .map(|_| bank.insert(make_vector(8), Temperature::Hot, 0).unwrap())
+            .collect();
+        let target = BankRef {
+            bank: bank.id,
+            entry: ids[0],
+        };
+        // Same entry id in another bank: must survive
+        let elsewhere = BankRef {
+            bank: BankId::from_raw(77),
+            entry: ids[0],
+        };
+        let edge = |edge_type, target| Edge {
+            edge_type,
+            target,
+            weight: 100,
+            created_tick: 0,
+        };
+        for &source in &ids[1..] {
+            bank.add_edge(source, edge(EdgeType::RelatedTo, target)).unwrap();
+        }
+        bank.add_edge(ids[1], edge(EdgeType::IsA, target)).unwrap();
+        bank.add_edge(ids[2], edge(EdgeType::RelatedTo, elsewhere)).unwrap();
+        assert_eq!(bank.reverse_edges(ids[0]).len(), 5);
 
-        let results = bank.query_sparse(&v, 1);
-        assert_eq!(results.len(), 1);
-        assert!(results[0].score > 200); // should be near-identical match
+        assert_eq!(bank.remove_all_edges_to(target), 4);
+        assert!(bank.all_edges().all(|(_, e)| e.target != target));
+        assert_eq!(bank.edges_from(ids[2]), &[edge(EdgeType::RelatedTo, elsewhere)]);
+        assert_eq!(bank.reverse_edges(ids[0]).len(), 1);
+        assert!(bank.validate_all().is_empty());
+        assert_eq!(bank.remove_all_edges_to(target), 0);
     }
 
     #[test]
-    fn add_edge_and_retrieve() {
+    fn edge_histogram_counts_types_and_weights() {
         let mut bank = make_bank();
         let id1 = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
-
+        let id2 = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
         let target = BankRef {
             bank: BankId::from_raw(2),
             entry: EntryId::from_raw(999),
         };
-        let edge = Edge {
-            edge_type: EdgeType::RelatedTo,
+        let edge = |edge_type, weight| Edge {
+            edge_type,
             target,
-            weight: 200,
+            weight,
             created_tick: 0,
         };
-        bank.add_edge(id1, edge).unwrap();
+        bank.add_edge(id1, edge(EdgeType::IsA, 10)).unwrap();
+        bank.add_edge(id1, edge(EdgeType::IsA, 255)).unwrap();
+        bank.add_edge(id1, edge(EdgeType::Causes, 40)).unwrap();
+        bank.add_edge(id2, edge(EdgeType::IsA, 31)).unwrap();
+        bank.add_edge(id2, edge(EdgeType::Custom, 128)).unwrap();
 
-        let edges = bank.edges_from(id1);
-        assert_eq!(edges.len(), 1);
-        assert_eq!(edges[0].edge_type, EdgeType::RelatedTo);
-        assert_eq!(edges[0].weight, 200);
+        let stats = bank.edge_histogram();
+        assert_eq!(stats.total, 5);
+        assert_eq!(stats.by_type.get(&EdgeType::IsA), Some(&3));
+        assert_eq!(stats.by_type.get(&EdgeType::Causes), Some(&1));
+        assert_eq!(stats.by_type.get(&EdgeType::Custom), Some(&1));
+        assert_eq!(stats.by_type.get(&EdgeType::HasA), None);
+        assert_eq!(stats.weight_buckets, [2, 1, 0, 0, 1, 0, 0, 1]);
     }
 
     #[test]
@@ -501,6 +3187,32 @@ mod tests {
         assert!(!bank.is_dirty());
     }
 
+    #[test]
+    fn entry_mut_marks_the_bank_dirty_only_on_change() {
+        let mut bank = make_bank();
+        let id = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        bank.mark_persisted(10);
+        let generation = bank.generation();
+
+        drop(bank.get_mut(id).unwrap());
+        assert!(!bank.is_dirty());
+        assert_eq!(bank.generation(), generation);
+
+        bank.get_mut(id).unwrap().confidence = 7;
+        assert!(bank.is_dirty());
+        assert_eq!(bank.mutations_since_persist(), 1);
+        assert_eq!(bank.generation(), generation + 1);
+
+        bank.mark_persisted(11);
+        bank.touch_entry(id, 12).unwrap();
+        assert!(bank.is_dirty());
+        assert_eq!(bank.get(id).unwrap().access_count(), 1);
+        assert!(matches!(
+            bank.touch_entry(EntryId::from_raw(1), 12),
+            Err(DataBankError::EntryNotFound { .. })
+        ));
+    }
+
     #[test]
     fn promote_and_demote_entry() {
         let mut bank = make_bank();
@@ -551,6 +3263,67 @@ mod tests {
         assert_eq!(bank.len(), 3);
     }
 
+    #[test]
+    fn evict_n_breaks_score_ties_by_oldest_id() {
+        // Each bank's map gets its own hash seed, so iteration order varies
+        for _ in 0..20 {
+            let config = BankConfig {
+                max_entries: 64,
+                audit_evictions: 8,
+                ..make_config(8)
+            };
+            let mut bank = DataBank::new(BankId::from_raw(1), "ties".into(), config);
+            let mut ids: Vec<EntryId> = (0..12)
+                .map(|_| bank.insert(make_vector(8), Temperature::Hot, 5).unwrap())
+                .collect();
+            ids.sort_unstable();
+            let score = bank.get(ids[0]).unwrap().eviction_score(50);
+            assert!(ids.iter().all(|&id| bank.get(id).unwrap().eviction_score(50) == score));
+
+            assert_eq!(bank.evict_n(4, 50), 4);
+            let evicted: Vec<EntryId> = bank.eviction_log().iter().map(|r| r.entry_id).collect();
+            assert_eq!(evicted, ids[..4]);
+            assert_eq!(bank.eviction_victim(50), Some(ids[4]));
+        }
+    }
+
+    #[test]
+    fn fork_is_independent_and_never_persists() {
+        let mut bank = make_bank();
+        let a = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        let b = bank.insert(make_vector(8), Temperature::Warm, 0).unwrap();
+        let edge = Edge {
+            edge_type: EdgeType::RelatedTo,
+            target: BankRef { bank: bank.id, entry: b },
+            weight: 50,
+            created_tick: 0,
+        };
+        bank.add_edge(a, edge).unwrap();
+
+        let mut fork = bank.fork();
+        assert!(fork.is_forked() && !bank.is_forked());
+        assert_eq!((fork.id, fork.name.as_str(), fork.len()), (bank.id, "test.bank", 2));
+        assert_eq!(fork.reverse_edges(b).len(), 1);
+        assert_eq!(
+            fork.query_sparse(&make_vector(8), 2).unwrap().len(),
+            bank.query_sparse(&make_vector(8), 2).unwrap().len()
+        );
+
+        fork.remove(b);
+        fork.insert(make_vector(8), Temperature::Cold, 1).unwrap();
+        fork.promote_entry(a).unwrap();
+        assert!(fork.is_dirty());
+        assert!(!fork.should_persist(u64::MAX));
+        assert!(bank.should_persist(u64::MAX));
+
+        assert_eq!(bank.len(), 2);
+        assert!(bank.get(b).is_some());
+        assert_eq!(bank.get(a).unwrap().temperature(), Temperature::Hot);
+        assert_eq!(bank.reverse_edges(b).len(), 1);
+        assert_eq!(bank.count_by_temperature(Temperature::Hot), 1);
+        assert_eq!(bank.count_by_temperature(Temperature::Cold), 0);
+    }
+
     #[test]
     fn compact_rebuilds_index() {
         let mut bank = make_bank();
@@ -560,10 +3333,475 @@ mod tests {
         bank.compact();
         assert_eq!(bank.len(), 1);
         // Query should still work after compact
-        let results = bank.query_sparse(&make_vector(8), 5);
+        let results = bank.query_sparse(&make_vector(8), 5).unwrap();
         assert_eq!(results.len(), 1);
     }
 
+    #[test]
+    fn auto_compaction_waits_for_threshold_and_next_mutation() {
+        let config = BankConfig { max_entries: 100, ..make_config(64) };
+        assert_eq!(config.auto_compact_threshold(), Some(10));
+        let unlimited = BankConfig { max_entries: 0, ..config.clone() };
+        assert_eq!(unlimited.auto_compact_threshold(), None);
+        let disabled = BankConfig { auto_compact_percent: 0, ..config.clone() };
+        assert_eq!(disabled.auto_compact_threshold(), None);
+
+        let one_hot = |dim: usize| {
+            let mut v = vec![Signal::ZERO; 64];
+            v[dim] = Signal::new_raw(1, 200, 1);
+            v
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "compacting".into(), config);
+        let mut ids: Vec<EntryId> =
+            (0..40).map(|i| bank.insert(one_hot(i), Temperature::Hot, 0).unwrap()).collect();
+        let assert_recall = |bank: &DataBank, ids: &[EntryId]| {
+            for (dim, &id) in ids.iter().enumerate() {
+                let hit = bank.query_sparse(&one_hot(dim), 1).unwrap();
+                if bank.get(id).is_some() {
+                    assert_eq!(hit[0].entry_id, id);
+                } else {
+                    assert!(hit.iter().all(|r| r.entry_id != id));
+                }
+            }
+        };
+
+        assert_eq!(bank.evict_n(9, 10), 9);
+        ids.push(bank.insert(one_hot(40), Temperature::Hot, 10).unwrap());
+        assert_eq!(bank.persistence_state().removals_since_compact, 9);
+
+        // Crossing the threshold inside the eviction loop does not compact.
+        assert_eq!(bank.evict_n(1, 10), 1);
+        assert_eq!(bank.persistence_state().removals_since_compact, 10);
+        assert_recall(&bank, &ids);
+
+        // The next mutation does.
+        let survivor = *ids.last().unwrap();
+        let edge = Edge {
+            edge_type: EdgeType::RelatedTo,
+            target: BankRef { bank: bank.id, entry: ids[20] },
+            weight: 100,
+            created_tick: 11,
+        };
+        bank.add_edge(survivor, edge).unwrap();
+        assert_eq!(bank.persistence_state().removals_since_compact, 0);
+        assert_eq!(bank.len(), 31);
+        assert_recall(&bank, &ids);
+    }
+
+    #[test]
+    fn rebase_ticks_preserves_relative_recency() {
+        let mut bank = make_bank();
+        let ids: Vec<EntryId> = (0..3)
+            .map(|_| bank.insert(make_vector(8), Temperature::Hot, 1_000).unwrap())
+            .collect();
+        for (id, tick) in ids.iter().zip([5_000u64, 9_000, 7_000]) {
+            bank.get_mut(*id).unwrap().touch(tick);
+        }
+        let order = |bank: &DataBank| {
+            let mut by_recency = ids.clone();
+            by_recency.sort_by_key(|id| bank.get(*id).unwrap().last_accessed_tick);
+            by_recency
+        };
+        let before = order(&bank);
+        let transient = bank.insert_ttl(make_vector(8), Temperature::Hot, 1_000, 9_000).unwrap();
+
+        // Old clock tick 4_000 becomes new clock tick 0
+        bank.rebase_ticks(4_000, 0);
+        assert_eq!(order(&bank), before);
+        assert_eq!(bank.get(ids[1]).unwrap().last_accessed_tick, 5_000);
+        // 6_000 ticks of TTL were left at the old 4_000, and still are
+        assert_eq!(bank.get(transient).unwrap().expires_at_tick, Some(6_000));
+        // Created at 1_000 < 4_000: clamps to 0
+        assert_eq!(bank.get(ids[0]).unwrap().created_tick, 0);
+
+        // Forward rebase keeps gaps exactly
+        bank.rebase_ticks(0, 50_000);
+        assert_eq!(order(&bank), before);
+        let gap = bank.get(ids[2]).unwrap().last_accessed_tick
+            - bank.get(ids[0]).unwrap().last_accessed_tick;
+        assert_eq!(gap, 2_000);
+        assert_eq!(bank.get(transient).unwrap().expires_at_tick, Some(56_000));
+    }
+
+    #[test]
+    fn pinned_entry_survives_demotion_and_eviction() {
+        let mut bank = make_bank();
+        let prior = bank.insert(make_vector(8), Temperature::Cold, 0).unwrap();
+        let plastic = bank.insert(make_vector(8), Temperature::Cold, 0).unwrap();
+        for id in [prior, plastic] {
+            bank.get_mut(id).unwrap().confidence = 0;
+        }
+        assert!(bank.pin(prior).unwrap());
+        assert!(!bank.pin(prior).unwrap());
+
+        assert_eq!(bank.demotion_pass(255), 1);
+        assert_eq!(bank.get(prior).unwrap().temperature, Temperature::Cold);
+        assert_eq!(bank.get(plastic).unwrap().temperature, Temperature::Set);
+        assert!(!bank.demote_entry(prior).unwrap());
+
+        // Forced eviction of everything only removes the unpinned entry
+        assert_eq!(bank.evict_n(10, 1_000), 1);
+        assert!(bank.get(prior).is_some());
+        assert!(bank.get(plastic).is_none());
+
+        // A bank full of pinned entries refuses inserts instead of evicting
+        let config = BankConfig {
+            max_entries: 1,
+            ..make_config(8)
+        };
+        let mut full = DataBank::new(BankId::from_raw(2), "test.full".into(), config);
+        let only = full.insert(make_vector(8), Temperature::Cold, 0).unwrap();
+        full.pin(only).unwrap();
+        assert_eq!(full.stats().pinned, 1);
+        assert!(matches!(
+            full.insert(make_vector(8), Temperature::Hot, 1),
+            Err(DataBankError::BankFull { capacity: 1 })
+        ));
+
+        assert!(bank.unpin(prior).unwrap());
+        assert_eq!(bank.stats().pinned, 0);
+        assert_eq!(bank.evict_n(10, 1_000), 1);
+        assert!(bank.is_empty());
+    }
+
+    #[test]
+    fn approx_bytes_tracks_growth() {
+        let config = BankConfig {
+            vector_width: 64,
+            max_entries: 0,
+            ..BankConfig::default()
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "bytes".into(), config);
+        let payload = 64 * std::mem::size_of::<Signal>() + 100;
+
+        let mut prev = bank.approx_bytes();
+        for tick in 0..16 {
+            let id = bank.insert(make_vector(64), Temperature::Hot, tick).unwrap();
+            bank.get_mut(id).unwrap().debug_tag = Some("x".repeat(100));
+            let now = bank.approx_bytes();
+            let grew = now - prev;
+            assert!(
+                grew >= payload && grew <= payload + 512,
+                "entry {tick} grew estimate by {grew}, payload {payload}"
+            );
+            prev = now;
+        }
+        assert_eq!(bank.stats().approx_bytes, prev);
+    }
+
+    #[test]
+    fn max_bytes_evicts_then_reports_full() {
+        let mut config = make_config(8);
+        config.max_entries = 0;
+        let empty = DataBank::new(BankId::from_raw(1), "probe".into(), config.clone());
+        let mut one = DataBank::new(BankId::from_raw(1), "probe".into(), config.clone());
+        one.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        let per_entry = one.approx_bytes() - empty.approx_bytes();
+        let budget = empty.approx_bytes() + 3 * per_entry;
+
+        config.max_bytes = Some(budget);
+        let mut bank = DataBank::new(BankId::from_raw(1), "capped".into(), config);
+        for tick in 0..6 {
+            bank.insert(make_vector(8), Temperature::Hot, tick).unwrap();
+        }
+        assert_eq!(bank.len(), 3);
+        assert!(bank.approx_bytes() <= budget);
+
+        let ids: Vec<EntryId> = bank.entries().map(|(&id, _)| id).collect();
+        for id in ids {
+            bank.pin(id).unwrap();
+        }
+        match bank.insert(make_vector(8), Temperature::Hot, 10) {
+            Err(DataBankError::BankOverBudget { max_bytes, used, needed }) => {
+                assert_eq!(max_bytes, budget);
+                assert_eq!(used, bank.approx_bytes());
+                assert!(used + needed > budget);
+            }
+            other => panic!("expected BankOverBudget, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn insert_hook_runs_before_eviction() {
+        let mut config = make_config(8);
+        config.max_entries = 2;
+        let mut bank = DataBank::new(BankId::from_raw(1), "hooked".into(), config);
+        let kept = [
+            bank.insert(make_vector(8), Temperature::Hot, 0).unwrap(),
+            bank.insert(make_vector(8), Temperature::Hot, 1).unwrap(),
+        ];
+
+        // A rejected insert leaves a full bank untouched
+        bank.set_insert_hook(Box::new(|entry: &mut BankEntry| {
+            entry.vector.pop();
+        }));
+        assert!(matches!(
+            bank.insert(make_vector(8), Temperature::Hot, 2),
+            Err(DataBankError::VectorWidthMismatch { expected: 8, got: 7 })
+        ));
+        assert!(kept.iter().all(|&id| bank.get(id).is_some()));
+
+        // The byte budget counts what the hook added
+        let mut config = make_config(8);
+        config.max_entries = 0;
+        let mut probe = DataBank::new(BankId::from_raw(1), "probe".into(), config.clone());
+        probe.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        config.max_bytes = Some(probe.approx_bytes());
+        let mut bank = DataBank::new(BankId::from_raw(1), "budget".into(), config);
+        bank.set_insert_hook(Box::new(|entry: &mut BankEntry| {
+            entry.debug_tag = Some("x".repeat(64));
+        }));
+        assert!(matches!(
+            bank.insert(make_vector(8), Temperature::Hot, 0),
+            Err(DataBankError::BankOverBudget { .. })
+        ));
+        assert!(bank.is_empty());
+    }
+
+    #[test]
+    fn approx_bytes_running_total_matches_recount() {
+        let mut config = make_config(8);
+        config.max_entries = 0;
+        config.max_edges_per_entry = 2;
+        config.edge_overflow = EdgeOverflow::EvictOldest;
+        config.exact_match_fast_path = true;
+        let mut bank = DataBank::new(BankId::from_raw(1), "bytes".into(), config);
+        let check = |bank: &DataBank, step: &str| {
+            assert_eq!(
+                bank.approx_bytes(),
+                core::mem::size_of::<DataBank>() + bank.recount_bytes(),
+                "{step}"
+            );
+        };
+
+        let ids: Vec<EntryId> = (0..6)
+            .map(|tick| bank.insert(make_vector(8), Temperature::Hot, tick).unwrap())
+            .collect();
+        check(&bank, "insert");
+        for (i, &source) in ids.iter().enumerate() {
+            for (n, &target) in ids.iter().enumerate().take(4) {
+                let edge = Edge {
+                    edge_type: EdgeType::RelatedTo,
+                    target: BankRef { bank: bank.id, entry: target },
+                    weight: 10,
+                    created_tick: (i * 4 + n) as u64,
+                };
+                bank.add_edge(source, edge).unwrap();
+            }
+        }
+        check(&bank, "add_edge with overflow");
+        bank.get_mut(ids[0]).unwrap().debug_tag = Some("x".repeat(64));
+        bank.set_debug_tag(ids[1], Some("tag".into())).unwrap();
+        check(&bank, "debug tags");
+        bank.set_vector(ids[2], vec![Signal::new_raw(-1, 3, 1); 8]).unwrap();
+        check(&bank, "set_vector");
+        bank.remove_all_edges_to(BankRef { bank: bank.id, entry: ids[3] });
+        check(&bank, "remove_all_edges_to");
+        bank.remove(ids[3]).unwrap();
+        bank.evict_n(2, 100);
+        check(&bank, "remove and evict");
+        bank.compact();
+        check(&bank, "compact");
+    }
+
+    struct CaptureLogger;
+
+    static CAPTURED: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            CAPTURED
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger;
+
+    #[test]
+    fn eviction_log_names_the_bank() {
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let config = BankConfig {
+            max_entries: 1,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(0x77), "visual.evict.log".into(), config);
+        bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        bank.insert(make_vector(8), Temperature::Hot, 1).unwrap();
+
+        let lines = CAPTURED
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with("bank 'visual.evict.log'") && l.contains("evicted entry")),
+            "{lines:?}"
+        );
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: std::sync::Mutex<Vec<(&'static str, EntryId)>>,
+    }
+
+    impl RecordingObserver {
+        fn record(&self, event: &'static str, id: EntryId) {
+            self.events.lock().unwrap().push((event, id));
+        }
+    }
+
+    impl BankObserver for RecordingObserver {
+        fn on_insert(&self, _bank_id: BankId, entry_id: EntryId) {
+            self.record("insert", entry_id);
+        }
+        fn on_remove(&self, _bank_id: BankId, entry_id: EntryId) {
+            self.record("remove", entry_id);
+        }
+        fn on_evict(&self, _bank_id: BankId, entry_id: EntryId) {
+            self.record("evict", entry_id);
+        }
+        fn on_promote(&self, _bank_id: BankId, entry_id: EntryId, _to: Temperature) {
+            self.record("promote", entry_id);
+        }
+        fn on_edge_added(&self, _bank_id: BankId, from: EntryId, _edge: &Edge) {
+            self.record("edge", from);
+        }
+    }
+
+    #[test]
+    fn observer_sees_evict_before_insert_at_capacity() {
+        let config = BankConfig {
+            max_entries: 1,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(0x78), "observed".into(), config);
+        let recorder = Arc::new(RecordingObserver::default());
+        bank.set_observer(recorder.clone());
+
+        let first = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        let second = bank.insert(make_vector(8), Temperature::Hot, 1).unwrap();
+        bank.promote_entry(second).unwrap();
+        bank.add_edge(
+            second,
+            Edge {
+                edge_type: EdgeType::RelatedTo,
+                target: BankRef {
+                    bank: BankId::from_raw(0x79),
+                    entry: EntryId::from_raw(1),
+                },
+                weight: 100,
+                created_tick: 2,
+            },
+        )
+        .unwrap();
+        bank.remove(second);
+
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec![
+                ("insert", first),
+                ("evict", first),
+                ("insert", second),
+                ("promote", second),
+                ("edge", second),
+                ("remove", second),
+            ]
+        );
+
+        assert!(bank.take_observer().is_some());
+        bank.insert(make_vector(8), Temperature::Hot, 3).unwrap();
+        assert_eq!(recorder.events.lock().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn degree_summary_counts_in_and_out_edges() {
+        let mut bank = make_bank();
+        let hub = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        let a = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        let b = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        let edge = |entry| Edge {
+            edge_type: EdgeType::RelatedTo,
+            target: BankRef { bank: bank.id, entry },
+            weight: 100,
+            created_tick: 0,
+        };
+        let (to_hub, to_a, to_b) = (edge(hub), edge(a), edge(b));
+        bank.add_edge(a, to_hub).unwrap();
+        bank.add_edge(b, to_hub).unwrap();
+        bank.add_edge(hub, to_a).unwrap();
+        bank.add_edge(hub, to_b).unwrap();
+        bank.add_edge(a, to_b).unwrap();
+
+        let mut expected = vec![(hub, 2, 2), (a, 1, 2), (b, 2, 1)];
+        expected.sort_unstable_by_key(|&(id, _, _)| id);
+        assert_eq!(bank.degree_summary(), expected);
+    }
+
+    #[test]
+    fn persistence_state_tracks_mutations_and_encoded_size() {
+        let mut bank = make_bank();
+        let id = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        bank.get_mut(id).unwrap().debug_tag = Some("tagged".into());
+        bank.insert(make_vector(8), Temperature::Hot, 1).unwrap();
+        bank.pin(id).unwrap();
+
+        let state = bank.persistence_state();
+        assert!(state.dirty);
+        assert_eq!(state.mutations_since_persist, 3);
+        assert_eq!(state.approx_encoded_bytes, crate::codec::encode(&bank).unwrap().len());
+
+        bank.mark_persisted(7);
+        let state = bank.persistence_state();
+        assert!(!state.dirty);
+        assert_eq!(state.mutations_since_persist, 0);
+        assert_eq!(state.last_persist_tick, 7);
+    }
+
+    #[test]
+    fn capacity_evictions_are_audited() {
+        let config = BankConfig {
+            max_entries: 2,
+            audit_evictions: 2,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(0x7a), "audited".into(), config);
+        let first = bank.insert(make_vector(8), Temperature::Hot, 0).unwrap();
+        bank.get_mut(first).unwrap().debug_tag = Some("first".into());
+        let second = bank.insert(make_vector(8), Temperature::Warm, 1).unwrap();
+        for _ in 0..3 {
+            bank.get_mut(second).unwrap().touch(2);
+        }
+        assert!(bank.eviction_log().is_empty());
+
+        // Hot, never-touched `first` is the lowest-scoring entry
+        bank.insert(make_vector(8), Temperature::Hot, 5).unwrap();
+        let log = bank.eviction_log();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].entry_id, first);
+        assert_eq!(log[0].tick, 5);
+        assert_eq!(log[0].temperature, Temperature::Hot);
+        assert_eq!(log[0].access_count, 0);
+        assert_eq!(log[0].debug_tag.as_deref(), Some("first"));
+        assert_eq!(log[0].reason, EvictionReason::Capacity);
+
+        // The ring keeps only the newest `audit_evictions` records
+        bank.evict_n(2, 6);
+        let log = bank.eviction_log();
+        assert_eq!(log.len(), 2);
+        assert!(log.iter().all(|r| r.reason == EvictionReason::Explicit));
+        assert!(log.iter().all(|r| r.entry_id != first));
+    }
+
     #[test]
     fn should_persist_logic() {
         let mut bank = make_bank();
@@ -575,4 +3813,296 @@ mod tests {
         }
         assert!(bank.should_persist(0));
     }
+
+    #[test]
+    fn bursts_within_flush_window_coalesce_into_one_flush() {
+        let config = BankConfig {
+            persist_after_mutations: 2,
+            persist_after_ticks: 1_000,
+            min_ticks_between_flushes: 100,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "burst".into(), config);
+        bank.mark_persisted(100);
+
+        let mut flush_ticks = Vec::new();
+        for tick in 101..=250 {
+            if tick == 110 || tick == 130 {
+                for _ in 0..3 {
+                    bank.insert(make_vector(8), Temperature::Hot, tick).unwrap();
+                }
+            }
+            if bank.should_persist(tick) {
+                flush_ticks.push((tick, bank.mutations_since_persist()));
+                bank.mark_persisted(tick);
+            }
+        }
+
+        assert_eq!(flush_ticks, vec![(200, 6)]);
+    }
+
+    #[test]
+    fn setters_keep_bank_bookkeeping_in_step() {
+        let config = BankConfig {
+            exact_match_fast_path: true,
+            ..make_config(4)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "set".into(), config);
+        let old = vec![Signal::new_raw(1, 50, 1); 4];
+        let new = vec![Signal::new_raw(-1, 90, 1); 4];
+        let id = bank.insert(old.clone(), Temperature::Hot, 0).unwrap();
+        bank.mark_persisted(0);
+
+        bank.set_vector(id, new.clone()).unwrap();
+        assert!(bank.is_dirty());
+        let entry = bank.get(id).unwrap();
+        assert_eq!(entry.vector(), &new[..]);
+        assert!(entry.validate());
+        assert_eq!(bank.query_sparse(&new, 1).unwrap()[0].entry_id, id);
+        assert!(!bank.exact_matches_into(&old, 1, &mut Vec::new()));
+        assert!(bank.exact_matches_into(&new, 1, &mut Vec::new()));
+        assert!(matches!(
+            bank.set_vector(id, vec![Signal::ZERO; 3]),
+            Err(DataBankError::VectorWidthMismatch { .. })
+        ));
+
+        assert!(bank.set_temperature(id, Temperature::Cool).unwrap());
+        assert!(!bank.set_temperature(id, Temperature::Cool).unwrap());
+        assert_eq!(bank.temperature_counts(), bank.recount_temperatures());
+        assert_eq!(bank.count_by_temperature(Temperature::Cool), 1);
+        bank.pin(id).unwrap();
+        assert!(!bank.set_temperature(id, Temperature::Hot).unwrap());
+        assert!(bank.set_temperature(id, Temperature::Cold).unwrap());
+        assert_eq!(bank.get(id).unwrap().temperature(), Temperature::Cold);
+
+        bank.mark_persisted(1);
+        bank.set_confidence(id, 7).unwrap();
+        bank.set_debug_tag(id, Some("cup".into())).unwrap();
+        assert_eq!(bank.mutations_since_persist(), 2);
+        let entry = bank.get(id).unwrap();
+        assert_eq!((entry.confidence(), entry.debug_tag()), (7, Some("cup")));
+        assert!(matches!(
+            bank.set_confidence(EntryId::from_raw(9), 1),
+            Err(DataBankError::EntryNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn dim_weights_change_which_entry_wins() {
+        let mut bank = DataBank::new(BankId::from_raw(1), "weights".into(), make_config(2));
+        // Dimension 0 is "shape", dimension 1 is "colour"
+        let sig = |polarity, magnitude| Signal::new_raw(polarity, magnitude, 1);
+        let same_shape = bank.insert(vec![sig(1, 100), sig(-1, 100)], Temperature::Hot, 0).unwrap();
+        let same_colour = bank.insert(vec![sig(1, 20), sig(1, 100)], Temperature::Hot, 0).unwrap();
+        let query = vec![sig(1, 100), sig(1, 100)];
+
+        assert_eq!(bank.query_sparse(&query, 1).unwrap()[0].entry_id, same_colour);
+        let unweighted = bank.query_sparse_dim_weighted(&query, 2, &[]).unwrap();
+        assert_eq!(unweighted[0].entry_id, same_colour);
+        assert_eq!(unweighted[0].score, bank.query_sparse(&query, 1).unwrap()[0].score);
+
+        let shape_first = bank.query_sparse_dim_weighted(&query, 2, &[20]).unwrap();
+        assert_eq!(shape_first[0].entry_id, same_shape);
+        assert_eq!(shape_first.len(), 2);
+        assert!(bank.query_sparse_dim_weighted(&query[..1], 2, &[20]).is_err());
+    }
+
+    #[test]
+    fn explain_match_agrees_with_query_score() {
+        let mut bank = make_bank();
+        let stored = make_vector(8);
+        let id = bank.insert(stored.clone(), Temperature::Hot, 0).unwrap();
+        let mut query = stored.clone();
+        query[1] = Signal::new_raw(-1, 40, 1);
+        query[5] = Signal::ZERO;
+
+        let explained = bank.explain_match(&query, id).unwrap();
+        assert_eq!(explained.score, bank.query_sparse(&query, 1).unwrap()[0].score);
+        assert_eq!(explained.active_dims, 7);
+        assert_eq!(explained.conflicting().map(|c| c.dim).collect::<Vec<_>>(), [1]);
+        assert_eq!(explained.supporting().count(), 6);
+        let dot: i64 = explained.contributions.iter().map(|c| c.product).sum();
+        assert_eq!(dot, explained.dot);
+        assert!(matches!(
+            bank.explain_match(&query, EntryId::from_raw(1)),
+            Err(DataBankError::EntryNotFound { .. })
+        ));
+    }
+
+    /// Deterministic pseudo-random vectors, so scores rarely tie.
+    fn seeded_vector(width: u16, seed: u64) -> Vec<Signal> {
+        let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        (0..width)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let bits = (state >> 33) as u32;
+                Signal::new_raw([-1, 0, 1][bits as usize % 3], (bits >> 8) as u8, 1)
+            })
+            .collect()
+    }
+
+    fn storage_bank(storage: EntryStorage, index_type: IndexType) -> DataBank {
+        let config = BankConfig {
+            max_entries: 512,
+            index_type,
+            storage,
+            ..make_config(32)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "store".into(), config);
+        let ids: Vec<EntryId> = (0..300)
+            .map(|seed| bank.insert(seeded_vector(32, seed), Temperature::Hot, seed).unwrap())
+            .collect();
+        // Removals reorder the slab, so results must not depend on order
+        for &id in ids.iter().step_by(7) {
+            bank.remove(id).unwrap();
+        }
+        bank
+    }
+
+    #[test]
+    fn storage_backends_answer_queries_identically() {
+        for index_type in [IndexType::BruteForce, IndexType::Ivf { k: 4, nprobe: 4 }] {
+            let map = storage_bank(EntryStorage::HashMap, index_type.clone());
+            let slab = storage_bank(EntryStorage::Slab, index_type.clone());
+            assert_eq!(slab.entry_map().storage(), EntryStorage::Slab);
+            assert_eq!(map.len(), slab.len());
+            assert_eq!(map.stats().temperatures, slab.stats().temperatures);
+            for seed in 1000..1010 {
+                let query = seeded_vector(32, seed);
+                // Ids are clock-derived, so match entries by their insert seed
+                let pairs = |bank: &DataBank| -> Vec<(u64, i32)> {
+                    let mut hits: Vec<_> = bank
+                        .query_sparse(&query, 20)
+                        .unwrap()
+                        .into_iter()
+                        .map(|r| (bank.get(r.entry_id).unwrap().created_tick, r.score))
+                        .collect();
+                    hits.sort_by_key(|&(seed, score)| (Reverse(score), seed));
+                    // Which of several tied entries makes the cut follows
+                    // iteration order, so leave the last score out
+                    let cutoff = hits.last().map_or(0, |&(_, score)| score);
+                    hits.retain(|&(_, score)| score > cutoff);
+                    hits
+                };
+                assert_eq!(pairs(&map), pairs(&slab));
+            }
+        }
+
+        let mut moved = storage_bank(EntryStorage::HashMap, IndexType::BruteForce);
+        moved.set_storage(EntryStorage::Slab);
+        assert_eq!(moved.config().storage, EntryStorage::Slab);
+        assert_eq!(moved.entry_map().storage(), EntryStorage::Slab);
+        let query = seeded_vector(32, 7);
+        let reference = storage_bank(EntryStorage::HashMap, IndexType::BruteForce);
+        let top = |bank: &DataBank| {
+            let hit = bank.query_sparse(&query, 1).unwrap()[0].entry_id;
+            bank.get(hit).unwrap().created_tick
+        };
+        assert_eq!(top(&moved), top(&reference));
+    }
+
+    /// Compares brute-force scan time on the two storage backends. Run with
+    /// `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "timing comparison, not a correctness check"]
+    fn storage_scan_timing() {
+        for storage in [EntryStorage::HashMap, EntryStorage::Slab] {
+            let config = BankConfig {
+                max_entries: 20_000,
+                index_type: IndexType::BruteForce,
+                storage,
+                ..make_config(64)
+            };
+            let mut bank = DataBank::new(BankId::from_raw(1), "scan".into(), config);
+            for seed in 0..20_000 {
+                bank.insert(seeded_vector(64, seed), Temperature::Hot, 0).unwrap();
+            }
+            let start = std::time::Instant::now();
+            for seed in 0..50 {
+                bank.query_sparse(&seeded_vector(64, seed + 100_000), 10).unwrap();
+            }
+            eprintln!("{storage:?}: 50 scans of 20k entries in {:?}", start.elapsed());
+        }
+    }
+
+    const UNIT_MAGNITUDES: [u8; 8] = [120, 90, 60, 40, 30, 20, 10, 5];
+    const UNIT_NORM_SQ: u64 = 29_125;
+
+    /// A shuffle with random signs of [`UNIT_MAGNITUDES`], so every vector
+    /// has squared norm [`UNIT_NORM_SQ`], as a prenormalized bank requires.
+    fn unit_vector(rng: &mut crate::rng::XorShift64Star) -> Vec<Signal> {
+        rng.sample_indices(8, 8)
+            .into_iter()
+            .map(|i| {
+                let polarity = if rng.below(2) == 0 { 1 } else { -1 };
+                Signal::new_raw(polarity, UNIT_MAGNITUDES[i], 1)
+            })
+            .collect()
+    }
+
+    fn metric_bank(metric: Metric, index_type: IndexType, count: u64) -> DataBank {
+        let config = BankConfig {
+            max_entries: 0,
+            index_type,
+            metric,
+            ..make_config(8)
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "unit".into(), config);
+        let mut rng = crate::rng::XorShift64Star::new(11);
+        for tick in 0..count {
+            bank.insert(unit_vector(&mut rng), Temperature::Hot, tick).unwrap();
+        }
+        bank
+    }
+
+    #[test]
+    fn prenormalized_dot_ranks_like_cosine() {
+        let dot = Metric::PrenormalizedDot {
+            norm_sq: UNIT_NORM_SQ,
+        };
+        let cosine_bank = metric_bank(Metric::Cosine, IndexType::BruteForce, 200);
+        let dot_bank = metric_bank(dot, IndexType::BruteForce, 200);
+        let mut rng = crate::rng::XorShift64Star::new(99);
+        for _ in 0..20 {
+            let query = unit_vector(&mut rng);
+            let by_cosine = cosine_bank.query_sparse(&query, 10).unwrap();
+            let by_dot = dot_bank.query_sparse(&query, 10).unwrap();
+            let scores = |hits: &[QueryResult]| hits.iter().map(|r| r.score).collect::<Vec<_>>();
+            assert_eq!(scores(&by_dot), scores(&by_cosine));
+            for hit in &by_dot {
+                let stored = &dot_bank.get(hit.entry_id).unwrap().vector;
+                assert_eq!(hit.score, sparse_cosine_similarity(&query, stored));
+            }
+        }
+
+        let stored = dot_bank.entries().next().unwrap().1.vector.clone();
+        assert_eq!(dot_bank.query_sparse(&stored, 1).unwrap()[0].score, 256);
+        let snapshot = dot_bank.snapshot();
+        assert_eq!(snapshot.query_sparse(&stored, 1)[0].score, 256);
+        assert!(BankConfig {
+            metric: Metric::PrenormalizedDot { norm_sq: 0 },
+            ..make_config(8)
+        }
+        .validate()
+        .is_err());
+    }
+
+    /// Compares brute-force scan time under the two metrics. Run with
+    /// `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "timing comparison, not a correctness check"]
+    fn metric_scan_timing() {
+        let dot = Metric::PrenormalizedDot {
+            norm_sq: UNIT_NORM_SQ,
+        };
+        for metric in [Metric::Cosine, dot] {
+            let bank = metric_bank(metric, IndexType::BruteForce, 20_000);
+            let mut rng = crate::rng::XorShift64Star::new(5);
+            let start = std::time::Instant::now();
+            for _ in 0..50 {
+                bank.query_sparse(&unit_vector(&mut rng), 10).unwrap();
+            }
+            eprintln!("{metric:?}: 50 scans of 20k entries in {:?}", start.elapsed());
+        }
+    }
 }
@@ -4,8 +4,9 @@
 //! i32 register slices (TVMR firmware format). Also packs EntryId (u64)
 //! into i32 pairs for register transport.
 
+use crate::prelude::*;
 use crate::similarity::QueryResult;
-use crate::types::EntryId;
+use crate::types::{BankId, EntryId};
 use ternary_signal::Signal;
 
 /// Convert a Signal vector to i32 register values.
@@ -26,6 +27,28 @@ pub fn i32_to_signals(values: &[i32]) -> Vec<Signal> {
         .collect()
 }
 
+/// Quantize a float to a Signal: `|value| x scale` rounds to the magnitude
+/// (multiplier 1), saturating at 255. Non-finite values become zero.
+pub fn f32_to_signal(value: f32, scale: u8) -> Signal {
+    if !value.is_finite() {
+        return Signal::ZERO;
+    }
+    let magnitude = (value.abs() * scale as f32 + 0.5).min(255.0) as u8;
+    if magnitude == 0 {
+        Signal::ZERO
+    } else {
+        Signal::new_raw(if value < 0.0 { -1 } else { 1 }, magnitude, 1)
+    }
+}
+
+/// Inverse of [`f32_to_signal`]: the signal's current divided by `scale`.
+pub fn signal_to_f32(signal: Signal, scale: u8) -> f32 {
+    if scale == 0 {
+        return 0.0;
+    }
+    signal.current() as f32 / scale as f32
+}
+
 /// Pack an EntryId (u64) into two i32 values (high, low).
 pub fn entry_id_to_i32_pair(id: EntryId) -> (i32, i32) {
     let raw = id.0;
@@ -40,6 +63,17 @@ pub fn i32_pair_to_entry_id(high: i32, low: i32) -> EntryId {
     EntryId(raw)
 }
 
+/// Pack a BankId (u64) into two i32 values (high, low).
+pub fn bank_id_to_i32_pair(id: BankId) -> (i32, i32) {
+    let raw = id.0;
+    ((raw >> 32) as i32, (raw & 0xFFFF_FFFF) as i32)
+}
+
+/// Unpack two i32 values into a BankId.
+pub fn i32_pair_to_bank_id(high: i32, low: i32) -> BankId {
+    BankId(((high as u64) << 32) | (low as u32 as u64))
+}
+
 /// Pack BankRef-like data into i32 slice: [bank_slot, entry_id_high, entry_id_low].
 pub fn bank_ref_to_i32_slice(slot: u8, entry: EntryId) -> [i32; 3] {
     let (high, low) = entry_id_to_i32_pair(entry);
@@ -74,6 +108,23 @@ pub fn traverse_results_to_i32(results: &[(u8, EntryId)]) -> Vec<i32> {
     out
 }
 
+/// Pack detailed traverse results `(slot, EntryId, hop, weight)` into i32
+/// register layout:
+///   [count, slot_0, id_high_0, id_low_0, hop_0, weight_0, slot_1, ...]
+pub fn traverse_results_detailed_to_i32(results: &[(u8, EntryId, u8, u8)]) -> Vec<i32> {
+    let mut out = Vec::with_capacity(1 + results.len() * 5);
+    out.push(results.len() as i32);
+    for &(slot, entry_id, hop, weight) in results {
+        out.push(slot as i32);
+        let (high, low) = entry_id_to_i32_pair(entry_id);
+        out.push(high);
+        out.push(low);
+        out.push(hop as i32);
+        out.push(weight as i32);
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +151,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_f32_quantization() {
+        assert_eq!(f32_to_signal(0.5, 100).current(), 50);
+        assert_eq!(f32_to_signal(-0.25, 100).current(), -25);
+        assert_eq!(f32_to_signal(0.001, 100), Signal::ZERO);
+        assert_eq!(f32_to_signal(9.0, 100).current(), 255);
+        assert_eq!(f32_to_signal(f32::NAN, 100), Signal::ZERO);
+        assert_eq!(signal_to_f32(f32_to_signal(-0.5, 200), 200), -0.5);
+    }
+
     #[test]
     fn test_entry_id_packing() {
         let id = EntryId(0x0123456789ABCDEF);
@@ -126,6 +187,13 @@ mod tests {
         assert_eq!(back, id);
     }
 
+    #[test]
+    fn test_bank_id_packing() {
+        let id = BankId(0xFEDC_BA98_7654_3210);
+        let (high, low) = bank_id_to_i32_pair(id);
+        assert_eq!(i32_pair_to_bank_id(high, low), id);
+    }
+
     #[test]
     fn test_query_results_packing() {
         let results = vec![
@@ -154,6 +222,17 @@ mod tests {
         assert_eq!(packed[4], 3); // slot_1
     }
 
+    #[test]
+    fn test_traverse_results_detailed_packing() {
+        let results = vec![(0u8, EntryId(42), 1u8, 200u8), (3u8, EntryId(99), 2u8, 150u8)];
+        let packed = traverse_results_detailed_to_i32(&results);
+        assert_eq!(packed.len(), 11);
+        assert_eq!(packed[0], 2); // count
+        let (h, l) = entry_id_to_i32_pair(EntryId(99));
+        assert_eq!(packed[6..], [3, h, l, 2, 150]);
+        assert_eq!(packed[4..6], [1, 200]); // hop_0, weight_0
+    }
+
     #[test]
     fn test_bank_ref_slice() {
         let id = EntryId(12345);
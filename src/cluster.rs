@@ -1,14 +1,46 @@
-use std::collections::{HashMap, VecDeque};
-use std::path::Path;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::cmp::Reverse;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, PoisonError};
+#[cfg(feature = "std")]
+use std::thread::JoinHandle;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
 use ternary_signal::Signal;
 
-use crate::bank::DataBank;
+#[cfg(feature = "std")]
+use crate::bank::BankLabel;
+use crate::bank::{DataBank, PersistenceState};
+#[cfg(feature = "std")]
 use crate::codec;
+#[cfg(feature = "std")]
+use crate::error::JournalError;
 use crate::error::{DataBankError, Result};
+use crate::index::{QueryFilter, QueryTrace};
+#[cfg(feature = "std")]
 use crate::journal::{self, JournalReader, JournalWriter};
-use crate::similarity::QueryResult;
+#[cfg(feature = "metrics")]
+use crate::metrics::BankMetrics;
+use crate::observer::{BankObserver, ClusterObserver};
+use crate::prelude::*;
+use crate::similarity::{isqrt_wide, sparse_cosine_similarity, QueryResult};
 use crate::types::*;
 
+/// Most entries per bank that [`BankCluster::find_duplicates`] compares
+/// pairwise. Larger banks are scanned over their first entries by id.
+pub const DEDUP_SCAN_LIMIT: usize = 2048;
+
+/// Journal records replayed between progress callbacks, see
+/// [`BankCluster::replay_journal_progress`].
+#[cfg(feature = "std")]
+pub const REPLAY_PROGRESS_INTERVAL: usize = 4096;
+
 /// Result of a cross-bank query.
 #[derive(Debug, Clone)]
 pub struct ClusterQueryResult {
@@ -19,6 +51,15 @@ pub struct ClusterQueryResult {
     pub normalized_score: i32,
 }
 
+/// Position after the last result of a [`BankCluster::query_all_paged`]
+/// page: its normalized score, bank, and entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryCursor {
+    pub score: i32,
+    pub bank_id: BankId,
+    pub entry_id: EntryId,
+}
+
 /// Multi-bank manager -- the brain's distributed representational memory.
 ///
 /// Each region owns one or more banks in the cluster. The cluster provides
@@ -28,7 +69,31 @@ pub struct ClusterQueryResult {
 pub struct BankCluster {
     banks: HashMap<BankId, DataBank>,
     name_index: HashMap<String, BankId>,
+    #[cfg(feature = "std")]
     journal_writer: Option<JournalWriter>,
+    /// Journal records appended but not yet flushed; non-zero only inside
+    /// a [`begin_tick`](Self::begin_tick) / [`end_tick`](Self::end_tick)
+    /// pair.
+    #[cfg(feature = "std")]
+    journal_unflushed: usize,
+    #[cfg(feature = "std")]
+    in_tick: bool,
+    observer: Option<Box<dyn ClusterObserver>>,
+    #[cfg(feature = "std")]
+    background_flush: Option<BackgroundFlush>,
+    /// `.bank` file each bank was last loaded from or written to.
+    #[cfg(feature = "std")]
+    bank_paths: HashMap<BankId, PathBuf>,
+    /// Banks moved with [`relocate_bank`](Self::relocate_bank); flushes
+    /// write them into their recorded directory.
+    #[cfg(feature = "std")]
+    relocated: HashSet<BankId>,
+    /// What the last incremental flush of each bank wrote, see
+    /// [`BankConfig::incremental_flush`].
+    #[cfg(feature = "std")]
+    persisted_images: HashMap<BankId, codec::PersistedImage>,
+    /// Per-bank query time above which `query_all` logs a warning.
+    slow_query_threshold_us: Option<u64>,
 }
 
 impl BankCluster {
@@ -37,18 +102,77 @@ impl BankCluster {
         Self {
             banks: HashMap::new(),
             name_index: HashMap::new(),
+            #[cfg(feature = "std")]
             journal_writer: None,
+            #[cfg(feature = "std")]
+            journal_unflushed: 0,
+            #[cfg(feature = "std")]
+            in_tick: false,
+            observer: None,
+            #[cfg(feature = "std")]
+            background_flush: None,
+            #[cfg(feature = "std")]
+            bank_paths: HashMap::new(),
+            #[cfg(feature = "std")]
+            relocated: HashSet::new(),
+            #[cfg(feature = "std")]
+            persisted_images: HashMap::new(),
+            slow_query_threshold_us: None,
         }
     }
 
+    /// Deep copy of every bank, each a [`DataBank::fork`], for speculative
+    /// mutation. The copy has no journal, observer or flush worker and
+    /// remembers no bank files, so nothing done to it reaches disk.
+    pub fn fork(&self) -> BankCluster {
+        let mut forked = Self::new();
+        forked.banks = self.banks.iter().map(|(&id, bank)| (id, bank.fork())).collect();
+        forked.name_index = self.name_index.clone();
+        forked.slow_query_threshold_us = self.slow_query_threshold_us;
+        forked
+    }
+
     /// Create an empty cluster with a journal writer for crash recovery.
+    #[cfg(feature = "std")]
     pub fn with_journal(journal_path: &Path) -> Result<Self> {
-        let writer = JournalWriter::open(journal_path)?;
-        Ok(Self {
-            banks: HashMap::new(),
-            name_index: HashMap::new(),
+        Ok(Self::with_journal_writer(JournalWriter::open(journal_path)?))
+    }
+
+    /// Create an empty cluster journaling through `writer`, e.g. one built
+    /// with [`JournalWriter::from_sink`].
+    #[cfg(feature = "std")]
+    pub fn with_journal_writer(writer: JournalWriter) -> Self {
+        Self {
             journal_writer: Some(writer),
-        })
+            ..Self::new()
+        }
+    }
+
+    /// Register an observer for cluster events, replacing any existing one.
+    pub fn set_observer(&mut self, observer: Box<dyn ClusterObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Remove and return the registered observer, if any.
+    pub fn take_observer(&mut self) -> Option<Box<dyn ClusterObserver>> {
+        self.observer.take()
+    }
+
+    /// Log a warning for every per-bank query in
+    /// [`query_all`](Self::query_all) that takes longer than `threshold_us`
+    /// microseconds, with its [`QueryTrace`]. `None` (the default) disables
+    /// tracing of cluster queries.
+    pub fn set_slow_query_threshold_us(&mut self, threshold_us: Option<u64>) {
+        self.slow_query_threshold_us = threshold_us;
+    }
+
+    /// Install one entry-event observer on every bank currently in the
+    /// cluster, replacing each bank's existing observer. Banks created or
+    /// added afterwards start without one.
+    pub fn set_bank_observer(&mut self, observer: Arc<dyn BankObserver>) {
+        for bank in self.banks.values_mut() {
+            bank.set_observer(Arc::clone(&observer));
+        }
     }
 
     /// Get a reference to a bank by ID.
@@ -75,32 +199,215 @@ impl BankCluster {
     }
 
     /// Get an existing bank or create a new one if it doesn't exist.
+    ///
+    /// An existing bank is returned as is, whatever `name` says. Creating a
+    /// bank whose name is already bound to a different id fails with
+    /// `DuplicateBankName`.
     pub fn get_or_create(
         &mut self,
         id: BankId,
         name: String,
         config: BankConfig,
-    ) -> &mut DataBank {
+    ) -> Result<&mut DataBank> {
         if !self.banks.contains_key(&id) {
+            self.check_name_free(id, &name)?;
+            if let Some(observer) = &self.observer {
+                observer.on_bank_created(id, &name);
+            }
             let bank = DataBank::new(id, name.clone(), config);
+            self.warn_if_never_persisted(&bank);
             self.banks.insert(id, bank);
-            self.name_index.insert(name, id);
+            if !name.is_empty() {
+                self.name_index.insert(name, id);
+            }
         }
-        self.banks.get_mut(&id).unwrap()
+        Ok(self.banks.get_mut(&id).unwrap())
     }
 
-    /// Add a bank to the cluster.
-    pub fn add(&mut self, bank: DataBank) {
+    /// Add a bank to the cluster, replacing any bank with the same id.
+    ///
+    /// Fails with `DuplicateBankName` if the bank's name is already bound
+    /// to a different id; the cluster is unchanged in that case.
+    pub fn add(&mut self, bank: DataBank) -> Result<()> {
         let id = bank.id;
         let name = bank.name.clone();
-        self.banks.insert(id, bank);
-        self.name_index.insert(name, id);
+        self.check_name_free(id, &name)?;
+        self.warn_if_never_persisted(&bank);
+        if let Some(observer) = &self.observer {
+            observer.on_bank_created(id, &name);
+        }
+        if let Some(replaced) = self.banks.insert(id, bank) {
+            if replaced.name != name {
+                self.name_index.remove(&replaced.name);
+            }
+        }
+        if !name.is_empty() {
+            self.name_index.insert(name, id);
+        }
+        Ok(())
+    }
+
+    /// Warn when an attached bank can never auto-persist and no journal
+    /// backs its mutations, so a crash would lose everything since load.
+    fn warn_if_never_persisted(&self, bank: &DataBank) {
+        #[cfg(feature = "std")]
+        let journaled = self.journal_writer.is_some();
+        #[cfg(not(feature = "std"))]
+        let journaled = false;
+        if !journaled && !bank.config().can_ever_persist() {
+            log::warn!(
+                "{}: persistence thresholds are unreachable and no journal is attached; \
+                 changes are only written by an explicit codec::save_atomic",
+                bank.label()
+            );
+        }
+    }
+
+    /// Rename a bank. Fails with `DuplicateBankName` if another bank already
+    /// answers to `new_name`. The bank is marked dirty, and the flush that
+    /// writes `{new_name}.bank` removes the `{old_name}.bank` it last wrote
+    /// to the same directory.
+    pub fn rename_bank(&mut self, id: BankId, new_name: String) -> Result<()> {
+        self.check_name_free(id, &new_name)?;
+        let bank = self.banks.get_mut(&id).ok_or(DataBankError::BankNotFound { id })?;
+        if bank.name == new_name {
+            return Ok(());
+        }
+        self.name_index.remove(&bank.name);
+        if !new_name.is_empty() {
+            self.name_index.insert(new_name.clone(), id);
+        }
+        log::info!("{}: renamed to {:?}", bank.label(), new_name);
+        bank.rename(new_name);
+        Ok(())
+    }
+
+    /// Reject `name` if another bank already answers to it. Empty names are
+    /// never indexed, so any number of unnamed banks may coexist.
+    fn check_name_free(&self, id: BankId, name: &str) -> Result<()> {
+        match self.name_index.get(name) {
+            Some(&bound) if bound != id => Err(DataBankError::DuplicateBankName {
+                name: name.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove every entry in `namespace` from a bank, see
+    /// [`DataBank::clear_namespace`]. With a journal configured the removal
+    /// is first recorded as `BatchEvict` records (split every 65,535 ids,
+    /// the record's limit); if journaling fails the bank is left untouched
+    /// and the error returned. Returns the removed ids.
+    pub fn clear_namespace(&mut self, id: BankId, namespace: u16) -> Result<Vec<EntryId>> {
+        #[cfg(feature = "std")]
+        if let Some(bank) = self.banks.get(&id) {
+            let mut doomed: Vec<EntryId> = bank
+                .entries()
+                .filter(|(_, e)| e.namespace == namespace)
+                .map(|(&entry_id, _)| entry_id)
+                .collect();
+            doomed.sort_unstable();
+            self.journal_batch_evict(id, &doomed)?;
+        }
+        let bank = self.banks.get_mut(&id).ok_or(DataBankError::BankNotFound { id })?;
+        Ok(bank.clear_namespace(namespace))
+    }
+
+    /// Journal the removal of `entry_ids` from bank `id` as `BatchEvict`
+    /// records, split every 65,535 ids (the record's limit).
+    #[cfg(feature = "std")]
+    fn journal_batch_evict(&mut self, id: BankId, entry_ids: &[EntryId]) -> Result<()> {
+        for chunk in entry_ids.chunks(u16::MAX as usize) {
+            self.journal_mutation(crate::journal::JournalEntry::BatchEvict {
+                bank_id: id,
+                entry_ids: chunk.to_vec(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Strip every edge in a bank that points at `target`, see
+    /// [`DataBank::remove_all_edges_to`]. With a journal configured the
+    /// removal is recorded first as one `RemoveEdgesTo` record; if
+    /// journaling fails the bank is left untouched and the error returned.
+    /// Returns the number of edges removed.
+    pub fn remove_all_edges_to(&mut self, id: BankId, target: BankRef) -> Result<usize> {
+        #[cfg(feature = "std")]
+        if self.banks.contains_key(&id) {
+            self.journal_mutation(crate::journal::JournalEntry::RemoveEdgesTo {
+                bank_id: id,
+                target,
+            })?;
+        }
+        let bank = self.banks.get_mut(&id).ok_or(DataBankError::BankNotFound { id })?;
+        Ok(bank.remove_all_edges_to(target))
+    }
+
+    /// Evict the `count` lowest-scoring entries from a bank.
+    /// With a journal configured the victims are first recorded as
+    /// `BatchEvict` records; if journaling fails nothing is evicted and the
+    /// error is returned.
+    /// Returns the number actually evicted.
+    pub fn evict(&mut self, id: BankId, count: usize, current_tick: u64) -> Result<usize> {
+        #[cfg(feature = "std")]
+        if let Some(bank) = self.banks.get(&id) {
+            let mut doomed = bank.eviction_candidates(count, current_tick);
+            doomed.sort_unstable();
+            self.journal_batch_evict(id, &doomed)?;
+        }
+        let bank = self
+            .banks
+            .get_mut(&id)
+            .ok_or(DataBankError::BankNotFound { id })?;
+        let evicted = bank.evict_n(count, current_tick);
+        if evicted > 0 {
+            if let Some(observer) = &self.observer {
+                observer.on_eviction(id, evicted);
+            }
+        }
+        Ok(evicted)
+    }
+
+    /// Maintenance pass: remove every entry whose TTL has run out at
+    /// `current_tick` from every bank, see [`DataBank::expire_entries`].
+    /// With a journal configured each bank's expired ids are first recorded
+    /// as `BatchEvict` records; if journaling fails that bank is left
+    /// untouched and the error returned (banks already swept stay swept).
+    /// Returns the total number removed.
+    pub fn expire_entries(&mut self, current_tick: u64) -> Result<usize> {
+        let mut ids: Vec<BankId> = self.banks.keys().copied().collect();
+        ids.sort_unstable();
+        let mut total = 0;
+        for id in ids {
+            #[cfg(feature = "std")]
+            {
+                let doomed = self.banks[&id].expiry_candidates(current_tick);
+                self.journal_batch_evict(id, &doomed)?;
+            }
+            let Some(bank) = self.banks.get_mut(&id) else {
+                continue;
+            };
+            let expired = bank.expire_entries(current_tick).len();
+            if expired > 0 {
+                if let Some(observer) = &self.observer {
+                    observer.on_eviction(id, expired);
+                }
+            }
+            total += expired;
+        }
+        Ok(total)
     }
 
     /// Remove a bank from the cluster.
     pub fn remove(&mut self, id: BankId) -> Option<DataBank> {
         if let Some(bank) = self.banks.remove(&id) {
             self.name_index.remove(&bank.name);
+            #[cfg(feature = "std")]
+            self.bank_paths.remove(&id);
+            #[cfg(feature = "std")]
+            self.relocated.remove(&id);
+            #[cfg(feature = "std")]
+            self.persisted_images.remove(&id);
             Some(bank)
         } else {
             None
@@ -112,6 +419,10 @@ impl BankCluster {
     /// The edge is added to the source entry. The reverse index on the
     /// target bank is NOT updated here (the target bank may not exist in
     /// this cluster if it's on a different host).
+    ///
+    /// With a journal configured the edge is first recorded as an `AddEdge`
+    /// record; if journaling fails the bank is left untouched and the error
+    /// returned.
     pub fn link(
         &mut self,
         from: BankRef,
@@ -120,18 +431,29 @@ impl BankCluster {
         weight: u8,
         tick: u64,
     ) -> Result<()> {
-        let source_bank = self
-            .banks
-            .get_mut(&from.bank)
-            .ok_or(DataBankError::BankNotFound { id: from.bank })?;
-
         let edge = Edge {
             edge_type,
             target: to,
             weight,
             created_tick: tick,
         };
+        #[cfg(feature = "std")]
+        if self
+            .banks
+            .get(&from.bank)
+            .is_some_and(|bank| bank.get(from.entry).is_some())
+        {
+            self.journal_mutation(crate::journal::JournalEntry::AddEdge {
+                bank_id: from.bank,
+                entry_id: from.entry,
+                edge,
+            })?;
+        }
 
+        let source_bank = self
+            .banks
+            .get_mut(&from.bank)
+            .ok_or(DataBankError::BankNotFound { id: from.bank })?;
         source_bank.add_edge(from.entry, edge)
     }
 
@@ -145,13 +467,110 @@ impl BankCluster {
         edge_type: EdgeType,
         depth: usize,
     ) -> Vec<BankRef> {
+        self.traverse_multi_source(&[start], edge_type, depth)
+    }
+
+    /// Traverse from several co-active seeds in one combined BFS.
+    ///
+    /// All seeds start at depth 0 and share one visited set, so a node
+    /// reachable from more than one seed is returned (and expanded) once.
+    /// Results are in BFS order; duplicate seeds are ignored.
+    pub fn traverse_multi_source(
+        &self,
+        starts: &[BankRef],
+        edge_type: EdgeType,
+        depth: usize,
+    ) -> Vec<BankRef> {
+        self.bfs(starts, &[edge_type], depth, |_| true)
+            .into_iter()
+            .map(|(target, _, _)| target)
+            .collect()
+    }
+
+    /// [`traverse`](Self::traverse), with each result's hop distance from
+    /// `start` (1 = direct neighbour) and the weight of the edge it was
+    /// reached through. Hops beyond 255 saturate.
+    pub fn traverse_detailed(
+        &self,
+        start: BankRef,
+        edge_type: EdgeType,
+        depth: usize,
+    ) -> Vec<(BankRef, u8, u8)> {
+        self.bfs(&[start], &[edge_type], depth, |_| true)
+    }
+
+    /// Like [`traverse`](Self::traverse), but only follows edges whose
+    /// target bank satisfies `target_bank_pred`. Edges into other banks are
+    /// neither returned nor expanded, which scopes recall to a modality
+    /// (e.g. only visual banks).
+    pub fn traverse_filtered(
+        &self,
+        start: BankRef,
+        edge_type: EdgeType,
+        depth: usize,
+        target_bank_pred: impl Fn(BankId) -> bool,
+    ) -> Vec<BankRef> {
+        self.bfs(&[start], &[edge_type], depth, target_bank_pred)
+            .into_iter()
+            .map(|(target, _, _)| target)
+            .collect()
+    }
+
+    /// Gather the fragments of one distributed concept, one vector per bank.
+    ///
+    /// Walks edges of any of `edge_types` up to `depth` hops from `seed`
+    /// and returns the seed's vector plus each reached bank's contributing
+    /// vector, ordered by bank id. A bank reached more than once contributes
+    /// its nearest fragment (first in BFS order). Fragments whose bank or
+    /// entry is not in this cluster are skipped; a missing seed yields an
+    /// empty result.
+    pub fn assemble(
+        &self,
+        seed: BankRef,
+        edge_types: &[EdgeType],
+        depth: usize,
+    ) -> Vec<(BankId, Vec<Signal>)> {
+        let mut fragments: HashMap<BankId, Vec<Signal>> = HashMap::new();
+        let reached = self.bfs(&[seed], edge_types, depth, |_| true);
+        for target in core::iter::once(seed).chain(reached.into_iter().map(|(t, _, _)| t)) {
+            if fragments.contains_key(&target.bank) {
+                continue;
+            }
+            let entry = self.banks.get(&target.bank).and_then(|b| b.get(target.entry));
+            let Some(entry) = entry else {
+                if target == seed {
+                    return Vec::new();
+                }
+                continue;
+            };
+            fragments.insert(target.bank, entry.vector().to_vec());
+        }
+        let mut assembled: Vec<(BankId, Vec<Signal>)> = fragments.into_iter().collect();
+        assembled.sort_by_key(|(bank, _)| *bank);
+        assembled
+    }
+
+    /// Combined BFS behind the traverse methods. Yields
+    /// `(target, hop, edge weight)` in visit order.
+    fn bfs(
+        &self,
+        starts: &[BankRef],
+        edge_types: &[EdgeType],
+        depth: usize,
+        follow: impl Fn(BankId) -> bool,
+    ) -> Vec<(BankRef, u8, u8)> {
         if depth == 0 {
             return Vec::new();
         }
 
-        let mut visited: Vec<BankRef> = Vec::new();
-        let mut queue: VecDeque<(BankRef, usize)> = VecDeque::new();
-        queue.push_back((start, 0));
+        let mut visited: Vec<(BankRef, u8, u8)> = Vec::new();
+        let mut seen: HashSet<BankRef> = HashSet::new();
+        let mut seeded: HashSet<BankRef> = HashSet::new();
+        let mut queue: VecDeque<(BankRef, usize)> = starts
+            .iter()
+            .filter(|s| seeded.insert(**s))
+            .map(|&s| (s, 0))
+            .collect();
 
         while let Some((current, current_depth)) = queue.pop_front() {
             if current_depth >= depth {
@@ -163,8 +582,12 @@ impl BankCluster {
             };
 
             for edge in bank.edges_from(current.entry) {
-                if edge.edge_type == edge_type && !visited.contains(&edge.target) {
-                    visited.push(edge.target);
+                if edge_types.contains(&edge.edge_type)
+                    && follow(edge.target.bank)
+                    && seen.insert(edge.target)
+                {
+                    let hop = (current_depth + 1).min(u8::MAX as usize) as u8;
+                    visited.push((edge.target, hop, edge.weight));
                     queue.push_back((edge.target, current_depth + 1));
                 }
             }
@@ -176,13 +599,36 @@ impl BankCluster {
     /// Query across ALL banks in the cluster.
     ///
     /// Takes per-bank query vectors (banks may have different widths).
-    /// Returns top_k results globally with z-score normalization.
+    /// Returns top_k results globally, ranked by `normalized_score`: each
+    /// bank's scores z-scored against its own results, or, for a bank with
+    /// fewer than three results or no spread, against the pooled raw scores
+    /// of all banks.
     pub fn query_all(
         &self,
         query_per_bank: &HashMap<BankId, Vec<Signal>>,
         top_k: usize,
     ) -> Vec<ClusterQueryResult> {
-        let mut all_results: Vec<ClusterQueryResult> = Vec::new();
+        self.query_all_with(query_per_bank, top_k, None)
+    }
+
+    /// [`query_all`](Self::query_all) scoring only entries that match
+    /// `filter` in each bank (see [`DataBank::query_sparse_filtered`]).
+    pub fn query_all_filtered(
+        &self,
+        query_per_bank: &HashMap<BankId, Vec<Signal>>,
+        top_k: usize,
+        filter: &QueryFilter,
+    ) -> Vec<ClusterQueryResult> {
+        self.query_all_with(query_per_bank, top_k, Some(filter))
+    }
+
+    fn query_all_with(
+        &self,
+        query_per_bank: &HashMap<BankId, Vec<Signal>>,
+        top_k: usize,
+        filter: Option<&QueryFilter>,
+    ) -> Vec<ClusterQueryResult> {
+        let mut per_bank = Vec::new();
 
         for (&bank_id, bank) in &self.banks {
             let query = match query_per_bank.get(&bank_id) {
@@ -190,34 +636,173 @@ impl BankCluster {
                 None => continue,
             };
 
-            let results = bank.query_sparse(query, top_k);
-            if results.is_empty() {
-                continue;
+            let results = match (filter, self.slow_query_threshold_us) {
+                (Some(filter), _) => bank.query_sparse_filtered(query, top_k, filter),
+                (None, None) => bank.query_lenient(query, top_k),
+                (None, Some(threshold)) => {
+                    let mut trace = QueryTrace::default();
+                    let results = bank.query_sparse_traced(query, top_k, &mut trace);
+                    if trace.micros > threshold {
+                        log::warn!(
+                            "{}: slow query micros={} threshold_us={} path={:?} \
+                             candidates={} buckets={} top_k={}",
+                            bank.label(),
+                            trace.micros,
+                            threshold,
+                            trace.path,
+                            trace.candidates_scanned,
+                            trace.buckets_probed,
+                            top_k
+                        );
+                    }
+                    results
+                }
+            };
+            per_bank.push((bank_id, bank.name.clone(), results));
+        }
+
+        let mut all_results = fuse_results(per_bank);
+        all_results.sort_by_key(|r| Reverse(r.normalized_score));
+        all_results.truncate(top_k);
+        all_results
+    }
+
+    /// Page through [`query_all`](Self::query_all)-style results.
+    ///
+    /// Results are ordered by normalized score (descending), then bank id,
+    /// then entry id. Pass the returned cursor to fetch the page after it;
+    /// `None` means there are no more matches. Z-scores are taken over
+    /// each bank's full match list rather than its top_k, so they stay the
+    /// same from page to page while the banks are unchanged.
+    pub fn query_all_paged(
+        &self,
+        query_per_bank: &HashMap<BankId, Vec<Signal>>,
+        page_size: usize,
+        cursor: Option<QueryCursor>,
+    ) -> (Vec<ClusterQueryResult>, Option<QueryCursor>) {
+        let mut per_bank = Vec::new();
+        for (&bank_id, bank) in &self.banks {
+            if let Some(query) = query_per_bank.get(&bank_id) {
+                per_bank.push((bank_id, bank.name.clone(), bank.query_lenient(query, bank.len())));
             }
+        }
+        let mut all_results = fuse_results(per_bank);
 
-            // Compute mean and stddev for z-score normalization
-            let (mean, stddev) = z_score_params(&results);
+        let key = |r: &ClusterQueryResult| (Reverse(r.normalized_score), r.bank_id, r.entry_id);
+        if let Some(cursor) = cursor {
+            let after = (Reverse(cursor.score), cursor.bank_id, cursor.entry_id);
+            all_results.retain(|r| key(r) > after);
+        }
+        all_results.sort_unstable_by_key(key);
+
+        let more = all_results.len() > page_size;
+        all_results.truncate(page_size);
+        let next = match all_results.last() {
+            Some(last) if more => Some(QueryCursor {
+                score: last.normalized_score,
+                bank_id: last.bank_id,
+                entry_id: last.entry_id,
+            }),
+            _ => None,
+        };
+        (all_results, next)
+    }
 
-            for r in &results {
-                let normalized = if stddev > 0 {
-                    ((r.score as i64 - mean as i64) * 256 / stddev as i64) as i32
-                } else {
-                    0
-                };
+    /// [`query_all`](Self::query_all), then reward candidates that are
+    /// linked to each other.
+    ///
+    /// A concept spread over several banks may score only moderately in
+    /// each, so every candidate whose edges reach another candidate in the
+    /// pooled results within `depth` hops (in either direction, any edge
+    /// type) gains `edge_bonus * weight / 255 * strength / 256` on its
+    /// normalized score. `weight` is the weakest edge on the strongest such
+    /// path; `strength` is the other candidate's normalized score mapped
+    /// from [-256, 256] (one stddev either side of the mean) onto [0, 256].
+    /// Each linked pair is counted once. Results are re-sorted globally
+    /// (ties by bank, then entry) and truncated to `top_k`; `depth` 0
+    /// leaves the query_all ranking unchanged.
+    pub fn query_with_association(
+        &self,
+        query_per_bank: &HashMap<BankId, Vec<Signal>>,
+        top_k: usize,
+        edge_bonus: u8,
+        depth: u8,
+    ) -> Vec<ClusterQueryResult> {
+        let mut per_bank = Vec::new();
+        for (&bank_id, bank) in &self.banks {
+            if let Some(query) = query_per_bank.get(&bank_id) {
+                per_bank.push((bank_id, bank.name.clone(), bank.query_lenient(query, top_k)));
+            }
+        }
+        let mut pool = fuse_results(per_bank);
 
-                all_results.push(ClusterQueryResult {
-                    bank_id,
-                    bank_name: bank.name.clone(),
-                    entry_id: r.entry_id,
-                    score: r.score,
-                    normalized_score: normalized,
-                });
+        let index: HashMap<BankRef, usize> = pool
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (BankRef { bank: r.bank_id, entry: r.entry_id }, i))
+            .collect();
+        // Strongest path weight per unordered candidate pair
+        let mut links: HashMap<(usize, usize), u8> = HashMap::new();
+        for (&start, &i) in &index {
+            for (reached, weight) in self.strongest_paths(start, depth) {
+                if let Some(&j) = index.get(&reached) {
+                    let link = links.entry((i.min(j), i.max(j))).or_insert(0);
+                    *link = (*link).max(weight);
+                }
             }
         }
 
-        all_results.sort_by(|a, b| b.normalized_score.cmp(&a.normalized_score));
-        all_results.truncate(top_k);
-        all_results
+        let strength: Vec<i64> = pool
+            .iter()
+            .map(|r| (r.normalized_score as i64 + 256).clamp(0, 512) / 2)
+            .collect();
+        let mut bonus = vec![0i64; pool.len()];
+        for (&(i, j), &weight) in &links {
+            let scale = edge_bonus as i64 * weight as i64;
+            bonus[i] += scale * strength[j] / (255 * 256);
+            bonus[j] += scale * strength[i] / (255 * 256);
+        }
+        for (result, bonus) in pool.iter_mut().zip(bonus) {
+            let boosted = result.normalized_score as i64 + bonus;
+            result.normalized_score = boosted.min(i32::MAX as i64) as i32;
+        }
+
+        pool.sort_unstable_by_key(|r| (Reverse(r.normalized_score), r.bank_id, r.entry_id));
+        pool.truncate(top_k);
+        pool
+    }
+
+    /// Every entry reachable from `start` within `depth` hops along
+    /// outgoing edges of any type, with the weight of the weakest edge on
+    /// the strongest path to it. Edges into banks outside this cluster are
+    /// not followed.
+    fn strongest_paths(&self, start: BankRef, depth: u8) -> HashMap<BankRef, u8> {
+        let mut best: HashMap<BankRef, u8> = HashMap::new();
+        let mut frontier = vec![(start, u8::MAX)];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for (node, reach) in frontier {
+                let Some(bank) = self.banks.get(&node.bank) else {
+                    continue;
+                };
+                for edge in bank.edges_from(node.entry) {
+                    if edge.target == start || !self.banks.contains_key(&edge.target.bank) {
+                        continue;
+                    }
+                    let weight = reach.min(edge.weight);
+                    let known = best.entry(edge.target).or_insert(0);
+                    if weight > *known {
+                        *known = weight;
+                        next.push((edge.target, weight));
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        best
     }
 
     /// Query a subset of banks by name prefix.
@@ -239,71 +824,839 @@ impl BankCluster {
         self.query_all(&query_map, top_k)
     }
 
+    /// The `top_n` most-connected entries across the cluster, as
+    /// `(entry, in_degree, out_degree)` sorted by total degree descending
+    /// (ties by bank, then entry id).
+    ///
+    /// In-degree counts edges from every bank in the cluster, so hubs fed
+    /// by other regions rank correctly. Edges pointing at entries outside
+    /// the cluster are ignored.
+    pub fn most_connected(&self, top_n: usize) -> Vec<(BankRef, usize, usize)> {
+        let mut in_degree: HashMap<BankRef, usize> = HashMap::new();
+        for bank in self.banks.values() {
+            for (_, edge) in bank.all_edges() {
+                *in_degree.entry(edge.target).or_insert(0) += 1;
+            }
+        }
+
+        let mut degrees: Vec<(BankRef, usize, usize)> = self
+            .banks
+            .values()
+            .flat_map(|bank| {
+                let in_degree = &in_degree;
+                bank.entries().map(move |(&entry, e)| {
+                    let r = BankRef { bank: bank.id, entry };
+                    (r, in_degree.get(&r).copied().unwrap_or(0), e.edges.len())
+                })
+            })
+            .collect();
+        degrees.sort_unstable_by(|a, b| {
+            (b.1 + b.2)
+                .cmp(&(a.1 + a.2))
+                .then(a.0.bank.cmp(&b.0.bank))
+                .then(a.0.entry.cmp(&b.0.entry))
+        });
+        degrees.truncate(top_n);
+        degrees
+    }
+
+    /// Group likely-duplicate entries within each bank.
+    ///
+    /// Two entries are linked when their sparse cosine similarity (x256)
+    /// is at least `min_similarity` in both directions; groups are the
+    /// connected components of those links, so every returned group has
+    /// two or more members. Comparison is pairwise, capped at
+    /// [`DEDUP_SCAN_LIMIT`] entries per bank. Output is sorted by bank,
+    /// then entry id, for stable diagnostics.
+    pub fn find_duplicates(&self, min_similarity: i32) -> Vec<Vec<BankRef>> {
+        let mut bank_ids: Vec<BankId> = self.banks.keys().copied().collect();
+        bank_ids.sort();
+
+        let mut groups = Vec::new();
+        for bank_id in bank_ids {
+            let bank = &self.banks[&bank_id];
+            let mut entries: Vec<(&EntryId, &crate::entry::BankEntry)> = bank.entries().collect();
+            entries.sort_by_key(|(id, _)| **id);
+            if entries.len() > DEDUP_SCAN_LIMIT {
+                log::warn!(
+                    "find_duplicates: {} has {} entries, scanning first {}",
+                    bank.label(),
+                    entries.len(),
+                    DEDUP_SCAN_LIMIT
+                );
+                entries.truncate(DEDUP_SCAN_LIMIT);
+            }
+
+            // Union-find over entry positions
+            let mut parent: Vec<usize> = (0..entries.len()).collect();
+            fn root(parent: &mut [usize], mut i: usize) -> usize {
+                while parent[i] != i {
+                    parent[i] = parent[parent[i]];
+                    i = parent[i];
+                }
+                i
+            }
+
+            for i in 0..entries.len() {
+                for j in (i + 1)..entries.len() {
+                    let (a, b) = (&entries[i].1.vector, &entries[j].1.vector);
+                    if sparse_cosine_similarity(a, b) >= min_similarity
+                        && sparse_cosine_similarity(b, a) >= min_similarity
+                    {
+                        let (ri, rj) = (root(&mut parent, i), root(&mut parent, j));
+                        if ri != rj {
+                            parent[rj.max(ri)] = ri.min(rj);
+                        }
+                    }
+                }
+            }
+
+            let mut by_root: HashMap<usize, Vec<BankRef>> = HashMap::new();
+            for (i, (id, _)) in entries.iter().enumerate() {
+                let r = root(&mut parent, i);
+                by_root.entry(r).or_default().push(BankRef {
+                    bank: bank_id,
+                    entry: **id,
+                });
+            }
+            let mut bank_groups: Vec<Vec<BankRef>> =
+                by_root.into_values().filter(|g| g.len() > 1).collect();
+            bank_groups.sort_by_key(|g| g[0].entry);
+            groups.extend(bank_groups);
+        }
+        groups
+    }
+
     /// Flush all dirty banks that have exceeded their persistence threshold.
     ///
-    /// Each bank is saved atomically (temp + rename) to the given directory.
-    /// Returns the number of banks flushed.
+    /// Each bank is saved atomically (temp + fsync + rename) to the given
+    /// directory, then the directory is fsynced once for the whole batch.
+    /// Banks are encoded through one reused buffer; with the `parallel`
+    /// feature the file writes run on scoped threads instead.
+    ///
+    /// A bank is marked persisted (and `on_flush` fires) only after its own
+    /// rename and the directory sync succeed. If any write fails, the banks
+    /// that were written are still marked and the first error is returned.
+    /// Banks with a background write in flight are skipped, as in
+    /// [`request_flush`](Self::request_flush); they are picked up once its
+    /// completion is applied. Returns the number of banks flushed.
+    #[cfg(feature = "std")]
     pub fn flush_dirty(&mut self, dir: &Path, current_tick: u64) -> Result<usize> {
-        let mut flushed = 0;
+        self.flush_dirty_report(dir, current_tick)
+            .map(|report| report.banks_flushed)
+    }
 
+    /// [`flush_dirty`](Self::flush_dirty), reporting the bytes written as
+    /// well as the bank count.
+    #[cfg(feature = "std")]
+    pub fn flush_dirty_report(&mut self, dir: &Path, current_tick: u64) -> Result<FlushReport> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("flush_dirty", tick = current_tick).entered();
         let ids_to_flush: Vec<BankId> = self
             .banks
             .iter()
             .filter(|(_, bank)| bank.should_persist(current_tick))
             .map(|(&id, _)| id)
             .collect();
+        self.flush_banks(dir, &ids_to_flush, current_tick)
+    }
 
-        for id in ids_to_flush {
-            if let Some(bank) = self.banks.get(&id) {
-                let path = dir.join(format!("{}.bank", bank.name));
-                codec::save_atomic(bank, &path)?;
-            }
-            if let Some(bank) = self.banks.get_mut(&id) {
-                bank.mark_persisted(current_tick);
-            }
-            flushed += 1;
-        }
+    /// [`flush_dirty`](Self::flush_dirty) restricted to banks whose names
+    /// start with `prefix`, so regions can persist on their own cadence
+    /// (see [`FlushSchedule`]).
+    ///
+    /// This does not touch the journal. Replay would re-apply records for
+    /// the banks written here on top of their newer snapshots, so a
+    /// journaled cluster should use
+    /// [`flush_prefix_with_journal`](Self::flush_prefix_with_journal).
+    #[cfg(feature = "std")]
+    pub fn flush_prefix(&mut self, dir: &Path, prefix: &str, current_tick: u64) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("flush_prefix", prefix, tick = current_tick).entered();
+        let ids_to_flush = self.dirty_ids_with_prefix(prefix, current_tick);
+        self.flush_banks(dir, &ids_to_flush, current_tick)
+            .map(|report| report.banks_flushed)
+    }
 
-        Ok(flushed)
+    #[cfg(feature = "std")]
+    fn dirty_ids_with_prefix(&self, prefix: &str, current_tick: u64) -> Vec<BankId> {
+        self.name_index
+            .iter()
+            .filter(|(name, _)| name.starts_with(prefix))
+            .filter(|(_, id)| self.banks[*id].should_persist(current_tick))
+            .map(|(_, &id)| id)
+            .collect()
     }
 
-    /// Load all `.bank` files from a directory into the cluster.
-    pub fn load_all(dir: &Path) -> Result<Self> {
-        let mut cluster = Self::new();
+    /// Write `ids`, sync the directory, and mark what was written.
+    #[cfg(feature = "std")]
+    fn flush_banks(
+        &mut self,
+        dir: &Path,
+        ids_to_flush: &[BankId],
+        current_tick: u64,
+    ) -> Result<FlushReport> {
+        let mut report = FlushReport::default();
+        let ids_to_flush = self.without_in_flight(ids_to_flush);
+        if ids_to_flush.is_empty() {
+            return Ok(report);
+        }
 
-        if !dir.exists() {
-            return Ok(cluster);
+        let (incremental, full): (Vec<BankId>, Vec<BankId>) =
+            ids_to_flush.iter().partition(|id| {
+                self.banks.get(id).is_some_and(|b| b.config().incremental_flush.is_some())
+            });
+        let mut results = self.write_banks(dir, &full);
+        results.extend(self.write_incremental(dir, &incremental));
+
+        let mut written = Vec::with_capacity(ids_to_flush.len());
+        let mut first_err = None;
+        for (id, path, result) in results {
+            match result {
+                Ok(bytes) => {
+                    report.bytes_written += bytes as u64;
+                    written.push((id, path));
+                }
+                Err(e) => {
+                    let name = self.banks.get(&id).map_or("?", |b| b.name.as_str());
+                    log::warn!("{}: flush to {:?} failed: {}", BankLabel { name, id }, path, e);
+                    first_err.get_or_insert(e);
+                }
+            }
         }
 
-        let entries = std::fs::read_dir(dir)?;
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().and_then(|e| e.to_str()) == Some("bank") {
-                match codec::load(&path) {
-                    Ok(bank) => {
-                        log::info!("loaded bank '{}' ({} entries)", bank.name, bank.len());
-                        cluster.add(bank);
-                    }
-                    Err(e) => {
-                        log::error!("failed to load {:?}: {}", path, e);
-                        return Err(e);
-                    }
+        let mut synced: Vec<&Path> = Vec::new();
+        for (_, path) in &written {
+            let parent = path.parent().unwrap_or(dir);
+            if !synced.contains(&parent) {
+                codec::sync_dir(parent)?;
+                synced.push(parent);
+            }
+        }
+        for (id, path) in &written {
+            if let Some(bank) = self.banks.get_mut(id) {
+                bank.mark_persisted(current_tick);
+                if let Err(e) = self.record_bank_path(*id, path) {
+                    first_err.get_or_insert(e);
                 }
             }
+            if let Some(observer) = &self.observer {
+                observer.on_flush(*id, path);
+            }
         }
 
-        Ok(cluster)
+        report.banks_flushed = written.len();
+        match first_err {
+            Some(e) => Err(e),
+            None => Ok(report),
+        }
     }
 
-    /// Get all bank IDs in the cluster.
-    pub fn bank_ids(&self) -> Vec<BankId> {
-        self.banks.keys().copied().collect()
+    /// `ids` minus the banks with a background write in flight.
+    #[cfg(feature = "std")]
+    fn without_in_flight(&self, ids: &[BankId]) -> Vec<BankId> {
+        match &self.background_flush {
+            Some(background) => {
+                ids.iter().filter(|id| !background.in_flight.contains(id)).copied().collect()
+            }
+            None => ids.to_vec(),
+        }
     }
 
-    /// Get all bank names in the cluster.
-    pub fn bank_names(&self) -> Vec<&str> {
-        self.name_index.keys().map(|s| s.as_str()).collect()
+    /// Encode and write each bank, reusing one encode buffer. Each result
+    /// is the number of bytes written.
+    #[cfg(all(feature = "std", not(feature = "parallel")))]
+    fn write_banks(&self, dir: &Path, ids: &[BankId]) -> Vec<(BankId, PathBuf, Result<usize>)> {
+        let mut buf = Vec::new();
+        ids.iter()
+            .filter_map(|id| self.banks.get(id))
+            .map(|bank| {
+                #[cfg(feature = "metrics")]
+                let started = std::time::Instant::now();
+                let path = flush_path(&self.bank_paths, &self.relocated, bank, dir);
+                let result = codec::encode_into(bank, &codec::EncodeOptions::default(), &mut buf)
+                    .and_then(|()| codec::write_atomic(&buf, &path))
+                    .map(|()| buf.len());
+                #[cfg(feature = "metrics")]
+                bank.metrics_recorder().record_flush_time(started.elapsed());
+                (bank.id, path, result)
+            })
+            .collect()
+    }
+
+    /// Write banks that set [`BankConfig::incremental_flush`] through
+    /// [`codec::save_incremental`], keeping each one's image for its next
+    /// flush. A failed write forgets the image, so the next flush of that
+    /// bank rewrites its file in full.
+    #[cfg(feature = "std")]
+    fn write_incremental(
+        &mut self,
+        dir: &Path,
+        ids: &[BankId],
+    ) -> Vec<(BankId, PathBuf, Result<usize>)> {
+        let images = &mut self.persisted_images;
+        ids.iter()
+            .filter_map(|id| self.banks.get(id))
+            .map(|bank| {
+                #[cfg(feature = "metrics")]
+                let started = std::time::Instant::now();
+                let path = flush_path(&self.bank_paths, &self.relocated, bank, dir);
+                let percent = bank.config().incremental_flush.unwrap_or(u8::MAX);
+                let image = images.entry(bank.id).or_default();
+                let result = codec::save_incremental(bank, &path, image, percent);
+                if result.is_err() {
+                    *image = codec::PersistedImage::default();
+                }
+                #[cfg(feature = "metrics")]
+                bank.metrics_recorder().record_flush_time(started.elapsed());
+                (bank.id, path, result.map(|write| write.bytes))
+            })
+            .collect()
+    }
+
+    /// Encode each bank, then write the files from scoped threads (one group
+    /// of banks per available core).
+    #[cfg(feature = "parallel")]
+    fn write_banks(&self, dir: &Path, ids: &[BankId]) -> Vec<(BankId, PathBuf, Result<usize>)> {
+        let mut results = Vec::with_capacity(ids.len());
+        let mut jobs: Vec<(BankId, PathBuf, Vec<u8>)> = Vec::with_capacity(ids.len());
+        for bank in ids.iter().filter_map(|id| self.banks.get(id)) {
+            #[cfg(feature = "metrics")]
+            let started = std::time::Instant::now();
+            let path = flush_path(&self.bank_paths, &self.relocated, bank, dir);
+            let encoded = codec::encode(bank);
+            #[cfg(feature = "metrics")]
+            bank.metrics_recorder().record_flush_time(started.elapsed());
+            match encoded {
+                Ok(bytes) => jobs.push((bank.id, path, bytes)),
+                Err(e) => results.push((bank.id, path, Err(e))),
+            }
+        }
+        if jobs.is_empty() {
+            return results;
+        }
+
+        let threads = std::thread::available_parallelism()
+            .map_or(4, |n| n.get())
+            .min(jobs.len());
+        let per_thread = jobs.len().div_ceil(threads);
+        #[cfg(feature = "metrics")]
+        let banks = &self.banks;
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = jobs
+                .chunks(per_thread)
+                .map(|group| {
+                    scope.spawn(move || {
+                        group
+                            .iter()
+                            .map(|(id, path, bytes)| {
+                                #[cfg(feature = "metrics")]
+                                let started = std::time::Instant::now();
+                                let result =
+                                    codec::write_atomic(bytes, path).map(|()| bytes.len());
+                                #[cfg(feature = "metrics")]
+                                if let Some(bank) = banks.get(id) {
+                                    bank.metrics_recorder().record_flush_time(started.elapsed());
+                                }
+                                (*id, path.clone(), result)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            for handle in handles {
+                let written = handle.join().unwrap_or_else(|p| std::panic::resume_unwind(p));
+                results.extend(written);
+            }
+        });
+        results
+    }
+
+    /// Directory the bank was last loaded from or written to, if any.
+    #[cfg(feature = "std")]
+    pub fn bank_dir(&self, id: BankId) -> Option<&Path> {
+        self.bank_paths.get(&id).and_then(|path| path.parent())
+    }
+
+    /// Record `path` as the bank's file. If the bank was last written in the
+    /// same directory under another name, that file is removed: `load_all`
+    /// would otherwise bring the old name back as a second copy.
+    #[cfg(feature = "std")]
+    fn record_bank_path(&mut self, id: BankId, path: &Path) -> Result<()> {
+        let Some(old) = self.bank_paths.insert(id, path.to_path_buf()) else {
+            return Ok(());
+        };
+        if old == path || old.parent() != path.parent() {
+            return Ok(());
+        }
+        match std::fs::remove_file(&old) {
+            Ok(()) => {
+                log::info!("removed stale {:?} after rename to {:?}", old, path);
+                codec::sync_dir(old.parent().unwrap_or(Path::new(".")))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Remove a `.bank` file written under a name no bank answers to any
+    /// more. Failures are only logged.
+    #[cfg(feature = "std")]
+    fn remove_orphaned_file(&self, path: &Path) {
+        let owned = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|name| self.name_index.contains_key(name));
+        if owned {
+            return;
+        }
+        if let Err(e) = std::fs::remove_file(path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("removing superseded {:?} failed: {}", path, e);
+            }
+        } else if let Some(parent) = path.parent() {
+            if let Err(e) = codec::sync_dir(parent) {
+                log::warn!("syncing {:?} failed: {}", parent, e);
+            }
+        }
+    }
+
+    /// Move a bank's `.bank` file to `new_dir`, e.g. onto a faster disk.
+    ///
+    /// The bank is written atomically into `new_dir` first; only then is the
+    /// file in its previous directory (see [`bank_dir`](Self::bank_dir))
+    /// removed, so a failure part way leaves at least one complete copy.
+    /// Its persistence state is unchanged. Later synchronous flushes write
+    /// the bank into `new_dir` whatever directory they are given; a
+    /// background worker keeps writing into its own directory, so restart
+    /// it there if it serves this bank. Fails if a background flush of the bank is
+    /// still in flight, since it would recreate the old file, and for a
+    /// [forked](DataBank::fork) bank, which is never written to disk.
+    #[cfg(feature = "std")]
+    pub fn relocate_bank(&mut self, id: BankId, new_dir: &Path) -> Result<()> {
+        let bank = self.banks.get(&id).ok_or(DataBankError::BankNotFound { id })?;
+        if bank.is_forked() {
+            return Err(DataBankError::InvalidConfig(format!(
+                "{}: forked banks are never written, relocate the original",
+                bank.label()
+            )));
+        }
+        if let Some(background) = &self.background_flush {
+            if background.in_flight.contains(&id) {
+                return Err(DataBankError::InvalidConfig(format!(
+                    "{}: background flush in flight, poll before relocating",
+                    bank.label()
+                )));
+            }
+        }
+        let path = new_dir.join(format!("{}.bank", bank.name));
+        codec::write_atomic(&codec::encode(bank)?, &path)?;
+        codec::sync_dir(new_dir)?;
+
+        self.relocated.insert(id);
+        // The image describes the old file; the next incremental flush
+        // rewrites the new one in full
+        self.persisted_images.remove(&id);
+        if let Some(old_path) = self.bank_paths.insert(id, path.clone()) {
+            if old_path != path {
+                match std::fs::remove_file(&old_path) {
+                    Ok(()) => codec::sync_dir(old_path.parent().unwrap_or(Path::new(".")))?,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        log::info!("{}: relocated to {:?}", bank.label(), new_dir);
+        Ok(())
+    }
+
+    /// Start a background flush worker writing into `dir`.
+    ///
+    /// Afterwards [`request_flush`](Self::request_flush) hands encoded
+    /// snapshots to the worker instead of writing on the caller's thread.
+    /// A worker that is already running is shut down (and drained) first.
+    #[cfg(feature = "std")]
+    pub fn start_flush_worker(&mut self, dir: &Path) -> Result<()> {
+        self.shutdown_flush_worker()?;
+        let (jobs, rx) = mpsc::channel();
+        let worker = FlushWorker::spawn(dir.to_path_buf(), rx)?;
+        self.background_flush = Some(BackgroundFlush {
+            jobs,
+            worker,
+            in_flight: HashSet::new(),
+        });
+        Ok(())
+    }
+
+    /// Encode every bank that should persist and enqueue it on the flush
+    /// worker. Banks with a write already in flight are skipped.
+    ///
+    /// Banks are NOT marked persisted here; that happens in
+    /// [`poll_flushes`](Self::poll_flushes) once the worker reports the
+    /// file durably written. Returns the number of banks enqueued.
+    #[cfg(feature = "std")]
+    pub fn request_flush(&mut self, current_tick: u64) -> Result<usize> {
+        let Some(background) = self.background_flush.as_mut() else {
+            return Err(DataBankError::InvalidConfig(
+                "request_flush called without a flush worker".into(),
+            ));
+        };
+
+        let mut enqueued = 0;
+        for (&id, bank) in &self.banks {
+            if background.in_flight.contains(&id) || !bank.should_persist(current_tick) {
+                continue;
+            }
+            #[cfg(feature = "metrics")]
+            let started = std::time::Instant::now();
+            let job = FlushJob {
+                bank_id: id,
+                file_name: format!("{}.bank", bank.name),
+                bytes: codec::encode(bank)?,
+                tick: current_tick,
+                mutations: bank.mutations_since_persist(),
+                generation: bank.generation(),
+            };
+            #[cfg(feature = "metrics")]
+            bank.metrics_recorder().record_flush_time(started.elapsed());
+            if background.jobs.send(job).is_err() {
+                return Err(DataBankError::Io(std::io::Error::new(
+                    std::io::ErrorKind::BrokenPipe,
+                    "flush worker has stopped",
+                )));
+            }
+            background.in_flight.insert(id);
+            enqueued += 1;
+        }
+        Ok(enqueued)
+    }
+
+    /// Apply completed background writes without blocking.
+    ///
+    /// Each successful write marks its bank persisted as of the snapshot
+    /// (later mutations stay dirty) and fires `on_flush`; a failed write
+    /// leaves the bank dirty so the next request retries it.
+    #[cfg(feature = "std")]
+    pub fn poll_flushes(&mut self) -> Vec<FlushCompletion> {
+        let Some(background) = self.background_flush.as_mut() else {
+            return Vec::new();
+        };
+        let completions = background.worker.try_completions();
+        for done in &completions {
+            self.apply_flush_completion(done);
+        }
+        completions
+    }
+
+    /// Background writes enqueued by [`request_flush`](Self::request_flush)
+    /// whose completion has not been applied yet. 0 without a worker.
+    #[cfg(feature = "std")]
+    pub fn pending_flushes(&self) -> usize {
+        self.background_flush
+            .as_ref()
+            .map_or(0, |background| background.in_flight.len())
+    }
+
+    /// Block until every pending background write has completed, or
+    /// `timeout` elapses, applying completions as they arrive exactly as
+    /// [`poll_flushes`](Self::poll_flushes) does. The worker keeps running.
+    ///
+    /// Returns the completions applied; if
+    /// [`pending_flushes`](Self::pending_flushes) is still non-zero
+    /// afterwards, the wait timed out.
+    #[cfg(feature = "std")]
+    pub fn wait_for_flushes(&mut self, timeout: Duration) -> Vec<FlushCompletion> {
+        let deadline = Instant::now() + timeout;
+        let mut completions = Vec::new();
+        while self.pending_flushes() > 0 {
+            let Some(background) = self.background_flush.as_ref() else {
+                break;
+            };
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let Some(done) = background.worker.recv_completion(remaining) else {
+                break;
+            };
+            self.apply_flush_completion(&done);
+            completions.push(done);
+        }
+        completions
+    }
+
+    /// Stop the flush worker after it finishes every queued write, and apply
+    /// all remaining completions. Returns the number of banks persisted.
+    #[cfg(feature = "std")]
+    pub fn shutdown_flush_worker(&mut self) -> Result<usize> {
+        let Some(background) = self.background_flush.take() else {
+            return Ok(0);
+        };
+        let BackgroundFlush { jobs, worker, .. } = background;
+        drop(jobs); // worker exits once the queue is drained
+        let completions = worker.join();
+
+        let mut persisted = 0;
+        let mut first_error = None;
+        for done in completions {
+            self.apply_flush_completion(&done);
+            match done.result {
+                Ok(()) => persisted += 1,
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(persisted),
+        }
+    }
+
+    /// Apply one background write. A superseded write (the bank was
+    /// renamed since the snapshot, or a newer snapshot is already
+    /// persisted) neither marks the bank nor becomes its recorded file, so
+    /// its cleanup cannot remove the live file; a copy under a name no bank
+    /// answers to any more is removed instead, as `load_all` would revive it.
+    #[cfg(feature = "std")]
+    fn apply_flush_completion(&mut self, done: &FlushCompletion) {
+        if let Some(background) = self.background_flush.as_mut() {
+            background.in_flight.remove(&done.bank_id);
+        }
+        match &done.result {
+            Ok(()) => {
+                if let Some(bank) = self.banks.get_mut(&done.bank_id) {
+                    let current = done
+                        .path
+                        .file_name()
+                        .is_some_and(|f| *f == *format!("{}.bank", bank.name));
+                    if current && done.generation >= bank.persisted_generation() {
+                        bank.mark_generation_persisted(done.tick, done.generation, done.mutations);
+                        if let Err(e) = self.record_bank_path(done.bank_id, &done.path) {
+                            log::warn!("stale file cleanup after {:?} failed: {}", done.path, e);
+                        }
+                    } else {
+                        log::info!(
+                            "{}: background write to {:?} superseded",
+                            bank.label(),
+                            done.path
+                        );
+                        if !current && self.bank_paths.get(&done.bank_id) != Some(&done.path) {
+                            self.remove_orphaned_file(&done.path);
+                        }
+                    }
+                }
+                if let Some(observer) = &self.observer {
+                    observer.on_flush(done.bank_id, &done.path);
+                }
+            }
+            Err(e) => {
+                let name = self.banks.get(&done.bank_id).map_or("?", |b| b.name.as_str());
+                let label = BankLabel {
+                    name,
+                    id: done.bank_id,
+                };
+                log::error!("{}: background flush to {:?} failed: {}", label, done.path, e);
+            }
+        }
+    }
+
+    /// Load all `.bank` files from a directory into the cluster.
+    #[cfg(feature = "std")]
+    pub fn load_all(dir: &Path) -> Result<Self> {
+        Self::load_all_report(dir).map(|(cluster, _)| cluster)
+    }
+
+    /// [`load_all`](Self::load_all), also returning what was loaded from
+    /// each file and how long it took to decode. Files are listed in path
+    /// order.
+    #[cfg(feature = "std")]
+    pub fn load_all_report(dir: &Path) -> Result<(Self, LoadReport)> {
+        let started = Instant::now();
+        let mut cluster = Self::new();
+        let mut report = LoadReport::default();
+
+        if !dir.exists() {
+            return Ok((cluster, report));
+        }
+
+        let entries = std::fs::read_dir(dir)?;
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("bank") {
+                let decode_started = Instant::now();
+                match codec::load(&path) {
+                    Ok(bank) => {
+                        log::info!("loaded {} ({} entries)", bank.label(), bank.len());
+                        report.banks.push(BankLoadRecord {
+                            path: path.clone(),
+                            bank_id: bank.id,
+                            entries: bank.len(),
+                            bytes: entry.metadata()?.len(),
+                            decode_micros: micros(decode_started.elapsed()),
+                        });
+                        cluster.bank_paths.insert(bank.id, path.clone());
+                        cluster.add(bank)?;
+                    }
+                    Err(e) => {
+                        log::error!("failed to load {:?}: {}", path, e);
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        report.banks.sort_by(|a, b| a.path.cmp(&b.path));
+        report.total_micros = micros(started.elapsed());
+        Ok((cluster, report))
+    }
+
+    /// Export every bank as `<name>.csv` in `dir` (created if missing) via
+    /// [`codec::export_csv`]. Returns the number of files written.
+    #[cfg(feature = "std")]
+    pub fn export_csv_dir(&self, dir: &Path, include_vectors: bool) -> Result<usize> {
+        std::fs::create_dir_all(dir)?;
+        for bank in self.banks.values() {
+            let file = std::fs::File::create(dir.join(format!("{}.csv", bank.name)))?;
+            codec::export_csv(bank, std::io::BufWriter::new(file), include_vectors)?;
+        }
+        Ok(self.banks.len())
+    }
+
+    /// Consume the cluster, yielding its banks.
+    #[cfg(feature = "std")]
+    pub(crate) fn into_banks(self) -> impl Iterator<Item = DataBank> {
+        self.banks.into_values()
+    }
+
+    /// Get all bank IDs in the cluster.
+    pub fn bank_ids(&self) -> Vec<BankId> {
+        self.banks.keys().copied().collect()
+    }
+
+    /// Configured vector width of every bank in the cluster.
+    pub fn widths(&self) -> HashMap<BankId, u16> {
+        self.banks
+            .iter()
+            .map(|(&id, bank)| (id, bank.config().vector_width))
+            .collect()
+    }
+
+    /// Check up front that `target` can take vectors from `source`.
+    ///
+    /// Cross-bank operations that move vectors, such as
+    /// [`migrate_entry`](Self::migrate_entry) and
+    /// [`merge_from`](Self::merge_from), call this before touching either
+    /// bank. Returns the shared width, `BankNotFound` if
+    /// either bank is missing, or `WidthIncompatible` naming `target` when
+    /// the widths differ.
+    pub fn check_width_compatible(&self, source: BankId, target: BankId) -> Result<u16> {
+        let width = |id: BankId| {
+            self.banks
+                .get(&id)
+                .map(|bank| bank.config().vector_width)
+                .ok_or(DataBankError::BankNotFound { id })
+        };
+        let (expected, got) = (width(source)?, width(target)?);
+        if expected != got {
+            return Err(DataBankError::WidthIncompatible {
+                expected,
+                got,
+                bank: target,
+            });
+        }
+        Ok(expected)
+    }
+
+    /// Move one entry into bank `to`, after checking the widths with
+    /// [`check_width_compatible`](Self::check_width_compatible). Returns the
+    /// entry's id in `to`.
+    ///
+    /// The copy keeps the vector, temperature, created tick, TTL,
+    /// namespace, outgoing edges, origin, pin, confidence and access
+    /// history; only the debug tag is dropped. Edges elsewhere that point
+    /// at the old id are not rewritten. A leased entry is refused with
+    /// `EntryLeased`, and one with more edges than `to` allows with
+    /// `EdgeLimitReached`.
+    ///
+    /// With a journal configured the copy is recorded as one `InsertCopy`
+    /// record for `to` and the removal as a `Remove` for the source bank,
+    /// each before it is applied, so replay rebuilds the entry whole even
+    /// if only one of the two banks was snapshotted since. If journaling
+    /// the removal fails, the error is returned and the entry is left in
+    /// both banks, exactly as the journal then describes.
+    pub fn migrate_entry(&mut self, from: BankRef, to: BankId) -> Result<EntryId> {
+        self.check_width_compatible(from.bank, to)?;
+        let source = &self.banks[&from.bank];
+        let entry = source
+            .get(from.entry)
+            .ok_or(DataBankError::EntryNotFound { id: from.entry })?;
+        if let Some((owner, _)) = source.lease_holder(from.entry) {
+            return Err(DataBankError::EntryLeased { id: from.entry, owner });
+        }
+        if from.bank == to {
+            return Ok(from.entry);
+        }
+        let max = self.banks[&to].config().max_edges_per_entry;
+        if max > 0 && entry.edges().len() > max as usize {
+            return Err(DataBankError::EdgeLimitReached { max });
+        }
+        let entry = entry.clone();
+
+        #[cfg(feature = "std")]
+        self.journal_mutation(crate::journal::JournalEntry::InsertCopy {
+            bank_id: to,
+            entry: entry.clone(),
+        })?;
+        let target = self.banks.get_mut(&to).ok_or(DataBankError::BankNotFound { id: to })?;
+        let before = target.len();
+        let id = target.insert_copy(&entry)?;
+        let evicted = before + 1 - target.len();
+        if evicted > 0 {
+            if let Some(observer) = &self.observer {
+                observer.on_eviction(to, evicted);
+            }
+        }
+        #[cfg(feature = "std")]
+        self.journal_mutation(crate::journal::JournalEntry::Remove {
+            bank_id: from.bank,
+            entry_id: from.entry,
+        })?;
+        if let Some(source) = self.banks.get_mut(&from.bank) {
+            source.remove(from.entry);
+        }
+        Ok(id)
+    }
+
+    /// Move every unleased entry of bank `source` into bank `target`, in id
+    /// order, through [`migrate_entry`](Self::migrate_entry). The widths
+    /// are checked once, before anything moves. Returns `(old, new)` id
+    /// pairs; leased entries stay behind.
+    pub fn merge_from(
+        &mut self,
+        source: BankId,
+        target: BankId,
+    ) -> Result<Vec<(EntryId, EntryId)>> {
+        self.check_width_compatible(source, target)?;
+        if source == target {
+            return Ok(Vec::new());
+        }
+        let bank = &self.banks[&source];
+        let mut ids: Vec<EntryId> = bank
+            .entries()
+            .map(|(&id, _)| id)
+            .filter(|&id| bank.lease_holder(id).is_none())
+            .collect();
+        ids.sort_unstable();
+        let mut moved = Vec::with_capacity(ids.len());
+        for old in ids {
+            let new = self.migrate_entry(BankRef { bank: source, entry: old }, target)?;
+            moved.push((old, new));
+        }
+        Ok(moved)
+    }
+
+    /// Get all bank names in the cluster.
+    pub fn bank_names(&self) -> Vec<&str> {
+        self.name_index.keys().map(|s| s.as_str()).collect()
     }
 
     /// Number of banks in the cluster.
@@ -316,106 +1669,737 @@ impl BankCluster {
         self.banks.is_empty()
     }
 
+    /// Estimated bytes held by all banks plus the name index. Uses the same
+    /// model as [`DataBank::approx_bytes`].
+    pub fn approx_bytes(&self) -> usize {
+        let banks: usize = self.banks.values().map(DataBank::approx_bytes).sum();
+        let names: usize = self
+            .name_index
+            .keys()
+            .map(|name| core::mem::size_of::<(String, BankId)>() + 1 + name.capacity())
+            .sum();
+        banks + names
+    }
+
+    /// Snapshot every bank's runtime counters, keyed by bank.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_snapshot(&self) -> HashMap<BankId, BankMetrics> {
+        self.banks
+            .iter()
+            .map(|(&id, bank)| (id, bank.metrics()))
+            .collect()
+    }
+
+    /// Dirty banks with their persistence state, most urgent first: banks
+    /// past their flush threshold, then by mutations since the last flush,
+    /// then by ticks since it, then by id.
+    pub fn dirty_banks(&self, current_tick: u64) -> Vec<(BankId, PersistenceState)> {
+        let mut dirty: Vec<(BankId, PersistenceState, bool)> = self
+            .banks
+            .iter()
+            .filter(|(_, bank)| bank.is_dirty())
+            .map(|(&id, bank)| (id, bank.persistence_state(), bank.should_persist(current_tick)))
+            .collect();
+        dirty.sort_by(|a, b| {
+            b.2.cmp(&a.2)
+                .then(b.1.mutations_since_persist.cmp(&a.1.mutations_since_persist))
+                .then(a.1.last_persist_tick.cmp(&b.1.last_persist_tick))
+                .then(a.0.cmp(&b.0))
+        });
+        dirty.into_iter().map(|(id, state, _)| (id, state)).collect()
+    }
+
+    /// Current journal size in bytes, including buffered writes. Zero when
+    /// the cluster has no journal.
+    #[cfg(feature = "std")]
+    pub fn journal_len_bytes(&self) -> Result<u64> {
+        match &self.journal_writer {
+            Some(writer) => Ok(writer.len_bytes()?),
+            None => Ok(0),
+        }
+    }
+
     /// Record a mutation to the journal (if one is configured).
+    ///
+    /// Outside a tick the record is flushed immediately. Between
+    /// [`begin_tick`](Self::begin_tick) and [`end_tick`](Self::end_tick) it
+    /// is buffered and flushed with the rest of the tick.
+    ///
+    /// Call this before applying the mutation, and skip the mutation if it
+    /// fails; [`apply_journaled`](Self::apply_journaled) does both.
+    #[cfg(feature = "std")]
     pub fn journal_mutation(&mut self, entry: crate::journal::JournalEntry) -> Result<()> {
         if let Some(ref mut writer) = self.journal_writer {
             writer.append(&entry)?;
-            writer.flush()?;
+            if self.in_tick {
+                self.journal_unflushed += 1;
+            } else {
+                writer.flush()?;
+            }
+            #[cfg(feature = "metrics")]
+            if let Some(bank) = self.banks.get(&entry.bank_id()) {
+                bank.metrics_recorder().record_journal_record();
+            }
         }
         Ok(())
     }
 
+    /// Apply a mutation write-ahead: journal it, then apply it exactly as
+    /// replay would. Returns whether its target bank/entry was found.
+    ///
+    /// A mutation that would be refused (its bank or entry is missing, or
+    /// it removes a leased entry) is neither journaled nor applied, and
+    /// `Ok(false)` is returned.
+    ///
+    /// The record is appended, and outside a tick flushed, before the bank
+    /// changes, so a crash can never leave a visible mutation unrecorded.
+    /// If the append or flush fails the mutation is not applied and the
+    /// error is returned; a failed flush is `SyncFailed` (Fatal), since the
+    /// record may still reach the file later. Inside a tick the mutation is
+    /// applied once appended and becomes durable at `end_tick`.
+    ///
+    /// An `Insert` takes whatever id the bank assigns, as in replay.
+    #[cfg(feature = "std")]
+    pub fn apply_journaled(&mut self, entry: crate::journal::JournalEntry) -> Result<bool> {
+        if !crate::journal::entry_applies(&entry, self) {
+            return Ok(false);
+        }
+        self.journal_mutation(entry.clone())?;
+        Ok(crate::journal::apply_entry(&entry, self))
+    }
+
+    /// Start buffering journal records until [`end_tick`](Self::end_tick).
+    ///
+    /// All mutations journaled during the tick share durability: a crash
+    /// before `end_tick` may lose any or all of them, but never part of an
+    /// earlier tick. Calling this inside a tick does nothing.
+    #[cfg(feature = "std")]
+    pub fn begin_tick(&mut self) {
+        self.in_tick = true;
+    }
+
+    /// Flush the journal records buffered since
+    /// [`begin_tick`](Self::begin_tick) and return to flushing each
+    /// mutation. Returns the number of records flushed.
+    #[cfg(feature = "std")]
+    pub fn end_tick(&mut self) -> Result<usize> {
+        self.in_tick = false;
+        let pending = core::mem::take(&mut self.journal_unflushed);
+        if pending > 0 {
+            if let Some(writer) = self.journal_writer.as_mut() {
+                writer.flush()?;
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Journal flushes since the journal was opened. Zero when the cluster
+    /// has no journal.
+    #[cfg(feature = "std")]
+    pub fn journal_flush_count(&self) -> u64 {
+        self.journal_writer.as_ref().map_or(0, JournalWriter::flush_count)
+    }
+
+    /// Read a journal file and replay it onto this cluster.
+    ///
+    /// Returns the number of entries replayed. Does not truncate the journal.
+    /// Corrupt or skipped records are logged; use
+    /// [`replay_journal_checked`](Self::replay_journal_checked) to inspect them.
+    #[cfg(feature = "std")]
+    pub fn replay_journal(&mut self, journal_path: &Path) -> Result<usize> {
+        let (count, issues) = self.replay_journal_checked(journal_path)?;
+        for issue in issues {
+            log::warn!("{:?}: {}", journal_path, issue);
+        }
+        Ok(count)
+    }
+
+    /// Replay a journal, returning the applied count and any non-fatal
+    /// journal problems (`CorruptRecord`, `ReplaySkipped`,
+    /// `ReplayChecksumMismatch`).
+    ///
+    /// Failing to open an existing journal is returned as an error.
+    #[cfg(feature = "std")]
+    pub fn replay_journal_checked(
+        &mut self,
+        journal_path: &Path,
+    ) -> Result<(usize, Vec<JournalError>)> {
+        self.replay_journal_progress(journal_path, |_, _| {})
+    }
+
+    /// [`replay_journal_checked`](Self::replay_journal_checked), streaming
+    /// the journal one record at a time so memory use does not grow with
+    /// its length, and calling `progress(records_applied, bytes_consumed)`
+    /// every [`REPLAY_PROGRESS_INTERVAL`] records and once at the end.
+    #[cfg(feature = "std")]
+    pub fn replay_journal_progress(
+        &mut self,
+        journal_path: &Path,
+        mut progress: impl FnMut(usize, u64),
+    ) -> Result<(usize, Vec<JournalError>)> {
+        let mut stream = JournalReader::stream(journal_path)?;
+        let mut counts = journal::ReplayCounts::default();
+        let mut read = 0usize;
+        while let Some(entry) = stream.next() {
+            counts.replay(&entry, self);
+            read += 1;
+            if read.is_multiple_of(REPLAY_PROGRESS_INTERVAL) {
+                progress(counts.applied, stream.bytes_consumed());
+            }
+        }
+        let mut issues: Vec<JournalError> = stream.take_error().into_iter().collect();
+        if read == 0 {
+            return Ok((0, issues));
+        }
+        progress(counts.applied, stream.bytes_consumed());
+        let count = counts.applied;
+        if counts.skipped > 0 {
+            issues.push(JournalError::ReplaySkipped { count: counts.skipped });
+        }
+        if counts.checksum_mismatches > 0 {
+            let count = counts.checksum_mismatches;
+            issues.push(JournalError::ReplayChecksumMismatch { count });
+        }
+        log::info!("replayed {} journal entries from {:?}", count, journal_path);
+        if let Some(observer) = &self.observer {
+            observer.on_replay(count);
+        }
+        Ok((count, issues))
+    }
+
     /// Load cluster from directory with journal replay.
     ///
     /// 1. Load all `.bank` files
     /// 2. Find and replay `.journal` file if it exists
     /// 3. Truncate journal after successful replay
+    ///
+    /// Journal problems that leave the snapshots intact are logged and
+    /// loading continues; see
+    /// [`load_with_journal_checked`](Self::load_with_journal_checked).
+    #[cfg(feature = "std")]
     pub fn load_with_journal(dir: &Path) -> Result<Self> {
-        let mut cluster = Self::load_all(dir)?;
+        let (cluster, issues) = Self::load_with_journal_checked(dir)?;
+        for issue in issues {
+            log::warn!("{:?}: {}", dir, issue);
+        }
+        Ok(cluster)
+    }
+
+    /// Load cluster from directory with journal replay, classifying failures.
+    ///
+    /// - `Err(..)`: a snapshot could not be loaded (`Io`, `Codec`,
+    ///   `ChecksumMismatch`), the existing journal could not be opened, or
+    ///   a fresh journal could not be created. The cluster is not usable.
+    /// - `Ok((cluster, issues))`: all snapshots loaded. `issues` lists
+    ///   journal records that were lost (`CorruptRecord`), could not be
+    ///   applied (`ReplaySkipped`), or were refused because their vector
+    ///   failed its entry checksum (`ReplayChecksumMismatch`); the caller
+    ///   decides whether to continue.
+    ///
+    /// Errors and issues carry a severity (`DataBankError::severity`,
+    /// `JournalError::severity`): everything returned as `Err` is Fatal and
+    /// every entry in `issues` is Degraded.
+    #[cfg(feature = "std")]
+    pub fn load_with_journal_checked(dir: &Path) -> Result<(Self, Vec<JournalError>)> {
+        Self::load_with_journal_progress(dir, |_, _| {})
+    }
+
+    /// [`load_with_journal_checked`](Self::load_with_journal_checked),
+    /// reporting replay progress as
+    /// [`replay_journal_progress`](Self::replay_journal_progress) does so
+    /// the caller can log boot progress through a long journal.
+    #[cfg(feature = "std")]
+    pub fn load_with_journal_progress(
+        dir: &Path,
+        progress: impl FnMut(usize, u64),
+    ) -> Result<(Self, Vec<JournalError>)> {
+        let (cluster, _, issues) = Self::load_with_journal_reported(dir, progress)?;
+        Ok((cluster, issues))
+    }
+
+    /// [`load_with_journal_checked`](Self::load_with_journal_checked),
+    /// returning a [`LoadReport`] instead of the raw journal issues: the
+    /// per-file outcomes of [`load_all_report`](Self::load_all_report), a
+    /// replay summary, and each issue rendered as a warning.
+    #[cfg(feature = "std")]
+    pub fn load_with_journal_report(dir: &Path) -> Result<(Self, LoadReport)> {
+        let (cluster, report, _) = Self::load_with_journal_reported(dir, |_, _| {})?;
+        Ok((cluster, report))
+    }
+
+    #[cfg(feature = "std")]
+    fn load_with_journal_reported(
+        dir: &Path,
+        progress: impl FnMut(usize, u64),
+    ) -> Result<(Self, LoadReport, Vec<JournalError>)> {
+        let started = Instant::now();
+        let (mut cluster, mut report) = Self::load_all_report(dir)?;
+        let mut issues = Vec::new();
 
         let journal_path = dir.join("databank.journal");
         if journal_path.exists() {
-            let entries = JournalReader::read_all(&journal_path)?;
-            if !entries.is_empty() {
-                let count = JournalReader::replay(&entries, &mut cluster)?;
-                log::info!("replayed {} journal entries from {:?}", count, journal_path);
-            }
-            journal::truncate_journal(&journal_path)?;
+            let bytes = std::fs::metadata(&journal_path)?.len();
+            let (applied, replay_issues) =
+                cluster.replay_journal_progress(&journal_path, progress)?;
+            issues = replay_issues;
+            journal::truncate_journal(&journal_path).map_err(JournalError::SyncFailed)?;
+            report.journal = Some(ReplaySummary::new(applied, bytes, &issues));
+            report.warnings.extend(issues.iter().map(|issue| issue.to_string()));
         }
 
         // Open a fresh journal for ongoing mutations
         let writer = JournalWriter::open(&journal_path)?;
         cluster.journal_writer = Some(writer);
 
-        Ok(cluster)
+        report.total_micros = micros(started.elapsed());
+        Ok((cluster, report, issues))
     }
 
     /// Flush dirty banks AND truncate journal.
     ///
     /// After a full snapshot, the journal is no longer needed because all
-    /// mutations are captured in the `.bank` files.
+    /// mutations are captured in the `.bank` files. While a background
+    /// write is in flight the journal is kept: that bank was skipped, and
+    /// its records are all that hold its newer mutations.
+    #[cfg(feature = "std")]
     pub fn flush_dirty_with_journal(
         &mut self,
         dir: &Path,
         current_tick: u64,
     ) -> Result<usize> {
-        let flushed = self.flush_dirty(dir, current_tick)?;
+        self.flush_dirty_with_journal_report(dir, current_tick)
+            .map(|report| report.banks_flushed)
+    }
 
-        if flushed > 0 {
+    /// [`flush_dirty_with_journal`](Self::flush_dirty_with_journal),
+    /// reporting the bytes written and whether the journal was truncated.
+    #[cfg(feature = "std")]
+    pub fn flush_dirty_with_journal_report(
+        &mut self,
+        dir: &Path,
+        current_tick: u64,
+    ) -> Result<FlushReport> {
+        let mut report = self.flush_dirty_report(dir, current_tick)?;
+
+        if report.banks_flushed > 0 && self.pending_flushes() == 0 {
             let journal_path = dir.join("databank.journal");
             journal::truncate_journal(&journal_path)?;
+            report.journal_truncated = true;
         }
 
-        Ok(flushed)
+        Ok(report)
     }
-}
 
-/// Compute mean and standard deviation of query result scores (integer arithmetic).
-fn z_score_params(results: &[QueryResult]) -> (i32, i32) {
-    if results.is_empty() {
-        return (0, 0);
-    }
-    let n = results.len() as i64;
-    let sum: i64 = results.iter().map(|r| r.score as i64).sum();
-    let mean = (sum / n) as i32;
+    /// Flush dirty banks under `prefix` AND drop their records from the
+    /// journal.
+    ///
+    /// Records for other banks stay in the journal, which is rewritten
+    /// atomically without the flushed banks' records (see
+    /// [`journal::drop_banks_from_journal`]). If only some banks could be
+    /// written, the journal is left untouched and the error returned.
+    #[cfg(feature = "std")]
+    pub fn flush_prefix_with_journal(
+        &mut self,
+        dir: &Path,
+        prefix: &str,
+        current_tick: u64,
+    ) -> Result<usize> {
+        // A bank skipped for an in-flight write keeps its records
+        let ids = self.without_in_flight(&self.dirty_ids_with_prefix(prefix, current_tick));
+        let flushed = self.flush_banks(dir, &ids, current_tick)?.banks_flushed;
+
+        if flushed > 0 {
+            let journal_path = dir.join("databank.journal");
+            if let Some(writer) = self.journal_writer.as_mut() {
+                writer.flush()?;
+            }
+            journal::drop_banks_from_journal(&journal_path, &ids)?;
+            if let Some(writer) = self.journal_writer.as_mut() {
+                writer.reopen(&journal_path)?;
+            }
+        }
 
-    if n < 2 {
-        return (mean, 1); // avoid division by zero; stddev=1 for single result
+        Ok(flushed)
     }
+}
 
-    let variance: i64 = results.iter()
-        .map(|r| {
-            let diff = r.score as i64 - mean as i64;
-            diff * diff
-        })
-        .sum::<i64>() / (n - 1);
+/// What one synchronous flush wrote, for I/O dashboards.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlushReport {
+    /// Banks written and marked persisted.
+    pub banks_flushed: usize,
+    /// Encoded `.bank` bytes written across those banks (file contents
+    /// only, not filesystem overhead).
+    pub bytes_written: u64,
+    /// Whether the journal was truncated afterwards.
+    pub journal_truncated: bool,
+}
 
-    let stddev = isqrt_i64(variance) as i32;
-    (mean, stddev.max(1)) // clamp to 1 to avoid division by zero
+/// What a boot-time load did, from
+/// [`BankCluster::load_all_report`] or
+/// [`BankCluster::load_with_journal_report`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadReport {
+    /// One record per `.bank` file loaded, in path order.
+    pub banks: Vec<BankLoadRecord>,
+    /// Journal replay, if a journal was found.
+    pub journal: Option<ReplaySummary>,
+    /// Non-fatal problems, one line each.
+    pub warnings: Vec<String>,
+    /// Wall time for the whole load, in microseconds.
+    pub total_micros: u64,
 }
 
-/// Integer square root (same algorithm as similarity.rs).
-fn isqrt_i64(n: i64) -> i64 {
-    if n <= 0 { return 0; }
-    if n == 1 { return 1; }
-    let mut x = 1i64 << (((64 - n.leading_zeros()) + 1) / 2);
-    for _ in 0..8 {
-        let next = (x + n / x) / 2;
-        if next >= x { break; }
-        x = next;
-    }
-    x
+/// One `.bank` file in a [`LoadReport`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BankLoadRecord {
+    pub path: PathBuf,
+    pub bank_id: BankId,
+    pub entries: usize,
+    /// File size on disk, delta sections included.
+    pub bytes: u64,
+    /// Time to read and decode the file, in microseconds.
+    pub decode_micros: u64,
 }
 
-impl Default for BankCluster {
-    fn default() -> Self {
-        Self::new()
+/// Journal replay outcome in a [`LoadReport`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplaySummary {
+    /// Records applied to the cluster.
+    pub applied: usize,
+    /// Records whose bank or entry no longer exists.
+    pub skipped: usize,
+    /// `Insert` records refused for failing their entry checksum.
+    pub checksum_mismatches: usize,
+    /// Byte offset of a corrupt record; everything from there was lost.
+    pub corrupt_at: Option<usize>,
+    /// Journal size before replay.
+    pub bytes: u64,
+}
+
+#[cfg(feature = "std")]
+impl ReplaySummary {
+    fn new(applied: usize, bytes: u64, issues: &[JournalError]) -> Self {
+        let mut summary = Self {
+            applied,
+            bytes,
+            ..Self::default()
+        };
+        for issue in issues {
+            match *issue {
+                JournalError::ReplaySkipped { count } => summary.skipped += count,
+                JournalError::ReplayChecksumMismatch { count } => {
+                    summary.checksum_mismatches += count
+                }
+                JournalError::CorruptRecord { offset } => summary.corrupt_at = Some(offset),
+                JournalError::OpenFailed(_) | JournalError::SyncFailed(_) => {}
+            }
+        }
+        summary
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[cfg(feature = "std")]
+fn micros(elapsed: Duration) -> u64 {
+    elapsed.as_micros().min(u64::MAX as u128) as u64
+}
+
+/// Per-prefix flush cadence for [`BankCluster::flush_prefix`], e.g.
+/// `temporal.` every 500 ticks and `occipital.` every 5000. The kernel asks
+/// [`due`](Self::due) each tick and flushes the prefixes it returns.
+#[derive(Debug, Clone, Default)]
+pub struct FlushSchedule {
+    /// `(prefix, interval, last due tick)`, in insertion order.
+    prefixes: Vec<(String, u64, u64)>,
+}
+
+impl FlushSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flush banks under `prefix` every `interval_ticks`, counting from tick
+    /// zero. Re-adding a prefix replaces its interval.
+    pub fn every(mut self, prefix: impl Into<String>, interval_ticks: u64) -> Self {
+        let prefix = prefix.into();
+        match self.prefixes.iter_mut().find(|(p, _, _)| *p == prefix) {
+            Some(slot) => slot.1 = interval_ticks,
+            None => self.prefixes.push((prefix, interval_ticks, 0)),
+        }
+        self
+    }
+
+    /// Prefixes whose interval has elapsed at `current_tick`. Each returned
+    /// prefix's clock restarts at `current_tick`, so the caller should flush
+    /// every one of them.
+    pub fn due(&mut self, current_tick: u64) -> Vec<&str> {
+        let mut due = Vec::new();
+        for (prefix, interval, last) in &mut self.prefixes {
+            if current_tick.saturating_sub(*last) >= *interval {
+                *last = current_tick;
+                due.push(prefix.as_str());
+            }
+        }
+        due
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Background flush
+// ---------------------------------------------------------------------------
+
+/// An encoded bank snapshot queued for writing.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FlushJob {
+    pub bank_id: BankId,
+    /// File name inside the worker's directory (`<bank name>.bank`).
+    pub file_name: String,
+    /// Encoded `.bank` bytes, captured when the flush was requested.
+    pub bytes: Vec<u8>,
+    /// Tick the snapshot was taken at.
+    pub tick: u64,
+    /// The bank's `mutations_since_persist` when the snapshot was taken.
+    pub mutations: u32,
+    /// The bank's [`generation`](DataBank::generation) when the snapshot
+    /// was taken.
+    pub generation: u64,
+}
+
+/// Outcome of one background write, reported back to the cluster.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FlushCompletion {
+    pub bank_id: BankId,
+    pub path: PathBuf,
+    pub tick: u64,
+    pub mutations: u32,
+    pub generation: u64,
+    /// `Ok` only once the file is synced and renamed into place.
+    pub result: Result<()>,
+}
+
+/// Where a flush into `dir` writes `bank`: its recorded directory once it
+/// has been [relocated](BankCluster::relocate_bank), `dir` otherwise.
+#[cfg(feature = "std")]
+fn flush_path(
+    bank_paths: &HashMap<BankId, PathBuf>,
+    relocated: &HashSet<BankId>,
+    bank: &DataBank,
+    dir: &Path,
+) -> PathBuf {
+    let dir = match bank_paths.get(&bank.id).and_then(|path| path.parent()) {
+        Some(recorded) if relocated.contains(&bank.id) => recorded,
+        _ => dir,
+    };
+    dir.join(format!("{}.bank", bank.name))
+}
+
+/// Background thread that performs atomic `.bank` writes.
+///
+/// Jobs arrive on the receiver passed to [`spawn`](Self::spawn); one
+/// [`FlushCompletion`] is reported per job, in order. The thread exits when
+/// every sender has been dropped and the queue is empty.
+#[cfg(feature = "std")]
+pub struct FlushWorker {
+    /// Behind a Mutex only so `FlushWorker` (and a cluster holding one) is
+    /// `Sync`; a bare `Receiver` is not.
+    completions: Mutex<Receiver<FlushCompletion>>,
+    handle: JoinHandle<()>,
+}
+
+#[cfg(feature = "std")]
+impl FlushWorker {
+    /// Spawn the worker thread writing into `dir`.
+    pub fn spawn(dir: PathBuf, rx: Receiver<FlushJob>) -> Result<Self> {
+        let (done_tx, completions) = mpsc::channel();
+        let handle = std::thread::Builder::new()
+            .name("databank-flush".into())
+            .spawn(move || {
+                for job in rx {
+                    let path = dir.join(&job.file_name);
+                    let result = codec::write_atomic(&job.bytes, &path);
+                    let done = FlushCompletion {
+                        bank_id: job.bank_id,
+                        path,
+                        tick: job.tick,
+                        mutations: job.mutations,
+                        generation: job.generation,
+                        result,
+                    };
+                    if done_tx.send(done).is_err() {
+                        break;
+                    }
+                }
+            })?;
+        Ok(Self {
+            completions: Mutex::new(completions),
+            handle,
+        })
+    }
+
+    /// Completions reported so far, without blocking.
+    pub fn try_completions(&self) -> Vec<FlushCompletion> {
+        self.completions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .try_iter()
+            .collect()
+    }
+
+    /// The next completion, waiting at most `timeout`. None on timeout or
+    /// once the thread has exited with nothing left to report.
+    pub fn recv_completion(&self, timeout: Duration) -> Option<FlushCompletion> {
+        self.completions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .recv_timeout(timeout)
+            .ok()
+    }
+
+    /// Wait for the thread to exit and return every unread completion.
+    /// Drop all job senders first or this blocks forever.
+    pub fn join(self) -> Vec<FlushCompletion> {
+        if self.handle.join().is_err() {
+            log::error!("flush worker panicked");
+        }
+        self.completions
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .try_iter()
+            .collect()
+    }
+}
+
+/// The cluster's handle on a running flush worker.
+#[cfg(feature = "std")]
+struct BackgroundFlush {
+    jobs: Sender<FlushJob>,
+    worker: FlushWorker,
+    in_flight: HashSet<BankId>,
+}
+
+/// Fewest results a bank needs before its scores are z-scored against each
+/// other; see [`fuse_results`].
+const MIN_Z_SCORE_RESULTS: usize = 3;
+
+/// Tag each bank's query results with its identity and put them all on one
+/// scale for a cross-bank ranking.
+///
+/// A bank's scores are z-scored against its own results, which cancels
+/// per-bank offsets such as one bank's vectors always scoring high. A bank
+/// with fewer than [`MIN_Z_SCORE_RESULTS`] results, or whose results all
+/// tie, has no spread of its own, so its scores are z-scored against the
+/// pooled raw scores of every bank instead; a lone perfect match then ranks
+/// by how it compares with everything else rather than flattening to 0. If
+/// the pool has no spread either, those results normalize to 0.
+pub(crate) fn fuse_results(
+    per_bank: Vec<(BankId, String, Vec<QueryResult>)>,
+) -> Vec<ClusterQueryResult> {
+    let pool: Vec<QueryResult> = per_bank.iter().flat_map(|(_, _, r)| r.iter().copied()).collect();
+    let pooled = ZScore::fit(&pool);
+
+    let mut fused = Vec::with_capacity(pool.len());
+    for (bank_id, bank_name, results) in per_bank {
+        let own = ZScore::fit(&results);
+        let z = if results.len() >= MIN_Z_SCORE_RESULTS && own.is_defined() {
+            own
+        } else {
+            pooled
+        };
+        fused.extend(results.iter().map(|r| ClusterQueryResult {
+            bank_id,
+            bank_name: bank_name.clone(),
+            entry_id: r.entry_id,
+            score: r.score,
+            normalized_score: z.normalize(r.score),
+        }));
+    }
+    fused
+}
+
+/// Bits the score spread is scaled up to before z-scoring.
+///
+/// Bounds: scores are `i32`, so the spread (max - min) is below 2^32 and
+/// the shift is always at least 8. Scaled deviations stay below 2^40, their
+/// squares below 2^80, and the sum of squares over up to 2^32 results below
+/// 2^112, all inside `i128`. A spread of 1 still gets 39 fractional bits,
+/// so tight clusters of scores keep their relative distances.
+const Z_SCORE_SPREAD_BITS: u32 = 40;
+
+/// Per-bank z-score parameters in adaptive fixed point (integer only).
+///
+/// Scores are rebased on the bank's minimum and shifted left so the spread
+/// fills [`Z_SCORE_SPREAD_BITS`]. Neither the rebase nor the shift changes
+/// z-scores, but together they avoid both `i64` overflow on wide spreads
+/// and a stddev that truncates to 0 or 1 on narrow ones.
+#[derive(Debug, Clone, Copy)]
+struct ZScore {
+    min: i64,
+    shift: u32,
+    /// Mean of the rebased, shifted scores.
+    mean: i128,
+    /// Sample standard deviation in the same units; 0 if it is undefined
+    /// (fewer than two results, or all scores equal).
+    stddev: i128,
+}
+
+impl ZScore {
+    fn fit(results: &[QueryResult]) -> Self {
+        let min = results.iter().map(|r| r.score as i64).min().unwrap_or(0);
+        let max = results.iter().map(|r| r.score as i64).max().unwrap_or(0);
+        let spread = (max - min) as u64;
+        if results.len() < 2 || spread == 0 {
+            return Self { min, shift: 0, mean: 0, stddev: 0 };
+        }
+
+        let shift = Z_SCORE_SPREAD_BITS - (64 - spread.leading_zeros());
+        let scaled = |score: i32| ((score as i64 - min) as i128) << shift;
+
+        let n = results.len() as i128;
+        let mean = results.iter().map(|r| scaled(r.score)).sum::<i128>() / n;
+        let variance = results
+            .iter()
+            .map(|r| {
+                let diff = scaled(r.score) - mean;
+                diff * diff
+            })
+            .sum::<i128>()
+            / (n - 1);
+
+        Self { min, shift, mean, stddev: isqrt_wide(variance) }
+    }
+
+    /// Whether the scores had a spread to normalize by.
+    fn is_defined(&self) -> bool {
+        self.stddev != 0
+    }
+
+    /// `(score - mean) / stddev` scaled by 256, saturated to `i32`.
+    /// Returns 0 when the stddev is undefined.
+    fn normalize(&self, score: i32) -> i32 {
+        if self.stddev == 0 {
+            return 0;
+        }
+        let diff = (((score as i64 - self.min) as i128) << self.shift) - self.mean;
+        (diff * 256 / self.stddev).clamp(i32::MIN as i128, i32::MAX as i128) as i32
+    }
+}
+
+impl Default for BankCluster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorSeverity;
     use ternary_signal::Signal;
 
     fn make_config(width: u16) -> BankConfig {
@@ -429,135 +2413,1170 @@ mod tests {
         }
     }
 
-    fn make_vector(width: u16) -> Vec<Signal> {
-        (0..width)
-            .map(|i| Signal::new_raw(1, (i % 255) as u8 + 1, 1))
-            .collect()
+    fn make_vector(width: u16) -> Vec<Signal> {
+        (0..width)
+            .map(|i| Signal::new_raw(1, (i % 255) as u8 + 1, 1))
+            .collect()
+    }
+
+    #[test]
+    fn create_and_lookup() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        cluster.get_or_create(id, "temporal.semantic".into(), make_config(64)).unwrap();
+
+        assert!(cluster.get(id).is_some());
+        assert!(cluster.get_by_name("temporal.semantic").is_some());
+        assert!(cluster.get_by_name("nonexistent").is_none());
+        assert_eq!(cluster.len(), 1);
+    }
+
+    #[test]
+    fn width_registry_rejects_mismatched_pairs() {
+        let mut cluster = BankCluster::new();
+        let (narrow, wide, twin) = (BankId::from_raw(1), BankId::from_raw(2), BankId::from_raw(3));
+        cluster.get_or_create(narrow, "narrow".into(), make_config(4)).unwrap();
+        cluster.get_or_create(wide, "wide".into(), make_config(8)).unwrap();
+        cluster.get_or_create(twin, "twin".into(), make_config(4)).unwrap();
+
+        let widths = cluster.widths();
+        assert_eq!(widths.len(), 3);
+        assert_eq!((widths[&narrow], widths[&wide]), (4, 8));
+
+        assert_eq!(cluster.check_width_compatible(narrow, twin).unwrap(), 4);
+        match cluster.check_width_compatible(narrow, wide) {
+            Err(e @ DataBankError::WidthIncompatible { expected: 4, got: 8, bank }) => {
+                assert_eq!(bank, wide);
+                assert_eq!(e.code(), 0x0101);
+                assert_eq!(e.severity(), ErrorSeverity::Recoverable);
+            }
+            other => panic!("expected WidthIncompatible, got {other:?}"),
+        }
+        let missing = BankId::from_raw(9);
+        assert!(matches!(
+            cluster.check_width_compatible(missing, narrow),
+            Err(DataBankError::BankNotFound { id }) if id == missing
+        ));
+    }
+
+    #[test]
+    fn migrate_entry_moves_vector_and_edges_between_matching_widths() {
+        let mut cluster = BankCluster::new();
+        let (narrow, wide, twin) = (BankId::from_raw(1), BankId::from_raw(2), BankId::from_raw(3));
+        cluster.get_or_create(wide, "wide".into(), make_config(8)).unwrap();
+        cluster.get_or_create(twin, "twin".into(), make_config(4)).unwrap();
+        let bank = cluster.get_or_create(narrow, "narrow".into(), make_config(4)).unwrap();
+        let vector = vec![Signal::new_raw(-1, 30, 2); 4];
+        let moving = bank.insert(vector.clone(), Temperature::Warm, 3).unwrap();
+        let other = bank.insert(make_vector(4), Temperature::Hot, 3).unwrap();
+        let from = BankRef { bank: narrow, entry: moving };
+        let to = BankRef { bank: narrow, entry: other };
+        cluster.link(from, to, EdgeType::RelatedTo, 70, 4).unwrap();
+
+        assert!(matches!(
+            cluster.migrate_entry(from, wide),
+            Err(DataBankError::WidthIncompatible { expected: 4, got: 8, bank }) if bank == wide
+        ));
+        assert!(cluster.get(narrow).unwrap().get(moving).is_some());
+
+        let moved = cluster.migrate_entry(from, twin).unwrap();
+        assert!(cluster.get(narrow).unwrap().get(moving).is_none());
+        let copy = cluster.get(twin).unwrap().get(moved).unwrap();
+        assert_eq!(copy.vector(), &vector[..]);
+        assert_eq!((copy.temperature(), copy.created_tick()), (Temperature::Warm, 3));
+        assert_eq!(copy.edges()[0].target, to);
+    }
+
+    #[test]
+    fn merge_from_rejects_mismatched_widths_and_moves_unleased_entries() {
+        let mut cluster = BankCluster::new();
+        let (narrow, wide, twin) = (BankId::from_raw(1), BankId::from_raw(2), BankId::from_raw(3));
+        cluster.get_or_create(wide, "wide".into(), make_config(8)).unwrap();
+        cluster.get_or_create(twin, "twin".into(), make_config(4)).unwrap();
+        let bank = cluster.get_or_create(narrow, "narrow".into(), make_config(4)).unwrap();
+        let ids: Vec<EntryId> = (0..3)
+            .map(|_| bank.insert(make_vector(4), Temperature::Hot, 0).unwrap())
+            .collect();
+        bank.lease(ids[1], 7, 10, 0).unwrap();
+
+        assert!(matches!(
+            cluster.merge_from(narrow, wide),
+            Err(DataBankError::WidthIncompatible { bank, .. }) if bank == wide
+        ));
+        assert_eq!(cluster.get(narrow).unwrap().len(), 3);
+
+        let moved = cluster.merge_from(narrow, twin).unwrap();
+        assert_eq!(moved.iter().map(|&(old, _)| old).collect::<Vec<_>>(), [ids[0], ids[2]]);
+        assert_eq!(cluster.get(twin).unwrap().len(), 2);
+        assert_eq!(cluster.get(narrow).unwrap().len(), 1);
+        assert!(cluster.get(narrow).unwrap().get(ids[1]).is_some());
+    }
+
+    #[test]
+    fn migrate_entry_is_journaled_and_replays() {
+        use crate::journal::JournalEntry;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("databank.journal");
+        let (a, b) = (BankId::from_raw(1), BankId::from_raw(2));
+        let mut cluster = BankCluster::with_journal(&path).unwrap();
+        cluster.get_or_create(b, "b".into(), make_config(4)).unwrap();
+        let bank = cluster.get_or_create(a, "a".into(), make_config(4)).unwrap();
+        let entry = bank.insert_in_namespace(make_vector(4), Temperature::Warm, 0, 5).unwrap();
+        let other = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        bank.pin(entry).unwrap();
+        bank.set_confidence(entry, 201).unwrap();
+        bank.touch_entry(entry, 3).unwrap();
+        let (from, to) = (BankRef { bank: a, entry }, BankRef { bank: a, entry: other });
+        cluster.link(from, to, EdgeType::IsA, 90, 4).unwrap();
+        cluster.link(from, BankRef { bank: b, entry: other }, EdgeType::HasA, 20, 4).unwrap();
+        // Replay starts from the banks as last persisted
+        let mut replica = BankCluster::new();
+        for id in [a, b] {
+            let bytes = codec::encode(cluster.get(id).unwrap()).unwrap();
+            replica.add(codec::decode(&bytes).unwrap()).unwrap();
+        }
+        let persisted = crate::journal::JournalReader::read_all(&path).unwrap().len();
+        let moved = cluster.migrate_entry(from, b).unwrap();
+
+        let records = crate::journal::JournalReader::read_all(&path).unwrap();
+        assert!(matches!(
+            &records[persisted..],
+            [JournalEntry::InsertCopy { bank_id, .. }, JournalEntry::Remove { entry_id, .. }]
+                if *bank_id == b && *entry_id == entry
+        ));
+        assert_eq!(
+            crate::journal::JournalReader::replay_counted(&records[persisted..], &mut replica),
+            (2, 0)
+        );
+        assert_eq!((replica.get(a).unwrap().len(), replica.get(b).unwrap().len()), (1, 1));
+
+        let live = cluster.get(b).unwrap().get(moved).unwrap();
+        let (_, replayed) = replica.get(b).unwrap().entries().next().unwrap();
+        for copy in [live, replayed] {
+            assert_eq!(copy.edges().len(), 2);
+            assert_eq!(copy.edges()[0].target, to);
+            assert_eq!((copy.namespace(), copy.is_pinned(), copy.confidence()), (5, true, 201));
+            assert_eq!((copy.access_count(), copy.last_accessed_tick()), (1, 3));
+            assert_eq!((copy.origin(), copy.temperature()), (a, Temperature::Warm));
+        }
+        assert_eq!(live.vector(), replayed.vector());
+        assert_eq!(live.meta_checksum(), replayed.meta_checksum());
+    }
+
+    #[test]
+    fn migrate_entry_refuses_more_edges_than_the_target_allows() {
+        let mut cluster = BankCluster::new();
+        let (a, b) = (BankId::from_raw(1), BankId::from_raw(2));
+        let config = BankConfig {
+            max_edges_per_entry: 1,
+            ..make_config(4)
+        };
+        cluster.get_or_create(b, "b".into(), config).unwrap();
+        let bank = cluster.get_or_create(a, "a".into(), make_config(4)).unwrap();
+        let entry = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        let other = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        let from = BankRef { bank: a, entry };
+        for edge_type in [EdgeType::IsA, EdgeType::HasA] {
+            cluster.link(from, BankRef { bank: a, entry: other }, edge_type, 50, 1).unwrap();
+        }
+
+        assert!(matches!(
+            cluster.migrate_entry(from, b),
+            Err(DataBankError::EdgeLimitReached { max: 1 })
+        ));
+        assert!(cluster.get(a).unwrap().get(entry).is_some());
+        assert!(cluster.get(b).unwrap().is_empty());
+    }
+
+    #[test]
+    fn duplicate_bank_name_is_rejected() {
+        let mut cluster = BankCluster::new();
+        let first = BankId::from_raw(1);
+        let second = BankId::from_raw(2);
+        cluster.get_or_create(first, "shared".into(), make_config(4)).unwrap();
+
+        let err = cluster
+            .get_or_create(second, "shared".into(), make_config(4))
+            .err()
+            .unwrap();
+        assert!(matches!(err, DataBankError::DuplicateBankName { ref name } if name == "shared"));
+        let err = cluster
+            .add(DataBank::new(second, "shared".into(), make_config(4)))
+            .unwrap_err();
+        assert!(matches!(err, DataBankError::DuplicateBankName { .. }));
+        assert!(cluster.get(second).is_none());
+        assert_eq!(cluster.get_by_name("shared").unwrap().id, first);
+
+        // Re-adding under the same id is a replacement, not a clash, and
+        // renaming through it frees the old name
+        cluster
+            .add(DataBank::new(first, "renamed".into(), make_config(4)))
+            .unwrap();
+        assert!(cluster.get_by_name("shared").is_none());
+        assert_eq!(cluster.get_by_name("renamed").unwrap().id, first);
+
+        // Unnamed banks are never indexed, so they cannot clash
+        cluster.get_or_create(second, String::new(), make_config(4)).unwrap();
+        cluster.get_or_create(BankId::from_raw(3), String::new(), make_config(4)).unwrap();
+        assert_eq!(cluster.len(), 3);
+        assert!(cluster.get_by_name("").is_none());
+    }
+
+    #[test]
+    fn clear_namespace_journals_batch_evict() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("databank.journal");
+        let mut cluster = BankCluster::with_journal(&path).unwrap();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "contexts".into(), make_config(4)).unwrap();
+        let vector = vec![Signal::new_raw(1, 10, 1); 4];
+        bank.insert(vector.clone(), Temperature::Hot, 0).unwrap();
+        let gone = bank.insert_in_namespace(vector, Temperature::Hot, 0, 7).unwrap();
+
+        assert_eq!(cluster.clear_namespace(id, 7).unwrap(), vec![gone]);
+        assert_eq!(cluster.get(id).unwrap().len(), 1);
+        assert!(cluster.clear_namespace(BankId::from_raw(2), 7).is_err());
+
+        let records = crate::journal::JournalReader::read_all(&path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(matches!(
+            &records[0],
+            crate::journal::JournalEntry::BatchEvict { entry_ids, .. } if entry_ids == &[gone]
+        ));
+    }
+
+    #[test]
+    fn remove_all_edges_to_journals_and_replays() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("databank.journal");
+        let id = BankId::from_raw(1);
+        let target = BankRef {
+            bank: BankId::from_raw(2),
+            entry: EntryId::from_raw(5),
+        };
+        let linked = |cluster: &mut BankCluster| {
+            let bank = cluster.get_or_create(id, "contexts".into(), make_config(4)).unwrap();
+            let vector = vec![Signal::new_raw(1, 10, 1); 4];
+            let sources: Vec<EntryId> = (0..3)
+                .map(|_| bank.insert(vector.clone(), Temperature::Hot, 0).unwrap())
+                .collect();
+            for source in sources {
+                let from = BankRef { bank: id, entry: source };
+                cluster.link(from, target, EdgeType::RelatedTo, 90, 1).unwrap();
+            }
+        };
+        let mut cluster = BankCluster::with_journal(&path).unwrap();
+        linked(&mut cluster);
+
+        assert_eq!(cluster.remove_all_edges_to(id, target).unwrap(), 3);
+        assert_eq!(cluster.get(id).unwrap().all_edges().count(), 0);
+        assert!(cluster.remove_all_edges_to(BankId::from_raw(9), target).is_err());
+
+        // Three AddEdge records from the links, then the removal
+        let records = crate::journal::JournalReader::read_all(&path).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(
+            records[3],
+            crate::journal::JournalEntry::RemoveEdgesTo { bank_id: id, target }
+        );
+        let mut replica = BankCluster::new();
+        linked(&mut replica);
+        assert!(crate::journal::apply_entry(&records[3], &mut replica));
+        assert_eq!(replica.get(id).unwrap().all_edges().count(), 0);
+    }
+
+    #[test]
+    fn load_report_lists_files_and_replay() {
+        use crate::journal::JournalEntry;
+
+        let dir = tempfile::tempdir().unwrap();
+        let (alpha, beta) = (BankId::from_raw(1), BankId::from_raw(2));
+        let mut cluster = BankCluster::load_with_journal(dir.path()).unwrap();
+        for (id, name, count) in [(alpha, "alpha", 3), (beta, "beta", 1)] {
+            let bank = cluster.get_or_create(id, name.into(), make_config(4)).unwrap();
+            for _ in 0..count {
+                bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+            }
+        }
+        assert_eq!(cluster.flush_dirty_with_journal(dir.path(), 1).unwrap(), 2);
+        let target = BankRef { bank: beta, entry: EntryId::from_raw(7) };
+        for bank_id in [alpha, BankId::from_raw(9)] {
+            cluster.journal_mutation(JournalEntry::RemoveEdgesTo { bank_id, target }).unwrap();
+        }
+        drop(cluster);
+
+        let (loaded, report) = BankCluster::load_with_journal_report(dir.path()).unwrap();
+        assert_eq!(loaded.len(), 2);
+        let files: Vec<(BankId, usize)> =
+            report.banks.iter().map(|b| (b.bank_id, b.entries)).collect();
+        assert_eq!(files, [(alpha, 3), (beta, 1)]);
+        for record in &report.banks {
+            assert_eq!(record.bytes, std::fs::metadata(&record.path).unwrap().len());
+        }
+        let journal = report.journal.unwrap();
+        assert_eq!((journal.applied, journal.skipped, journal.corrupt_at), (1, 1, None));
+        assert!(journal.bytes > 0);
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].contains("skipped"), "{:?}", report.warnings);
+
+        let (_, plain) = BankCluster::load_all_report(dir.path()).unwrap();
+        assert_eq!(plain.banks.len(), 2);
+        assert_eq!(plain.journal, None);
+    }
+
+    /// A journal whose disk has gone away.
+    struct FailingSink;
+
+    impl std::io::Write for FailingSink {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk gone"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Err(std::io::Error::other("disk gone"))
+        }
+    }
+
+    impl crate::journal::JournalSink for FailingSink {
+        fn len_bytes(&self) -> std::io::Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn failed_journal_append_leaves_bank_unmodified() {
+        let writer = JournalWriter::from_sink(Box::new(FailingSink));
+        let mut cluster = BankCluster::with_journal_writer(writer);
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "contexts".into(), make_config(4)).unwrap();
+        let vector = vec![Signal::new_raw(1, 10, 1); 4];
+        let kept = bank.insert_in_namespace(vector, Temperature::Hot, 0, 7).unwrap();
+
+        assert!(cluster.clear_namespace(id, 7).is_err());
+        assert_eq!(cluster.get(id).unwrap().len(), 1);
+
+        let touch = crate::journal::JournalEntry::Touch {
+            bank_id: id,
+            entry_id: kept,
+            tick: 5,
+        };
+        assert!(cluster.apply_journaled(touch).is_err());
+        let entry = cluster.get(id).unwrap().get(kept).unwrap();
+        assert_eq!((entry.access_count, entry.last_accessed_tick), (0, 0));
+    }
+
+    #[test]
+    fn apply_journaled_records_then_applies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("databank.journal");
+        let mut cluster = BankCluster::with_journal(&path).unwrap();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "contexts".into(), make_config(4)).unwrap();
+        let vector = vec![Signal::new_raw(1, 10, 1); 4];
+        let entry_id = bank.insert(vector, Temperature::Hot, 0).unwrap();
+
+        let set = crate::journal::JournalEntry::SetTemperature {
+            bank_id: id,
+            entry_id,
+            temperature: Temperature::Cold,
+        };
+        assert!(cluster.apply_journaled(set).unwrap());
+        assert_eq!(cluster.get(id).unwrap().get(entry_id).unwrap().temperature, Temperature::Cold);
+        assert_eq!(crate::journal::JournalReader::read_all(&path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn apply_journaled_skips_refused_mutations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("databank.journal");
+        let mut cluster = BankCluster::with_journal(&path).unwrap();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "contexts".into(), make_config(4)).unwrap();
+        let entry_id = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        bank.lease(entry_id, 7, 10, 0).unwrap();
+
+        let remove = crate::journal::JournalEntry::Remove { bank_id: id, entry_id };
+        assert!(!cluster.apply_journaled(remove).unwrap());
+        let missing = crate::journal::JournalEntry::Touch {
+            bank_id: id,
+            entry_id: EntryId::from_raw(99),
+            tick: 1,
+        };
+        assert!(!cluster.apply_journaled(missing).unwrap());
+
+        assert!(cluster.get(id).unwrap().get(entry_id).is_some());
+        assert!(crate::journal::JournalReader::read_all(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn link_evict_and_expire_are_journaled() {
+        use crate::journal::JournalEntry;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("databank.journal");
+        let mut cluster = BankCluster::with_journal(&path).unwrap();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "contexts".into(), make_config(4)).unwrap();
+        let a = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        let b = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        let c = bank.insert_ttl(make_vector(4), Temperature::Hot, 0, 5).unwrap();
+
+        let to = BankRef { bank: id, entry: b };
+        cluster.link(BankRef { bank: id, entry: a }, to, EdgeType::RelatedTo, 50, 1).unwrap();
+        assert_eq!(cluster.expire_entries(10).unwrap(), 1);
+        assert_eq!(cluster.evict(id, 1, 10).unwrap(), 1);
+        assert_eq!(cluster.get(id).unwrap().len(), 1);
+
+        let records = crate::journal::JournalReader::read_all(&path).unwrap();
+        assert!(matches!(
+            records.as_slice(),
+            [
+                JournalEntry::AddEdge { entry_id, .. },
+                JournalEntry::BatchEvict { entry_ids: expired, .. },
+                JournalEntry::BatchEvict { entry_ids: evicted, .. },
+            ] if *entry_id == a && expired == &[c] && evicted.len() == 1
+        ));
+    }
+
+    #[test]
+    fn remove_bank() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        cluster.get_or_create(id, "test".into(), make_config(32)).unwrap();
+        assert_eq!(cluster.len(), 1);
+
+        let removed = cluster.remove(id);
+        assert!(removed.is_some());
+        assert_eq!(cluster.len(), 0);
+        assert!(cluster.get_by_name("test").is_none());
+    }
+
+    #[test]
+    fn cross_bank_linking() {
+        let mut cluster = BankCluster::new();
+        let id_a = BankId::from_raw(1);
+        let id_b = BankId::from_raw(2);
+
+        let bank_a = cluster.get_or_create(id_a, "bank_a".into(), make_config(4)).unwrap();
+        let entry_a = bank_a
+            .insert(make_vector(4), Temperature::Hot, 0)
+            .unwrap();
+
+        let bank_b = cluster.get_or_create(id_b, "bank_b".into(), make_config(4)).unwrap();
+        let entry_b = bank_b
+            .insert(make_vector(4), Temperature::Hot, 0)
+            .unwrap();
+
+        let from = BankRef {
+            bank: id_a,
+            entry: entry_a,
+        };
+        let to = BankRef {
+            bank: id_b,
+            entry: entry_b,
+        };
+
+        cluster
+            .link(from, to, EdgeType::SoundsLike, 200, 0)
+            .unwrap();
+
+        // Verify edge exists
+        let edges = cluster.get(id_a).unwrap().edges_from(entry_a);
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].edge_type, EdgeType::SoundsLike);
+        assert_eq!(edges[0].target, to);
+    }
+
+    #[test]
+    fn most_connected_counts_cross_bank_in_degree() {
+        let mut cluster = BankCluster::new();
+        let id_a = BankId::from_raw(1);
+        let id_b = BankId::from_raw(2);
+        let hub = cluster
+            .get_or_create(id_a, "hubs".into(), make_config(4)).unwrap()
+            .insert(make_vector(4), Temperature::Hot, 0)
+            .unwrap();
+        let leaves: Vec<EntryId> = (0..3)
+            .map(|_| {
+                cluster
+                    .get_or_create(id_b, "leaves".into(), make_config(4)).unwrap()
+                    .insert(make_vector(4), Temperature::Hot, 0)
+                    .unwrap()
+            })
+            .collect();
+
+        let hub_ref = BankRef { bank: id_a, entry: hub };
+        for &leaf in &leaves {
+            let leaf_ref = BankRef { bank: id_b, entry: leaf };
+            cluster.link(leaf_ref, hub_ref, EdgeType::IsA, 100, 0).unwrap();
+        }
+        let first_leaf = BankRef { bank: id_b, entry: leaves[0] };
+        cluster.link(hub_ref, first_leaf, EdgeType::HasA, 100, 0).unwrap();
+
+        let top = cluster.most_connected(2);
+        assert_eq!(top[0], (hub_ref, 3, 1));
+        assert_eq!(top[1], (first_leaf, 1, 1));
+        assert_eq!(cluster.most_connected(10).len(), 4);
+    }
+
+    #[test]
+    fn traverse_follows_edges() {
+        let mut cluster = BankCluster::new();
+        let id_a = BankId::from_raw(1);
+        let id_b = BankId::from_raw(2);
+        let id_c = BankId::from_raw(3);
+
+        let bank_a = cluster.get_or_create(id_a, "a".into(), make_config(4)).unwrap();
+        let ea = bank_a.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+
+        let bank_b = cluster.get_or_create(id_b, "b".into(), make_config(4)).unwrap();
+        let eb = bank_b.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+
+        let bank_c = cluster.get_or_create(id_c, "c".into(), make_config(4)).unwrap();
+        let ec = bank_c.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+
+        // a -> b -> c (chain of RelatedTo edges)
+        let ref_a = BankRef { bank: id_a, entry: ea };
+        let ref_b = BankRef { bank: id_b, entry: eb };
+        let ref_c = BankRef { bank: id_c, entry: ec };
+
+        cluster.link(ref_a, ref_b, EdgeType::RelatedTo, 200, 0).unwrap();
+        cluster.link(ref_b, ref_c, EdgeType::RelatedTo, 150, 0).unwrap();
+
+        // Depth 1: should find b
+        let d1 = cluster.traverse(ref_a, EdgeType::RelatedTo, 1);
+        assert_eq!(d1.len(), 1);
+        assert_eq!(d1[0], ref_b);
+
+        // Depth 2: should find b and c
+        let d2 = cluster.traverse(ref_a, EdgeType::RelatedTo, 2);
+        assert_eq!(d2.len(), 2);
+        assert!(d2.contains(&ref_b));
+        assert!(d2.contains(&ref_c));
+
+        // Depth 0: nothing
+        let d0 = cluster.traverse(ref_a, EdgeType::RelatedTo, 0);
+        assert!(d0.is_empty());
+
+        // Wrong edge type: nothing
+        let wrong = cluster.traverse(ref_a, EdgeType::LooksLike, 2);
+        assert!(wrong.is_empty());
+
+        // Detailed: hop distance and the weight of the edge followed
+        let detailed = cluster.traverse_detailed(ref_a, EdgeType::RelatedTo, 3);
+        assert_eq!(detailed, [(ref_b, 1, 200), (ref_c, 2, 150)]);
+    }
+
+    #[test]
+    fn traverse_multi_source_dedups_shared_nodes() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "multi".into(), make_config(4)).unwrap();
+        let mut refs = Vec::new();
+        for _ in 0..4 {
+            let entry = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+            refs.push(BankRef { bank: id, entry });
+        }
+        let (seed_a, seed_b, shared, tail) = (refs[0], refs[1], refs[2], refs[3]);
+
+        // a -> shared, b -> shared, shared -> tail
+        cluster.link(seed_a, shared, EdgeType::RelatedTo, 200, 0).unwrap();
+        cluster.link(seed_b, shared, EdgeType::RelatedTo, 200, 0).unwrap();
+        cluster.link(shared, tail, EdgeType::RelatedTo, 200, 0).unwrap();
+
+        let reached = cluster.traverse_multi_source(&[seed_a, seed_b], EdgeType::RelatedTo, 2);
+        assert_eq!(reached, vec![shared, tail]);
+
+        // Depth is measured from whichever seed reaches a node first
+        let d1 = cluster.traverse_multi_source(&[seed_a, seed_b, seed_a], EdgeType::RelatedTo, 1);
+        assert_eq!(d1, vec![shared]);
+
+        assert!(cluster.traverse_multi_source(&[], EdgeType::RelatedTo, 3).is_empty());
+    }
+
+    #[test]
+    fn traverse_filtered_only_follows_allowed_target_banks() {
+        let mut cluster = BankCluster::new();
+        let id_a = BankId::from_raw(1);
+        let id_vis = BankId::from_raw(2);
+        let id_aud = BankId::from_raw(3);
+        let mut insert = |id: BankId, name: &str| {
+            let bank = cluster.get_or_create(id, name.into(), make_config(4)).unwrap();
+            let entry = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+            BankRef { bank: id, entry }
+        };
+        let start = insert(id_a, "concept");
+        let visual = insert(id_vis, "visual");
+        let auditory = insert(id_aud, "auditory");
+        let visual_tail = insert(id_vis, "visual");
+
+        cluster.link(start, visual, EdgeType::RelatedTo, 200, 0).unwrap();
+        cluster.link(start, auditory, EdgeType::RelatedTo, 200, 0).unwrap();
+        cluster.link(auditory, visual_tail, EdgeType::RelatedTo, 200, 0).unwrap();
+
+        let reached = cluster.traverse_filtered(start, EdgeType::RelatedTo, 3, |b| b == id_vis);
+        assert_eq!(reached, vec![visual]);
+
+        let all = cluster.traverse_filtered(start, EdgeType::RelatedTo, 3, |_| true);
+        assert_eq!(all.len(), 3);
+        assert!(all.contains(&auditory) && all.contains(&visual_tail));
+    }
+
+    #[test]
+    fn find_duplicates_groups_near_identical_entries() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "dups".into(), make_config(4)).unwrap();
+        let sig = |p: i8, m: u8| Signal::new_raw(p, m, 1);
+        let original = bank
+            .insert(vec![sig(1, 200), sig(1, 100), sig(-1, 50), sig(1, 10)], Temperature::Hot, 0)
+            .unwrap();
+        let near = bank
+            .insert(vec![sig(1, 198), sig(1, 101), sig(-1, 52), sig(1, 10)], Temperature::Hot, 0)
+            .unwrap();
+        let distinct = bank
+            .insert(vec![sig(-1, 200), sig(1, 5), sig(1, 200), sig(-1, 90)], Temperature::Hot, 0)
+            .unwrap();
+
+        // Same vectors in another bank are not cross-bank duplicates
+        let other = BankId::from_raw(2);
+        let bank = cluster.get_or_create(other, "alone".into(), make_config(4)).unwrap();
+        bank.insert(vec![sig(1, 200), sig(1, 100), sig(-1, 50), sig(1, 10)], Temperature::Hot, 0)
+            .unwrap();
+
+        let groups = cluster.find_duplicates(250);
+        assert_eq!(groups.len(), 1);
+        let mut members: Vec<EntryId> = groups[0].iter().map(|r| r.entry).collect();
+        members.sort();
+        let mut expected = vec![original, near];
+        expected.sort();
+        assert_eq!(members, expected);
+        assert!(groups[0].iter().all(|r| r.bank == id));
+        assert!(!members.contains(&distinct));
+
+        // Impossible threshold: no groups
+        assert!(cluster.find_duplicates(257).is_empty());
+    }
+
+    #[test]
+    fn flush_and_load_round_trip() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "test.round.trip".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        bank.insert(make_vector(4), Temperature::Warm, 0).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let flushed = cluster.flush_dirty(dir.path(), 100).unwrap();
+        assert_eq!(flushed, 1);
+
+        // Load back
+        let loaded = BankCluster::load_all(dir.path()).unwrap();
+        assert_eq!(loaded.len(), 1);
+        let loaded_bank = loaded.get_by_name("test.round.trip").unwrap();
+        assert_eq!(loaded_bank.len(), 2);
+        assert_eq!(loaded_bank.id, id);
+    }
+
+    #[test]
+    fn fork_never_flushes_over_the_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cluster = BankCluster::load_with_journal(dir.path()).unwrap();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "plans".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        assert_eq!(cluster.flush_dirty_with_journal(dir.path(), 1).unwrap(), 1);
+        let file = dir.path().join("plans.bank");
+        let saved = std::fs::read(&file).unwrap();
+
+        let mut imagined = cluster.fork();
+        assert_eq!(imagined.bank_ids(), [id]);
+        let fork = imagined.get_by_name_mut("plans").unwrap();
+        assert!(fork.is_forked());
+        for tick in 2..5 {
+            fork.insert(make_vector(4), Temperature::Hot, tick).unwrap();
+        }
+        imagined.clear_namespace(id, 0).unwrap();
+        assert_eq!(imagined.flush_dirty(dir.path(), 100).unwrap(), 0);
+        imagined.start_flush_worker(dir.path()).unwrap();
+        assert_eq!(imagined.request_flush(100).unwrap(), 0);
+        assert_eq!(imagined.shutdown_flush_worker().unwrap(), 0);
+        assert!(imagined.dirty_banks(100).iter().all(|(_, state)| state.dirty));
+        let elsewhere = tempfile::tempdir().unwrap();
+        for target in [dir.path(), elsewhere.path()] {
+            assert!(matches!(
+                imagined.relocate_bank(id, target),
+                Err(DataBankError::InvalidConfig(_))
+            ));
+        }
+        assert!(std::fs::read_dir(elsewhere.path()).unwrap().next().is_none());
+
+        assert_eq!(std::fs::read(&file).unwrap(), saved);
+        assert_eq!(cluster.journal_len_bytes().unwrap(), 0);
+        assert_eq!(cluster.get(id).unwrap().len(), 1);
+        assert!(!cluster.get(id).unwrap().is_forked());
+    }
+
+    #[test]
+    fn flush_after_rename_removes_the_old_file() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "motor.draft".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        cluster.flush_dirty(dir.path(), 10).unwrap();
+        assert!(dir.path().join("motor.draft.bank").is_file());
+
+        cluster.rename_bank(id, "motor.cortex".into()).unwrap();
+        assert!(cluster.get_by_name("motor.draft").is_none());
+        assert_eq!(cluster.get_by_name("motor.cortex").unwrap().id, id);
+        assert_eq!(cluster.flush_dirty(dir.path(), 20).unwrap(), 1);
+        assert!(!dir.path().join("motor.draft.bank").exists());
+
+        let loaded = BankCluster::load_all(dir.path()).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.get(id).unwrap().name, "motor.cortex");
+        assert!(loaded.get_by_name("motor.draft").is_none());
+
+        cluster.get_or_create(BankId::from_raw(2), "taken".into(), make_config(4)).unwrap();
+        assert!(matches!(
+            cluster.rename_bank(id, "taken".into()),
+            Err(DataBankError::DuplicateBankName { .. })
+        ));
+        assert_eq!(cluster.get(id).unwrap().name, "motor.cortex");
+    }
+
+    #[test]
+    fn incremental_flush_appends_changed_entries() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let config = BankConfig {
+            incremental_flush: Some(50),
+            ..make_config(16)
+        };
+        let bank = cluster.get_or_create(id, "hippocampus".into(), config).unwrap();
+        let ids: Vec<EntryId> = (0..64u64)
+            .map(|i| bank.insert(make_vector(16), Temperature::Hot, i).unwrap())
+            .collect();
+        let dir = tempfile::tempdir().unwrap();
+        let base = cluster.flush_dirty_report(dir.path(), 10).unwrap().bytes_written;
+
+        cluster.get_mut(id).unwrap().set_confidence(ids[3], 222).unwrap();
+        let report = cluster.flush_dirty_report(dir.path(), 20).unwrap();
+        assert_eq!(report.banks_flushed, 1);
+        assert!(report.bytes_written * 20 < base, "{} vs {base}", report.bytes_written);
+        let path = dir.path().join("hippocampus.bank");
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), base + report.bytes_written);
+
+        let loaded = BankCluster::load_all(dir.path()).unwrap();
+        let bank = loaded.get(id).unwrap();
+        assert_eq!(bank.len(), 64);
+        assert_eq!(bank.get(ids[3]).unwrap().confidence, 222);
+        assert!(!bank.is_dirty());
+    }
+
+    #[test]
+    fn relocate_bank_moves_file_between_dirs() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "motor.cortex".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        let slow = tempfile::tempdir().unwrap();
+        let fast = tempfile::tempdir().unwrap();
+        cluster.flush_dirty(slow.path(), 10).unwrap();
+        assert_eq!(cluster.bank_dir(id), Some(slow.path()));
+
+        cluster.relocate_bank(id, fast.path()).unwrap();
+        assert_eq!(cluster.bank_dir(id), Some(fast.path()));
+        assert!(!slow.path().join("motor.cortex.bank").exists());
+        assert!(BankCluster::load_all(slow.path()).unwrap().is_empty());
+        let loaded = BankCluster::load_all(fast.path()).unwrap();
+        assert_eq!(loaded.get(id).unwrap().len(), 1);
+        assert_eq!(loaded.bank_dir(id), Some(fast.path()));
+
+        assert!(matches!(
+            cluster.relocate_bank(BankId::from_raw(9), fast.path()),
+            Err(DataBankError::BankNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn flush_after_relocate_writes_the_new_dir() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let config = BankConfig {
+            incremental_flush: Some(50),
+            ..make_config(4)
+        };
+        let bank = cluster.get_or_create(id, "motor.cortex".into(), config).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        let other = BankId::from_raw(2);
+        let bank = cluster.get_or_create(other, "motor.other".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        let slow = tempfile::tempdir().unwrap();
+        let fast = tempfile::tempdir().unwrap();
+        cluster.flush_dirty(slow.path(), 10).unwrap();
+        cluster.relocate_bank(id, fast.path()).unwrap();
+
+        cluster.get_mut(id).unwrap().insert(make_vector(4), Temperature::Hot, 11).unwrap();
+        cluster.get_mut(other).unwrap().insert(make_vector(4), Temperature::Hot, 11).unwrap();
+        assert_eq!(cluster.flush_dirty(slow.path(), 12).unwrap(), 2);
+        assert!(!slow.path().join("motor.cortex.bank").exists());
+        assert_eq!(codec::load(&fast.path().join("motor.cortex.bank")).unwrap().len(), 2);
+        // Banks that were never relocated still go where the caller says
+        assert_eq!(codec::load(&slow.path().join("motor.other.bank")).unwrap().len(), 2);
+        assert_eq!(cluster.bank_dir(id), Some(fast.path()));
+    }
+
+    #[test]
+    fn flush_dirty_marks_only_banks_whose_write_succeeded() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory squatting on one bank's file name makes its rename fail
+        std::fs::create_dir(dir.path().join("blocked.bank")).unwrap();
+
+        let mut cluster = BankCluster::new();
+        let ok_id = BankId::from_raw(1);
+        let blocked_id = BankId::from_raw(2);
+        for (id, name) in [(ok_id, "fine"), (blocked_id, "blocked")] {
+            let bank = cluster.get_or_create(id, name.into(), make_config(4)).unwrap();
+            bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        }
+
+        assert!(cluster.flush_dirty(dir.path(), 10).is_err());
+        assert!(!cluster.get(ok_id).unwrap().is_dirty());
+        assert!(cluster.get(blocked_id).unwrap().is_dirty());
+        assert!(dir.path().join("fine.bank").is_file());
+    }
+
+    #[test]
+    fn flush_report_counts_bytes_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_path = dir.path().join("databank.journal");
+        let mut cluster = BankCluster::with_journal(&journal_path).unwrap();
+        for (raw, name) in [(1, "small"), (2, "large")] {
+            let bank = cluster
+                .get_or_create(BankId::from_raw(raw), name.into(), make_config(4))
+                .unwrap();
+            for tick in 0..raw * 3 {
+                bank.insert(make_vector(4), Temperature::Hot, tick).unwrap();
+            }
+        }
+        let expected: u64 = ["small", "large"]
+            .iter()
+            .map(|name| codec::encode(cluster.get_by_name(name).unwrap()).unwrap().len() as u64)
+            .sum();
+
+        let report = cluster.flush_dirty_with_journal_report(dir.path(), 10).unwrap();
+        assert_eq!(report.banks_flushed, 2);
+        assert!(report.bytes_written > 0);
+        assert_eq!(report.bytes_written, expected);
+        assert!(report.journal_truncated);
+        let on_disk: u64 = ["small.bank", "large.bank"]
+            .iter()
+            .map(|f| std::fs::metadata(dir.path().join(f)).unwrap().len())
+            .sum();
+        assert_eq!(report.bytes_written, on_disk);
+
+        // Nothing dirty: nothing written, journal left alone
+        let report = cluster.flush_dirty_with_journal_report(dir.path(), 20).unwrap();
+        assert_eq!(report, FlushReport::default());
     }
 
+    /// Compares per-bank `save_atomic` + directory sync against the batched
+    /// `flush_dirty`. Run with `cargo test --release -- --ignored --nocapture`.
     #[test]
-    fn create_and_lookup() {
+    #[ignore = "timing comparison, not a correctness check"]
+    fn flush_dirty_timing() {
+        let dir = tempfile::tempdir().unwrap();
         let mut cluster = BankCluster::new();
-        let id = BankId::from_raw(1);
-        cluster.get_or_create(id, "temporal.semantic".into(), make_config(64));
+        for i in 0..30u64 {
+            let config = BankConfig {
+                max_entries: 256,
+                ..make_config(64)
+            };
+            let bank = cluster
+                .get_or_create(BankId::from_raw(i + 1), format!("bank{i}"), config)
+                .unwrap();
+            for _ in 0..128 {
+                bank.insert(make_vector(64), Temperature::Hot, 0).unwrap();
+            }
+        }
 
-        assert!(cluster.get(id).is_some());
-        assert!(cluster.get_by_name("temporal.semantic").is_some());
-        assert!(cluster.get_by_name("nonexistent").is_none());
-        assert_eq!(cluster.len(), 1);
+        let start = std::time::Instant::now();
+        for id in cluster.bank_ids() {
+            let bank = cluster.get(id).unwrap();
+            let path = dir.path().join(format!("{}.baseline.bank", bank.name));
+            codec::save_atomic(bank, &path).unwrap();
+            codec::sync_dir(dir.path()).unwrap();
+        }
+        let per_bank = start.elapsed();
+
+        let start = std::time::Instant::now();
+        assert_eq!(cluster.flush_dirty(dir.path(), 1).unwrap(), 30);
+        let batched = start.elapsed();
+
+        eprintln!("30 banks: per-bank {per_bank:?}, batched flush_dirty {batched:?}");
     }
 
     #[test]
-    fn remove_bank() {
+    fn export_csv_dir_writes_one_file_per_bank() {
+        let dir = tempfile::tempdir().unwrap();
         let mut cluster = BankCluster::new();
-        let id = BankId::from_raw(1);
-        cluster.get_or_create(id, "test".into(), make_config(32));
-        assert_eq!(cluster.len(), 1);
+        for (raw, name, entries) in [(1, "csv.visual", 2), (2, "csv.audio", 3)] {
+            let bank = cluster
+                .get_or_create(BankId::from_raw(raw), name.into(), make_config(4))
+                .unwrap();
+            for tick in 0..entries {
+                bank.insert(make_vector(4), Temperature::Hot, tick).unwrap();
+            }
+        }
 
-        let removed = cluster.remove(id);
-        assert!(removed.is_some());
-        assert_eq!(cluster.len(), 0);
-        assert!(cluster.get_by_name("test").is_none());
+        let out = dir.path().join("csv");
+        assert_eq!(cluster.export_csv_dir(&out, false).unwrap(), 2);
+        let audio = std::fs::read_to_string(out.join("csv.audio.csv")).unwrap();
+        assert_eq!(audio.lines().count(), 1 + 3);
+        assert!(audio.starts_with("id,temperature,"));
+        assert!(out.join("csv.visual.csv").is_file());
     }
 
     #[test]
-    fn cross_bank_linking() {
+    fn query_all_filtered_applies_the_range_in_every_bank() {
         let mut cluster = BankCluster::new();
-        let id_a = BankId::from_raw(1);
-        let id_b = BankId::from_raw(2);
+        let mut queries = HashMap::new();
+        for raw in 1..=2 {
+            let id = BankId::from_raw(raw);
+            let bank = cluster
+                .get_or_create(id, format!("episodic.{raw}"), make_config(4))
+                .unwrap();
+            for tick in [100, 200, 300] {
+                bank.insert(make_vector(4), Temperature::Hot, tick).unwrap();
+            }
+            queries.insert(id, make_vector(4));
+        }
 
-        let bank_a = cluster.get_or_create(id_a, "bank_a".into(), make_config(4));
-        let entry_a = bank_a
-            .insert(make_vector(4), Temperature::Hot, 0)
-            .unwrap();
+        let filter = QueryFilter::created_between(200, 300);
+        let results = cluster.query_all_filtered(&queries, 10, &filter);
+        assert_eq!(results.len(), 4);
+        for r in &results {
+            let entry = cluster.get(r.bank_id).unwrap().get(r.entry_id).unwrap();
+            assert!(entry.created_tick >= 200);
+        }
+        assert_eq!(cluster.query_all(&queries, 10).len(), 6);
+    }
 
-        let bank_b = cluster.get_or_create(id_b, "bank_b".into(), make_config(4));
-        let entry_b = bank_b
-            .insert(make_vector(4), Temperature::Hot, 0)
-            .unwrap();
+    #[test]
+    fn paged_query_has_no_overlaps_or_gaps() {
+        let mut cluster = BankCluster::new();
+        let mut queries = HashMap::new();
+        for raw in 1..=3 {
+            let id = BankId::from_raw(raw);
+            let bank = cluster.get_or_create(id, format!("paged.{raw}"), make_config(4)).unwrap();
+            for i in 0..4u8 {
+                let v = vec![
+                    Signal::new_raw(1, 200, 1),
+                    Signal::new_raw(1, 40 + i * 30, 1),
+                    Signal::new_raw(-1, 10 + i * 20, 1),
+                    Signal::new_raw(1, 90, 1),
+                ];
+                bank.insert(v, Temperature::Hot, i as u64).unwrap();
+            }
+            queries.insert(id, make_vector(4));
+        }
 
-        let from = BankRef {
-            bank: id_a,
-            entry: entry_a,
-        };
-        let to = BankRef {
-            bank: id_b,
-            entry: entry_b,
-        };
+        let (everything, none) = cluster.query_all_paged(&queries, 100, None);
+        assert_eq!(everything.len(), 12);
+        assert!(none.is_none());
 
-        cluster
-            .link(from, to, EdgeType::SoundsLike, 200, 0)
-            .unwrap();
+        let (first, cursor) = cluster.query_all_paged(&queries, 7, None);
+        let cursor = cursor.expect("more results remain");
+        let (second, last) = cluster.query_all_paged(&queries, 7, Some(cursor));
+        assert_eq!((first.len(), second.len()), (7, 5));
+        assert!(last.is_none());
 
-        // Verify edge exists
-        let edges = cluster.get(id_a).unwrap().edges_from(entry_a);
-        assert_eq!(edges.len(), 1);
-        assert_eq!(edges[0].edge_type, EdgeType::SoundsLike);
-        assert_eq!(edges[0].target, to);
+        let paged: Vec<(BankId, EntryId)> = first
+            .iter()
+            .chain(&second)
+            .map(|r| (r.bank_id, r.entry_id))
+            .collect();
+        let whole: Vec<(BankId, EntryId)> =
+            everything.iter().map(|r| (r.bank_id, r.entry_id)).collect();
+        assert_eq!(paged, whole);
     }
 
     #[test]
-    fn traverse_follows_edges() {
+    fn flush_prefix_writes_only_matching_banks() {
+        let dir = tempfile::tempdir().unwrap();
         let mut cluster = BankCluster::new();
-        let id_a = BankId::from_raw(1);
-        let id_b = BankId::from_raw(2);
-        let id_c = BankId::from_raw(3);
-
-        let bank_a = cluster.get_or_create(id_a, "a".into(), make_config(4));
-        let ea = bank_a.insert(make_vector(4), Temperature::Hot, 0).unwrap();
-
-        let bank_b = cluster.get_or_create(id_b, "b".into(), make_config(4));
-        let eb = bank_b.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        for (raw, name) in [(1, "temporal.a"), (2, "temporal.b"), (3, "occipital.v1")] {
+            cluster
+                .get_or_create(BankId::from_raw(raw), name.into(), make_config(4)).unwrap()
+                .insert(make_vector(4), Temperature::Hot, 0)
+                .unwrap();
+        }
 
-        let bank_c = cluster.get_or_create(id_c, "c".into(), make_config(4));
-        let ec = bank_c.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        assert_eq!(cluster.flush_prefix(dir.path(), "temporal.", 1).unwrap(), 2);
+        assert!(dir.path().join("temporal.a.bank").is_file());
+        assert!(!dir.path().join("occipital.v1.bank").exists());
+        assert!(cluster.get(BankId::from_raw(3)).unwrap().is_dirty());
+        assert_eq!(cluster.flush_prefix(dir.path(), "temporal.", 2).unwrap(), 0);
+        assert_eq!(cluster.flush_dirty(dir.path(), 2).unwrap(), 1);
+    }
 
-        // a -> b -> c (chain of RelatedTo edges)
-        let ref_a = BankRef { bank: id_a, entry: ea };
-        let ref_b = BankRef { bank: id_b, entry: eb };
-        let ref_c = BankRef { bank: id_c, entry: ec };
+    #[test]
+    fn flush_prefix_with_journal_keeps_other_banks_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_path = dir.path().join("databank.journal");
+        let mut cluster = BankCluster::with_journal(&journal_path).unwrap();
+        let (temporal, occipital) = (BankId::from_raw(1), BankId::from_raw(2));
+        for (id, name) in [(temporal, "temporal.a"), (occipital, "occipital.v1")] {
+            let entry_id = cluster
+                .get_or_create(id, name.into(), make_config(4)).unwrap()
+                .insert(make_vector(4), Temperature::Hot, 0)
+                .unwrap();
+            cluster
+                .journal_mutation(crate::journal::JournalEntry::Touch {
+                    bank_id: id,
+                    entry_id,
+                    tick: 1,
+                })
+                .unwrap();
+        }
 
-        cluster.link(ref_a, ref_b, EdgeType::RelatedTo, 200, 0).unwrap();
-        cluster.link(ref_b, ref_c, EdgeType::RelatedTo, 150, 0).unwrap();
+        assert_eq!(
+            cluster.flush_prefix_with_journal(dir.path(), "temporal.", 1).unwrap(),
+            1
+        );
+        let left = JournalReader::read_all(&journal_path).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].bank_id(), occipital);
 
-        // Depth 1: should find b
-        let d1 = cluster.traverse(ref_a, EdgeType::RelatedTo, 1);
-        assert_eq!(d1.len(), 1);
-        assert_eq!(d1[0], ref_b);
+        // The writer follows the rewritten file
+        cluster
+            .journal_mutation(crate::journal::JournalEntry::Remove {
+                bank_id: temporal,
+                entry_id: EntryId::from_raw(9),
+            })
+            .unwrap();
+        assert_eq!(JournalReader::read_all(&journal_path).unwrap().len(), 2);
+    }
 
-        // Depth 2: should find b and c
-        let d2 = cluster.traverse(ref_a, EdgeType::RelatedTo, 2);
-        assert_eq!(d2.len(), 2);
-        assert!(d2.contains(&ref_b));
-        assert!(d2.contains(&ref_c));
+    #[test]
+    fn flush_schedule_restarts_each_prefix_clock() {
+        let mut schedule = FlushSchedule::new()
+            .every("temporal.", 500)
+            .every("occipital.", 5_000)
+            .every("temporal.", 400);
+        assert!(schedule.due(399).is_empty());
+        assert_eq!(schedule.due(400), vec!["temporal."]);
+        assert!(schedule.due(799).is_empty());
+        assert_eq!(schedule.due(5_000), vec!["temporal.", "occipital."]);
+        assert_eq!(schedule.due(5_400), vec!["temporal."]);
+    }
 
-        // Depth 0: nothing
-        let d0 = cluster.traverse(ref_a, EdgeType::RelatedTo, 0);
-        assert!(d0.is_empty());
+    #[test]
+    fn tick_batches_journal_flushes() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_path = dir.path().join("ticked.journal");
+        let mut cluster = BankCluster::with_journal(&journal_path).unwrap();
+        let id = BankId::from_raw(1);
+        let entry_id = cluster
+            .get_or_create(id, "ticked".into(), make_config(4)).unwrap()
+            .insert(make_vector(4), Temperature::Hot, 0)
+            .unwrap();
+        let touch = |tick| crate::journal::JournalEntry::Touch {
+            bank_id: id,
+            entry_id,
+            tick,
+        };
 
-        // Wrong edge type: nothing
-        let wrong = cluster.traverse(ref_a, EdgeType::LooksLike, 2);
-        assert!(wrong.is_empty());
+        cluster.begin_tick();
+        for _ in 0..3 {
+            cluster.journal_mutation(touch(1)).unwrap();
+        }
+        assert_eq!(cluster.journal_flush_count(), 0);
+        assert_eq!(cluster.end_tick().unwrap(), 3);
+        assert_eq!(cluster.journal_flush_count(), 1);
+        assert_eq!(JournalReader::read_all(&journal_path).unwrap().len(), 3);
+
+        // An empty tick costs nothing; outside a tick every record flushes
+        cluster.begin_tick();
+        assert_eq!(cluster.end_tick().unwrap(), 0);
+        cluster.journal_mutation(touch(2)).unwrap();
+        cluster.journal_mutation(touch(2)).unwrap();
+        assert_eq!(cluster.journal_flush_count(), 3);
     }
 
+    #[cfg(feature = "metrics")]
     #[test]
-    fn flush_and_load_round_trip() {
-        let mut cluster = BankCluster::new();
+    fn metrics_snapshot_counts_bank_activity() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_path = dir.path().join("cluster.journal");
+        let mut cluster = BankCluster::with_journal(&journal_path).unwrap();
         let id = BankId::from_raw(1);
-        let bank = cluster.get_or_create(id, "test.round.trip".into(), make_config(4));
+        let config = BankConfig {
+            max_entries: 2,
+            ..make_config(4)
+        };
+        let bank = cluster.get_or_create(id, "metered".into(), config).unwrap();
+        let first = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
         bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
-        bank.insert(make_vector(4), Temperature::Warm, 0).unwrap();
+        bank.remove(first);
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        bank.query_sparse(&make_vector(4), 1).unwrap();
+        bank.query_sparse(&make_vector(4), 1).unwrap();
+        cluster
+            .journal_mutation(crate::journal::JournalEntry::Remove {
+                bank_id: id,
+                entry_id: first,
+            })
+            .unwrap();
+        cluster.flush_dirty(dir.path(), 1_000).unwrap();
+
+        let metrics = &cluster.metrics_snapshot()[&id];
+        assert_eq!(metrics.inserts, 4);
+        assert_eq!(metrics.removes, 1);
+        assert_eq!(metrics.evictions, 1);
+        assert_eq!(metrics.queries, 2);
+        assert_eq!(metrics.query_latency.iter().sum::<u64>(), 2);
+        assert_eq!(metrics.journal_records, 1);
+        assert_eq!(metrics.flushes, 1);
+    }
 
+    #[test]
+    fn dirty_banks_are_ordered_by_urgency() {
         let dir = tempfile::tempdir().unwrap();
-        let flushed = cluster.flush_dirty(dir.path(), 100).unwrap();
-        assert_eq!(flushed, 1);
+        let mut cluster = BankCluster::with_journal(&dir.path().join("c.journal")).unwrap();
+        assert_eq!(cluster.journal_len_bytes().unwrap(), 0);
 
-        // Load back
-        let loaded = BankCluster::load_all(dir.path()).unwrap();
-        assert_eq!(loaded.len(), 1);
-        let loaded_bank = loaded.get_by_name("test.round.trip").unwrap();
-        assert_eq!(loaded_bank.len(), 2);
-        assert_eq!(loaded_bank.id, id);
+        let config = BankConfig {
+            persist_after_mutations: 3,
+            persist_after_ticks: 1_000,
+            ..make_config(4)
+        };
+        let (quiet, busy, due, clean) = (
+            BankId::from_raw(1),
+            BankId::from_raw(2),
+            BankId::from_raw(3),
+            BankId::from_raw(4),
+        );
+        for (id, name, inserts) in [(quiet, "quiet", 1), (busy, "busy", 2), (due, "due", 3)] {
+            let bank = cluster.get_or_create(id, name.into(), config.clone()).unwrap();
+            for _ in 0..inserts {
+                bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+            }
+        }
+        cluster.get_or_create(clean, "clean".into(), config).unwrap();
+
+        let order: Vec<BankId> = cluster.dirty_banks(10).iter().map(|(id, _)| *id).collect();
+        assert_eq!(order, vec![due, busy, quiet]);
+        assert_eq!(cluster.dirty_banks(10)[1].1.mutations_since_persist, 2);
+
+        cluster
+            .journal_mutation(crate::journal::JournalEntry::Remove {
+                bank_id: quiet,
+                entry_id: EntryId::from_raw(1),
+            })
+            .unwrap();
+        assert!(cluster.journal_len_bytes().unwrap() > 0);
     }
 
     #[test]
@@ -573,10 +3592,14 @@ mod tests {
         let id_a = BankId::from_raw(1);
         let id_b = BankId::from_raw(2);
 
-        let bank_a = cluster.get_or_create(id_a, "temporal.semantic".into(), make_config(4));
+        let bank_a = cluster
+            .get_or_create(id_a, "temporal.semantic".into(), make_config(4))
+            .unwrap();
         bank_a.insert(make_vector(4), Temperature::Hot, 0).unwrap();
 
-        let bank_b = cluster.get_or_create(id_b, "temporal.auditory".into(), make_config(4));
+        let bank_b = cluster
+            .get_or_create(id_b, "temporal.auditory".into(), make_config(4))
+            .unwrap();
         bank_b.insert(make_vector(4), Temperature::Hot, 0).unwrap();
 
         let mut queries = HashMap::new();
@@ -591,6 +3614,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn z_score_survives_extreme_and_narrow_spreads() {
+        let results = |scores: &[i32]| -> Vec<QueryResult> {
+            scores
+                .iter()
+                .enumerate()
+                .map(|(i, &score)| QueryResult {
+                    entry_id: EntryId::from_raw(i as u64),
+                    score,
+                })
+                .collect()
+        };
+        let normalized = |scores: &[i32]| -> Vec<i32> {
+            fuse_results(vec![(BankId::from_raw(1), "b".into(), results(scores))])
+                .iter()
+                .map(|r| r.normalized_score)
+                .collect()
+        };
+
+        // The i64 sum of squares used to overflow here.
+        let wide = normalized(&[i32::MAX, i32::MIN, 0, 1]);
+        assert!(wide[0] > wide[3] && wide[3] >= wide[2] && wide[2] > wide[1]);
+        // z = ±1.2247 for the extremes
+        assert!((310..=316).contains(&wide[0]), "got {}", wide[0]);
+        assert!((-316..=-310).contains(&wide[1]), "got {}", wide[1]);
+
+        // A stddev under 1 used to truncate to 1, giving 0 / 256 here.
+        let narrow = normalized(&[100, 101, 100, 101]);
+        assert_eq!(narrow[0], narrow[2]);
+        assert_eq!(narrow[0], -narrow[1]);
+        // z = ±0.866
+        assert!((220..=222).contains(&narrow[1]), "got {}", narrow[1]);
+
+        assert_eq!(normalized(&[7, 7, 7]), vec![0, 0, 0]);
+        assert_eq!(normalized(&[i32::MIN]), vec![0]);
+    }
+
+    #[test]
+    fn single_perfect_match_outranks_a_spread_of_weak_ones() {
+        let mut cluster = BankCluster::new();
+        let spatial = BankId::from_raw(1);
+        let semantic = BankId::from_raw(2);
+        let sig = |polarity, magnitude| Signal::new_raw(polarity, magnitude, 1);
+        let query = vec![sig(1, 100), sig(1, 100), sig(1, 100), sig(1, 100)];
+
+        let perfect = cluster
+            .get_or_create(spatial, "spatial".into(), make_config(4))
+            .unwrap()
+            .insert(query.clone(), Temperature::Hot, 0)
+            .unwrap();
+        let bank = cluster.get_or_create(semantic, "semantic".into(), make_config(4)).unwrap();
+        for weak in [
+            vec![sig(1, 100), sig(-1, 90), sig(-1, 80), sig(0, 0)],
+            vec![sig(1, 100), sig(-1, 70), sig(-1, 100), sig(1, 10)],
+            vec![sig(1, 100), sig(-1, 100), sig(-1, 60), sig(1, 30)],
+            vec![sig(1, 100), sig(-1, 50), sig(0, 0), sig(-1, 40)],
+        ] {
+            bank.insert(weak, Temperature::Hot, 0).unwrap();
+        }
+
+        let queries: HashMap<BankId, Vec<Signal>> =
+            [(spatial, query.clone()), (semantic, query)].into_iter().collect();
+        let results = cluster.query_all(&queries, 5);
+        assert_eq!(results.len(), 5);
+        assert_eq!((results[0].bank_id, results[0].entry_id), (spatial, perfect));
+        assert_eq!(results[0].score, 256);
+        assert!(results[1..].iter().all(|r| r.bank_id == semantic && r.score < 128));
+        assert!(results[0].normalized_score > results[1].normalized_score);
+    }
+
     #[test]
     fn query_by_prefix_filters() {
         let mut cluster = BankCluster::new();
@@ -598,11 +3691,11 @@ mod tests {
         let id_b = BankId::from_raw(2);
         let id_c = BankId::from_raw(3);
 
-        cluster.get_or_create(id_a, "temporal.semantic".into(), make_config(4))
+        cluster.get_or_create(id_a, "temporal.semantic".into(), make_config(4)).unwrap()
             .insert(make_vector(4), Temperature::Hot, 0).unwrap();
-        cluster.get_or_create(id_b, "temporal.auditory".into(), make_config(4))
+        cluster.get_or_create(id_b, "temporal.auditory".into(), make_config(4)).unwrap()
             .insert(make_vector(4), Temperature::Hot, 0).unwrap();
-        cluster.get_or_create(id_c, "occipital.v4".into(), make_config(4))
+        cluster.get_or_create(id_c, "occipital.v4".into(), make_config(4)).unwrap()
             .insert(make_vector(4), Temperature::Hot, 0).unwrap();
 
         // Query only temporal.* banks
@@ -613,6 +3706,343 @@ mod tests {
         }
     }
 
+    #[derive(Default)]
+    struct CountingObserver {
+        created: std::sync::atomic::AtomicUsize,
+        flushed: std::sync::atomic::AtomicUsize,
+        evicted: std::sync::atomic::AtomicUsize,
+        replayed: std::sync::atomic::AtomicUsize,
+    }
+
+    impl ClusterObserver for std::sync::Arc<CountingObserver> {
+        fn on_bank_created(&self, _bank_id: BankId, _name: &str) {
+            self.created.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        fn on_flush(&self, _bank_id: BankId, _path: &Path) {
+            self.flushed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        fn on_eviction(&self, _bank_id: BankId, count: usize) {
+            self.evicted.fetch_add(count, std::sync::atomic::Ordering::Relaxed);
+        }
+        fn on_replay(&self, count: usize) {
+            self.replayed.fetch_add(count, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn observer_receives_cluster_events() {
+        use std::sync::atomic::Ordering;
+        use std::sync::Arc;
+
+        let counts = Arc::new(CountingObserver::default());
+        let mut cluster = BankCluster::new();
+        cluster.set_observer(Box::new(counts.clone()));
+
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "observed".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        // Existing bank: no second creation event
+        cluster.get_or_create(id, "observed".into(), make_config(4)).unwrap();
+        assert_eq!(counts.created.load(Ordering::Relaxed), 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        cluster.flush_dirty(dir.path(), 10).unwrap();
+        assert_eq!(counts.flushed.load(Ordering::Relaxed), 1);
+
+        assert_eq!(cluster.evict(id, 1, 20).unwrap(), 1);
+        assert_eq!(counts.evicted.load(Ordering::Relaxed), 1);
+        // Either entry may have been evicted; touch the survivor.
+        let eid = *cluster.get(id).unwrap().entries().next().unwrap().0;
+
+        let journal_path = dir.path().join("databank.journal");
+        {
+            let mut writer = JournalWriter::open(&journal_path).unwrap();
+            writer
+                .append(&crate::journal::JournalEntry::Touch {
+                    bank_id: id,
+                    entry_id: eid,
+                    tick: 30,
+                })
+                .unwrap();
+            writer.flush().unwrap();
+        }
+        cluster.replay_journal(&journal_path).unwrap();
+        assert_eq!(counts.replayed.load(Ordering::Relaxed), 1);
+
+        // Loading replays before an observer can be installed; the count
+        // comes back in the report instead.
+        let (_, report) = BankCluster::load_with_journal_report(dir.path()).unwrap();
+        assert_eq!(report.journal.map(|j| j.applied), Some(1));
+        assert_eq!(counts.replayed.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn observer_sees_migration_evictions_but_not_bank_local_ones() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct Evictions(AtomicUsize);
+        impl BankObserver for Evictions {
+            fn on_evict(&self, _bank_id: BankId, _entry_id: EntryId) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let counts = Arc::new(CountingObserver::default());
+        let mut cluster = BankCluster::new();
+        cluster.set_observer(Box::new(counts.clone()));
+        let (source, target) = (BankId::from_raw(1), BankId::from_raw(2));
+        let mut small = make_config(4);
+        small.max_entries = 2;
+        cluster.get_or_create(source, "source".into(), make_config(4)).unwrap();
+        cluster.get_or_create(target, "target".into(), small).unwrap();
+        let per_entry = Arc::new(Evictions::default());
+        cluster.set_bank_observer(per_entry.clone());
+
+        // A bank making room for an insert made directly on it
+        let bank = cluster.get_mut(target).unwrap();
+        for tick in 0..3 {
+            bank.insert(make_vector(4), Temperature::Hot, tick).unwrap();
+        }
+        assert_eq!(per_entry.0.load(Ordering::Relaxed), 1);
+        assert_eq!(counts.evicted.load(Ordering::Relaxed), 0);
+
+        // A migration making room goes through the cluster
+        let bank = cluster.get_mut(source).unwrap();
+        let moving = bank.insert(make_vector(4), Temperature::Hot, 5).unwrap();
+        cluster.migrate_entry(BankRef { bank: source, entry: moving }, target).unwrap();
+        assert_eq!(per_entry.0.load(Ordering::Relaxed), 2);
+        assert_eq!(counts.evicted.load(Ordering::Relaxed), 1);
+        assert_eq!(cluster.get(target).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn load_with_journal_checked_classifies_journal_damage() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = BankId::from_raw(1);
+        let eid = {
+            let mut cluster = BankCluster::new();
+            let bank = cluster.get_or_create(id, "durable".into(), make_config(4)).unwrap();
+            let eid = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+            cluster.flush_dirty(dir.path(), 10).unwrap();
+            eid
+        };
+
+        let journal_path = dir.path().join("databank.journal");
+        {
+            let mut writer = JournalWriter::open(&journal_path).unwrap();
+            writer
+                .append(&crate::journal::JournalEntry::Touch {
+                    bank_id: id,
+                    entry_id: eid,
+                    tick: 30,
+                })
+                .unwrap();
+            // References a bank that was never snapshotted
+            writer
+                .append(&crate::journal::JournalEntry::Remove {
+                    bank_id: BankId::from_raw(99),
+                    entry_id: eid,
+                })
+                .unwrap();
+            writer.flush().unwrap();
+        }
+        // Torn write at the tail
+        let mut data = std::fs::read(&journal_path).unwrap();
+        let good_len = data.len();
+        data.extend_from_slice(&[0, 1, 2]);
+        std::fs::write(&journal_path, &data).unwrap();
+
+        let (cluster, issues) = BankCluster::load_with_journal_checked(dir.path()).unwrap();
+        assert_eq!(cluster.get(id).unwrap().get(eid).unwrap().access_count, 1);
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(issues[0], JournalError::CorruptRecord { offset } if offset == good_len));
+        assert!(matches!(issues[1], JournalError::ReplaySkipped { count: 1 }));
+        assert!(issues.iter().all(|i| i.severity() == ErrorSeverity::Degraded));
+
+        // A damaged snapshot is fatal and surfaces as a codec/checksum error
+        let bank_path = dir.path().join("durable.bank");
+        let mut bytes = std::fs::read(&bank_path).unwrap();
+        bytes[0] = b'X';
+        std::fs::write(&bank_path, &bytes).unwrap();
+        match BankCluster::load_with_journal_checked(dir.path()) {
+            Err(e) => {
+                assert_eq!(e.error_code(), crate::error::ErrorCode::Codec);
+                assert_eq!(e.severity(), ErrorSeverity::Fatal);
+            }
+            Ok(_) => panic!("corrupt snapshot must not load"),
+        }
+    }
+
+    #[test]
+    fn background_flush_marks_persisted_only_after_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "background".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+
+        cluster.start_flush_worker(dir.path()).unwrap();
+        assert_eq!(cluster.request_flush(10).unwrap(), 1);
+        // Still dirty: nothing is persisted until the worker reports back,
+        // no matter how far the write has progressed
+        assert!(cluster.get(id).unwrap().is_dirty());
+        // In flight: not enqueued twice
+        assert_eq!(cluster.request_flush(11).unwrap(), 0);
+
+        // A mutation after the snapshot must survive the completion
+        cluster
+            .get_mut(id)
+            .unwrap()
+            .insert(make_vector(4), Temperature::Hot, 12)
+            .unwrap();
+
+        assert_eq!(cluster.shutdown_flush_worker().unwrap(), 1);
+        let path = dir.path().join("background.bank");
+        assert!(path.exists());
+        assert_eq!(codec::load(&path).unwrap().len(), 1);
+        let bank = cluster.get(id).unwrap();
+        assert!(bank.is_dirty());
+        assert_eq!(bank.mutations_since_persist(), 1);
+    }
+
+    #[test]
+    fn background_flush_superseded_by_rename_keeps_the_live_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "motor.draft".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+
+        cluster.start_flush_worker(dir.path()).unwrap();
+        assert_eq!(cluster.request_flush(10).unwrap(), 1);
+        cluster.rename_bank(id, "motor.cortex".into()).unwrap();
+        cluster
+            .get_mut(id)
+            .unwrap()
+            .insert(make_vector(4), Temperature::Hot, 11)
+            .unwrap();
+        // The write under the old name is still in flight: left alone
+        assert_eq!(cluster.flush_dirty(dir.path(), 12).unwrap(), 0);
+
+        cluster.shutdown_flush_worker().unwrap();
+        // The late completion neither marks the renamed bank clean nor
+        // leaves a file under the old name behind
+        assert!(cluster.get(id).unwrap().is_dirty());
+        assert!(!dir.path().join("motor.draft.bank").exists());
+
+        assert_eq!(cluster.flush_dirty(dir.path(), 13).unwrap(), 1);
+        let live = dir.path().join("motor.cortex.bank");
+        assert_eq!(codec::load(&live).unwrap().len(), 2);
+
+        // A superseded completion arriving after the sync flush must not
+        // delete the live file either
+        cluster.apply_flush_completion(&FlushCompletion {
+            bank_id: id,
+            path: dir.path().join("motor.draft.bank"),
+            tick: 10,
+            mutations: 1,
+            generation: 1,
+            result: Ok(()),
+        });
+        assert!(live.is_file());
+        assert!(!cluster.get(id).unwrap().is_dirty());
+
+        let loaded = BankCluster::load_all(dir.path()).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.get(id).unwrap().name, "motor.cortex");
+        assert_eq!(loaded.get(id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn journal_flushes_keep_records_of_banks_in_flight() {
+        use crate::journal::{JournalEntry, JournalReader};
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("databank.journal");
+        let mut cluster = BankCluster::with_journal(&path).unwrap();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "motor.cortex".into(), make_config(4)).unwrap();
+        let entry_id = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+
+        cluster.start_flush_worker(dir.path()).unwrap();
+        assert_eq!(cluster.request_flush(10).unwrap(), 1);
+        let set = JournalEntry::SetTemperature {
+            bank_id: id,
+            entry_id,
+            temperature: Temperature::Cold,
+        };
+        assert!(cluster.apply_journaled(set).unwrap());
+
+        assert_eq!(cluster.flush_prefix_with_journal(dir.path(), "motor.", 11).unwrap(), 0);
+        assert_eq!(cluster.flush_dirty_with_journal(dir.path(), 11).unwrap(), 0);
+        assert_eq!(JournalReader::read_all(&path).unwrap().len(), 1);
+
+        cluster.wait_for_flushes(Duration::from_secs(5));
+        assert_eq!(cluster.flush_dirty_with_journal(dir.path(), 12).unwrap(), 1);
+        assert!(JournalReader::read_all(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn wait_for_flushes_keeps_newer_mutations_dirty() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "barrier".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+        assert_eq!(cluster.pending_flushes(), 0);
+
+        cluster.start_flush_worker(dir.path()).unwrap();
+        assert_eq!(cluster.request_flush(10).unwrap(), 1);
+        assert_eq!(cluster.pending_flushes(), 1);
+
+        // While the write is in flight: a synchronous flush resets the
+        // mutation counter, then one more mutation lands. Counting alone
+        // would let the late completion mark this bank clean.
+        let bank = cluster.get_mut(id).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 11).unwrap();
+        bank.mark_persisted(11);
+        bank.insert(make_vector(4), Temperature::Hot, 12).unwrap();
+        assert_eq!(bank.generation(), 3);
+
+        let completions = cluster.wait_for_flushes(Duration::from_secs(10));
+        assert_eq!(completions.len(), 1);
+        assert!(completions[0].result.is_ok());
+        assert_eq!(completions[0].generation, 1);
+        assert_eq!(cluster.pending_flushes(), 0);
+        let bank = cluster.get(id).unwrap();
+        assert!(bank.is_dirty());
+        assert_eq!(bank.mutations_since_persist(), 1);
+
+        // Nothing pending: returns at once
+        assert!(cluster.wait_for_flushes(Duration::ZERO).is_empty());
+
+        // A completion for the current generation does clean the bank
+        assert_eq!(cluster.request_flush(13).unwrap(), 1);
+        cluster.wait_for_flushes(Duration::from_secs(10));
+        assert!(!cluster.get(id).unwrap().is_dirty());
+        cluster.shutdown_flush_worker().unwrap();
+    }
+
+    #[test]
+    fn failed_background_write_leaves_bank_dirty() {
+        let dir = tempfile::tempdir().unwrap();
+        // A regular file where the output directory should be
+        let blocked = dir.path().join("not_a_dir");
+        std::fs::write(&blocked, b"x").unwrap();
+
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(1);
+        let bank = cluster.get_or_create(id, "doomed".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+
+        cluster.start_flush_worker(&blocked).unwrap();
+        assert_eq!(cluster.request_flush(10).unwrap(), 1);
+        assert!(cluster.shutdown_flush_worker().is_err());
+        assert!(cluster.get(id).unwrap().is_dirty());
+        assert!(cluster.request_flush(20).is_err(), "worker is gone");
+    }
+
     #[test]
     fn load_all_nonexistent_dir() {
         let cluster = BankCluster::load_all(Path::new("/nonexistent/path/that/does/not/exist"));
@@ -4,7 +4,7 @@
 //! ```text
 //! [0..4]   Magic: b"BANK"
 //! [4..6]   Version: u16 LE = 3
-//! [6..8]   Flags: u16 LE = 0
+//! [6..8]   Flags: u16 LE (see FLAG_* constants)
 //! [8..12]  Total size: u32 LE (patched after encode)
 //! [12..20] Checksum: u64 LE xxhash64 (patched after encode)
 //! [20..28] BankId: u64 LE
@@ -15,58 +15,295 @@
 //! v3 stores each signal as 3 bytes: polarity (i8 as u8), magnitude (u8), multiplier (u8).
 //! v2 stored 1 byte per signal (PackedSignal raw u8) -- lossy, no longer supported.
 //! v1 stored 2 bytes per signal (polarity + magnitude, no multiplier) -- no longer supported.
+//!
+//! Flags:
+//! - bit 0 (`FLAG_EXTENDED_TEMPERATURE`): at least one entry uses a
+//!   temperature discriminant beyond the original four (`Temperature::Set`).
+//!   Readers that predate the flag reject such files on the unknown
+//!   temperature byte; current readers reject unknown flag bits outright.
+//! - bit 1 (`FLAG_ENTRY_FLAGS`): every entry carries one extra byte of
+//!   per-entry flags after its confidence byte (`ENTRY_FLAG_PINNED` = bit 0,
+//!   `ENTRY_FLAG_NAMESPACE` = bit 1, followed by a u16 LE namespace;
+//!   `ENTRY_FLAG_EXPIRES` = bit 2, followed by the u64 LE expiry tick).
+//!   Only set when some entry needs it, so older files stay byte-identical.
+//! - bit 7 (`FLAG_META_CHECKSUMS`): every entry carries a second u32 LE
+//!   CRC32 after its vector checksum, over its edges and lifecycle fields
+//!   (see [`BankEntry::compute_meta_checksum`]). Set unless
+//!   [`EncodeOptions::vector_checksums_only`] asks for files that builds
+//!   predating the flag can read. Without it, entries validate by their
+//!   vector checksum alone.
+//!
+//! The remaining flags are opt-in through [`EncodeOptions`]; the default
+//! options produce exactly the layout above.
+//! - bit 2 (`FLAG_SPARSE_VECTORS`): each vector is its width (u16), a count
+//!   of stored signals (u16), then `(index u16, 3 signal bytes)` per signal
+//!   that is not `Signal::ZERO`, in ascending index order.
+//! - bit 3 (`FLAG_METADATA`): a metadata section follows the state
+//!   counters carrying the runtime-only config fields: edge overflow (u8),
+//!   min ticks between flushes (u64), max bytes (u64, 0 = none), and the
+//!   exact-match fast path (u8).
+//! - bit 4 (`FLAG_INDEX`): an index section follows (after metadata, if
+//!   present): index kind (u8, 0 = brute force, 1 = IVF), k (u32), and
+//!   nprobe (u32). Centroids are rebuilt on load, not stored.
+//! - bit 5 (`FLAG_COMPRESSED`): everything after the header is PackBits
+//!   run-length encoded. The checksum and total size cover the compressed
+//!   bytes; decode errors report offsets into the decompressed image.
+//! - bit 6 (`FLAG_QUANTIZED`): a codebook section follows the config:
+//!   prototype count (u16), then that many vectors of width signals. Each
+//!   entry's vector is its width (u16), a prototype index (u16), a count of
+//!   corrections (u16), then `(index u16, 3 signal bytes)` per correction in
+//!   ascending index order; other dimensions take the prototype's signal.
+//!   Takes precedence over `FLAG_SPARSE_VECTORS`. Stored entry checksums
+//!   are of the original vectors and are recomputed on load.
+//!
+//! Delta sections (see [`save_incremental`]) may follow the base, past its
+//! total size, so [`decode`] reads a file with deltas as its base alone and
+//! [`load`] applies them in order:
+//! ```text
+//! [0..4]   Magic: b"BDLT"
+//! [4..8]   Section size: u32 LE, this header included
+//! [8..16]  Checksum: u64 LE xxhash64 of bytes [16..size]
+//! [16..18] Entry flags: u16 LE (FLAG_* bits the entries are encoded with)
+//! [18..22] Changed entry count: u32 LE
+//! [22..26] Removed entry count: u32 LE
+//! [26..]   Changed entries (entry layout above), removed EntryIds (u64 LE
+//!          each), then the state counters
+//! ```
+//! Each changed entry replaces or adds the entry with its id. A delta that
+//! is truncated or fails its checksum (a crash mid-append) ends the deltas
+//! applied, with a warning.
 
-use std::collections::HashMap;
+#[cfg(feature = "std")]
 use std::path::Path;
 
+use serde::Serialize;
 use ternary_signal::Signal;
 
-use crate::bank::DataBank;
-use crate::entry::BankEntry;
-use crate::error::{DataBankError, Result};
+use crate::bank::{BankLabel, DataBank};
+use crate::entry::{BankEntry, EdgeList};
+use crate::error::{CodecError, CodecErrorKind, DataBankError, Result};
+use crate::prelude::*;
+use crate::quantize::{Codebook, QuantizedVector};
 use crate::types::*;
 
 const MAGIC: &[u8; 4] = b"BANK";
-const VERSION: u16 = 3;
+
+/// The `.bank` format version this build writes and reads (header bytes
+/// 4..6). Files of any other version are rejected.
+pub const FORMAT_VERSION: u16 = 3;
 const HEADER_SIZE: usize = 32;
 
+/// Header flag: entries may use extended temperature discriminants.
+pub const FLAG_EXTENDED_TEMPERATURE: u16 = 0x0001;
+
+/// Header flag: each entry carries a per-entry flags byte.
+pub const FLAG_ENTRY_FLAGS: u16 = 0x0002;
+
+/// Header flag: vectors are stored sparsely (non-zero signals only).
+pub const FLAG_SPARSE_VECTORS: u16 = 0x0004;
+
+/// Header flag: a runtime-config metadata section follows the state counters.
+pub const FLAG_METADATA: u16 = 0x0008;
+
+/// Header flag: an index-configuration section follows the state counters.
+pub const FLAG_INDEX: u16 = 0x0010;
+
+/// Header flag: the body after the header is PackBits-compressed.
+pub const FLAG_COMPRESSED: u16 = 0x0020;
+
+/// Header flag: vectors are stored against a codebook.
+pub const FLAG_QUANTIZED: u16 = 0x0040;
+
+/// Header flag: each entry stores a metadata checksum after its vector
+/// checksum.
+pub const FLAG_META_CHECKSUMS: u16 = 0x0080;
+
+/// All header flag bits this codec understands.
+const KNOWN_FLAGS: u16 = FLAG_EXTENDED_TEMPERATURE
+    | FLAG_ENTRY_FLAGS
+    | FLAG_SPARSE_VECTORS
+    | FLAG_METADATA
+    | FLAG_INDEX
+    | FLAG_COMPRESSED
+    | FLAG_QUANTIZED
+    | FLAG_META_CHECKSUMS;
+
+/// Per-entry flag: entry is pinned (never demoted or evicted).
+pub const ENTRY_FLAG_PINNED: u8 = 0x01;
+
+/// Per-entry flag: a u16 LE namespace follows the flags byte. Absent means
+/// namespace 0.
+pub const ENTRY_FLAG_NAMESPACE: u8 = 0x02;
+
+/// All per-entry flag bits this codec understands.
+/// Per-entry flag: a u64 LE `expires_at_tick` follows the namespace (if
+/// any). Absent means the entry never expires.
+pub const ENTRY_FLAG_EXPIRES: u8 = 0x04;
+
+const KNOWN_ENTRY_FLAGS: u8 = ENTRY_FLAG_PINNED | ENTRY_FLAG_NAMESPACE | ENTRY_FLAG_EXPIRES;
+
+const DELTA_MAGIC: &[u8; 4] = b"BDLT";
+const DELTA_HEADER_SIZE: usize = 26;
+
+/// Flags delta entries are written (and fingerprinted) with: every
+/// per-entry field, whatever the rest of the bank needs.
+#[cfg(feature = "std")]
+const DELTA_ENTRY_FLAGS: u16 = FLAG_EXTENDED_TEMPERATURE | FLAG_ENTRY_FLAGS | FLAG_META_CHECKSUMS;
+
 // ---------------------------------------------------------------------------
 // Encode (v3)
 // ---------------------------------------------------------------------------
 
+/// Per-save choices for optional `.bank` features. The default writes the
+/// same bytes as [`encode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// PackBits-compress everything after the header (`FLAG_COMPRESSED`).
+    pub compress: bool,
+    /// Store only non-zero signals of each vector (`FLAG_SPARSE_VECTORS`).
+    pub sparse: bool,
+    /// Persist runtime-only config fields (`FLAG_METADATA`).
+    pub include_metadata: bool,
+    /// Persist the index configuration (`FLAG_INDEX`).
+    pub include_index: bool,
+    /// Store vectors against a codebook (`FLAG_QUANTIZED`), correcting
+    /// dimensions whose current is off by more than this tolerance; see
+    /// [`crate::quantize`]. `Some(0)` is lossless. Overrides `sparse`.
+    ///
+    /// Only the file shrinks: decoded vectors are full width in memory.
+    /// The codebook is the bank's kept one
+    /// ([`DataBank::train_codebook`]), or one trained for this encode.
+    pub quantize: Option<u16>,
+    /// Leave out the per-entry metadata checksums (`FLAG_META_CHECKSUMS`),
+    /// for readers that predate them.
+    pub vector_checksums_only: bool,
+}
+
+impl EncodeOptions {
+    /// Header flag bits these options request.
+    pub fn flags(&self) -> u16 {
+        let mut flags = 0;
+        if self.compress {
+            flags |= FLAG_COMPRESSED;
+        }
+        if self.quantize.is_some() {
+            flags |= FLAG_QUANTIZED;
+        } else if self.sparse {
+            flags |= FLAG_SPARSE_VECTORS;
+        }
+        if self.include_metadata {
+            flags |= FLAG_METADATA;
+        }
+        if self.include_index {
+            flags |= FLAG_INDEX;
+        }
+        if !self.vector_checksums_only {
+            flags |= FLAG_META_CHECKSUMS;
+        }
+        flags
+    }
+}
+
 /// Encode a DataBank into the binary `.bank` v3 format.
 pub fn encode(bank: &DataBank) -> Result<Vec<u8>> {
+    encode_with(bank, &EncodeOptions::default())
+}
+
+/// Encode a DataBank with the given optional features enabled.
+pub fn encode_with(bank: &DataBank, options: &EncodeOptions) -> Result<Vec<u8>> {
     let mut buf = Vec::with_capacity(4096);
+    encode_into(bank, options, &mut buf)?;
+    Ok(buf)
+}
 
-    // -- Header (32 bytes, with placeholders for size + checksum) --
-    buf.extend_from_slice(MAGIC);
-    write_u16(&mut buf, VERSION);
-    write_u16(&mut buf, 0); // flags
-    write_u32(&mut buf, 0); // total_size placeholder
-    write_u64(&mut buf, 0); // checksum placeholder
-    write_u64(&mut buf, bank.id.0);
-    write_u16(&mut buf, bank.config().vector_width);
-    write_u16(&mut buf, bank.len() as u16);
+/// Encode into `buf`, clearing it first. Flushing many banks through one
+/// buffer pays for its growth once instead of once per bank, and a buffer
+/// already large enough (a pooled or shared-memory staging buffer) is
+/// written in place without reallocating.
+pub fn encode_into(bank: &DataBank, options: &EncodeOptions, buf: &mut Vec<u8>) -> Result<()> {
+    buf.clear();
 
-    // -- Bank name --
-    write_str(&mut buf, &bank.name);
+    let mut flags = options.flags();
+    if bank.entries().any(|(_, e)| e.temperature.is_extended()) {
+        flags |= FLAG_EXTENDED_TEMPERATURE;
+    }
+    if bank
+        .entries()
+        .any(|(_, e)| e.pinned || e.namespace != 0 || e.expires_at_tick.is_some())
+    {
+        flags |= FLAG_ENTRY_FLAGS;
+    }
 
-    // -- Config --
-    write_u32(&mut buf, bank.config().persist_after_mutations);
-    write_u64(&mut buf, bank.config().persist_after_ticks);
-    write_u32(&mut buf, bank.config().max_entries);
-    write_u16(&mut buf, bank.config().vector_width);
-    write_u16(&mut buf, bank.config().max_edges_per_entry);
+    // -- Header (32 bytes, with placeholders for size + checksum) --
+    buf.extend_from_slice(MAGIC);
+    write_u16(buf, FORMAT_VERSION);
+    write_u16(buf, flags);
+    write_u32(buf, 0); // total_size placeholder
+    write_u64(buf, 0); // checksum placeholder
+    write_u64(buf, bank.id.0);
+    write_u16(buf, bank.config().vector_width);
+    write_u16(buf, bank.len() as u16);
+
+    encode_preamble(buf, bank);
+
+    // -- Codebook --
+    // Training runs k-means over every entry, so prefer the kept codebook
+    let trained;
+    let codebook = match (options.quantize, bank.codebook()) {
+        (None, _) => None,
+        (Some(tolerance), Some(kept)) => Some((kept, tolerance)),
+        (Some(tolerance), None) => {
+            trained = Codebook::train(bank);
+            Some((&trained, tolerance))
+        }
+    };
+    if let Some((codebook, _)) = &codebook {
+        write_u16(buf, codebook.len() as u16);
+        for prototype in codebook.prototypes() {
+            for s in prototype {
+                write_signal(buf, s);
+            }
+        }
+    }
 
     // -- Entries --
     for (_, entry) in bank.entries() {
-        encode_entry(&mut buf, entry);
+        let quantized = codebook
+            .as_ref()
+            .map(|(codebook, tolerance)| codebook.quantize(&entry.vector, *tolerance));
+        encode_entry(buf, entry, flags, quantized.as_ref());
     }
 
-    // -- State counters --
-    write_u32(&mut buf, bank.next_seq());
-    write_u32(&mut buf, bank.mutations_since_persist());
-    write_u64(&mut buf, bank.last_persist_tick());
+    encode_counters(buf, bank);
+
+    // -- Optional sections --
+    let config = bank.config();
+    if flags & FLAG_METADATA != 0 {
+        buf.push(edge_overflow_to_u8(config.edge_overflow));
+        write_u64(buf, config.min_ticks_between_flushes);
+        write_u64(buf, config.max_bytes.unwrap_or(0) as u64);
+        buf.push(config.exact_match_fast_path as u8);
+    }
+    if flags & FLAG_INDEX != 0 {
+        match config.index_type {
+            crate::ivf::IndexType::BruteForce => {
+                buf.push(0);
+                write_u32(buf, 0);
+                write_u32(buf, 0);
+            }
+            crate::ivf::IndexType::Ivf { k, nprobe } => {
+                buf.push(1);
+                write_u32(buf, k as u32);
+                write_u32(buf, nprobe as u32);
+            }
+        }
+    }
+
+    if flags & FLAG_COMPRESSED != 0 {
+        let body = pack_bits(&buf[HEADER_SIZE..]);
+        buf.truncate(HEADER_SIZE);
+        buf.extend_from_slice(&body);
+    }
 
     // -- Patch header --
     let total_size = buf.len() as u32;
@@ -75,19 +312,87 @@ pub fn encode(bank: &DataBank) -> Result<Vec<u8>> {
     let checksum = xxhash_rust::xxh3::xxh3_64(&buf[HEADER_SIZE..]);
     buf[12..20].copy_from_slice(&checksum.to_le_bytes());
 
-    Ok(buf)
+    Ok(())
+}
+
+/// Bank name and the persisted config fields, which follow the header.
+fn encode_preamble(buf: &mut Vec<u8>, bank: &DataBank) {
+    // -- Bank name --
+    write_str(buf, &bank.name);
+
+    // -- Config --
+    write_u32(buf, bank.config().persist_after_mutations);
+    write_u64(buf, bank.config().persist_after_ticks);
+    write_u32(buf, bank.config().max_entries);
+    write_u16(buf, bank.config().vector_width);
+    write_u16(buf, bank.config().max_edges_per_entry);
+}
+
+/// State counters, which follow the entries (of the base and each delta).
+fn encode_counters(buf: &mut Vec<u8>, bank: &DataBank) {
+    write_u32(buf, bank.next_seq());
+    write_u32(buf, bank.mutations_since_persist());
+    write_u64(buf, bank.last_persist_tick());
+}
+
+/// Byte length of `encode(bank)` with default options, computed from the
+/// entry shapes without encoding anything.
+pub(crate) fn encoded_len(bank: &DataBank) -> usize {
+    const CONFIG: usize = 4 + 8 + 4 + 2 + 2;
+    const STATE: usize = 4 + 4 + 8;
+    // id, width, edge count, origin, temperature, 2 ticks, access count,
+    // confidence, tag marker, both checksums
+    const ENTRY_FIXED: usize = 8 + 2 + 2 + 8 + 1 + 8 + 8 + 4 + 1 + 1 + 4 + 4;
+    const EDGE: usize = 1 + 8 + 8 + 1 + 8;
+
+    let mut any_flags = false;
+    let entries: usize = bank
+        .entries()
+        .map(|(_, e)| {
+            any_flags |= e.pinned || e.namespace != 0 || e.expires_at_tick.is_some();
+            ENTRY_FIXED
+                + e.vector.len() * 3
+                + e.edges.len() * EDGE
+                + e.debug_tag.as_ref().map_or(0, |tag| 2 + tag.len())
+                + if e.namespace != 0 { 2 } else { 0 }
+                + if e.expires_at_tick.is_some() { 8 } else { 0 }
+        })
+        .sum();
+    let entry_flags = if any_flags { bank.len() } else { 0 };
+    HEADER_SIZE + 2 + bank.name.len() + CONFIG + entries + entry_flags + STATE
 }
 
-fn encode_entry(buf: &mut Vec<u8>, entry: &BankEntry) {
+fn encode_entry(
+    buf: &mut Vec<u8>,
+    entry: &BankEntry,
+    flags: u16,
+    quantized: Option<&QuantizedVector>,
+) {
     // EntryId
     write_u64(buf, entry.id.0);
 
     // Vector -- v3: 3 bytes per Signal (polarity, magnitude, multiplier)
     write_u16(buf, entry.vector.len() as u16);
-    for s in &entry.vector {
-        buf.push(s.polarity as u8);
-        buf.push(s.magnitude);
-        buf.push(s.multiplier);
+    if let Some(quantized) = quantized {
+        write_u16(buf, quantized.prototype);
+        write_u16(buf, quantized.corrections.len() as u16);
+        for (i, s) in &quantized.corrections {
+            write_u16(buf, *i);
+            write_signal(buf, s);
+        }
+    } else if flags & FLAG_SPARSE_VECTORS != 0 {
+        let stored = entry.vector.iter().filter(|s| **s != Signal::ZERO).count();
+        write_u16(buf, stored as u16);
+        for (i, s) in entry.vector.iter().enumerate() {
+            if *s != Signal::ZERO {
+                write_u16(buf, i as u16);
+                write_signal(buf, s);
+            }
+        }
+    } else {
+        for s in &entry.vector {
+            write_signal(buf, s);
+        }
     }
 
     // Edges
@@ -114,6 +419,27 @@ fn encode_entry(buf: &mut Vec<u8>, entry: &BankEntry) {
     write_u32(buf, entry.access_count);
     buf.push(entry.confidence);
 
+    // Per-entry flags
+    if flags & FLAG_ENTRY_FLAGS != 0 {
+        let mut entry_flags = 0;
+        if entry.pinned {
+            entry_flags |= ENTRY_FLAG_PINNED;
+        }
+        if entry.namespace != 0 {
+            entry_flags |= ENTRY_FLAG_NAMESPACE;
+        }
+        if entry.expires_at_tick.is_some() {
+            entry_flags |= ENTRY_FLAG_EXPIRES;
+        }
+        buf.push(entry_flags);
+        if entry.namespace != 0 {
+            write_u16(buf, entry.namespace);
+        }
+        if let Some(at) = entry.expires_at_tick {
+            write_u64(buf, at);
+        }
+    }
+
     // Debug tag
     match &entry.debug_tag {
         Some(tag) => {
@@ -123,59 +449,122 @@ fn encode_entry(buf: &mut Vec<u8>, entry: &BankEntry) {
         None => buf.push(0),
     }
 
-    // Checksum
+    // Checksums
     write_u32(buf, entry.checksum);
+    if flags & FLAG_META_CHECKSUMS != 0 {
+        write_u32(buf, entry.meta_checksum);
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Decode
 // ---------------------------------------------------------------------------
 
+/// Decode a bank from a borrowed buffer, such as a shared-memory segment,
+/// without any intermediate copy of the encoded bytes.
+///
+/// The bank still owns its entries, so vectors, names, and tags are copied
+/// out of `data`; nothing else is. Bytes past the header's total size are
+/// ignored, so `data` may be a larger region the bank was written into.
+/// Compressed buffers are rejected because they can only be parsed from a
+/// decompressed copy; use [`decode`] for those.
+pub fn decode_borrowed(data: &[u8]) -> Result<DataBank> {
+    if data.len() >= 8 && &data[0..4] == MAGIC {
+        let flags = u16::from_le_bytes([data[6], data[7]]);
+        if flags & FLAG_COMPRESSED != 0 {
+            return Err(codec_err(
+                CodecErrorKind::UnsupportedFlags,
+                6,
+                "compressed buffers cannot be decoded without copying; use decode",
+            ));
+        }
+    }
+    decode(data)
+}
+
 /// Decode a binary `.bank` buffer into a DataBank.
 /// Only v3 format is supported. v1 and v2 files will fail with a clear error.
+///
+/// Every failure is reported as [`DataBankError::Codec`] carrying the byte
+/// offset of the failing read and, inside the entry section, the index of
+/// the entry being decoded.
+///
+/// Delta sections after the base are ignored; see [`decode_with_deltas`].
 pub fn decode(data: &[u8]) -> Result<DataBank> {
+    decode_image(data, false)
+}
+
+/// [`decode`], then apply the delta sections written by
+/// [`save_incremental`] after the base.
+pub fn decode_with_deltas(data: &[u8]) -> Result<DataBank> {
+    decode_image(data, true)
+}
+
+fn decode_image(data: &[u8], with_deltas: bool) -> Result<DataBank> {
     if data.len() < HEADER_SIZE {
-        return Err(DataBankError::Codec("data too short for header".into()));
+        return Err(codec_err(
+            CodecErrorKind::Truncated,
+            data.len(),
+            format!("data too short for header: {} bytes", data.len()),
+        ));
     }
 
     // -- Header --
     if &data[0..4] != MAGIC {
-        return Err(DataBankError::Codec(format!(
-            "bad magic: expected BANK, got {:?}",
-            &data[0..4]
-        )));
+        return Err(codec_err(
+            CodecErrorKind::BadMagic,
+            0,
+            format!("bad magic: expected BANK, got {:?}", &data[0..4]),
+        ));
     }
 
     let mut pos = 4;
-    let version = read_u16(data, &mut pos);
+    let version = read_u16(data, &mut pos)?;
     if version == 1 || version == 2 {
-        return Err(DataBankError::Codec(format!(
-            "v{version} .bank files are no longer supported (lossy PackedSignal format). \
-             Re-encode data with the v3 codec using full Signal (3 bytes per signal)."
-        )));
+        return Err(codec_err(
+            CodecErrorKind::UnsupportedVersion,
+            4,
+            format!(
+                "v{version} .bank files are no longer supported (lossy PackedSignal format). \
+                 Re-encode data with the v3 codec using full Signal (3 bytes per signal)."
+            ),
+        ));
     }
-    if version != 3 {
-        return Err(DataBankError::Codec(format!(
-            "unsupported version: {version}"
-        )));
+    if version != FORMAT_VERSION {
+        return Err(codec_err(
+            CodecErrorKind::UnsupportedVersion,
+            4,
+            format!("unsupported version: {version}"),
+        ));
     }
 
-    let _flags = read_u16(data, &mut pos);
-    let total_size = read_u32(data, &mut pos);
-    if data.len() < total_size as usize {
-        return Err(DataBankError::Codec(format!(
-            "truncated: expected {total_size} bytes, got {}",
-            data.len()
-        )));
+    let flags = read_u16(data, &mut pos)?;
+    if flags & !KNOWN_FLAGS != 0 {
+        return Err(codec_err(
+            CodecErrorKind::UnsupportedFlags,
+            6,
+            format!("unsupported flags: {flags:#06x}"),
+        ));
     }
+    let total_size = read_u32(data, &mut pos)? as usize;
+    if total_size < HEADER_SIZE || data.len() < total_size {
+        return Err(codec_err(
+            CodecErrorKind::Truncated,
+            8,
+            format!("truncated: expected {total_size} bytes, got {}", data.len()),
+        ));
+    }
+    // Never read past the declared size, even if the buffer is longer.
+    let file = data;
+    let data = &data[..total_size];
 
-    let stored_checksum = read_u64(data, &mut pos);
-    let bank_id = BankId(read_u64(data, &mut pos));
-    let vector_width = read_u16(data, &mut pos);
-    let entry_count = read_u16(data, &mut pos);
+    let stored_checksum = read_u64(data, &mut pos)?;
+    let bank_id = BankId(read_u64(data, &mut pos)?);
+    let vector_width = read_u16(data, &mut pos)?;
+    let entry_count = read_u16(data, &mut pos)?;
 
     // Verify checksum
-    let computed_checksum = xxhash_rust::xxh3::xxh3_64(&data[HEADER_SIZE..total_size as usize]);
+    let computed_checksum = xxhash_rust::xxh3::xxh3_64(&data[HEADER_SIZE..]);
     if stored_checksum != computed_checksum {
         return Err(DataBankError::ChecksumMismatch {
             expected: stored_checksum,
@@ -183,17 +572,28 @@ pub fn decode(data: &[u8]) -> Result<DataBank> {
         });
     }
 
+    // Parse compressed files from their decompressed image
+    let decompressed;
+    let data = if flags & FLAG_COMPRESSED != 0 {
+        let mut image = data[..HEADER_SIZE].to_vec();
+        unpack_bits(&data[HEADER_SIZE..], &mut image, HEADER_SIZE)?;
+        decompressed = image;
+        decompressed.as_slice()
+    } else {
+        data
+    };
+
     // -- Bank name --
     let name = read_str(data, &mut pos)?;
 
     // -- Config --
-    let persist_after_mutations = read_u32(data, &mut pos);
-    let persist_after_ticks = read_u64(data, &mut pos);
-    let max_entries = read_u32(data, &mut pos);
-    let cfg_vector_width = read_u16(data, &mut pos);
-    let max_edges_per_entry = read_u16(data, &mut pos);
+    let persist_after_mutations = read_u32(data, &mut pos)?;
+    let persist_after_ticks = read_u64(data, &mut pos)?;
+    let max_entries = read_u32(data, &mut pos)?;
+    let cfg_vector_width = read_u16(data, &mut pos)?;
+    let max_edges_per_entry = read_u16(data, &mut pos)?;
 
-    let config = BankConfig {
+    let mut config = BankConfig {
         persist_after_mutations,
         persist_after_ticks,
         max_entries,
@@ -202,36 +602,135 @@ pub fn decode(data: &[u8]) -> Result<DataBank> {
         ..BankConfig::default()
     };
 
+    // -- Codebook --
+    let codebook = if flags & FLAG_QUANTIZED != 0 {
+        let count = read_u16(data, &mut pos)? as usize;
+        let mut prototypes = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut prototype = Vec::with_capacity(vector_width as usize);
+            for _ in 0..vector_width {
+                prototype.push(read_signal(data, &mut pos)?);
+            }
+            prototypes.push(prototype);
+        }
+        Some(Codebook::new(vector_width as usize, prototypes)?)
+    } else {
+        None
+    };
+
     // -- Entries --
     let mut entries = HashMap::with_capacity(entry_count as usize);
     let mut reverse_edges: HashMap<EntryId, Vec<(BankRef, EdgeType)>> = HashMap::new();
 
-    for _ in 0..entry_count {
-        let entry = decode_entry(data, &mut pos, vector_width, bank_id)?;
-
-        // Rebuild reverse edges
-        for edge in &entry.edges {
-            reverse_edges
-                .entry(edge.target.entry)
-                .or_default()
-                .push((
-                    BankRef {
-                        bank: bank_id,
-                        entry: entry.id,
-                    },
-                    edge.edge_type,
-                ));
+    for index in 0..entry_count as usize {
+        let entry = decode_entry(data, &mut pos, vector_width, flags, codebook.as_ref())
+            .map_err(|e| at_entry(e, index))?;
+
+        if !entry.validate() {
+            // Decode stays lenient: keep the entry, but report it precisely
+            log::warn!(
+                "{}: {}",
+                BankLabel {
+                    name: &name,
+                    id: bank_id,
+                },
+                DataBankError::EntryCorrupted {
+                    bank: bank_id,
+                    entry: entry.id,
+                    expected: entry.checksum,
+                    actual: entry.compute_checksum(),
+                }
+            );
+        }
+        if entry.meta_checksum != entry.compute_meta_checksum() {
+            log::warn!(
+                "{}: {}",
+                BankLabel {
+                    name: &name,
+                    id: bank_id,
+                },
+                DataBankError::EntryMetadataCorrupted {
+                    bank: bank_id,
+                    entry: entry.id,
+                    expected: entry.meta_checksum,
+                    actual: entry.compute_meta_checksum(),
+                }
+            );
         }
 
+        // Rebuild reverse edges
+        link_reverse(&mut reverse_edges, bank_id, &entry);
         entries.insert(entry.id, entry);
     }
 
     // -- State counters --
-    let next_seq = read_u32(data, &mut pos);
-    let mutations_since_persist = read_u32(data, &mut pos);
-    let last_persist_tick = read_u64(data, &mut pos);
+    let mut next_seq = read_u32(data, &mut pos)?;
+    let mut mutations_since_persist = read_u32(data, &mut pos)?;
+    let mut last_persist_tick = read_u64(data, &mut pos)?;
+
+    // -- Optional sections --
+    if flags & FLAG_METADATA != 0 {
+        let overflow_offset = pos;
+        let overflow_raw = read_u8(data, &mut pos)?;
+        config.edge_overflow = edge_overflow_from_u8(overflow_raw).ok_or_else(|| {
+            codec_err(
+                CodecErrorKind::InvalidEnum,
+                overflow_offset,
+                format!("invalid edge overflow policy: {overflow_raw}"),
+            )
+        })?;
+        config.min_ticks_between_flushes = read_u64(data, &mut pos)?;
+        config.max_bytes = match read_u64(data, &mut pos)? {
+            0 => None,
+            n => Some(n as usize),
+        };
+        config.exact_match_fast_path = read_u8(data, &mut pos)? != 0;
+    }
+    if flags & FLAG_INDEX != 0 {
+        let kind_offset = pos;
+        let kind = read_u8(data, &mut pos)?;
+        let k = read_u32(data, &mut pos)? as usize;
+        let nprobe = read_u32(data, &mut pos)? as usize;
+        config.index_type = match kind {
+            0 => crate::ivf::IndexType::BruteForce,
+            1 => crate::ivf::IndexType::Ivf { k, nprobe },
+            _ => {
+                return Err(codec_err(
+                    CodecErrorKind::InvalidEnum,
+                    kind_offset,
+                    format!("invalid index kind: {kind}"),
+                ))
+            }
+        };
+    }
+
+    // -- Delta sections --
+    let mut pos = total_size;
+    while with_deltas && pos < file.len() {
+        let delta = match decode_delta(file, &mut pos, vector_width) {
+            Ok(delta) => delta,
+            Err(e) => {
+                let label = BankLabel { name: &name, id: bank_id };
+                log::warn!("{label}: ignoring .bank deltas from byte {pos}: {e}");
+                break;
+            }
+        };
+        for entry in delta.changed {
+            if let Some(old) = entries.remove(&entry.id) {
+                unlink_reverse(&mut reverse_edges, bank_id, &old);
+            }
+            link_reverse(&mut reverse_edges, bank_id, &entry);
+            entries.insert(entry.id, entry);
+        }
+        for id in delta.removed {
+            if let Some(old) = entries.remove(&id) {
+                unlink_reverse(&mut reverse_edges, bank_id, &old);
+            }
+        }
+        (next_seq, mutations_since_persist, last_persist_tick) = delta.counters;
+    }
 
-    Ok(DataBank::restore(
+    let mut bank = DataBank::restore(
         bank_id,
         name,
         config,
@@ -240,46 +739,226 @@ pub fn decode(data: &[u8]) -> Result<DataBank> {
         next_seq,
         mutations_since_persist,
         last_persist_tick,
-    ))
+    );
+    if let Some(codebook) = codebook {
+        bank.keep_codebook(codebook);
+    }
+    Ok(bank)
+}
+
+/// Add a decoded entry's edges to the reverse index.
+fn link_reverse(
+    reverse_edges: &mut HashMap<EntryId, Vec<(BankRef, EdgeType)>>,
+    bank_id: BankId,
+    entry: &BankEntry,
+) {
+    let source = BankRef {
+        bank: bank_id,
+        entry: entry.id,
+    };
+    for edge in &entry.edges {
+        reverse_edges
+            .entry(edge.target.entry)
+            .or_default()
+            .push((source, edge.edge_type));
+    }
+}
+
+/// Remove what [`link_reverse`] added for `entry`.
+fn unlink_reverse(
+    reverse_edges: &mut HashMap<EntryId, Vec<(BankRef, EdgeType)>>,
+    bank_id: BankId,
+    entry: &BankEntry,
+) {
+    let source = BankRef {
+        bank: bank_id,
+        entry: entry.id,
+    };
+    for edge in &entry.edges {
+        if let Some(back) = reverse_edges.get_mut(&edge.target.entry) {
+            if let Some(i) = back.iter().position(|&p| p == (source, edge.edge_type)) {
+                back.remove(i);
+            }
+            if back.is_empty() {
+                reverse_edges.remove(&edge.target.entry);
+            }
+        }
+    }
+}
+
+/// One decoded delta section.
+struct Delta {
+    changed: Vec<BankEntry>,
+    removed: Vec<EntryId>,
+    counters: (u32, u32, u64),
+}
+
+/// Decode the delta section at `file[*pos..]`, advancing `pos` past it only
+/// if the whole section is intact.
+fn decode_delta(file: &[u8], pos: &mut usize, vector_width: u16) -> Result<Delta> {
+    let start = *pos;
+    let mut cursor = start;
+    let magic: [u8; 4] = read_bytes(file, &mut cursor)?;
+    if &magic != DELTA_MAGIC {
+        return Err(codec_err(CodecErrorKind::BadMagic, start, "bad delta magic"));
+    }
+    let size = read_u32(file, &mut cursor)? as usize;
+    let end = start.saturating_add(size);
+    if size < DELTA_HEADER_SIZE || file.len() < end {
+        return Err(codec_err(
+            CodecErrorKind::Truncated,
+            start + 4,
+            format!("truncated delta: expected {size} bytes"),
+        ));
+    }
+    let data = &file[..end];
+    let stored_checksum = read_u64(data, &mut cursor)?;
+    let computed_checksum = xxhash_rust::xxh3::xxh3_64(&data[start + 16..]);
+    if stored_checksum != computed_checksum {
+        return Err(DataBankError::ChecksumMismatch {
+            expected: stored_checksum,
+            actual: computed_checksum,
+        });
+    }
+
+    let flags = read_u16(data, &mut cursor)?;
+    if flags & !KNOWN_FLAGS != 0 || flags & (FLAG_COMPRESSED | FLAG_QUANTIZED) != 0 {
+        return Err(codec_err(
+            CodecErrorKind::UnsupportedFlags,
+            start + 16,
+            format!("unsupported delta flags: {flags:#06x}"),
+        ));
+    }
+    let changed_count = read_u32(data, &mut cursor)? as usize;
+    let removed_count = read_u32(data, &mut cursor)? as usize;
+
+    let mut changed = Vec::with_capacity(changed_count.min(size));
+    for index in 0..changed_count {
+        let entry = decode_entry(data, &mut cursor, vector_width, flags, None)
+            .map_err(|e| at_entry(e, index))?;
+        changed.push(entry);
+    }
+    let mut removed = Vec::with_capacity(removed_count.min(size / 8));
+    for _ in 0..removed_count {
+        removed.push(EntryId(read_u64(data, &mut cursor)?));
+    }
+    let counters = (
+        read_u32(data, &mut cursor)?,
+        read_u32(data, &mut cursor)?,
+        read_u64(data, &mut cursor)?,
+    );
+    if cursor != end {
+        return Err(codec_err(
+            CodecErrorKind::SectionOverrun,
+            cursor,
+            format!("delta declares {size} bytes but holds {}", cursor - start),
+        ));
+    }
+    *pos = end;
+    Ok(Delta {
+        changed,
+        removed,
+        counters,
+    })
 }
 
 fn decode_entry(
     data: &[u8],
     pos: &mut usize,
     expected_width: u16,
-    _bank_id: BankId,
+    flags: u16,
+    codebook: Option<&Codebook>,
 ) -> Result<BankEntry> {
-    let entry_id = EntryId(read_u64(data, pos));
+    let extended_temperature = flags & FLAG_EXTENDED_TEMPERATURE != 0;
+
+    let entry_id = EntryId(read_u64(data, pos)?);
 
     // Vector
-    let vec_len = read_u16(data, pos) as usize;
+    let vec_offset = *pos;
+    let vec_len = read_u16(data, pos)? as usize;
     if vec_len != expected_width as usize {
-        return Err(DataBankError::Codec(format!(
-            "entry vector width {vec_len} != bank width {expected_width}"
-        )));
+        return Err(codec_err(
+            CodecErrorKind::WidthMismatch,
+            vec_offset,
+            format!("entry vector width {vec_len} != bank width {expected_width}"),
+        ));
     }
 
     // v3: 3 bytes per signal (polarity i8 as u8, magnitude u8, multiplier u8)
-    let mut vector = Vec::with_capacity(vec_len);
-    for _ in 0..vec_len {
-        let polarity = read_u8(data, pos) as i8;
-        let magnitude = read_u8(data, pos);
-        let multiplier = read_u8(data, pos);
-        vector.push(Signal::new_raw(polarity, magnitude, multiplier));
-    }
+    let vector = if let Some(codebook) = codebook {
+        let prototype_offset = *pos;
+        let prototype = read_u16(data, pos)?;
+        let count = read_u16(data, pos)? as usize;
+        let mut corrections = Vec::with_capacity(count);
+        let mut next_min = 0;
+        for _ in 0..count {
+            let index_offset = *pos;
+            let index = read_u16(data, pos)?;
+            if (index as usize) < next_min || index as usize >= vec_len {
+                return Err(codec_err(
+                    CodecErrorKind::WidthMismatch,
+                    index_offset,
+                    format!("correction index {index} out of order or beyond width {vec_len}"),
+                ));
+            }
+            corrections.push((index, read_signal(data, pos)?));
+            next_min = index as usize + 1;
+        }
+        codebook
+            .reconstruct(&QuantizedVector {
+                prototype,
+                corrections,
+            })
+            .ok_or_else(|| {
+                codec_err(
+                    CodecErrorKind::SectionOverrun,
+                    prototype_offset,
+                    format!("prototype {prototype} beyond codebook of {}", codebook.len()),
+                )
+            })?
+    } else if flags & FLAG_SPARSE_VECTORS != 0 {
+        let mut vector = vec![Signal::ZERO; vec_len];
+        let stored = read_u16(data, pos)? as usize;
+        let mut next_min = 0;
+        for _ in 0..stored {
+            let index_offset = *pos;
+            let index = read_u16(data, pos)? as usize;
+            if index < next_min || index >= vec_len {
+                return Err(codec_err(
+                    CodecErrorKind::WidthMismatch,
+                    index_offset,
+                    format!("sparse index {index} out of order or beyond width {vec_len}"),
+                ));
+            }
+            vector[index] = read_signal(data, pos)?;
+            next_min = index + 1;
+        }
+        vector
+    } else {
+        let mut vector = Vec::with_capacity(vec_len);
+        for _ in 0..vec_len {
+            vector.push(read_signal(data, pos)?);
+        }
+        vector
+    };
 
     // Edges
-    let edge_count = read_u16(data, pos) as usize;
-    let mut edges = Vec::with_capacity(edge_count);
+    let edge_count = read_u16(data, pos)? as usize;
+    let mut edges = EdgeList::with_capacity(edge_count.min(data.len().saturating_sub(*pos)));
     for _ in 0..edge_count {
-        let edge_type_raw = read_u8(data, pos);
+        let edge_offset = *pos;
+        let edge_type_raw = read_u8(data, pos)?;
         let edge_type = EdgeType::from_u8(edge_type_raw).ok_or_else(|| {
-            DataBankError::Codec(format!("invalid edge type: {edge_type_raw}"))
+            codec_err(
+                CodecErrorKind::InvalidEnum,
+                edge_offset,
+                format!("invalid edge type: {edge_type_raw}"),
+            )
         })?;
-        let target_bank = BankId(read_u64(data, pos));
-        let target_entry = EntryId(read_u64(data, pos));
-        let weight = read_u8(data, pos);
-        let created_tick = read_u64(data, pos);
+        let target_bank = BankId(read_u64(data, pos)?);
+        let target_entry = EntryId(read_u64(data, pos)?);
+        let weight = read_u8(data, pos)?;
+        let created_tick = read_u64(data, pos)?;
         edges.push(Edge {
             edge_type,
             target: BankRef {
@@ -292,33 +971,69 @@ fn decode_entry(
     }
 
     // Origin
-    let origin = BankId(read_u64(data, pos));
+    let origin = BankId(read_u64(data, pos)?);
 
     // Temperature
-    let temp_raw = read_u8(data, pos);
+    let temp_offset = *pos;
+    let temp_raw = read_u8(data, pos)?;
     let temperature = Temperature::from_u8(temp_raw)
-        .ok_or_else(|| DataBankError::Codec(format!("invalid temperature: {temp_raw}")))?;
+        .filter(|t| extended_temperature || !t.is_extended())
+        .ok_or_else(|| {
+            codec_err(
+                CodecErrorKind::InvalidEnum,
+                temp_offset,
+                format!("invalid temperature: {temp_raw}"),
+            )
+        })?;
 
     // Ticks
-    let created_tick = read_u64(data, pos);
-    let last_accessed_tick = read_u64(data, pos);
+    let created_tick = read_u64(data, pos)?;
+    let last_accessed_tick = read_u64(data, pos)?;
 
     // Access + confidence
-    let access_count = read_u32(data, pos);
-    let confidence = read_u8(data, pos);
+    let access_count = read_u32(data, pos)?;
+    let confidence = read_u8(data, pos)?;
+
+    // Per-entry flags
+    let mut pinned = false;
+    let mut namespace = 0;
+    let mut expires_at_tick = None;
+    if flags & FLAG_ENTRY_FLAGS != 0 {
+        let flags_offset = *pos;
+        let entry_flags = read_u8(data, pos)?;
+        if entry_flags & !KNOWN_ENTRY_FLAGS != 0 {
+            return Err(codec_err(
+                CodecErrorKind::UnsupportedFlags,
+                flags_offset,
+                format!("unsupported entry flags: {entry_flags:#04x}"),
+            ));
+        }
+        pinned = entry_flags & ENTRY_FLAG_PINNED != 0;
+        if entry_flags & ENTRY_FLAG_NAMESPACE != 0 {
+            namespace = read_u16(data, pos)?;
+        }
+        if entry_flags & ENTRY_FLAG_EXPIRES != 0 {
+            expires_at_tick = Some(read_u64(data, pos)?);
+        }
+    }
 
     // Debug tag
-    let has_tag = read_u8(data, pos);
+    let has_tag = read_u8(data, pos)?;
     let debug_tag = if has_tag != 0 {
         Some(read_str(data, pos)?)
     } else {
         None
     };
 
-    // Checksum
-    let checksum = read_u32(data, pos);
+    // Checksums
+    let checksum = read_u32(data, pos)?;
+    let meta_checksum = if flags & FLAG_META_CHECKSUMS != 0 {
+        Some(read_u32(data, pos)?)
+    } else {
+        None
+    };
 
-    Ok(BankEntry {
+    let mut entry = BankEntry {
         id: entry_id,
         vector,
         edges,
@@ -328,18 +1043,216 @@ fn decode_entry(
         last_accessed_tick,
         access_count,
         confidence,
+        pinned,
+        namespace,
+        expires_at_tick,
         debug_tag,
         checksum,
-    })
+        meta_checksum: 0,
+    };
+    match meta_checksum {
+        Some(stored) => entry.meta_checksum = stored,
+        None => entry.refresh_meta_checksum(),
+    }
+    // A quantized vector may differ from the one the checksum was taken of
+    if codebook.is_some() {
+        entry.checksum = entry.compute_checksum();
+    }
+    Ok(entry)
+}
+
+fn codec_err(kind: CodecErrorKind, offset: usize, context: impl Into<String>) -> DataBankError {
+    DataBankError::Codec(CodecError::new(kind, offset, context))
+}
+
+/// Attach the entry index to a codec error raised inside the entry section.
+fn at_entry(err: DataBankError, index: usize) -> DataBankError {
+    match err {
+        DataBankError::Codec(mut e) => {
+            e.entry_index = Some(index);
+            DataBankError::Codec(e)
+        }
+        other => other,
+    }
 }
 
 // ---------------------------------------------------------------------------
-// File I/O
+// File I/O (std only)
 // ---------------------------------------------------------------------------
 
 /// Save a bank to disk atomically (temp file + rename).
+#[cfg(feature = "std")]
 pub fn save_atomic(bank: &DataBank, path: &Path) -> Result<()> {
+    save_atomic_with(bank, path, &EncodeOptions::default())
+}
+
+/// Save a bank atomically with the given optional features enabled.
+#[cfg(feature = "std")]
+pub fn save_atomic_with(bank: &DataBank, path: &Path, options: &EncodeOptions) -> Result<()> {
+    let data = encode_with(bank, options)?;
+    write_atomic(&data, path)
+}
+
+/// What [`save_incremental`] last wrote to a `.bank` file: enough to tell
+/// which entries have changed since, without reading the file back.
+/// The default knows nothing, so the next save is a full rewrite.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct PersistedImage {
+    path: std::path::PathBuf,
+    /// Length of the base snapshot, and of the file with its deltas.
+    base_len: u64,
+    file_len: u64,
+    /// The base's header checksum, to notice the file being replaced.
+    base_checksum: u64,
+    /// Hash of the bank name and persisted config.
+    preamble: u64,
+    /// Hash of each entry's encoding, by id.
+    fingerprints: HashMap<EntryId, u64>,
+}
+
+#[cfg(feature = "std")]
+impl PersistedImage {
+    /// Whether the file at `path` still has the length and base checksum
+    /// this image recorded. A missing file never matches.
+    fn matches_file(&self) -> Result<bool> {
+        let mut file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        if file.metadata()?.len() != self.file_len {
+            return Ok(false);
+        }
+        let mut header = [0u8; 20];
+        std::io::Read::read_exact(&mut file, &mut header)?;
+        Ok(header[12..20] == self.base_checksum.to_le_bytes())
+    }
+}
+
+/// What one [`save_incremental`] call wrote.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncrementalWrite {
+    /// Bytes written: the whole file, or the appended delta.
+    pub bytes: usize,
+    /// Whether the file was rewritten rather than appended to.
+    pub full: bool,
+    /// Entries added or changed, and removed, since the previous write.
+    /// Every entry counts as changed on a full rewrite.
+    pub changed: usize,
+    pub removed: usize,
+}
+
+/// Save a bank by appending a delta of the entries changed or removed
+/// since the write `image` describes, falling back to a full
+/// [`save_atomic`] when a delta cannot be appended or would push the
+/// deltas past `rewrite_percent` percent of the base snapshot's size.
+///
+/// A delta needs `image` to describe the file now at `path` (same path,
+/// length, and base checksum) and the bank name and persisted config to be
+/// unchanged. The
+/// delta is fsynced before returning. Read the result with [`load`] or
+/// [`decode_with_deltas`].
+#[cfg(feature = "std")]
+pub fn save_incremental(
+    bank: &DataBank,
+    path: &Path,
+    image: &mut PersistedImage,
+    rewrite_percent: u8,
+) -> Result<IncrementalWrite> {
+    let mut scratch = Vec::new();
+    encode_preamble(&mut scratch, bank);
+    let preamble = xxhash_rust::xxh3::xxh3_64(&scratch);
+    let mut fingerprints = HashMap::with_capacity(bank.len());
+    for (&id, entry) in bank.entries() {
+        scratch.clear();
+        encode_entry(&mut scratch, entry, DELTA_ENTRY_FLAGS, None);
+        fingerprints.insert(id, xxhash_rust::xxh3::xxh3_64(&scratch));
+    }
+
+    if image.path == path && image.preamble == preamble && image.matches_file()? {
+        let mut changed: Vec<EntryId> = fingerprints
+            .iter()
+            .filter(|&(id, fp)| image.fingerprints.get(id) != Some(fp))
+            .map(|(&id, _)| id)
+            .collect();
+        let mut removed: Vec<EntryId> = image
+            .fingerprints
+            .keys()
+            .filter(|id| !fingerprints.contains_key(id))
+            .copied()
+            .collect();
+        changed.sort_unstable();
+        removed.sort_unstable();
+
+        let delta = encode_delta(bank, &changed, &removed);
+        let deltas = image.file_len - image.base_len + delta.len() as u64;
+        if deltas * 100 <= image.base_len * rewrite_percent as u64 {
+            let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+            std::io::Write::write_all(&mut file, &delta)?;
+            file.sync_all()?;
+            image.file_len += delta.len() as u64;
+            image.fingerprints = fingerprints;
+            return Ok(IncrementalWrite {
+                bytes: delta.len(),
+                full: false,
+                changed: changed.len(),
+                removed: removed.len(),
+            });
+        }
+    }
+
     let data = encode(bank)?;
+    write_atomic(&data, path)?;
+    *image = PersistedImage {
+        path: path.to_path_buf(),
+        base_len: data.len() as u64,
+        file_len: data.len() as u64,
+        base_checksum: read_u64(&data, &mut 12)?,
+        preamble,
+        fingerprints,
+    };
+    Ok(IncrementalWrite {
+        bytes: data.len(),
+        full: true,
+        changed: bank.len(),
+        removed: 0,
+    })
+}
+
+/// Encode a delta section holding `changed` (which must be in the bank)
+/// and `removed`, followed by the bank's current state counters.
+#[cfg(feature = "std")]
+fn encode_delta(bank: &DataBank, changed: &[EntryId], removed: &[EntryId]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(DELTA_HEADER_SIZE);
+    buf.extend_from_slice(DELTA_MAGIC);
+    write_u32(&mut buf, 0); // size placeholder
+    write_u64(&mut buf, 0); // checksum placeholder
+    write_u16(&mut buf, DELTA_ENTRY_FLAGS);
+    write_u32(&mut buf, changed.len() as u32);
+    write_u32(&mut buf, removed.len() as u32);
+    for entry in changed.iter().filter_map(|id| bank.get(*id)) {
+        encode_entry(&mut buf, entry, DELTA_ENTRY_FLAGS, None);
+    }
+    for id in removed {
+        write_u64(&mut buf, id.0);
+    }
+    encode_counters(&mut buf, bank);
+
+    let size = buf.len() as u32;
+    buf[4..8].copy_from_slice(&size.to_le_bytes());
+    let checksum = xxhash_rust::xxh3::xxh3_64(&buf[16..]);
+    buf[8..16].copy_from_slice(&checksum.to_le_bytes());
+    buf
+}
+
+/// Write already-encoded bank bytes atomically (temp file + fsync + rename).
+///
+/// Returns only after the data has been synced, so callers may treat
+/// `Ok(())` as durable.
+#[cfg(feature = "std")]
+pub fn write_atomic(data: &[u8], path: &Path) -> Result<()> {
     let temp = path.with_extension("bank.tmp");
 
     // Ensure parent directory exists
@@ -347,96 +1260,767 @@ pub fn save_atomic(bank: &DataBank, path: &Path) -> Result<()> {
         std::fs::create_dir_all(parent)?;
     }
 
-    std::fs::write(&temp, &data)?;
+    {
+        let mut file = std::fs::File::create(&temp)?;
+        std::io::Write::write_all(&mut file, data)?;
+        file.sync_all()?;
+    }
     std::fs::rename(&temp, path)?;
     Ok(())
 }
 
-/// Load a bank from a `.bank` file.
+/// Fsync a directory so renames completed inside it are durable. Batch
+/// writers call this once after all their renames rather than per file.
+/// A no-op on non-Unix platforms, where directories cannot be synced.
+#[cfg(feature = "std")]
+pub fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    std::fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Load a bank from a `.bank` file, applying any delta sections written
+/// by [`save_incremental`].
+#[cfg(feature = "std")]
 pub fn load(path: &Path) -> Result<DataBank> {
     let data = std::fs::read(path)?;
-    decode(&data)
+    decode_with_deltas(&data)
 }
 
 // ---------------------------------------------------------------------------
-// Primitive read/write helpers (little-endian)
+// Snapshot diff
 // ---------------------------------------------------------------------------
 
-fn write_u16(buf: &mut Vec<u8>, v: u16) {
-    buf.extend_from_slice(&v.to_le_bytes());
+/// Differences between two snapshots of a bank, as reported by [`diff`].
+/// Every id list is sorted ascending; an entry changed in several ways
+/// appears in each matching list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BankDiff {
+    /// Entries only in the old snapshot.
+    pub removed: Vec<EntryId>,
+    /// Entries only in the new snapshot.
+    pub added: Vec<EntryId>,
+    /// Entries whose vector checksum differs.
+    pub vector_changed: Vec<EntryId>,
+    /// Entries whose temperature differs.
+    pub temperature_changed: Vec<EntryId>,
+    /// Entries whose edge set differs (edge order is ignored).
+    pub edges_changed: Vec<EntryId>,
+    /// Entries whose confidence differs.
+    pub confidence_changed: Vec<EntryId>,
+    /// Config fields that differ, in declaration order.
+    pub config_changes: Vec<ConfigChange>,
 }
 
-fn write_u32(buf: &mut Vec<u8>, v: u32) {
-    buf.extend_from_slice(&v.to_le_bytes());
+/// One differing [`BankConfig`] field, values rendered with `Debug`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
 }
 
-fn write_u64(buf: &mut Vec<u8>, v: u64) {
-    buf.extend_from_slice(&v.to_le_bytes());
+impl BankDiff {
+    /// Whether the snapshots are equivalent in every compared respect.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty()
+            && self.added.is_empty()
+            && self.vector_changed.is_empty()
+            && self.temperature_changed.is_empty()
+            && self.edges_changed.is_empty()
+            && self.confidence_changed.is_empty()
+            && self.config_changes.is_empty()
+    }
 }
 
-fn write_str(buf: &mut Vec<u8>, s: &str) {
-    write_u16(buf, s.len() as u16);
-    buf.extend_from_slice(s.as_bytes());
+/// Compare two snapshots of a bank entry by entry, matching on [`EntryId`].
+///
+/// Access counts, ticks, and debug tags are not compared; they change on
+/// every read and would drown out the differences worth reviewing.
+pub fn diff(old: &DataBank, new: &DataBank) -> BankDiff {
+    let mut out = BankDiff::default();
+    for (id, before) in old.entries() {
+        let Some(after) = new.get(*id) else {
+            out.removed.push(*id);
+            continue;
+        };
+        if before.checksum != after.checksum {
+            out.vector_changed.push(*id);
+        }
+        if before.temperature != after.temperature {
+            out.temperature_changed.push(*id);
+        }
+        if !same_edges(&before.edges, &after.edges) {
+            out.edges_changed.push(*id);
+        }
+        if before.confidence != after.confidence {
+            out.confidence_changed.push(*id);
+        }
+    }
+    out.added = new
+        .entries()
+        .map(|(id, _)| *id)
+        .filter(|id| old.get(*id).is_none())
+        .collect();
+    for ids in [
+        &mut out.removed,
+        &mut out.added,
+        &mut out.vector_changed,
+        &mut out.temperature_changed,
+        &mut out.edges_changed,
+        &mut out.confidence_changed,
+    ] {
+        ids.sort_unstable();
+    }
+    out.config_changes = diff_config(old.config(), new.config());
+    out
 }
 
-fn read_u8(data: &[u8], pos: &mut usize) -> u8 {
-    let v = data[*pos];
-    *pos += 1;
-    v
+/// [`diff`] two `.bank` files.
+#[cfg(feature = "std")]
+pub fn diff_files(old: &Path, new: &Path) -> Result<BankDiff> {
+    Ok(diff(&load(old)?, &load(new)?))
 }
 
-fn read_u16(data: &[u8], pos: &mut usize) -> u16 {
-    let v = u16::from_le_bytes([data[*pos], data[*pos + 1]]);
-    *pos += 2;
-    v
+/// Edge lists equal as multisets.
+fn same_edges(a: &[Edge], b: &[Edge]) -> bool {
+    let count = |list: &[Edge], edge: &Edge| list.iter().filter(|e| *e == edge).count();
+    a.len() == b.len() && a.iter().all(|edge| count(a, edge) == count(b, edge))
 }
 
-fn read_u32(data: &[u8], pos: &mut usize) -> u32 {
-    let v = u32::from_le_bytes([
-        data[*pos],
-        data[*pos + 1],
-        data[*pos + 2],
-        data[*pos + 3],
-    ]);
-    *pos += 4;
-    v
+fn diff_config(old: &BankConfig, new: &BankConfig) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    let mut compare = |field: &'static str, old: String, new: String| {
+        if old != new {
+            changes.push(ConfigChange { field, old, new });
+        }
+    };
+    macro_rules! field {
+        ($name:ident) => {
+            compare(
+                stringify!($name),
+                format!("{:?}", old.$name),
+                format!("{:?}", new.$name),
+            )
+        };
+    }
+    field!(persist_after_mutations);
+    field!(persist_after_ticks);
+    field!(min_ticks_between_flushes);
+    field!(max_entries);
+    field!(max_bytes);
+    field!(vector_width);
+    field!(max_edges_per_entry);
+    field!(edge_overflow);
+    field!(index_type);
+    field!(exact_match_fast_path);
+    field!(audit_evictions);
+    field!(eviction_mode);
+    field!(storage);
+    field!(metric);
+    field!(incremental_flush);
+    field!(auto_compact_percent);
+    changes
 }
 
-fn read_u64(data: &[u8], pos: &mut usize) -> u64 {
-    let v = u64::from_le_bytes([
-        data[*pos],
-        data[*pos + 1],
-        data[*pos + 2],
-        data[*pos + 3],
-        data[*pos + 4],
-        data[*pos + 5],
-        data[*pos + 6],
-        data[*pos + 7],
-    ]);
-    *pos += 8;
-    v
+// ---------------------------------------------------------------------------
+// CSV export (std only)
+// ---------------------------------------------------------------------------
+
+/// Write one CSV row per entry, ordered by id, for analysis tools.
+///
+/// Columns: `id` (raw u64), `temperature`, `confidence`, `access_count`,
+/// `created_tick`, `last_accessed_tick`, `edge_count`, `debug_tag`, then
+/// with `include_vectors` one `v{i}` column per dimension holding
+/// `polarity x magnitude`. Fields containing commas, quotes, or line
+/// breaks are quoted per RFC 4180. This is a one-way export; it cannot be
+/// loaded back as a bank.
+#[cfg(feature = "std")]
+pub fn export_csv(
+    bank: &DataBank,
+    mut writer: impl std::io::Write,
+    include_vectors: bool,
+) -> Result<()> {
+    let mut line = String::from(
+        "id,temperature,confidence,access_count,created_tick,last_accessed_tick,edge_count,debug_tag",
+    );
+    if include_vectors {
+        for i in 0..bank.config().vector_width {
+            line.push_str(&format!(",v{i}"));
+        }
+    }
+    line.push('\n');
+    writer.write_all(line.as_bytes())?;
+
+    let mut entries: Vec<&BankEntry> = bank.entries().map(|(_, e)| e).collect();
+    entries.sort_unstable_by_key(|e| e.id);
+    for entry in entries {
+        line.clear();
+        line.push_str(&format!(
+            "{},{},{},{},{},{},{},",
+            entry.id.0,
+            entry.temperature,
+            entry.confidence,
+            entry.access_count,
+            entry.created_tick,
+            entry.last_accessed_tick,
+            entry.edges.len()
+        ));
+        push_csv_field(&mut line, entry.debug_tag.as_deref().unwrap_or(""));
+        if include_vectors {
+            for s in &entry.vector {
+                line.push_str(&format!(",{}", s.polarity as i32 * s.magnitude as i32));
+            }
+        }
+        line.push('\n');
+        writer.write_all(line.as_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
 }
 
-fn read_str(data: &[u8], pos: &mut usize) -> Result<String> {
-    let len = read_u16(data, pos) as usize;
-    if *pos + len > data.len() {
-        return Err(DataBankError::Codec("string extends past end of data".into()));
+/// Append `field`, quoting it if it contains a delimiter, quote, or line
+/// break.
+#[cfg(feature = "std")]
+fn push_csv_field(line: &mut String, field: &str) {
+    if field.contains([',', '"', '\n', '\r']) {
+        line.push('"');
+        line.push_str(&field.replace('"', "\"\""));
+        line.push('"');
+    } else {
+        line.push_str(field);
     }
-    let s = std::str::from_utf8(&data[*pos..*pos + len])
-        .map_err(|e| DataBankError::Codec(format!("invalid UTF-8: {e}")))?
-        .to_string();
-    *pos += len;
-    Ok(s)
 }
 
 // ---------------------------------------------------------------------------
-// Tests
+// Primitive read/write helpers (little-endian)
 // ---------------------------------------------------------------------------
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
+fn write_u16(buf: &mut Vec<u8>, v: u16) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn write_u32(buf: &mut Vec<u8>, v: u32) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn write_u64(buf: &mut Vec<u8>, v: u64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    write_u16(buf, s.len() as u16);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn write_signal(buf: &mut Vec<u8>, s: &Signal) {
+    buf.push(s.polarity as u8);
+    buf.push(s.magnitude);
+    buf.push(s.multiplier);
+}
+
+fn edge_overflow_to_u8(policy: EdgeOverflow) -> u8 {
+    match policy {
+        EdgeOverflow::Reject => 0,
+        EdgeOverflow::EvictWeakest => 1,
+        EdgeOverflow::EvictOldest => 2,
+    }
+}
+
+fn edge_overflow_from_u8(v: u8) -> Option<EdgeOverflow> {
+    match v {
+        0 => Some(EdgeOverflow::Reject),
+        1 => Some(EdgeOverflow::EvictWeakest),
+        2 => Some(EdgeOverflow::EvictOldest),
+        _ => None,
+    }
+}
+
+/// PackBits run-length encoding: a control byte `n` in 0..=127 is followed
+/// by `n + 1` literal bytes; `n` in -127..=-1 (as i8) repeats the next byte
+/// `1 - n` times.
+fn pack_bits(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 2 + 8);
+    let mut i = 0;
+    while i < data.len() {
+        let run = data[i..].iter().take(128).take_while(|&&b| b == data[i]).count();
+        if run >= 3 {
+            out.push((1 - run as i16) as i8 as u8);
+            out.push(data[i]);
+            i += run;
+            continue;
+        }
+        // Literal stretch up to the next run of 3 or 128 bytes
+        let start = i;
+        while i < data.len() && i - start < 128 {
+            if i + 2 < data.len() && data[i] == data[i + 1] && data[i] == data[i + 2] {
+                break;
+            }
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&data[start..i]);
+    }
+    out
+}
+
+/// Inverse of [`pack_bits`], appending to `out`. `base` is the file offset
+/// of `data`, used for error locations.
+fn unpack_bits(data: &[u8], out: &mut Vec<u8>, base: usize) -> Result<()> {
+    let mut pos = 0;
+    while pos < data.len() {
+        let control = data[pos] as i8;
+        let control_offset = base + pos;
+        pos += 1;
+        if control >= 0 {
+            let len = control as usize + 1;
+            let literal = data.get(pos..pos + len).ok_or_else(|| {
+                codec_err(
+                    CodecErrorKind::Truncated,
+                    control_offset,
+                    format!("compressed literal of {len} bytes runs past end of data"),
+                )
+            })?;
+            out.extend_from_slice(literal);
+            pos += len;
+        } else if control != i8::MIN {
+            let byte = *data.get(pos).ok_or_else(|| {
+                codec_err(
+                    CodecErrorKind::Truncated,
+                    control_offset,
+                    "compressed run is missing its byte",
+                )
+            })?;
+            out.resize(out.len() + (1 - control as isize) as usize, byte);
+            pos += 1;
+        }
+    }
+    Ok(())
+}
+
+fn read_signal(data: &[u8], pos: &mut usize) -> Result<Signal> {
+    let polarity = read_u8(data, pos)? as i8;
+    let magnitude = read_u8(data, pos)?;
+    let multiplier = read_u8(data, pos)?;
+    Ok(Signal::new_raw(polarity, magnitude, multiplier))
+}
+
+fn read_bytes<const N: usize>(data: &[u8], pos: &mut usize) -> Result<[u8; N]> {
+    let bytes = data.get(*pos..*pos + N).ok_or_else(|| {
+        codec_err(
+            CodecErrorKind::SectionOverrun,
+            *pos,
+            format!("{N}-byte read past end of data ({} bytes)", data.len()),
+        )
+    })?;
+    *pos += N;
+    Ok(bytes.try_into().expect("slice has length N"))
+}
+
+fn read_u8(data: &[u8], pos: &mut usize) -> Result<u8> {
+    Ok(read_bytes::<1>(data, pos)?[0])
+}
+
+fn read_u16(data: &[u8], pos: &mut usize) -> Result<u16> {
+    Ok(u16::from_le_bytes(read_bytes(data, pos)?))
+}
+
+fn read_u32(data: &[u8], pos: &mut usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(read_bytes(data, pos)?))
+}
+
+fn read_u64(data: &[u8], pos: &mut usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(data, pos)?))
+}
+
+fn read_str(data: &[u8], pos: &mut usize) -> Result<String> {
+    let len_offset = *pos;
+    let len = read_u16(data, pos)? as usize;
+    if *pos + len > data.len() {
+        return Err(codec_err(
+            CodecErrorKind::SectionOverrun,
+            len_offset,
+            format!("string of {len} bytes extends past end of data"),
+        ));
+    }
+    let s = core::str::from_utf8(&data[*pos..*pos + len])
+        .map_err(|e| codec_err(CodecErrorKind::BadString, *pos, format!("invalid UTF-8: {e}")))?
+        .to_string();
+    *pos += len;
+    Ok(s)
+}
+
+// ---------------------------------------------------------------------------
+// Format fixtures
+// ---------------------------------------------------------------------------
+
+/// Golden-file fixtures for format compatibility tests.
+///
+/// `tests/fixtures/` holds `.bank` files and a journal written from the
+/// builders here and committed to the repository. `tests/format_fixtures.rs`
+/// checks that the current decoders read them back as exactly what the
+/// builders produce, so a change that stops an older file from loading, or
+/// makes it load differently, fails there.
+///
+/// Nothing rewrites the fixtures automatically. After a deliberate format
+/// change (a new [`FORMAT_VERSION`], flag or journal tag), add a builder
+/// for it and regenerate explicitly with
+///
+/// ```text
+/// cargo test --test format_fixtures -- --ignored regenerate_fixtures
+/// ```
+///
+/// Test support only, behind the non-default `testing` feature; not part
+/// of the stable API.
+#[cfg(feature = "testing")]
+pub mod testing {
+    use std::path::Path;
+
+    use ternary_signal::Signal;
+
+    use super::{encode_with, write_atomic, EncodeOptions};
+    use crate::bank::DataBank;
+    use crate::entry::BankEntry;
+    use crate::error::Result;
+    use crate::ivf::IndexType;
+    use crate::journal::{JournalEntry, JournalWriter};
+    use crate::prelude::*;
+    use crate::types::*;
+
+    /// File name of the journal fixture.
+    pub const JOURNAL_FIXTURE: &str = "all_tags.journal";
+
+    /// A bank and the options its fixture file is written with.
+    pub struct BankFixture {
+        pub file_name: &'static str,
+        pub bank: DataBank,
+        pub options: EncodeOptions,
+    }
+
+    const BANK: BankId = BankId(0x0001_0002_0003_0004);
+    const OTHER_BANK: BankId = BankId(0xDEAD);
+
+    fn sig(polarity: i8, magnitude: u8, multiplier: u8) -> Signal {
+        Signal::new_raw(polarity, magnitude, multiplier)
+    }
+
+    fn entry(seq: u64, vector: Vec<Signal>, temperature: Temperature, tick: u64) -> BankEntry {
+        BankEntry::new(EntryId(0x0100 + seq), vector, BANK, temperature, tick)
+    }
+
+    fn edge(edge_type: EdgeType, bank: BankId, seq: u64, weight: u8, tick: u64) -> Edge {
+        Edge {
+            edge_type,
+            target: BankRef {
+                bank,
+                entry: EntryId(0x0100 + seq),
+            },
+            weight,
+            created_tick: tick,
+        }
+    }
+
+    /// A bank holding exactly `entries`, with fixed state counters and
+    /// checksums refreshed after the builders' direct field writes.
+    fn bank(name: &str, config: BankConfig, entries: Vec<BankEntry>) -> DataBank {
+        let mut map = HashMap::new();
+        let mut reverse_edges: HashMap<EntryId, Vec<(BankRef, EdgeType)>> = HashMap::new();
+        for mut entry in entries {
+            entry.refresh_checksum();
+            let source = BankRef {
+                bank: BANK,
+                entry: entry.id,
+            };
+            for edge in &entry.edges {
+                reverse_edges
+                    .entry(edge.target.entry)
+                    .or_default()
+                    .push((source, edge.edge_type));
+            }
+            map.insert(entry.id, entry);
+        }
+        DataBank::restore(BANK, name.into(), config, map, reverse_edges, 42, 3, 900)
+    }
+
+    /// Three plain entries: every original temperature but Cool, a debug
+    /// tag, an in-bank and a cross-bank edge. Default options.
+    fn plain() -> DataBank {
+        let config = BankConfig {
+            vector_width: 4,
+            max_entries: 100,
+            max_edges_per_entry: 8,
+            ..BankConfig::default()
+        };
+        let vector = vec![sig(1, 100, 1), sig(-1, 50, 2), Signal::ZERO, sig(1, 255, 255)];
+        let mut a = entry(1, vector, Temperature::Hot, 10);
+        a.edges.push(edge(EdgeType::RelatedTo, BANK, 2, 200, 11));
+        a.edges.push(edge(EdgeType::SoundsLike, OTHER_BANK, 0xBEEF, 180, 15));
+        a.access_count = 7;
+        a.last_accessed_tick = 30;
+        a.debug_tag = Some("glass".into());
+        let vector = vec![sig(-1, 80, 1), sig(1, 160, 1), sig(1, 30, 4), sig(-1, 90, 1)];
+        let mut b = entry(2, vector, Temperature::Warm, 20);
+        b.confidence = 250;
+        let c = entry(3, vec![Signal::ZERO; 4], Temperature::Cold, 25);
+        bank("fixture.plain", config, vec![a, b, c])
+    }
+
+    /// Pinned, namespaced, expiring and `Set` entries: the per-entry flags
+    /// byte and the extended-temperature header flag.
+    fn entry_flags() -> DataBank {
+        let config = BankConfig {
+            vector_width: 2,
+            ..BankConfig::default()
+        };
+        let mut pinned = entry(1, vec![sig(1, 10, 1), sig(1, 20, 1)], Temperature::Set, 1);
+        pinned.pinned = true;
+        let mut namespaced = entry(2, vec![sig(-1, 10, 1), sig(1, 20, 1)], Temperature::Cool, 2);
+        namespaced.namespace = 7;
+        let mut expiring = entry(3, vec![sig(1, 10, 3), Signal::ZERO], Temperature::Hot, 3);
+        expiring.namespace = 0xFFFF;
+        expiring.expires_at_tick = Some(5_000);
+        let plain = entry(4, vec![Signal::ZERO, sig(-1, 1, 1)], Temperature::Hot, 4);
+        bank("fixture.entry_flags", config, vec![pinned, namespaced, expiring, plain])
+    }
+
+    /// Mostly-zero vectors and runtime config written with every optional
+    /// section: sparse vectors, metadata, index, compression.
+    fn options() -> DataBank {
+        let config = BankConfig {
+            vector_width: 16,
+            edge_overflow: EdgeOverflow::EvictWeakest,
+            min_ticks_between_flushes: 50,
+            max_bytes: Some(1 << 20),
+            exact_match_fast_path: true,
+            index_type: IndexType::Ivf { k: 2, nprobe: 1 },
+            ..BankConfig::default()
+        };
+        let entries = (0..4u8)
+            .map(|i| {
+                let mut vector = vec![Signal::ZERO; 16];
+                vector[i as usize * 3] = sig(1, 40 + i, 1);
+                vector[15] = sig(-1, 200, 2);
+                entry(i as u64, vector, Temperature::Warm, i as u64 * 10)
+            })
+            .collect();
+        bank("fixture.options", config, entries)
+    }
+
+    /// Entries clustered around two prototypes, stored losslessly against a
+    /// codebook.
+    fn quantized() -> DataBank {
+        let config = BankConfig {
+            vector_width: 8,
+            ..BankConfig::default()
+        };
+        let entries = (0..8u8)
+            .map(|i| {
+                let polarity = if i % 2 == 0 { 1 } else { -1 };
+                let mut vector = vec![sig(polarity, 100, 1); 8];
+                vector[(i / 2) as usize] = sig(polarity, 100 + i, 1);
+                entry(i as u64, vector, Temperature::Cool, i as u64)
+            })
+            .collect();
+        bank("fixture.quantized", config, entries)
+    }
+
+    /// Every `.bank` fixture, freshly built. All but `meta_checksums.bank`
+    /// predate `FLAG_META_CHECKSUMS` and are written without it.
+    pub fn bank_fixtures() -> Vec<BankFixture> {
+        let legacy = EncodeOptions {
+            vector_checksums_only: true,
+            ..EncodeOptions::default()
+        };
+        vec![
+            BankFixture {
+                file_name: "plain.bank",
+                bank: plain(),
+                options: legacy,
+            },
+            BankFixture {
+                file_name: "entry_flags.bank",
+                bank: entry_flags(),
+                options: legacy,
+            },
+            BankFixture {
+                file_name: "options.bank",
+                bank: options(),
+                options: EncodeOptions {
+                    compress: true,
+                    sparse: true,
+                    include_metadata: true,
+                    include_index: true,
+                    quantize: None,
+                    vector_checksums_only: true,
+                },
+            },
+            BankFixture {
+                file_name: "quantized.bank",
+                bank: quantized(),
+                options: EncodeOptions {
+                    quantize: Some(0),
+                    ..legacy
+                },
+            },
+            BankFixture {
+                file_name: "meta_checksums.bank",
+                bank: plain(),
+                options: EncodeOptions::default(),
+            },
+        ]
+    }
+
+    /// One record of every journal tag, in tag order. Compressed blocks
+    /// need the `zstd` feature and are not covered.
+    pub fn journal_fixture() -> Vec<JournalEntry> {
+        let entry_id = EntryId(0x0101);
+        vec![
+            JournalEntry::Insert {
+                bank_id: BANK,
+                entry_id,
+                vector: vec![sig(1, 100, 1), sig(-1, 50, 2), Signal::ZERO, sig(1, 255, 255)],
+                temperature: Temperature::Hot,
+                tick: 10,
+                checksum: None,
+                expires_at_tick: None,
+                namespace: 0,
+            },
+            JournalEntry::Remove {
+                bank_id: BANK,
+                entry_id: EntryId(0x0102),
+            },
+            JournalEntry::Touch {
+                bank_id: BANK,
+                entry_id,
+                tick: 11,
+            },
+            JournalEntry::AddEdge {
+                bank_id: BANK,
+                entry_id,
+                edge: edge(EdgeType::SoundsLike, OTHER_BANK, 0xBEEF, 180, 12),
+            },
+            JournalEntry::SetTemperature {
+                bank_id: BANK,
+                entry_id,
+                temperature: Temperature::Set,
+            },
+            JournalEntry::Promote {
+                bank_id: BANK,
+                entry_id,
+                new_temp: Temperature::Cool,
+            },
+            JournalEntry::Demote {
+                bank_id: BANK,
+                entry_id,
+                new_temp: Temperature::Warm,
+            },
+            JournalEntry::BatchEvict {
+                bank_id: BANK,
+                entry_ids: vec![EntryId(0x0103), EntryId(0x0104)],
+            },
+            JournalEntry::Pin {
+                bank_id: BANK,
+                entry_id,
+            },
+            JournalEntry::Unpin {
+                bank_id: BANK,
+                entry_id,
+            },
+            JournalEntry::TouchMany {
+                bank_id: BANK,
+                entry_id,
+                tick: 20,
+                count: 9,
+            },
+            JournalEntry::Insert {
+                bank_id: BANK,
+                entry_id: EntryId(0x0105),
+                vector: vec![sig(-1, 7, 3), Signal::ZERO, sig(1, 1, 1), sig(1, 80, 4)],
+                temperature: Temperature::Cold,
+                tick: 21,
+                checksum: Some(0xC0FF_EE11),
+                expires_at_tick: None,
+                namespace: 0,
+            },
+            JournalEntry::RemoveEdgesTo {
+                bank_id: BANK,
+                target: BankRef {
+                    bank: OTHER_BANK,
+                    entry: EntryId(0xBEEF),
+                },
+            },
+            JournalEntry::Insert {
+                bank_id: BANK,
+                entry_id: EntryId(0x0106),
+                vector: vec![sig(1, 3, 1), sig(-1, 60, 5), Signal::ZERO, Signal::ZERO],
+                temperature: Temperature::Warm,
+                tick: 22,
+                checksum: Some(0x5EED_0013),
+                expires_at_tick: Some(500),
+                namespace: 0,
+            },
+            JournalEntry::Insert {
+                bank_id: BANK,
+                entry_id: EntryId(0x0107),
+                vector: vec![Signal::ZERO, sig(1, 9, 2), sig(-1, 200, 1), sig(1, 4, 4)],
+                temperature: Temperature::Cool,
+                tick: 23,
+                checksum: Some(0x5EED_0014),
+                expires_at_tick: None,
+                namespace: 3,
+            },
+            JournalEntry::InsertCopy {
+                bank_id: BANK,
+                entry: copied_entry(),
+            },
+        ]
+    }
+
+    /// The entry of the journal fixture's `InsertCopy`: every carried
+    /// field away from its default.
+    fn copied_entry() -> BankEntry {
+        let vector = vec![sig(1, 12, 3), Signal::ZERO, sig(-1, 90, 1), sig(1, 2, 200)];
+        let mut copied = BankEntry::new(EntryId(0x0108), vector, OTHER_BANK, Temperature::Set, 24);
+        copied.edges.push(edge(EdgeType::IsA, BANK, 1, 77, 25));
+        copied.edges.push(edge(EdgeType::Causes, OTHER_BANK, 0xBEEF, 3, 26));
+        copied.last_accessed_tick = 27;
+        copied.access_count = 4;
+        copied.confidence = 250;
+        copied.pinned = true;
+        copied.namespace = 2;
+        copied.expires_at_tick = Some(900);
+        copied.refresh_meta_checksum();
+        copied
+    }
+
+    /// Rewrite every fixture in `dir`, replacing what is there. Only for
+    /// deliberate format changes; see the module docs.
+    pub fn regenerate_fixtures(dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        for fixture in bank_fixtures() {
+            let bytes = encode_with(&fixture.bank, &fixture.options)?;
+            write_atomic(&bytes, &dir.join(fixture.file_name))?;
+        }
+        let path = dir.join(JOURNAL_FIXTURE);
+        crate::journal::truncate_journal(&path)?;
+        let mut writer = JournalWriter::open(&path)?;
+        for record in journal_fixture() {
+            writer.append(&record)?;
+        }
+        writer.flush()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
     fn make_bank_with_entries() -> DataBank {
         let id = BankId::from_raw(0x0001_0002_0003_0004);
         let config = BankConfig {
@@ -477,7 +2061,7 @@ mod tests {
         bank.add_edge(id1, edge).unwrap();
 
         // Touch an entry
-        if let Some(e) = bank.get_mut(id2) {
+        if let Some(mut e) = bank.get_mut(id2) {
             e.touch(30);
             e.debug_tag = Some("test_entry".into());
         }
@@ -558,6 +2142,19 @@ mod tests {
         assert_eq!(decoded.len(), 0);
     }
 
+    #[test]
+    fn unlimited_capacity_round_trip() {
+        let config = BankConfig {
+            vector_width: 4,
+            max_entries: 0,
+            ..BankConfig::default()
+        };
+        let bank = DataBank::new(BankId::from_raw(7), "unlimited.bank".into(), config);
+        let decoded = decode(&encode(&bank).unwrap()).unwrap();
+        assert_eq!(decoded.config().max_entries, 0);
+        assert_eq!(decoded.stats().capacity, None);
+    }
+
     #[test]
     fn file_round_trip() {
         let bank = make_bank_with_entries();
@@ -572,6 +2169,72 @@ mod tests {
         assert_eq!(loaded.len(), bank.len());
     }
 
+    #[test]
+    fn incremental_save_appends_small_deltas() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("incremental.bank");
+        let config = BankConfig {
+            vector_width: 32,
+            max_entries: 0,
+            ..BankConfig::default()
+        };
+        let mut bank = DataBank::new(BankId::from_raw(9), "incremental".into(), config);
+        let ids: Vec<EntryId> = (0..200u8)
+            .map(|i| {
+                let v = (0..32).map(|d| Signal::new_raw(1, i.wrapping_add(d), 1)).collect();
+                bank.insert(v, Temperature::Hot, i as u64).unwrap()
+            })
+            .collect();
+        let mut image = PersistedImage::default();
+
+        let first = save_incremental(&bank, &path, &mut image, 50).unwrap();
+        assert!(first.full);
+        let base_len = std::fs::metadata(&path).unwrap().len() as usize;
+        assert_eq!(first.bytes, base_len);
+
+        bank.set_confidence(ids[7], 200).unwrap();
+        bank.remove(ids[8]);
+        let second = save_incremental(&bank, &path, &mut image, 50).unwrap();
+        assert!(!second.full);
+        assert_eq!((second.changed, second.removed), (1, 1));
+        assert!(second.bytes * 40 < base_len, "delta of {} bytes", second.bytes);
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len(), base_len + second.bytes);
+
+        let loaded = load(&path).unwrap();
+        assert_same_entries(&bank, &loaded);
+        assert_eq!(loaded.get(ids[7]).unwrap().confidence, 200);
+        assert!(loaded.get(ids[8]).is_none());
+        assert_eq!(loaded.next_seq(), bank.next_seq());
+        // Readers without delta support see the base
+        assert_eq!(decode(&data).unwrap().len(), 200);
+
+        // A delta torn by a crash is ignored
+        let before = bank.get(ids[0]).unwrap().confidence;
+        bank.set_confidence(ids[0], 1).unwrap();
+        let third = save_incremental(&bank, &path, &mut image, 50).unwrap();
+        assert!(!third.full);
+        let data = std::fs::read(&path).unwrap();
+        let torn = decode_with_deltas(&data[..data.len() - 3]).unwrap();
+        assert_eq!(torn.get(ids[0]).unwrap().confidence, before);
+        assert_eq!(torn.get(ids[7]).unwrap().confidence, 200);
+
+        // Deltas past the ratio rewrite the file
+        for &id in &ids[9..160] {
+            bank.set_confidence(id, 9).unwrap();
+        }
+        let fourth = save_incremental(&bank, &path, &mut image, 50).unwrap();
+        assert!(fourth.full);
+        assert_eq!(std::fs::metadata(&path).unwrap().len() as usize, fourth.bytes);
+        assert_same_entries(&bank, &load(&path).unwrap());
+
+        // A file rewritten behind the image's back is rewritten again
+        bank.set_confidence(ids[199], 3).unwrap();
+        save_atomic(&bank, &path).unwrap();
+        bank.set_confidence(ids[199], 4).unwrap();
+        assert!(save_incremental(&bank, &path, &mut image, 50).unwrap().full);
+    }
+
     #[test]
     fn signal_lossless_round_trip() {
         // Verify that Signal survives encode->decode without loss
@@ -600,6 +2263,472 @@ mod tests {
         }
     }
 
+    #[test]
+    fn set_temperature_round_trip_sets_flag() {
+        let mut bank = make_bank_with_entries();
+        let plain = encode(&bank).unwrap();
+        assert_eq!(u16::from_le_bytes([plain[6], plain[7]]), FLAG_META_CHECKSUMS);
+
+        let id = *bank.entries().next().unwrap().0;
+        bank.get_mut(id).unwrap().temperature = Temperature::Set;
+        let encoded = encode(&bank).unwrap();
+        assert_eq!(
+            u16::from_le_bytes([encoded[6], encoded[7]]) & FLAG_EXTENDED_TEMPERATURE,
+            FLAG_EXTENDED_TEMPERATURE
+        );
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(decoded.get(id).unwrap().temperature, Temperature::Set);
+    }
+
+    #[test]
+    fn set_temperature_without_flag_rejected() {
+        let mut bank = make_bank_with_entries();
+        let id = *bank.entries().next().unwrap().0;
+        bank.get_mut(id).unwrap().temperature = Temperature::Set;
+        let mut data = encode(&bank).unwrap();
+        // Clear the flag: the Set discriminant must no longer be accepted
+        data[6..8].copy_from_slice(&0u16.to_le_bytes());
+        let err = decode(&data).err().unwrap();
+        assert!(format!("{err}").contains("invalid temperature"), "{err}");
+        assert_eq!(err.code(), 0x0700 | CodecErrorKind::InvalidEnum as u16);
+    }
+
+    #[test]
+    fn pinned_round_trip_sets_entry_flags() {
+        let mut bank = make_bank_with_entries();
+        let plain = encode(&bank).unwrap();
+        assert_eq!(u16::from_le_bytes([plain[6], plain[7]]) & FLAG_ENTRY_FLAGS, 0);
+
+        let id = *bank.entries().next().unwrap().0;
+        bank.pin(id).unwrap();
+        let encoded = encode(&bank).unwrap();
+        assert_eq!(
+            u16::from_le_bytes([encoded[6], encoded[7]]) & FLAG_ENTRY_FLAGS,
+            FLAG_ENTRY_FLAGS
+        );
+        // One extra byte per entry
+        assert_eq!(encoded.len(), plain.len() + bank.len());
+
+        let decoded = decode(&encoded).unwrap();
+        for (eid, entry) in decoded.entries() {
+            assert_eq!(entry.pinned, *eid == id);
+        }
+    }
+
+    #[test]
+    fn namespace_round_trips_through_entry_flags() {
+        let mut bank = make_bank_with_entries();
+        let plain = encode(&bank).unwrap();
+        let id = *bank.entries().next().unwrap().0;
+        bank.get_mut(id).unwrap().namespace = 0x1234;
+
+        let encoded = encode(&bank).unwrap();
+        assert_ne!(u16::from_le_bytes([encoded[6], encoded[7]]) & FLAG_ENTRY_FLAGS, 0);
+        // A flags byte per entry plus the namespace itself
+        assert_eq!(encoded.len(), plain.len() + bank.len() + 2);
+        assert_eq!(encoded.len(), encoded_len(&bank));
+
+        let decoded = decode(&encoded).unwrap();
+        for (eid, entry) in decoded.entries() {
+            assert_eq!(entry.namespace, if *eid == id { 0x1234 } else { 0 });
+            assert!(!entry.pinned);
+        }
+    }
+
+    #[test]
+    fn expiry_round_trips_through_entry_flags() {
+        let mut bank = make_bank_with_entries();
+        let plain = encode(&bank).unwrap();
+        let id = *bank.entries().next().unwrap().0;
+        bank.get_mut(id).unwrap().expires_at_tick = Some(12_345);
+
+        let encoded = encode(&bank).unwrap();
+        // A flags byte per entry plus the expiry tick
+        assert_eq!(encoded.len(), plain.len() + bank.len() + 8);
+        assert_eq!(encoded.len(), encoded_len(&bank));
+
+        let decoded = decode(&encoded).unwrap();
+        for (eid, entry) in decoded.entries() {
+            let expected = (*eid == id).then_some(12_345);
+            assert_eq!(entry.expires_at_tick, expected);
+        }
+    }
+
+    const OPTION_FLAGS: u16 = FLAG_SPARSE_VECTORS | FLAG_METADATA | FLAG_INDEX | FLAG_COMPRESSED;
+
+    fn header_flags(data: &[u8]) -> u16 {
+        u16::from_le_bytes([data[6], data[7]])
+    }
+
+    /// A bank with mostly-zero vectors and every runtime-only config field
+    /// set away from its default.
+    fn make_sparse_bank() -> DataBank {
+        let config = BankConfig {
+            vector_width: 16,
+            max_entries: 100,
+            edge_overflow: EdgeOverflow::EvictOldest,
+            min_ticks_between_flushes: 50,
+            max_bytes: Some(1 << 20),
+            exact_match_fast_path: true,
+            index_type: crate::ivf::IndexType::BruteForce,
+            ..BankConfig::default()
+        };
+        let mut bank = DataBank::new(BankId::from_raw(7), "sparse".into(), config);
+        for i in 0..8u8 {
+            let mut v = vec![Signal::ZERO; 16];
+            v[i as usize] = Signal::new_raw(1, 100 + i, 1);
+            v[15] = Signal::new_raw(-1, 40, 2);
+            let id = bank.insert(v, Temperature::Hot, i as u64).unwrap();
+            bank.get_mut(id).unwrap().debug_tag = Some(format!("entry{i}"));
+        }
+        bank
+    }
+
+    fn assert_same_entries(a: &DataBank, b: &DataBank) {
+        assert_eq!(a.len(), b.len());
+        for (id, entry) in a.entries() {
+            let other = b.get(*id).expect("entry survives round trip");
+            assert_eq!(other.vector, entry.vector);
+            assert_eq!(other.edges.as_slice(), entry.edges.as_slice());
+            assert_eq!(other.debug_tag, entry.debug_tag);
+            assert!(other.validate());
+        }
+    }
+
+    #[test]
+    fn default_encode_options_match_encode() {
+        let bank = make_bank_with_entries();
+        let plain = encode(&bank).unwrap();
+        assert_eq!(encode_with(&bank, &EncodeOptions::default()).unwrap(), plain);
+        assert_eq!(header_flags(&plain) & OPTION_FLAGS, 0);
+    }
+
+    #[test]
+    fn each_encode_option_sets_its_flag_and_round_trips() {
+        let bank = make_sparse_bank();
+        let plain = encode(&bank).unwrap();
+        let off = EncodeOptions::default();
+        let cases = [
+            (EncodeOptions { compress: true, ..off }, FLAG_COMPRESSED),
+            (EncodeOptions { sparse: true, ..off }, FLAG_SPARSE_VECTORS),
+            (EncodeOptions { include_metadata: true, ..off }, FLAG_METADATA),
+            (EncodeOptions { include_index: true, ..off }, FLAG_INDEX),
+            (EncodeOptions { quantize: Some(0), sparse: true, ..off }, FLAG_QUANTIZED),
+        ];
+        for (options, flag) in cases {
+            let encoded = encode_with(&bank, &options).unwrap();
+            let flags = header_flags(&encoded) & (OPTION_FLAGS | FLAG_QUANTIZED);
+            assert_eq!(flags, flag, "{options:?}");
+            let decoded = decode(&encoded).unwrap();
+            assert_same_entries(&bank, &decoded);
+        }
+
+        let sparse = encode_with(&bank, &EncodeOptions { sparse: true, ..off }).unwrap();
+        assert!(sparse.len() < plain.len());
+        let compressed = encode_with(&bank, &EncodeOptions { compress: true, ..off }).unwrap();
+        assert!(compressed.len() < plain.len());
+    }
+
+    #[test]
+    fn quantized_vectors_stay_within_tolerance_and_keep_top1() {
+        const TOLERANCE: u16 = 16;
+        let config = BankConfig {
+            vector_width: 32,
+            max_entries: 0,
+            index_type: crate::ivf::IndexType::BruteForce,
+            ..BankConfig::default()
+        };
+        let mut bank = DataBank::new(BankId::from_raw(9), "test.vq".into(), config);
+        let mut state = 7u32;
+        let mut next = move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            state >> 16
+        };
+        let prototypes: Vec<Vec<i32>> = (0..3)
+            .map(|_| {
+                (0..32)
+                    .map(|_| {
+                        let magnitude = 60 + (next() % 60) as i32;
+                        if next() % 2 == 0 { magnitude } else { -magnitude }
+                    })
+                    .collect()
+            })
+            .collect();
+        for prototype in &prototypes {
+            for member in 0..32 {
+                // Noise inside the tolerance, plus one flipped dimension
+                // that makes each member distinguishable
+                let vector = prototype
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &current)| {
+                        let current = if i == member { -current } else { current };
+                        let noise = (next() % (TOLERANCE as u32 / 2)) as i32;
+                        Signal::from_current(current + noise * current.signum())
+                    })
+                    .collect();
+                bank.insert(vector, Temperature::Hot, 0).unwrap();
+            }
+        }
+
+        let plain = encode(&bank).unwrap();
+        let options = EncodeOptions {
+            quantize: Some(TOLERANCE),
+            ..EncodeOptions::default()
+        };
+        let encoded = encode_with(&bank, &options).unwrap();
+        assert!(encoded.len() * 3 < plain.len() * 2, "{} vs {}", encoded.len(), plain.len());
+
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(decoded.len(), bank.len());
+        for (id, entry) in bank.entries() {
+            let rebuilt = decoded.get(*id).unwrap();
+            assert!(rebuilt.validate());
+            for (a, b) in entry.vector.iter().zip(&rebuilt.vector) {
+                assert!(a.current().abs_diff(b.current()) <= TOLERANCE as u32);
+            }
+            let before = bank.query_sparse(&entry.vector, 1).unwrap();
+            let after = decoded.query_sparse(&entry.vector, 1).unwrap();
+            assert_eq!(before[0].entry_id, after[0].entry_id);
+        }
+    }
+
+    #[test]
+    fn quantized_encodes_reuse_the_kept_codebook() {
+        let mut bank = make_sparse_bank();
+        let width = bank.config().vector_width as usize;
+        let options = EncodeOptions {
+            quantize: Some(0),
+            ..EncodeOptions::default()
+        };
+        // One all-zero prototype: nothing training would produce
+        let kept = Codebook::new(width, vec![vec![Signal::ZERO; width]]).unwrap();
+        bank.keep_codebook(kept.clone());
+
+        let decoded = decode(&encode_with(&bank, &options).unwrap()).unwrap();
+        assert_eq!(decoded.codebook(), Some(&kept));
+        assert_same_entries(&bank, &decoded);
+        // The loaded codebook is kept for the next save
+        let again = decode(&encode_with(&decoded, &options).unwrap()).unwrap();
+        assert_eq!(again.codebook(), Some(&kept));
+
+        let trained = bank.train_codebook().clone();
+        assert_ne!(trained, kept);
+        assert_eq!(bank.codebook(), Some(&trained));
+        // A codebook of another width is not used
+        bank.keep_codebook(Codebook::new(width + 1, Vec::new()).unwrap());
+        assert_eq!(bank.codebook(), None);
+    }
+
+    #[test]
+    fn metadata_and_index_sections_restore_runtime_config() {
+        let bank = make_sparse_bank();
+
+        let plain = decode(&encode(&bank).unwrap()).unwrap();
+        assert_eq!(plain.config().edge_overflow, EdgeOverflow::Reject);
+        assert_eq!(plain.config().max_bytes, None);
+        assert!(matches!(plain.config().index_type, crate::ivf::IndexType::Ivf { .. }));
+
+        let all = EncodeOptions {
+            compress: true,
+            sparse: true,
+            include_metadata: true,
+            include_index: true,
+            quantize: None,
+            vector_checksums_only: false,
+        };
+        let encoded = encode_with(&bank, &all).unwrap();
+        assert_eq!(header_flags(&encoded) & OPTION_FLAGS, OPTION_FLAGS);
+        let decoded = decode(&encoded).unwrap();
+        assert_same_entries(&bank, &decoded);
+        let config = decoded.config();
+        assert_eq!(config.edge_overflow, EdgeOverflow::EvictOldest);
+        assert_eq!(config.min_ticks_between_flushes, 50);
+        assert_eq!(config.max_bytes, Some(1 << 20));
+        assert!(config.exact_match_fast_path);
+        assert!(matches!(config.index_type, crate::ivf::IndexType::BruteForce));
+    }
+
+    #[test]
+    fn encode_into_presized_buffer_matches_encode() {
+        let bank = make_bank_with_entries();
+        let expected = encode(&bank).unwrap();
+
+        let mut buf = Vec::with_capacity(expected.len() + 64);
+        buf.extend_from_slice(b"stale bytes from the last bank");
+        let (ptr, capacity) = (buf.as_ptr(), buf.capacity());
+        encode_into(&bank, &EncodeOptions::default(), &mut buf).unwrap();
+        assert_eq!(buf, expected);
+        assert_eq!((buf.as_ptr(), buf.capacity()), (ptr, capacity));
+
+        // A shared region may be larger than the bank written into it
+        buf.resize(capacity, 0xAA);
+        let decoded = decode_borrowed(&buf).unwrap();
+        assert_eq!(decoded.len(), bank.len());
+        assert_eq!(decoded.name, bank.name);
+
+        let compressed = EncodeOptions {
+            compress: true,
+            ..EncodeOptions::default()
+        };
+        let packed = encode_with(&bank, &compressed).unwrap();
+        assert!(decode(&packed).is_ok());
+        match decode_borrowed(&packed) {
+            Err(DataBankError::Codec(e)) => assert_eq!(e.kind, CodecErrorKind::UnsupportedFlags),
+            _ => panic!("compressed input needs a copy"),
+        }
+    }
+
+    #[test]
+    fn meta_checksum_catches_corrupt_metadata_past_the_file_hash() {
+        let config = BankConfig {
+            vector_width: 4,
+            ..BankConfig::default()
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "meta".into(), config);
+        let id = bank.insert(vec![Signal::new_raw(1, 90, 1); 4], Temperature::Hot, 5).unwrap();
+        bank.set_confidence(id, 77).unwrap();
+        assert!(bank.validate_all().is_empty());
+
+        // name, config, then the entry up to its confidence byte
+        let confidence_at =
+            HEADER_SIZE + 2 + 4 + 20 + 8 + 2 + 4 * 3 + 2 + 8 + 1 + 8 + 8 + 4;
+        let corrupt = |options: &EncodeOptions| {
+            let mut data = encode_with(&bank, options).unwrap();
+            assert_eq!(data[confidence_at], 77);
+            data[confidence_at] = 78;
+            let checksum = xxhash_rust::xxh3::xxh3_64(&data[HEADER_SIZE..]);
+            data[12..20].copy_from_slice(&checksum.to_le_bytes());
+            decode(&data).unwrap()
+        };
+
+        let decoded = corrupt(&EncodeOptions::default());
+        assert_eq!(decoded.get(id).unwrap().confidence, 78);
+        match decoded.validate_all().as_slice() {
+            [DataBankError::EntryMetadataCorrupted { entry, .. }] => assert_eq!(*entry, id),
+            other => panic!("expected one EntryMetadataCorrupted, got {other:?}"),
+        }
+
+        // Files without the flag validate by the vector checksum alone
+        let legacy = EncodeOptions {
+            vector_checksums_only: true,
+            ..EncodeOptions::default()
+        };
+        let decoded = corrupt(&legacy);
+        assert_eq!(header_flags(&encode_with(&bank, &legacy).unwrap()), 0);
+        assert!(decoded.validate_all().is_empty());
+    }
+
+    #[test]
+    fn pack_bits_round_trips_runs_and_literals() {
+        let mut data = vec![7u8; 300];
+        data.extend(0..=255u8);
+        data.extend([1, 1, 2, 2, 2, 3]);
+        let packed = pack_bits(&data);
+        let mut unpacked = Vec::new();
+        unpack_bits(&packed, &mut unpacked, 0).unwrap();
+        assert_eq!(unpacked, data);
+
+        let truncated = &packed[..packed.len() - 1];
+        assert!(unpack_bits(truncated, &mut Vec::new(), 0).is_err());
+    }
+
+    #[test]
+    fn unknown_flags_rejected() {
+        let mut data = encode(&make_bank_with_entries()).unwrap();
+        data[6..8].copy_from_slice(&0x8000u16.to_le_bytes());
+        let err = decode(&data).err().unwrap();
+        assert!(format!("{err}").contains("unsupported flags"), "{err}");
+    }
+
+    /// Recompute the body checksum after a test has patched the buffer.
+    fn reseal(data: &mut [u8]) {
+        let checksum = xxhash_rust::xxh3::xxh3_64(&data[HEADER_SIZE..]);
+        data[12..20].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    /// Byte offset and decode index of the entry with the given id.
+    fn locate_entry(bank: &DataBank, data: &[u8], id: EntryId) -> (usize, usize) {
+        let needle = id.0.to_le_bytes();
+        let mut starts: Vec<(usize, EntryId)> = bank
+            .entries()
+            .map(|(eid, _)| {
+                let bytes = eid.0.to_le_bytes();
+                let at = data.windows(8).position(|w| w == bytes).unwrap();
+                (at, *eid)
+            })
+            .collect();
+        starts.sort();
+        let index = starts.iter().position(|(_, eid)| *eid == id).unwrap();
+        let offset = data.windows(8).position(|w| w == needle).unwrap();
+        (offset, index)
+    }
+
+    fn codec_error(result: Result<DataBank>) -> CodecError {
+        match result {
+            Err(DataBankError::Codec(e)) => e,
+            Err(other) => panic!("expected codec error, got {other}"),
+            Ok(_) => panic!("expected decode to fail"),
+        }
+    }
+
+    #[test]
+    fn header_errors_have_kind_and_offset() {
+        let mut data = encode(&make_bank_with_entries()).unwrap();
+        data[0] = b'X';
+        let e = codec_error(decode(&data));
+        assert_eq!(e.kind, CodecErrorKind::BadMagic);
+        assert_eq!(e.offset, 0);
+        assert_eq!(e.entry_index, None);
+
+        let data = encode(&make_bank_with_entries()).unwrap();
+        let e = codec_error(decode(&data[..HEADER_SIZE + 2]));
+        assert_eq!(e.kind, CodecErrorKind::Truncated);
+    }
+
+    #[test]
+    fn corrupt_edge_type_reports_entry_index() {
+        let bank = make_bank_with_entries();
+        // The entry carrying the SoundsLike edge
+        let (id, _) = bank.entries().find(|(_, e)| !e.edges.is_empty()).unwrap();
+        let id = *id;
+        let mut data = encode(&bank).unwrap();
+        let (start, index) = locate_entry(&bank, &data, id);
+
+        // id (8) + vec len (2) + 4 signals * 3 + edge count (2)
+        let edge_type_at = start + 8 + 2 + 4 * 3 + 2;
+        assert_eq!(data[edge_type_at], EdgeType::SoundsLike as u8);
+        data[edge_type_at] = 0xEE;
+        reseal(&mut data);
+
+        let e = codec_error(decode(&data));
+        assert_eq!(e.kind, CodecErrorKind::InvalidEnum);
+        assert_eq!(e.offset, edge_type_at);
+        assert_eq!(e.entry_index, Some(index));
+        let msg = format!("{}", DataBankError::Codec(e));
+        assert!(msg.contains(&format!("offset {edge_type_at}")), "{msg}");
+        assert!(msg.contains(&format!("entry {index}")), "{msg}");
+    }
+
+    #[test]
+    fn overrunning_edge_count_reports_entry_index() {
+        let bank = make_bank_with_entries();
+        for (id, _) in bank.entries() {
+            let mut data = encode(&bank).unwrap();
+            let (start, index) = locate_entry(&bank, &data, *id);
+            let edge_count_at = start + 8 + 2 + 4 * 3;
+            data[edge_count_at..edge_count_at + 2].copy_from_slice(&u16::MAX.to_le_bytes());
+            reseal(&mut data);
+
+            let e = codec_error(decode(&data));
+            assert_eq!(e.entry_index, Some(index));
+            assert!(
+                matches!(e.kind, CodecErrorKind::SectionOverrun | CodecErrorKind::InvalidEnum),
+                "{e}"
+            );
+        }
+    }
+
     #[test]
     fn v2_files_rejected_with_clear_error() {
         // Construct a minimal v2 header to verify it's rejected
@@ -621,4 +2750,122 @@ mod tests {
             Ok(_) => panic!("expected v2 decode to fail"),
         }
     }
+
+    /// Minimal RFC 4180 reader for checking exports.
+    fn parse_csv(text: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let (mut row, mut field) = (Vec::new(), String::new());
+        let mut chars = text.chars().peekable();
+        let mut quoted = false;
+        while let Some(c) = chars.next() {
+            match (quoted, c) {
+                (true, '"') if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                (true, '"') => quoted = false,
+                (true, c) => field.push(c),
+                (false, '"') => quoted = true,
+                (false, ',') => row.push(std::mem::take(&mut field)),
+                (false, '\n') => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                (false, c) => field.push(c),
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn csv_export_parses_back() {
+        let mut bank = make_bank_with_entries();
+        let tagged = bank
+            .entries()
+            .find(|(_, e)| e.debug_tag.is_some())
+            .map(|(&id, _)| id)
+            .unwrap();
+        bank.get_mut(tagged).unwrap().debug_tag = Some("cup, \"blue\"\nchipped".into());
+
+        let mut out = Vec::new();
+        export_csv(&bank, &mut out, true).unwrap();
+        let rows = parse_csv(std::str::from_utf8(&out).unwrap());
+        assert_eq!(rows.len(), 1 + bank.len());
+        assert_eq!(rows[0].len(), 8 + 4);
+        assert_eq!(rows[0][11], "v3");
+        assert!(rows.iter().all(|r| r.len() == 12));
+
+        // Rows are in id order: the Hot entry with one edge comes first
+        assert_eq!(rows[1][1], "hot");
+        assert_eq!(rows[1][6], "1");
+        assert_eq!(rows[1][8..], ["100", "-50", "0", "200"]);
+        let tagged_row = rows.iter().find(|r| r[0] == tagged.0.to_string()).unwrap();
+        assert_eq!(tagged_row[7], "cup, \"blue\"\nchipped");
+        assert_eq!(tagged_row[3], "1");
+
+        let mut out = Vec::new();
+        export_csv(&bank, &mut out, false).unwrap();
+        let rows = parse_csv(std::str::from_utf8(&out).unwrap());
+        assert!(rows.iter().all(|r| r.len() == 8));
+    }
+
+    #[test]
+    fn diff_categorizes_entry_and_config_changes() {
+        let mut old = make_bank_with_entries();
+        let doomed = old.insert(vec![Signal::new_raw(1, 7, 1); 4], Temperature::Cold, 40).unwrap();
+        let mut ids: Vec<EntryId> = old.entries().map(|(id, _)| *id).collect();
+        ids.sort_unstable();
+        let (linked, touched) = (ids[0], ids[1]);
+
+        let mut new = decode(&encode(&old).unwrap()).unwrap();
+        assert!(diff(&old, &new).is_empty());
+
+        new.remove(doomed);
+        let added = new.insert(vec![Signal::new_raw(-1, 9, 1); 4], Temperature::Hot, 50).unwrap();
+        {
+            let mut entry = new.get_mut(linked).unwrap();
+            entry.vector[2] = Signal::new_raw(1, 1, 1);
+            entry.checksum = entry.compute_checksum();
+            entry.edges.clear();
+        }
+        {
+            let mut entry = new.get_mut(touched).unwrap();
+            entry.temperature = Temperature::Cold;
+            entry.confidence = entry.confidence.wrapping_add(1);
+            entry.touch(99); // access bookkeeping is not a difference
+        }
+
+        let report = diff(&old, &new);
+        assert_eq!(report.removed, vec![doomed]);
+        assert_eq!(report.added, vec![added]);
+        assert_eq!(report.vector_changed, vec![linked]);
+        assert_eq!(report.edges_changed, vec![linked]);
+        assert_eq!(report.temperature_changed, vec![touched]);
+        assert_eq!(report.confidence_changed, vec![touched]);
+        assert!(report.config_changes.is_empty());
+
+        let tuned = BankConfig {
+            max_entries: 50,
+            edge_overflow: EdgeOverflow::EvictOldest,
+            ..old.config().clone()
+        };
+        let changes = diff_config(old.config(), &tuned);
+        let fields: Vec<&str> = changes.iter().map(|c| c.field).collect();
+        assert_eq!(fields, ["max_entries", "edge_overflow"]);
+        assert_eq!((changes[0].old.as_str(), changes[0].new.as_str()), ("100", "50"));
+    }
+
+    #[test]
+    fn diff_files_compares_saved_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.bank"), dir.path().join("b.bank"));
+        let mut bank = make_bank_with_entries();
+        save_atomic(&bank, &a).unwrap();
+        let added = bank.insert(vec![Signal::ZERO; 4], Temperature::Warm, 60).unwrap();
+        save_atomic(&bank, &b).unwrap();
+
+        let report = diff_files(&a, &b).unwrap();
+        assert_eq!(report.added, vec![added]);
+        assert!(report.removed.is_empty());
+    }
 }
@@ -0,0 +1,143 @@
+//! GraphViz Export
+//!
+//! Renders the cluster's cross-bank edge graph as a DOT digraph, so a
+//! distributed concept can be inspected visually instead of rebuilt from
+//! logs. Output is sorted (banks by name, entries by id, edges in stored
+//! order) and therefore stable enough to diff and pin in tests.
+
+use alloc::collections::VecDeque;
+use core::fmt::Write;
+
+use crate::bank::DataBank;
+use crate::cluster::BankCluster;
+use crate::entry::BankEntry;
+use crate::prelude::*;
+use crate::types::{BankId, BankRef, EdgeType, EntryId};
+
+/// Restricts what [`BankCluster::export_dot`] renders. All restrictions
+/// combine; the default filter renders the whole cluster.
+#[derive(Debug, Clone, Default)]
+pub struct GraphFilter {
+    /// Only banks whose name starts with this prefix.
+    pub name_prefix: Option<String>,
+    /// Only edges of these types (also limits neighborhood expansion).
+    pub edge_types: Option<HashSet<EdgeType>>,
+    /// Only entries reachable from this entry within `depth` outgoing hops,
+    /// plus the entry itself. Keeps large clusters renderable.
+    pub neighborhood: Option<(BankRef, usize)>,
+}
+
+impl GraphFilter {
+    fn allows_bank(&self, bank: &DataBank) -> bool {
+        self.name_prefix
+            .as_deref()
+            .is_none_or(|prefix| bank.name.starts_with(prefix))
+    }
+
+    fn allows_edge(&self, edge_type: EdgeType) -> bool {
+        self.edge_types
+            .as_ref()
+            .is_none_or(|types| types.contains(&edge_type))
+    }
+}
+
+impl BankCluster {
+    /// Export the edge graph as a GraphViz digraph.
+    ///
+    /// Nodes are named `bankname/entryseq` and labelled `bankname/debug_tag`
+    /// when the entry has a tag. Edges are labelled with their type and
+    /// weight. An edge is drawn only when both endpoints are rendered, so
+    /// edges leaving the filter (or the cluster) are dropped.
+    pub fn export_dot(&self, filter: Option<&GraphFilter>) -> String {
+        let unfiltered = GraphFilter::default();
+        let filter = filter.unwrap_or(&unfiltered);
+
+        let mut banks: Vec<&DataBank> = self
+            .bank_ids()
+            .into_iter()
+            .filter_map(|id| self.get(id))
+            .filter(|bank| filter.allows_bank(bank))
+            .collect();
+        banks.sort_by(|a, b| a.name.cmp(&b.name));
+        let by_id: HashMap<BankId, &DataBank> = banks.iter().map(|b| (b.id, *b)).collect();
+
+        let reachable = filter
+            .neighborhood
+            .map(|(start, depth)| self.neighborhood(start, depth, filter));
+        let rendered = |r: &BankRef| {
+            by_id.get(&r.bank).is_some_and(|bank| bank.get(r.entry).is_some())
+                && reachable.as_ref().is_none_or(|set| set.contains(r))
+        };
+
+        let mut out = String::from("digraph databank {\n");
+        let mut edges = String::new();
+        for bank in &banks {
+            let mut entries: Vec<(EntryId, &BankEntry)> =
+                bank.entries().map(|(&id, entry)| (id, entry)).collect();
+            entries.sort_unstable_by_key(|&(id, _)| id);
+            for (id, entry) in entries {
+                if !rendered(&BankRef { bank: bank.id, entry: id }) {
+                    continue;
+                }
+                let label = match &entry.debug_tag {
+                    Some(tag) => format!("{}/{}", bank.name, tag),
+                    None => node_name(bank, id),
+                };
+                let _ = writeln!(
+                    out,
+                    "  \"{}\" [label=\"{}\"];",
+                    escape(&node_name(bank, id)),
+                    escape(&label)
+                );
+
+                for edge in &entry.edges {
+                    if !filter.allows_edge(edge.edge_type) || !rendered(&edge.target) {
+                        continue;
+                    }
+                    let target_bank = by_id[&edge.target.bank];
+                    let _ = writeln!(
+                        edges,
+                        "  \"{}\" -> \"{}\" [label=\"{:?} {}\"];",
+                        escape(&node_name(bank, id)),
+                        escape(&node_name(target_bank, edge.target.entry)),
+                        edge.edge_type,
+                        edge.weight
+                    );
+                }
+            }
+        }
+        out.push_str(&edges);
+        out.push_str("}\n");
+        out
+    }
+
+    /// Entries within `depth` outgoing hops of `start` (including it),
+    /// following only edge types the filter allows.
+    fn neighborhood(&self, start: BankRef, depth: usize, filter: &GraphFilter) -> HashSet<BankRef> {
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([(start, 0)]);
+        while let Some((current, hops)) = queue.pop_front() {
+            if hops >= depth {
+                continue;
+            }
+            let Some(bank) = self.get(current.bank) else {
+                continue;
+            };
+            for edge in bank.edges_from(current.entry) {
+                if filter.allows_edge(edge.edge_type) && seen.insert(edge.target) {
+                    queue.push_back((edge.target, hops + 1));
+                }
+            }
+        }
+        seen
+    }
+}
+
+fn node_name(bank: &DataBank, id: EntryId) -> String {
+    format!("{}/{}", bank.name, id.seq())
+}
+
+/// Escape a string for use inside a DOT double-quoted ID.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
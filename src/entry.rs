@@ -1,45 +1,81 @@
 use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
 use ternary_signal::Signal;
 
 use crate::error::{DataBankError, Result};
+use crate::prelude::*;
 use crate::types::{BankId, BankRef, Edge, EntryId, Temperature};
 
+/// Edges stored inline in an entry before spilling to the heap.
+///
+/// Most entries carry only a handful of edges, so the first
+/// `INLINE_EDGES` cost no allocation.
+pub const INLINE_EDGES: usize = 4;
+
+/// Edge storage for a [`BankEntry`]. Derefs to `[Edge]` and serializes as a
+/// plain sequence, exactly like `Vec<Edge>`.
+pub type EdgeList = SmallVec<[Edge; INLINE_EDGES]>;
+
 /// A single entry in a databank — one fragment of a distributed concept.
 ///
 /// Each entry stores a signal vector (the representational pattern), typed
 /// edges to related entries (possibly in other banks), and lifecycle metadata.
 /// The vector width is fixed per bank and validated on insertion.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Fields are read through accessors and changed through
+/// [`DataBank`](crate::bank::DataBank) methods, which keep the bank's index,
+/// checksums, per-temperature counts and dirty state in step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BankEntry {
     /// Unique entry identifier (temporally sortable).
-    pub id: EntryId,
+    pub(crate) id: EntryId,
     /// The representational signal vector. Fixed width per bank.
     /// Each Signal encodes the full s = p × m × k equation in 3 bytes.
-    pub vector: Vec<Signal>,
+    pub(crate) vector: Vec<Signal>,
     /// Typed, weighted edges to other entries (cross-bank allowed).
-    pub edges: Vec<Edge>,
+    pub(crate) edges: EdgeList,
     /// Which bank originally created this entry.
-    pub origin: BankId,
+    pub(crate) origin: BankId,
     /// Thermogram-compatible temperature lifecycle state.
-    pub temperature: Temperature,
+    pub(crate) temperature: Temperature,
     /// Tick when this entry was created.
-    pub created_tick: u64,
+    pub(crate) created_tick: u64,
     /// Tick when this entry was last accessed (read or touched).
-    pub last_accessed_tick: u64,
+    pub(crate) last_accessed_tick: u64,
     /// Number of times this entry has been accessed.
-    pub access_count: u32,
+    pub(crate) access_count: u32,
     /// Confidence score (0-255). Higher = more reliable.
-    pub confidence: u8,
+    pub(crate) confidence: u8,
+    /// Innate prior: never demoted or evicted, even in a plastic bank.
+    #[serde(default)]
+    pub(crate) pinned: bool,
+    /// Context partition within the bank (e.g. one conversation). 0 is the
+    /// default namespace.
+    #[serde(default)]
+    pub(crate) namespace: u16,
+    /// Tick from which the entry counts as expired: skipped by
+    /// [`DataBank::query_sparse_at`] and removed by
+    /// [`DataBank::expire_entries`], unless pinned. None lives until
+    /// evicted.
+    ///
+    /// [`DataBank::query_sparse_at`]: crate::bank::DataBank::query_sparse_at
+    /// [`DataBank::expire_entries`]: crate::bank::DataBank::expire_entries
+    #[serde(default)]
+    pub(crate) expires_at_tick: Option<u64>,
     /// Human-readable label for debugging/introspection. Optional.
-    pub debug_tag: Option<String>,
+    pub(crate) debug_tag: Option<String>,
     /// CRC32 checksum of the vector data for integrity verification.
-    pub checksum: u32,
+    pub(crate) checksum: u32,
+    /// CRC32 checksum of everything else that reaches a `.bank` file but
+    /// the debug tag, see [`compute_meta_checksum`](Self::compute_meta_checksum).
+    #[serde(default)]
+    pub(crate) meta_checksum: u32,
 }
 
 impl BankEntry {
     /// Create a new entry with the given vector and metadata.
     ///
-    /// The checksum is computed automatically from the vector data.
+    /// Both checksums are computed automatically.
     pub fn new(
         id: EntryId,
         vector: Vec<Signal>,
@@ -47,42 +83,152 @@ impl BankEntry {
         temperature: Temperature,
         tick: u64,
     ) -> Self {
-        let checksum = compute_vector_checksum(&vector);
-        Self {
+        let checksum = vector_checksum(&vector);
+        let mut entry = Self {
             id,
             vector,
-            edges: Vec::new(),
+            edges: EdgeList::new(),
             origin,
             temperature,
             created_tick: tick,
             last_accessed_tick: tick,
             access_count: 0,
             confidence: 128, // neutral default
+            pinned: false,
+            namespace: 0,
+            expires_at_tick: None,
             debug_tag: None,
             checksum,
+            meta_checksum: 0,
+        };
+        entry.refresh_meta_checksum();
+        entry
+    }
+
+    /// Unique entry identifier.
+    pub fn id(&self) -> EntryId {
+        self.id
+    }
+
+    /// The representational signal vector.
+    pub fn vector(&self) -> &[Signal] {
+        &self.vector
+    }
+
+    /// Outgoing edges, in insertion order.
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Which bank originally created this entry.
+    pub fn origin(&self) -> BankId {
+        self.origin
+    }
+
+    pub fn temperature(&self) -> Temperature {
+        self.temperature
+    }
+
+    pub fn created_tick(&self) -> u64 {
+        self.created_tick
+    }
+
+    pub fn last_accessed_tick(&self) -> u64 {
+        self.last_accessed_tick
+    }
+
+    pub fn access_count(&self) -> u32 {
+        self.access_count
+    }
+
+    pub fn confidence(&self) -> u8 {
+        self.confidence
+    }
+
+    /// Whether the entry is exempt from demotion and eviction.
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    pub fn namespace(&self) -> u16 {
+        self.namespace
+    }
+
+    /// Tick from which the entry counts as expired, if it has a TTL.
+    pub fn expires_at_tick(&self) -> Option<u64> {
+        self.expires_at_tick
+    }
+
+    pub fn debug_tag(&self) -> Option<&str> {
+        self.debug_tag.as_deref()
+    }
+
+    /// The stored checksum, see [`validate`](Self::validate).
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+
+    /// The stored metadata checksum, see [`validate`](Self::validate).
+    pub fn meta_checksum(&self) -> u32 {
+        self.meta_checksum
+    }
+
+    /// Every field, mutable, with no invariant kept: the checksums are not
+    /// refreshed and a bank holding the entry sees none of the changes.
+    /// For callers still writing the formerly public fields.
+    #[deprecated(
+        since = "0.6.0",
+        note = "use the accessors, and DataBank setters such as set_vector and set_temperature"
+    )]
+    pub fn raw_mut(&mut self) -> RawEntryMut<'_> {
+        RawEntryMut {
+            id: &mut self.id,
+            vector: &mut self.vector,
+            edges: &mut self.edges,
+            origin: &mut self.origin,
+            temperature: &mut self.temperature,
+            created_tick: &mut self.created_tick,
+            last_accessed_tick: &mut self.last_accessed_tick,
+            access_count: &mut self.access_count,
+            confidence: &mut self.confidence,
+            pinned: &mut self.pinned,
+            namespace: &mut self.namespace,
+            expires_at_tick: &mut self.expires_at_tick,
+            debug_tag: &mut self.debug_tag,
+            checksum: &mut self.checksum,
+            meta_checksum: &mut self.meta_checksum,
         }
     }
 
     /// Record an access: increment count and update last-accessed tick.
-    pub fn touch(&mut self, tick: u64) {
+    pub(crate) fn touch(&mut self, tick: u64) {
         self.access_count = self.access_count.saturating_add(1);
         self.last_accessed_tick = tick;
+        self.refresh_meta_checksum();
+    }
+
+    /// Whether the entry's TTL has run out at `tick`. A pinned entry never
+    /// expires while pinned, as it is never evicted.
+    pub fn is_expired(&self, tick: u64) -> bool {
+        !self.pinned && self.expires_at_tick.is_some_and(|at| tick >= at)
     }
 
     /// Add a directed edge from this entry to another.
     ///
     /// Returns an error if the entry already has `max` edges.
-    pub fn add_edge(&mut self, edge: Edge, max: u16) -> Result<()> {
+    pub(crate) fn add_edge(&mut self, edge: Edge, max: u16) -> Result<()> {
         if self.edges.len() >= max as usize {
             return Err(DataBankError::EdgeLimitReached { max });
         }
         self.edges.push(edge);
+        self.refresh_meta_checksum();
         Ok(())
     }
 
     /// Remove all edges pointing to a specific target.
-    pub fn remove_edges_to(&mut self, target: BankRef) {
+    pub(crate) fn remove_edges_to(&mut self, target: BankRef) {
         self.edges.retain(|e| e.target != target);
+        self.refresh_meta_checksum();
     }
 
     /// Compute a hybrid eviction score. Lower = more evictable.
@@ -95,6 +241,7 @@ impl BankEntry {
             Temperature::Hot => 10,
             Temperature::Warm => 50,
             Temperature::Cool => 200,
+            Temperature::Set => 500,
             Temperature::Cold => 1000,
         };
 
@@ -112,25 +259,24 @@ impl BankEntry {
         temperature_weight + recency + access + conf
     }
 
-    /// Promote temperature one step: Hot->Warm, Warm->Cool, Cool->Cold.
+    /// Promote temperature one step: Hot->Warm, Warm->Cool, Cool->Set, Set->Cold.
     /// Returns true if promoted, false if already Cold.
-    pub fn promote(&mut self) -> bool {
-        match self.temperature {
-            Temperature::Hot => { self.temperature = Temperature::Warm; true }
-            Temperature::Warm => { self.temperature = Temperature::Cool; true }
-            Temperature::Cool => { self.temperature = Temperature::Cold; true }
-            Temperature::Cold => false,
+    pub(crate) fn promote(&mut self) -> bool {
+        match self.temperature.promoted() {
+            Some(t) => { self.temperature = t; self.refresh_meta_checksum(); true }
+            None => false,
         }
     }
 
-    /// Demote temperature one step: Cold->Cool, Cool->Warm, Warm->Hot.
-    /// Returns true if demoted, false if already Hot.
-    pub fn demote(&mut self) -> bool {
-        match self.temperature {
-            Temperature::Cold => { self.temperature = Temperature::Cool; true }
-            Temperature::Cool => { self.temperature = Temperature::Warm; true }
-            Temperature::Warm => { self.temperature = Temperature::Hot; true }
-            Temperature::Hot => false,
+    /// Demote temperature one step: Cold->Set, Set->Cool, Cool->Warm, Warm->Hot.
+    /// Returns true if demoted, false if already Hot or pinned.
+    pub(crate) fn demote(&mut self) -> bool {
+        if self.pinned {
+            return false;
+        }
+        match self.temperature.demoted() {
+            Some(t) => { self.temperature = t; self.refresh_meta_checksum(); true }
+            None => false,
         }
     }
 
@@ -145,8 +291,9 @@ impl BankEntry {
     }
 
     /// Check if this entry should be demoted (confidence below threshold).
+    /// Pinned entries are never eligible.
     pub fn demotion_eligible(&self, confidence_threshold: u8) -> bool {
-        if self.temperature == Temperature::Hot {
+        if self.pinned || self.temperature == Temperature::Hot {
             return false; // already at min
         }
         self.confidence < confidence_threshold
@@ -154,17 +301,95 @@ impl BankEntry {
 
     /// Recompute the CRC32 checksum from the current vector data.
     pub fn compute_checksum(&self) -> u32 {
-        compute_vector_checksum(&self.vector)
+        vector_checksum(&self.vector)
     }
 
-    /// Verify that the stored checksum matches the vector data.
+    /// Store the checksums of the current vector and metadata.
+    pub fn refresh_checksum(&mut self) {
+        self.checksum = self.compute_checksum();
+        self.refresh_meta_checksum();
+    }
+
+    /// CRC32 over a canonical serialization of the entry's metadata: origin,
+    /// temperature, created and last-accessed ticks, access count,
+    /// confidence, pin, namespace, expiry, then every edge (type, target,
+    /// weight, created tick) in order, integers little-endian.
+    pub fn compute_meta_checksum(&self) -> u32 {
+        let mut crc = Crc32::new();
+        crc.u64(self.origin.0);
+        crc.byte(self.temperature.as_u8());
+        crc.u64(self.created_tick);
+        crc.u64(self.last_accessed_tick);
+        crc.bytes(&self.access_count.to_le_bytes());
+        crc.byte(self.confidence);
+        crc.byte(self.pinned as u8);
+        crc.bytes(&self.namespace.to_le_bytes());
+        match self.expires_at_tick {
+            Some(at) => {
+                crc.byte(1);
+                crc.u64(at);
+            }
+            None => crc.byte(0),
+        }
+        crc.bytes(&(self.edges.len() as u32).to_le_bytes());
+        for edge in &self.edges {
+            crc.byte(edge.edge_type.as_u8());
+            crc.u64(edge.target.bank.0);
+            crc.u64(edge.target.entry.0);
+            crc.byte(edge.weight);
+            crc.u64(edge.created_tick);
+        }
+        crc.finish()
+    }
+
+    pub(crate) fn refresh_meta_checksum(&mut self) {
+        self.meta_checksum = self.compute_meta_checksum();
+    }
+
+    /// Verify that both stored checksums match the vector and metadata.
     pub fn validate(&self) -> bool {
         self.checksum == self.compute_checksum()
+            && self.meta_checksum == self.compute_meta_checksum()
     }
+
+    /// Estimated bytes held by this entry: the struct itself plus its vector
+    /// buffer, spilled edge storage, and debug tag. Allocator headers are not
+    /// counted.
+    pub fn approx_bytes(&self) -> usize {
+        let edges = if self.edges.spilled() {
+            self.edges.capacity() * core::mem::size_of::<Edge>()
+        } else {
+            0
+        };
+        core::mem::size_of::<Self>()
+            + self.vector.capacity() * core::mem::size_of::<Signal>()
+            + edges
+            + self.debug_tag.as_ref().map_or(0, String::capacity)
+    }
+}
+
+/// Mutable view of every [`BankEntry`] field, from the deprecated
+/// [`BankEntry::raw_mut`]. Nothing written here is validated or tracked.
+pub struct RawEntryMut<'a> {
+    pub id: &'a mut EntryId,
+    pub vector: &'a mut Vec<Signal>,
+    pub edges: &'a mut EdgeList,
+    pub origin: &'a mut BankId,
+    pub temperature: &'a mut Temperature,
+    pub created_tick: &'a mut u64,
+    pub last_accessed_tick: &'a mut u64,
+    pub access_count: &'a mut u32,
+    pub confidence: &'a mut u8,
+    pub pinned: &'a mut bool,
+    pub namespace: &'a mut u16,
+    pub expires_at_tick: &'a mut Option<u64>,
+    pub debug_tag: &'a mut Option<String>,
+    pub checksum: &'a mut u32,
+    pub meta_checksum: &'a mut u32,
 }
 
 /// Compute CRC32 checksum over Signal bytes (3 bytes per signal: polarity, magnitude, multiplier).
-fn compute_vector_checksum(vector: &[Signal]) -> u32 {
+pub(crate) fn vector_checksum(vector: &[Signal]) -> u32 {
     let mut crc: u32 = 0xFFFF_FFFF;
     for s in vector {
         crc = crc32_update(crc, s.polarity as u8);
@@ -174,6 +399,33 @@ fn compute_vector_checksum(vector: &[Signal]) -> u32 {
     crc ^ 0xFFFF_FFFF
 }
 
+/// Incremental CRC32 over a byte stream, for checksums of several fields.
+struct Crc32(u32);
+
+impl Crc32 {
+    fn new() -> Self {
+        Self(0xFFFF_FFFF)
+    }
+
+    fn byte(&mut self, byte: u8) {
+        self.0 = crc32_update(self.0, byte);
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.byte(b);
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn finish(self) -> u32 {
+        self.0 ^ 0xFFFF_FFFF
+    }
+}
+
 /// CRC32 single-byte update (IEEE polynomial, same as crc32fast).
 fn crc32_update(crc: u32, byte: u8) -> u32 {
     let mut c = crc ^ (byte as u32);
@@ -191,6 +443,8 @@ fn crc32_update(crc: u32, byte: u8) -> u32 {
 mod tests {
     use super::*;
     use crate::types::BankId;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
 
     fn make_entry(width: usize, tick: u64) -> BankEntry {
         let vector: Vec<Signal> = (0..width)
@@ -206,6 +460,18 @@ mod tests {
         assert!(entry.validate());
     }
 
+    #[test]
+    #[allow(deprecated)]
+    fn raw_mut_skips_checksum_until_refreshed() {
+        let mut entry = make_entry(8, 0);
+        *entry.raw_mut().vector = vec![Signal::new_raw(-1, 3, 1); 8];
+        *entry.raw_mut().confidence = 9;
+        assert!(!entry.validate());
+        entry.refresh_checksum();
+        assert!(entry.validate());
+        assert_eq!(entry.confidence(), 9);
+    }
+
     #[test]
     fn touch_increments_access() {
         let mut entry = make_entry(32, 0);
@@ -287,6 +553,18 @@ mod tests {
         assert!(cold.eviction_score(100) > hot.eviction_score(100));
     }
 
+    #[test]
+    fn eviction_score_set_between_cool_and_cold() {
+        let mut cool = make_entry(32, 0);
+        cool.temperature = Temperature::Cool;
+        let mut set = make_entry(32, 0);
+        set.temperature = Temperature::Set;
+        let mut cold = make_entry(32, 0);
+        cold.temperature = Temperature::Cold;
+        assert!(set.eviction_score(100) > cool.eviction_score(100));
+        assert!(cold.eviction_score(100) > set.eviction_score(100));
+    }
+
     #[test]
     fn eviction_score_recent_higher() {
         let mut recent = make_entry(32, 0);
@@ -305,6 +583,8 @@ mod tests {
         assert!(entry.promote());
         assert_eq!(entry.temperature, Temperature::Cool);
         assert!(entry.promote());
+        assert_eq!(entry.temperature, Temperature::Set);
+        assert!(entry.promote());
         assert_eq!(entry.temperature, Temperature::Cold);
         assert!(!entry.promote()); // already Cold
     }
@@ -314,6 +594,8 @@ mod tests {
         let mut entry = make_entry(32, 0);
         entry.temperature = Temperature::Cold;
         assert!(entry.demote());
+        assert_eq!(entry.temperature, Temperature::Set);
+        assert!(entry.demote());
         assert_eq!(entry.temperature, Temperature::Cool);
         assert!(entry.demote());
         assert_eq!(entry.temperature, Temperature::Warm);
@@ -322,6 +604,17 @@ mod tests {
         assert!(!entry.demote()); // already Hot
     }
 
+    #[test]
+    fn pinned_entry_resists_demotion() {
+        let mut entry = make_entry(32, 0);
+        entry.temperature = Temperature::Cold;
+        entry.confidence = 0;
+        entry.pinned = true;
+        assert!(!entry.demotion_eligible(255));
+        assert!(!entry.demote());
+        assert_eq!(entry.temperature, Temperature::Cold);
+    }
+
     #[test]
     fn promotion_eligibility() {
         let mut entry = make_entry(32, 0);
@@ -357,4 +650,58 @@ mod tests {
         entry.vector[0] = Signal::new_raw(-1, 255, 1);
         assert!(!entry.validate());
     }
+
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    /// Heap allocations made by the current thread while running `f`.
+    fn allocations_during(f: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.with(Cell::get);
+        f();
+        ALLOCATIONS.with(Cell::get) - before
+    }
+
+    #[test]
+    fn inline_edges_do_not_allocate() {
+        let mut entry = make_entry(8, 0);
+        let edge = Edge {
+            edge_type: crate::types::EdgeType::RelatedTo,
+            target: BankRef {
+                bank: BankId::from_raw(2),
+                entry: EntryId::from_raw(100),
+            },
+            weight: 200,
+            created_tick: 0,
+        };
+
+        let inline = allocations_during(|| {
+            for _ in 0..INLINE_EDGES {
+                entry.add_edge(edge, 32).unwrap();
+            }
+        });
+        assert_eq!(inline, 0);
+        assert!(!entry.edges.spilled());
+
+        let spill = allocations_during(|| entry.add_edge(edge, 32).unwrap());
+        assert_eq!(spill, 1);
+        assert!(entry.edges.spilled());
+        assert_eq!(entry.edges.len(), INLINE_EDGES + 1);
+    }
 }
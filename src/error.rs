@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::types::{BankId, EntryId};
 
 /// All errors that can occur in databank operations.
@@ -7,10 +8,26 @@ pub enum DataBankError {
     #[error("vector width mismatch: bank expects {expected}, got {got}")]
     VectorWidthMismatch { expected: u16, got: u16 },
 
+    /// A cross-bank operation pairs banks of different vector widths.
+    ///
+    /// Raised up front, before anything is mutated, so callers can tell a
+    /// conceptually invalid operation from one malformed vector.
+    #[error("bank {bank:?} has vector width {got}, operation expects {expected}")]
+    WidthIncompatible { expected: u16, got: u16, bank: BankId },
+
     /// Bank has reached its maximum entry capacity.
     #[error("bank is full (capacity: {capacity})")]
     BankFull { capacity: u32 },
 
+    /// The bank's `max_bytes` budget cannot fit the new entry, even after
+    /// evicting every evictable entry.
+    #[error("bank over byte budget: {used} of {max_bytes} bytes used, entry needs {needed}")]
+    BankOverBudget {
+        max_bytes: usize,
+        used: usize,
+        needed: usize,
+    },
+
     /// Requested entry does not exist in the bank.
     #[error("entry not found: {id:?}")]
     EntryNotFound { id: EntryId },
@@ -24,17 +41,558 @@ pub enum DataBankError {
     BankNotFound { id: BankId },
 
     /// File I/O error during persistence.
+    #[cfg(feature = "std")]
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
     /// Binary format error (bad magic, truncated, invalid structure).
     #[error("codec error: {0}")]
-    Codec(String),
+    Codec(CodecError),
+
+    /// Delta-journal failure. The `.bank` snapshots are unaffected.
+    #[cfg(feature = "std")]
+    #[error("journal error: {0}")]
+    Journal(JournalError),
+
+    /// A single entry's vector no longer matches its stored CRC32.
+    #[error("entry {entry:?} in bank {bank:?} corrupted: crc expected {expected:#010x}, got {actual:#010x}")]
+    EntryCorrupted {
+        bank: BankId,
+        entry: EntryId,
+        expected: u32,
+        actual: u32,
+    },
+
+    /// A single entry's edges or lifecycle metadata no longer match its
+    /// stored metadata CRC32.
+    #[error("entry {entry:?} in bank {bank:?} metadata corrupted: crc expected {expected:#010x}, got {actual:#010x}")]
+    EntryMetadataCorrupted {
+        bank: BankId,
+        entry: EntryId,
+        expected: u32,
+        actual: u32,
+    },
+
+    /// Bank configuration is internally inconsistent.
+    #[error("invalid bank config: {0}")]
+    InvalidConfig(String),
+
+    /// Text could not be parsed into an identifier or enum value.
+    #[error("cannot parse {what} from {input:?}")]
+    Parse { what: &'static str, input: String },
 
     /// Checksum verification failed after decode.
     #[error("checksum mismatch: expected {expected:#018x}, got {actual:#018x}")]
     ChecksumMismatch { expected: u64, actual: u64 },
+
+    /// A DomainOp referenced a bank slot with no bound BankId.
+    #[error("bank slot {slot} not bound")]
+    SlotNotBound { slot: u8 },
+
+    /// A DomainOp's register operands were malformed.
+    #[error("invalid operand: {0}")]
+    InvalidOperand(String),
+
+    /// A bank name is already bound to a different bank in the cluster.
+    #[error("bank name {name:?} is already in use")]
+    DuplicateBankName { name: String },
+
+    /// The entry is leased by another owner.
+    #[error("entry {id} is leased by owner {owner}")]
+    EntryLeased { id: EntryId, owner: u32 },
+}
+
+/// What went wrong while decoding a `.bank` buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum CodecErrorKind {
+    /// Magic bytes are not `BANK`.
+    BadMagic = 1,
+    /// Format version is not supported by this reader.
+    UnsupportedVersion = 2,
+    /// Buffer is shorter than the header or its declared total size.
+    Truncated = 3,
+    /// A byte does not map to a known enum value (edge type, temperature).
+    InvalidEnum = 4,
+    /// A length-prefixed string is not valid UTF-8.
+    BadString = 5,
+    /// A length or count field points past the end of the data.
+    SectionOverrun = 6,
+    /// Header flags contain bits this reader does not understand.
+    UnsupportedFlags = 7,
+    /// An entry's vector width disagrees with the bank header.
+    WidthMismatch = 8,
+}
+
+/// A decode failure with its location in the buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecError {
+    pub kind: CodecErrorKind,
+    /// Byte offset where the failing read started.
+    pub offset: usize,
+    /// Zero-based index of the entry being decoded, if inside the entry section.
+    pub entry_index: Option<usize>,
+    /// Human-readable detail.
+    pub context: String,
+}
+
+impl CodecError {
+    pub fn new(kind: CodecErrorKind, offset: usize, context: impl Into<String>) -> Self {
+        Self {
+            kind,
+            offset,
+            entry_index: None,
+            context: context.into(),
+        }
+    }
+}
+
+impl core::fmt::Display for CodecError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?} at offset {}", self.kind, self.offset)?;
+        if let Some(index) = self.entry_index {
+            write!(f, " (entry {index})")?;
+        }
+        write!(f, ": {}", self.context)
+    }
+}
+
+impl core::error::Error for CodecError {}
+
+/// What went wrong with the delta journal.
+///
+/// Kept apart from [`CodecError`] so recovery code can tell "the snapshot is
+/// fine but the journal is damaged" from "the snapshot itself is bad".
+#[cfg(feature = "std")]
+#[derive(Debug, thiserror::Error)]
+pub enum JournalError {
+    /// The journal file could not be opened for reading or appending.
+    #[error("cannot open journal: {0}")]
+    OpenFailed(#[source] std::io::Error),
+    /// A record failed to decode (bad CRC, unknown tag, or truncated).
+    /// Records before `offset` were recovered; everything after is lost.
+    #[error("corrupt journal record at byte {offset}")]
+    CorruptRecord { offset: usize },
+    /// Replay skipped records whose bank or entry no longer exists.
+    #[error("{count} journal records skipped during replay")]
+    ReplaySkipped { count: usize },
+    /// Replay refused `Insert` records whose vector does not match the
+    /// entry checksum their producer journaled with them.
+    #[error("{count} journal inserts failed their entry checksum")]
+    ReplayChecksumMismatch { count: usize },
+    /// Appending to or flushing the journal failed.
+    #[error("journal sync failed: {0}")]
+    SyncFailed(#[source] std::io::Error),
+}
+
+#[cfg(feature = "std")]
+impl JournalError {
+    /// Sub-code (low byte of an `ErrorCode::Journal` code).
+    pub fn sub_code(&self) -> u8 {
+        match self {
+            Self::OpenFailed(_) => 1,
+            Self::CorruptRecord { .. } => 2,
+            Self::ReplaySkipped { .. } => 3,
+            Self::SyncFailed(_) => 4,
+            Self::ReplayChecksumMismatch { .. } => 5,
+        }
+    }
+
+    /// `CorruptRecord`, `ReplaySkipped` and `ReplayChecksumMismatch` are
+    /// Degraded: the snapshots are intact and only journaled mutations were
+    /// lost. `OpenFailed` and `SyncFailed` are Fatal: durability can no
+    /// longer be guaranteed.
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            Self::CorruptRecord { .. }
+            | Self::ReplaySkipped { .. }
+            | Self::ReplayChecksumMismatch { .. } => ErrorSeverity::Degraded,
+            Self::OpenFailed(_) | Self::SyncFailed(_) => ErrorSeverity::Fatal,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<JournalError> for DataBankError {
+    fn from(e: JournalError) -> Self {
+        Self::Journal(e)
+    }
+}
+
+/// How kernel policy code should react to an error.
+///
+/// Ordered from least to most severe, so the worst of several errors is
+/// their `max()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorSeverity {
+    /// The operation failed but all state is intact. Retry, pick another
+    /// target, or ignore.
+    Recoverable,
+    /// Some data was lost or skipped, but what remains is consistent.
+    /// Continue, and surface a warning.
+    Degraded,
+    /// State cannot be trusted or persisted. Abort the current load/flush.
+    Fatal,
+}
+
+/// Stable error categories for FFI and firmware status registers.
+///
+/// A full error code is `category << 8 | sub_code`. The category values
+/// below are part of the public contract and must never be renumbered;
+/// new categories take the next unused value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum ErrorCode {
+    VectorWidthMismatch = 0x0100,
+    BankFull = 0x0200,
+    EntryNotFound = 0x0300,
+    EdgeLimitReached = 0x0400,
+    BankNotFound = 0x0500,
+    Io = 0x0600,
+    Codec = 0x0700,
+    InvalidConfig = 0x0800,
+    Parse = 0x0900,
+    ChecksumMismatch = 0x0A00,
+    SlotNotBound = 0x0B00,
+    InvalidOperand = 0x0C00,
+    Journal = 0x0D00,
+    EntryCorrupted = 0x0E00,
+    DuplicateBankName = 0x0F00,
+    EntryLeased = 0x1000,
+}
+
+impl ErrorCode {
+    /// Every category, in code order.
+    pub const ALL: [Self; 16] = [
+        Self::VectorWidthMismatch,
+        Self::BankFull,
+        Self::EntryNotFound,
+        Self::EdgeLimitReached,
+        Self::BankNotFound,
+        Self::Io,
+        Self::Codec,
+        Self::InvalidConfig,
+        Self::Parse,
+        Self::ChecksumMismatch,
+        Self::SlotNotBound,
+        Self::InvalidOperand,
+        Self::Journal,
+        Self::EntryCorrupted,
+        Self::DuplicateBankName,
+        Self::EntryLeased,
+    ];
+
+    /// Recover the category from a full code (sub-code is ignored).
+    pub fn from_code(code: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| *c as u16 == code & 0xFF00)
+    }
+}
+
+/// I/O sub-codes (low byte of an `ErrorCode::Io` code).
+pub mod io_sub_code {
+    pub const OTHER: u8 = 0x00;
+    pub const NOT_FOUND: u8 = 0x01;
+    pub const PERMISSION_DENIED: u8 = 0x02;
+    pub const ALREADY_EXISTS: u8 = 0x03;
+    pub const UNEXPECTED_EOF: u8 = 0x04;
+    pub const WRITE_ZERO: u8 = 0x05;
+    pub const INTERRUPTED: u8 = 0x06;
+}
+
+impl DataBankError {
+    /// Category of this error.
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            Self::VectorWidthMismatch { .. } | Self::WidthIncompatible { .. } => {
+                ErrorCode::VectorWidthMismatch
+            }
+            Self::BankFull { .. } | Self::BankOverBudget { .. } => ErrorCode::BankFull,
+            Self::EntryNotFound { .. } => ErrorCode::EntryNotFound,
+            Self::EdgeLimitReached { .. } => ErrorCode::EdgeLimitReached,
+            Self::BankNotFound { .. } => ErrorCode::BankNotFound,
+            #[cfg(feature = "std")]
+            Self::Io(_) => ErrorCode::Io,
+            Self::Codec(_) => ErrorCode::Codec,
+            Self::InvalidConfig(_) => ErrorCode::InvalidConfig,
+            Self::Parse { .. } => ErrorCode::Parse,
+            Self::ChecksumMismatch { .. } => ErrorCode::ChecksumMismatch,
+            Self::SlotNotBound { .. } => ErrorCode::SlotNotBound,
+            Self::InvalidOperand(_) => ErrorCode::InvalidOperand,
+            #[cfg(feature = "std")]
+            Self::Journal(_) => ErrorCode::Journal,
+            Self::EntryCorrupted { .. } | Self::EntryMetadataCorrupted { .. } => {
+                ErrorCode::EntryCorrupted
+            }
+            Self::DuplicateBankName { .. } => ErrorCode::DuplicateBankName,
+            Self::EntryLeased { .. } => ErrorCode::EntryLeased,
+        }
+    }
+
+    /// Stable numeric code: `category << 8 | sub_code`.
+    ///
+    /// I/O errors carry an [`io_sub_code`] derived from their `ErrorKind`;
+    /// codec errors carry their [`CodecErrorKind`] discriminant; journal
+    /// errors carry [`JournalError::sub_code`]; `WidthIncompatible` is
+    /// sub-code 1 of `VectorWidthMismatch`, `BankOverBudget` is sub-code 1
+    /// of `BankFull` and `EntryMetadataCorrupted` is sub-code 1 of
+    /// `EntryCorrupted`; all other variants use sub-code 0.
+    pub fn code(&self) -> u16 {
+        let sub = match self {
+            #[cfg(feature = "std")]
+            Self::Io(e) => io_kind_sub_code(e.kind()),
+            Self::Codec(e) => e.kind as u8,
+            #[cfg(feature = "std")]
+            Self::Journal(e) => e.sub_code(),
+            Self::WidthIncompatible { .. }
+            | Self::BankOverBudget { .. }
+            | Self::EntryMetadataCorrupted { .. } => 1,
+            _ => 0,
+        };
+        self.error_code() as u16 | sub as u16
+    }
+
+    /// How serious this error is for the caller.
+    ///
+    /// - `VectorWidthMismatch`, `WidthIncompatible`, `EntryNotFound`,
+    ///   `EdgeLimitReached`, `BankNotFound`, `Parse`, `SlotNotBound`,
+    ///   `InvalidOperand`, `DuplicateBankName`, `EntryLeased`: Recoverable --
+    ///   the request was rejected, nothing changed.
+    /// - `BankFull`, `BankOverBudget`: Recoverable -- only raised after
+    ///   eviction was attempted; evict explicitly or retry later.
+    /// - `Journal`: see [`JournalError::severity`] -- a damaged journal tail
+    ///   or skipped records are Degraded, failing to open or sync is Fatal.
+    /// - `EntryCorrupted`, `EntryMetadataCorrupted`: Degraded -- one entry
+    ///   is bad; drop or quarantine it and keep the rest of the bank.
+    /// - `Io`, `Codec` (bad magic, unsupported version, truncation, ...),
+    ///   `ChecksumMismatch` (whole-snapshot checksum), `InvalidConfig`:
+    ///   Fatal.
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            Self::VectorWidthMismatch { .. }
+            | Self::WidthIncompatible { .. }
+            | Self::BankFull { .. }
+            | Self::BankOverBudget { .. }
+            | Self::EntryNotFound { .. }
+            | Self::EdgeLimitReached { .. }
+            | Self::BankNotFound { .. }
+            | Self::Parse { .. }
+            | Self::SlotNotBound { .. }
+            | Self::InvalidOperand(_)
+            | Self::DuplicateBankName { .. }
+            | Self::EntryLeased { .. } => ErrorSeverity::Recoverable,
+            #[cfg(feature = "std")]
+            Self::Journal(e) => e.severity(),
+            Self::EntryCorrupted { .. } | Self::EntryMetadataCorrupted { .. } => {
+                ErrorSeverity::Degraded
+            }
+            #[cfg(feature = "std")]
+            Self::Io(_) => ErrorSeverity::Fatal,
+            Self::Codec(_) | Self::ChecksumMismatch { .. } | Self::InvalidConfig(_) => {
+                ErrorSeverity::Fatal
+            }
+        }
+    }
+
+    /// Recover the category from a code produced by [`DataBankError::code`].
+    pub fn from_code(code: u16) -> Option<ErrorCode> {
+        ErrorCode::from_code(code)
+    }
+}
+
+#[cfg(feature = "std")]
+fn io_kind_sub_code(kind: std::io::ErrorKind) -> u8 {
+    use std::io::ErrorKind;
+    match kind {
+        ErrorKind::NotFound => io_sub_code::NOT_FOUND,
+        ErrorKind::PermissionDenied => io_sub_code::PERMISSION_DENIED,
+        ErrorKind::AlreadyExists => io_sub_code::ALREADY_EXISTS,
+        ErrorKind::UnexpectedEof => io_sub_code::UNEXPECTED_EOF,
+        ErrorKind::WriteZero => io_sub_code::WRITE_ZERO,
+        ErrorKind::Interrupted => io_sub_code::INTERRUPTED,
+        _ => io_sub_code::OTHER,
+    }
 }
 
 /// Convenience alias for databank results.
-pub type Result<T> = std::result::Result<T, DataBankError>;
+pub type Result<T> = core::result::Result<T, DataBankError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes_are_stable() {
+        // These values are a public contract. Do not change them.
+        let cases: Vec<(DataBankError, u16)> = vec![
+            (DataBankError::VectorWidthMismatch { expected: 4, got: 8 }, 0x0100),
+            (
+                DataBankError::WidthIncompatible { expected: 4, got: 8, bank: BankId(1) },
+                0x0101,
+            ),
+            (DataBankError::BankFull { capacity: 1 }, 0x0200),
+            (DataBankError::BankOverBudget { max_bytes: 1, used: 1, needed: 1 }, 0x0201),
+            (DataBankError::EntryNotFound { id: EntryId(1) }, 0x0300),
+            (DataBankError::EdgeLimitReached { max: 1 }, 0x0400),
+            (DataBankError::BankNotFound { id: BankId(1) }, 0x0500),
+            (DataBankError::Io(std::io::Error::other("x")), 0x0600),
+            (
+                DataBankError::Io(std::io::Error::from(std::io::ErrorKind::NotFound)),
+                0x0601,
+            ),
+            (
+                DataBankError::Io(std::io::Error::from(std::io::ErrorKind::PermissionDenied)),
+                0x0602,
+            ),
+            (
+                DataBankError::Codec(CodecError::new(CodecErrorKind::BadMagic, 0, "x")),
+                0x0701,
+            ),
+            (
+                DataBankError::Codec(CodecError::new(CodecErrorKind::WidthMismatch, 0, "x")),
+                0x0708,
+            ),
+            (DataBankError::InvalidConfig("x".into()), 0x0800),
+            (DataBankError::Parse { what: "x", input: String::new() }, 0x0900),
+            (DataBankError::ChecksumMismatch { expected: 0, actual: 1 }, 0x0A00),
+            (DataBankError::SlotNotBound { slot: 3 }, 0x0B00),
+            (DataBankError::InvalidOperand("x".into()), 0x0C00),
+            (DataBankError::DuplicateBankName { name: "x".into() }, 0x0F00),
+            (DataBankError::EntryLeased { id: EntryId(1), owner: 2 }, 0x1000),
+            (
+                DataBankError::Journal(JournalError::OpenFailed(std::io::Error::other("x"))),
+                0x0D01,
+            ),
+            (
+                DataBankError::Journal(JournalError::CorruptRecord { offset: 9 }),
+                0x0D02,
+            ),
+            (
+                DataBankError::Journal(JournalError::ReplaySkipped { count: 2 }),
+                0x0D03,
+            ),
+            (
+                DataBankError::Journal(JournalError::SyncFailed(std::io::Error::other("x"))),
+                0x0D04,
+            ),
+            (
+                DataBankError::Journal(JournalError::ReplayChecksumMismatch { count: 1 }),
+                0x0D05,
+            ),
+            (
+                DataBankError::EntryCorrupted {
+                    bank: BankId(1),
+                    entry: EntryId(2),
+                    expected: 3,
+                    actual: 4,
+                },
+                0x0E00,
+            ),
+            (
+                DataBankError::EntryMetadataCorrupted {
+                    bank: BankId(1),
+                    entry: EntryId(2),
+                    expected: 3,
+                    actual: 4,
+                },
+                0x0E01,
+            ),
+        ];
+        for (err, code) in cases {
+            assert_eq!(err.code(), code, "{err}");
+            assert_eq!(DataBankError::from_code(code), Some(err.error_code()));
+        }
+    }
+
+    #[test]
+    fn severity_classification() {
+        use ErrorSeverity::*;
+        let cases: Vec<(DataBankError, ErrorSeverity)> = vec![
+            (DataBankError::VectorWidthMismatch { expected: 4, got: 8 }, Recoverable),
+            (
+                DataBankError::WidthIncompatible { expected: 4, got: 8, bank: BankId(1) },
+                Recoverable,
+            ),
+            (DataBankError::BankFull { capacity: 1 }, Recoverable),
+            (DataBankError::BankOverBudget { max_bytes: 1, used: 1, needed: 1 }, Recoverable),
+            (DataBankError::EntryNotFound { id: EntryId(1) }, Recoverable),
+            (DataBankError::EdgeLimitReached { max: 1 }, Recoverable),
+            (DataBankError::BankNotFound { id: BankId(1) }, Recoverable),
+            (DataBankError::Parse { what: "x", input: String::new() }, Recoverable),
+            (DataBankError::SlotNotBound { slot: 3 }, Recoverable),
+            (DataBankError::InvalidOperand("x".into()), Recoverable),
+            (DataBankError::DuplicateBankName { name: "x".into() }, Recoverable),
+            (DataBankError::EntryLeased { id: EntryId(1), owner: 2 }, Recoverable),
+            (
+                DataBankError::Journal(JournalError::CorruptRecord { offset: 9 }),
+                Degraded,
+            ),
+            (
+                DataBankError::Journal(JournalError::ReplaySkipped { count: 2 }),
+                Degraded,
+            ),
+            (
+                DataBankError::Journal(JournalError::ReplayChecksumMismatch { count: 1 }),
+                Degraded,
+            ),
+            (
+                DataBankError::Journal(JournalError::SyncFailed(std::io::Error::other("x"))),
+                Fatal,
+            ),
+            (
+                DataBankError::Journal(JournalError::OpenFailed(std::io::Error::other("x"))),
+                Fatal,
+            ),
+            (
+                DataBankError::EntryCorrupted {
+                    bank: BankId(1),
+                    entry: EntryId(2),
+                    expected: 3,
+                    actual: 4,
+                },
+                Degraded,
+            ),
+            (
+                DataBankError::EntryMetadataCorrupted {
+                    bank: BankId(1),
+                    entry: EntryId(2),
+                    expected: 3,
+                    actual: 4,
+                },
+                Degraded,
+            ),
+            (DataBankError::Io(std::io::Error::other("x")), Fatal),
+            (
+                DataBankError::Codec(CodecError::new(CodecErrorKind::BadMagic, 0, "x")),
+                Fatal,
+            ),
+            (
+                DataBankError::Codec(CodecError::new(CodecErrorKind::UnsupportedVersion, 4, "x")),
+                Fatal,
+            ),
+            (DataBankError::ChecksumMismatch { expected: 0, actual: 1 }, Fatal),
+            (DataBankError::InvalidConfig("x".into()), Fatal),
+        ];
+        for (err, severity) in cases {
+            assert_eq!(err.severity(), severity, "{err}");
+        }
+        assert!(Recoverable < Degraded && Degraded < Fatal);
+    }
+
+    #[test]
+    fn codec_error_display_includes_location() {
+        let mut e = CodecError::new(CodecErrorKind::InvalidEnum, 1234, "invalid edge type: 37");
+        assert_eq!(format!("{e}"), "InvalidEnum at offset 1234: invalid edge type: 37");
+        e.entry_index = Some(5);
+        assert_eq!(
+            format!("{e}"),
+            "InvalidEnum at offset 1234 (entry 5): invalid edge type: 37"
+        );
+    }
+
+    #[test]
+    fn from_code_rejects_unknown_categories() {
+        assert_eq!(ErrorCode::from_code(0x0000), None);
+        assert_eq!(ErrorCode::from_code(0xFF00), None);
+        assert_eq!(ErrorCode::from_code(0x0605), Some(ErrorCode::Io));
+    }
+}
@@ -0,0 +1,423 @@
+//! C ABI
+//!
+//! A small opaque-handle surface for embedding a cluster in non-Rust
+//! kernels, compiled in with the `ffi` feature. The matching header is
+//! `include/databank.h`, generated by cbindgen from `cbindgen.toml`.
+//!
+//! Every function returns `DATABANK_OK` (0) or a stable error code from
+//! [`DataBankError::code`], whose category (`code & 0xFF00`) is an
+//! [`ErrorCode`]; a panic is caught at the boundary and reported as
+//! [`DATABANK_ERR_PANIC`]. The message for the last failure on the
+//! calling thread is available from [`databank_last_error`].
+//!
+//! Vectors and results cross the boundary in the register layout of the
+//! [`bridge`] module: query vectors are signal currents, entry ids are
+//! `(high, low)` pairs, and query results are
+//! `[count, score_0, id_high_0, id_low_0, ...]`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+
+use crate::bridge;
+use crate::cluster::BankCluster;
+use crate::error::{DataBankError, ErrorCode};
+use crate::types::Temperature;
+
+/// Success.
+pub const DATABANK_OK: i32 = 0;
+
+/// A Rust panic was caught at the boundary. The handle should be freed and
+/// not used again.
+pub const DATABANK_ERR_PANIC: i32 = -1;
+
+/// Mask selecting the category of a returned error code, a
+/// `DatabankErrorCode` value. The low byte is a category-specific sub-code.
+pub const DATABANK_ERROR_CATEGORY_MASK: i32 = 0xFF00;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A failure on its way out through the C ABI.
+struct FfiError {
+    code: i32,
+    message: String,
+}
+
+impl FfiError {
+    fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code: code as i32,
+            message: message.into(),
+        }
+    }
+
+    fn invalid(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidOperand, message)
+    }
+}
+
+impl From<DataBankError> for FfiError {
+    fn from(e: DataBankError) -> Self {
+        Self {
+            code: e.code() as i32,
+            message: e.to_string(),
+        }
+    }
+}
+
+fn set_last_error(message: String) {
+    // Interior NULs would truncate the C string; replace them.
+    let message = CString::new(message.replace('\0', "?")).unwrap_or_default();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(message));
+}
+
+/// Run `body`, converting errors and panics to a status code and recording
+/// the message for `databank_last_error`.
+fn guard(body: impl FnOnce() -> Result<(), FfiError>) -> i32 {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => DATABANK_OK,
+        Ok(Err(e)) => {
+            set_last_error(e.message);
+            e.code
+        }
+        Err(_) => {
+            set_last_error("panic inside databank".into());
+            DATABANK_ERR_PANIC
+        }
+    }
+}
+
+/// Borrow a NUL-terminated UTF-8 string argument.
+unsafe fn str_arg<'a>(ptr: *const c_char, what: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::invalid(format!("{what} is null")));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| FfiError::invalid(format!("{what} is not valid UTF-8")))
+}
+
+/// Borrow an `int32_t` array argument. A null pointer is allowed only for
+/// an empty array.
+unsafe fn slice_arg<'a>(ptr: *const i32, len: usize, what: &str) -> Result<&'a [i32], FfiError> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if ptr.is_null() {
+        return Err(FfiError::invalid(format!("{what} is null")));
+    }
+    Ok(std::slice::from_raw_parts(ptr, len))
+}
+
+/// Load every `.bank` file in `dir` into a new cluster.
+///
+/// Returns an owned handle, or null on failure (see
+/// [`databank_last_error`]). Free the handle with
+/// [`databank_cluster_free`].
+///
+/// # Safety
+///
+/// `dir` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn databank_cluster_load_dir(dir: *const c_char) -> *mut BankCluster {
+    let mut handle = std::ptr::null_mut();
+    guard(|| {
+        let dir = str_arg(dir, "dir")?;
+        let cluster = BankCluster::load_all(Path::new(dir))?;
+        handle = Box::into_raw(Box::new(cluster));
+        Ok(())
+    });
+    handle
+}
+
+/// Query the bank named `bank_name` with `query_len` signal currents.
+///
+/// On entry `*out_len` is the capacity of `out_results` in `int32_t`s; on
+/// success it is the number written, `1 + 3 * count`. A buffer of
+/// `1 + 3 * top_k` is always large enough; a smaller one fails with
+/// `InvalidOperand` and `*out_len` set to the required length.
+///
+/// # Safety
+///
+/// `handle` must come from [`databank_cluster_load_dir`] and not be freed.
+/// `bank_name` must be a valid NUL-terminated string, `query` must point to
+/// `query_len` readable values, `out_results` to `*out_len` writable
+/// values, and `out_len` must be valid for reads and writes.
+#[no_mangle]
+pub unsafe extern "C" fn databank_cluster_query(
+    handle: *const BankCluster,
+    bank_name: *const c_char,
+    query: *const i32,
+    query_len: usize,
+    top_k: u32,
+    out_results: *mut i32,
+    out_len: *mut usize,
+) -> i32 {
+    guard(|| {
+        let cluster = handle
+            .as_ref()
+            .ok_or_else(|| FfiError::invalid("handle is null"))?;
+        let name = str_arg(bank_name, "bank_name")?;
+        let query = slice_arg(query, query_len, "query")?;
+        let out_len = out_len
+            .as_mut()
+            .ok_or_else(|| FfiError::invalid("out_len is null"))?;
+        let bank = cluster.get_by_name(name).ok_or_else(|| {
+            FfiError::new(ErrorCode::BankNotFound, format!("no bank named {name:?}"))
+        })?;
+
+        let results = bank.query_lenient(&bridge::i32_to_signals(query), top_k as usize);
+        let packed = bridge::query_results_to_i32(&results);
+        if packed.len() > *out_len {
+            let capacity = *out_len;
+            *out_len = packed.len();
+            return Err(FfiError::invalid(format!(
+                "out_results holds {capacity} values, {} needed",
+                packed.len()
+            )));
+        }
+        if out_results.is_null() {
+            return Err(FfiError::invalid("out_results is null"));
+        }
+        std::ptr::copy_nonoverlapping(packed.as_ptr(), out_results, packed.len());
+        *out_len = packed.len();
+        Ok(())
+    })
+}
+
+/// Insert a vector of `vector_len` signal currents into the bank named
+/// `bank_name` at `temperature` (0 = Hot .. 4 = Set) and `tick`.
+///
+/// On success the new entry id is written to `out_id` as `(high, low)`.
+///
+/// # Safety
+///
+/// `handle` must come from [`databank_cluster_load_dir`] and not be freed
+/// or used concurrently. `bank_name` must be a valid NUL-terminated string,
+/// `vector` must point to `vector_len` readable values, and `out_id` must
+/// be null or point to two writable values.
+#[no_mangle]
+pub unsafe extern "C" fn databank_cluster_write(
+    handle: *mut BankCluster,
+    bank_name: *const c_char,
+    vector: *const i32,
+    vector_len: usize,
+    temperature: u8,
+    tick: u64,
+    out_id: *mut i32,
+) -> i32 {
+    guard(|| {
+        let cluster = handle
+            .as_mut()
+            .ok_or_else(|| FfiError::invalid("handle is null"))?;
+        let name = str_arg(bank_name, "bank_name")?;
+        let vector = slice_arg(vector, vector_len, "vector")?;
+        let temperature = Temperature::from_u8(temperature)
+            .ok_or_else(|| FfiError::invalid(format!("unknown temperature {temperature}")))?;
+        let bank = cluster.get_by_name_mut(name).ok_or_else(|| {
+            FfiError::new(ErrorCode::BankNotFound, format!("no bank named {name:?}"))
+        })?;
+
+        let id = bank.insert(bridge::i32_to_signals(vector), temperature, tick)?;
+        if !out_id.is_null() {
+            let (high, low) = bridge::entry_id_to_i32_pair(id);
+            *out_id = high;
+            *out_id.add(1) = low;
+        }
+        Ok(())
+    })
+}
+
+/// Free a cluster handle. Null is ignored. Unsaved changes are discarded.
+///
+/// # Safety
+///
+/// `handle` must be null or come from [`databank_cluster_load_dir`], and
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn databank_cluster_free(handle: *mut BankCluster) {
+    if !handle.is_null() {
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(handle))));
+    }
+}
+
+/// Message for the most recent failure on the calling thread, or null if
+/// nothing has failed yet. The string stays valid until the next failing
+/// call on the same thread.
+#[no_mangle]
+pub extern "C" fn databank_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bank::DataBank;
+    use crate::codec;
+    use crate::types::{BankConfig, BankId, EntryId};
+    use ternary_signal::Signal;
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    fn last_error() -> String {
+        let ptr = databank_last_error();
+        assert!(!ptr.is_null());
+        unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string()
+    }
+
+    /// A directory holding one saved bank named `ffi.semantic` of width 4.
+    fn saved_cluster() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let config = BankConfig {
+            vector_width: 4,
+            ..BankConfig::default()
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "ffi.semantic".into(), config);
+        bank.insert(vec![Signal::new_raw(-1, 90, 1); 4], Temperature::Warm, 1)
+            .unwrap();
+        codec::save_atomic(&bank, &dir.path().join("ffi.semantic.bank")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn write_then_query_round_trips_through_the_c_abi() {
+        let dir = saved_cluster();
+        let path = c(dir.path().to_str().unwrap());
+        let name = c("ffi.semantic");
+        let vector = bridge::signals_to_i32(&[
+            Signal::new_raw(1, 200, 1),
+            Signal::new_raw(1, 100, 1),
+            Signal::ZERO,
+            Signal::new_raw(-1, 50, 1),
+        ]);
+
+        unsafe {
+            let handle = databank_cluster_load_dir(path.as_ptr());
+            assert!(!handle.is_null());
+
+            let mut id = [0i32; 2];
+            let status = databank_cluster_write(
+                handle,
+                name.as_ptr(),
+                vector.as_ptr(),
+                vector.len(),
+                Temperature::Hot as u8,
+                5,
+                id.as_mut_ptr(),
+            );
+            assert_eq!(status, DATABANK_OK);
+            let written: EntryId = bridge::i32_pair_to_entry_id(id[0], id[1]);
+
+            let mut out = [0i32; 1 + 3 * 2];
+            let mut out_len = out.len();
+            let status = databank_cluster_query(
+                handle,
+                name.as_ptr(),
+                vector.as_ptr(),
+                vector.len(),
+                2,
+                out.as_mut_ptr(),
+                &mut out_len,
+            );
+            assert_eq!(status, DATABANK_OK);
+            assert_eq!(out_len, 7);
+            assert_eq!(out[0], 2);
+            assert_eq!(bridge::i32_pair_to_entry_id(out[2], out[3]), written);
+            assert!(out[1] > out[4], "best match first");
+
+            // Too small a buffer reports the length it needs
+            let mut small = [0i32; 4];
+            let mut small_len = small.len();
+            let status = databank_cluster_query(
+                handle,
+                name.as_ptr(),
+                vector.as_ptr(),
+                vector.len(),
+                2,
+                small.as_mut_ptr(),
+                &mut small_len,
+            );
+            assert_eq!(status, ErrorCode::InvalidOperand as i32);
+            assert_eq!(small_len, 7);
+
+            databank_cluster_free(handle);
+        }
+    }
+
+    #[test]
+    fn failures_return_codes_and_set_last_error() {
+        let dir = saved_cluster();
+        let path = c(dir.path().to_str().unwrap());
+        let vector = [0i32; 4];
+        let mut out = [0i32; 4];
+        let mut out_len = out.len();
+
+        unsafe {
+            let handle = databank_cluster_load_dir(path.as_ptr());
+            let missing = c("ffi.nowhere");
+            let status = databank_cluster_query(
+                handle,
+                missing.as_ptr(),
+                vector.as_ptr(),
+                vector.len(),
+                1,
+                out.as_mut_ptr(),
+                &mut out_len,
+            );
+            assert_eq!(status, ErrorCode::BankNotFound as i32);
+            assert!(last_error().contains("ffi.nowhere"));
+
+            let name = c("ffi.semantic");
+            let status = databank_cluster_write(
+                handle,
+                name.as_ptr(),
+                vector.as_ptr(),
+                3,
+                0,
+                1,
+                std::ptr::null_mut(),
+            );
+            assert_eq!(status, ErrorCode::VectorWidthMismatch as i32);
+
+            let status = databank_cluster_write(
+                std::ptr::null_mut(),
+                name.as_ptr(),
+                vector.as_ptr(),
+                vector.len(),
+                0,
+                1,
+                std::ptr::null_mut(),
+            );
+            assert_eq!(status, ErrorCode::InvalidOperand as i32);
+            assert_eq!(last_error(), "handle is null");
+
+            databank_cluster_free(handle);
+            databank_cluster_free(std::ptr::null_mut());
+
+            // A file where the directory should be
+            let file = c(dir.path().join("ffi.semantic.bank").to_str().unwrap());
+            assert!(databank_cluster_load_dir(file.as_ptr()).is_null());
+            assert!(!last_error().is_empty());
+        }
+    }
+
+    #[test]
+    fn header_matches_error_codes() {
+        let header = include_str!("../include/databank.h");
+        for code in ErrorCode::ALL {
+            let line = format!("DatabankErrorCode_{code:?} = {},", code as u16);
+            assert!(header.contains(&line), "include/databank.h lacks {line}");
+        }
+        let mask = format!("#define DATABANK_ERROR_CATEGORY_MASK {DATABANK_ERROR_CATEGORY_MASK}");
+        assert!(header.contains(&mask));
+    }
+}
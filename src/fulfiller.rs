@@ -4,12 +4,25 @@
 //! DomainOps. Maps per-interpreter bank slots to global BankIds and
 //! converts between register i32 format and Signal vectors.
 
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::bank::LeaseToken;
 use crate::bridge;
 use crate::cluster::BankCluster;
+use crate::error::{DataBankError, ErrorSeverity};
+use crate::prelude::*;
+use crate::similarity::QueryResult;
 use crate::types::{BankId, Edge, EdgeType, EntryId, Temperature};
 
 /// Maps per-interpreter bank_slot (u8) to global BankId.
 /// The kernel initializes this per-region during boot.
+///
+/// Borrow model: fulfillment takes `&BankSlotMap`, and rebinding needs
+/// `&mut`, so the compiler rules out a rebind while a query is resolving
+/// slots. Kernels that rebind from another thread keep an
+/// [`AtomicBankSlotMap`] and hand each tick a [`snapshot`] of it.
+///
+/// [`snapshot`]: AtomicBankSlotMap::snapshot
 #[derive(Clone)]
 pub struct BankSlotMap {
     slots: [Option<BankId>; 256],
@@ -36,6 +49,14 @@ impl BankSlotMap {
     pub fn unbind(&mut self, slot: u8) {
         self.slots[slot as usize] = None;
     }
+
+    /// Lowest slot bound to `bank_id`, if any.
+    pub fn slot_of(&self, bank_id: BankId) -> Option<u8> {
+        self.slots
+            .iter()
+            .position(|s| *s == Some(bank_id))
+            .map(|slot| slot as u8)
+    }
 }
 
 impl Default for BankSlotMap {
@@ -44,6 +65,64 @@ impl Default for BankSlotMap {
     }
 }
 
+/// Raw value of an unbound [`AtomicBankSlotMap`] slot.
+const UNBOUND_SLOT: u64 = u64::MAX;
+
+/// Slot map that can be rebound and resolved concurrently without locks.
+///
+/// Each slot is one `AtomicU64` holding the raw BankId, so a resolve sees
+/// either the old or the new binding, never a mix. `BankId(u64::MAX)` is
+/// reserved to mean "unbound" and cannot be bound.
+pub struct AtomicBankSlotMap {
+    slots: [AtomicU64; 256],
+}
+
+impl AtomicBankSlotMap {
+    pub fn new() -> Self {
+        Self {
+            slots: core::array::from_fn(|_| AtomicU64::new(UNBOUND_SLOT)),
+        }
+    }
+
+    /// Bind a slot index to a global BankId.
+    pub fn bind(&self, slot: u8, bank_id: BankId) {
+        debug_assert_ne!(bank_id.0, UNBOUND_SLOT, "BankId(u64::MAX) is reserved");
+        self.slots[slot as usize].store(bank_id.0, Ordering::Release);
+    }
+
+    /// Resolve a slot index to a global BankId.
+    pub fn resolve(&self, slot: u8) -> Option<BankId> {
+        match self.slots[slot as usize].load(Ordering::Acquire) {
+            UNBOUND_SLOT => None,
+            raw => Some(BankId(raw)),
+        }
+    }
+
+    /// Unbind a slot.
+    pub fn unbind(&self, slot: u8) {
+        self.slots[slot as usize].store(UNBOUND_SLOT, Ordering::Release);
+    }
+
+    /// Copy the current bindings into a plain [`BankSlotMap`] for the
+    /// fulfiller. Each slot is read atomically; bindings changed during the
+    /// copy may or may not be included.
+    pub fn snapshot(&self) -> BankSlotMap {
+        let mut map = BankSlotMap::new();
+        for slot in 0..=255u8 {
+            if let Some(id) = self.resolve(slot) {
+                map.bind(slot, id);
+            }
+        }
+        map
+    }
+}
+
+impl Default for AtomicBankSlotMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Result of fulfilling a bank DomainOp.
 #[derive(Debug, Clone)]
 pub enum FulfillResult {
@@ -55,8 +134,51 @@ pub enum FulfillResult {
     },
     /// No register output (write-only ops like BankLink, BankTouch, BankDelete).
     Ok,
-    /// Error during fulfillment.
-    Error(String),
+    /// Error during fulfillment. `code` is the stable
+    /// [`DataBankError::code`] value for firmware status registers;
+    /// `severity` is [`DataBankError::severity`]; `detail` is the
+    /// human-readable message.
+    Error {
+        code: u16,
+        severity: ErrorSeverity,
+        detail: String,
+    },
+}
+
+impl FulfillResult {
+    /// Build an Error result from a DataBankError.
+    pub fn error(e: DataBankError) -> Self {
+        FulfillResult::Error {
+            code: e.code(),
+            severity: e.severity(),
+            detail: e.to_string(),
+        }
+    }
+
+    /// Build an Error result for a failed op, prefixing the detail with the op name.
+    pub fn op_error(op: &str, e: DataBankError) -> Self {
+        FulfillResult::Error {
+            code: e.code(),
+            severity: e.severity(),
+            detail: format!("{op} failed: {e}"),
+        }
+    }
+
+    /// The error code, if this is an Error result.
+    pub fn error_code(&self) -> Option<u16> {
+        match self {
+            FulfillResult::Error { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// The error severity, if this is an Error result.
+    pub fn severity(&self) -> Option<ErrorSeverity> {
+        match self {
+            FulfillResult::Error { severity, .. } => Some(*severity),
+            _ => None,
+        }
+    }
 }
 
 /// Stateless fulfiller for bank DomainOps.
@@ -70,19 +192,40 @@ impl BankFulfiller {
         bank_slot: u8,
         source_data: &[i32],
         top_k: u8,
+    ) -> FulfillResult {
+        Self::query_with_scratch(
+            cluster,
+            slot_map,
+            bank_slot,
+            source_data,
+            top_k,
+            &mut Vec::new(),
+        )
+    }
+
+    /// Fulfill a BankQuery DomainOp using `scratch` for the intermediate
+    /// results. The kernel keeps one scratch buffer per tick and passes it to
+    /// every query so result storage is allocated once, not per op.
+    pub fn query_with_scratch(
+        cluster: &BankCluster,
+        slot_map: &BankSlotMap,
+        bank_slot: u8,
+        source_data: &[i32],
+        top_k: u8,
+        scratch: &mut Vec<QueryResult>,
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let bank = match cluster.get(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
 
         let query_signals = bridge::i32_to_signals(source_data);
-        let results = bank.query_sparse(&query_signals, top_k as usize);
-        let packed = bridge::query_results_to_i32(&results);
+        bank.query_sparse_into(&query_signals, top_k as usize, scratch);
+        let packed = bridge::query_results_to_i32(scratch);
         let len = packed.len();
 
         FulfillResult::WriteRegister {
@@ -92,6 +235,50 @@ impl BankFulfiller {
         }
     }
 
+    /// Fulfill a BankQueryContrastive DomainOp.
+    /// source_data: [cue_len, cue..., inhibit...]; the inhibitor is
+    /// everything after the cue. Output is packed like [`query`](Self::query).
+    pub fn query_contrastive(
+        cluster: &BankCluster,
+        slot_map: &BankSlotMap,
+        bank_slot: u8,
+        source_data: &[i32],
+        inhibit_weight: u8,
+        top_k: u8,
+    ) -> FulfillResult {
+        let bank_id = match slot_map.resolve(bank_slot) {
+            Some(id) => id,
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
+        };
+        let bank = match cluster.get(bank_id) {
+            Some(b) => b,
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
+        };
+
+        let (cue, inhibit) = match source_data.split_first() {
+            Some((&cue_len, rest)) if cue_len >= 0 && cue_len as usize <= rest.len() => {
+                rest.split_at(cue_len as usize)
+            }
+            _ => {
+                return FulfillResult::error(DataBankError::InvalidOperand(
+                    "BankQueryContrastive: source must have [cue_len, cue..., inhibit...]"
+                        .into(),
+                ))
+            }
+        };
+        let cue = bridge::i32_to_signals(cue);
+        let inhibit = bridge::i32_to_signals(inhibit);
+        let results = bank.query_contrastive(&cue, &inhibit, inhibit_weight, top_k as usize);
+        let packed = bridge::query_results_to_i32(&results);
+        let len = packed.len();
+
+        FulfillResult::WriteRegister {
+            register_index: 0,
+            data: packed,
+            shape: vec![len],
+        }
+    }
+
     /// Fulfill a BankWrite DomainOp.
     pub fn write(
         cluster: &mut BankCluster,
@@ -103,11 +290,11 @@ impl BankFulfiller {
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let bank = match cluster.get_mut(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
 
         let vector = bridge::i32_to_signals(source_data);
@@ -120,7 +307,7 @@ impl BankFulfiller {
                     shape: vec![2],
                 }
             }
-            Err(e) => FulfillResult::Error(format!("BankWrite failed: {}", e)),
+            Err(e) => FulfillResult::op_error("BankWrite", e),
         }
     }
 
@@ -133,15 +320,17 @@ impl BankFulfiller {
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let bank = match cluster.get(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
 
         if source_data.len() < 2 {
-            return FulfillResult::Error("BankLoad: source must have [id_high, id_low]".into());
+            return FulfillResult::error(DataBankError::InvalidOperand(
+                "BankLoad: source must have [id_high, id_low]".into(),
+            ));
         }
         let entry_id = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
         match bank.get(entry_id) {
@@ -154,7 +343,7 @@ impl BankFulfiller {
                     shape: vec![len],
                 }
             }
-            None => FulfillResult::Error(format!("Entry {:?} not found", entry_id)),
+            None => FulfillResult::error(DataBankError::EntryNotFound { id: entry_id }),
         }
     }
 
@@ -168,23 +357,21 @@ impl BankFulfiller {
         tick: u64,
     ) -> FulfillResult {
         if source_data.len() < 6 {
-            return FulfillResult::Error(
+            return FulfillResult::error(DataBankError::InvalidOperand(
                 "BankLink: source must have [from_hi, from_lo, to_slot, to_hi, to_lo, weight]"
                     .into(),
-            );
+            ));
         }
 
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let from_entry = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
         let to_slot = source_data[2] as u8;
         let to_bank_id = match slot_map.resolve(to_slot) {
             Some(id) => id,
-            None => {
-                return FulfillResult::Error(format!("Target bank slot {} not bound", to_slot))
-            }
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: to_slot }),
         };
         let to_entry = bridge::i32_pair_to_entry_id(source_data[3], source_data[4]);
         let weight = source_data[5].clamp(0, 255) as u8;
@@ -202,12 +389,66 @@ impl BankFulfiller {
 
         let bank = match cluster.get_mut(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
 
         match bank.add_edge(from_entry, edge) {
             Ok(()) => FulfillResult::Ok,
-            Err(e) => FulfillResult::Error(format!("BankLink failed: {}", e)),
+            Err(e) => FulfillResult::op_error("BankLink", e),
+        }
+    }
+
+    /// Fulfill a BankLink DomainOp whose target is a full BankId rather
+    /// than a local slot.
+    ///
+    /// Only the source slot must be bound. The target bank may live in
+    /// another region or process, matching [`BankCluster::link`]; the edge
+    /// is stored either way, it just won't be traversable locally until the
+    /// target bank is present.
+    pub fn link_absolute(
+        cluster: &mut BankCluster,
+        slot_map: &BankSlotMap,
+        bank_slot: u8,
+        source_data: &[i32],
+        edge_type: u8,
+        tick: u64,
+    ) -> FulfillResult {
+        if source_data.len() < 7 {
+            return FulfillResult::error(DataBankError::InvalidOperand(
+                "BankLinkAbsolute: source must have \
+                 [from_hi, from_lo, to_bank_hi, to_bank_lo, to_hi, to_lo, weight]"
+                    .into(),
+            ));
+        }
+
+        let bank_id = match slot_map.resolve(bank_slot) {
+            Some(id) => id,
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
+        };
+        let from_entry = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
+        let to_bank_id = bridge::i32_pair_to_bank_id(source_data[2], source_data[3]);
+        let to_entry = bridge::i32_pair_to_entry_id(source_data[4], source_data[5]);
+        let weight = source_data[6].clamp(0, 255) as u8;
+
+        let et = EdgeType::from_u8(edge_type).unwrap_or(EdgeType::RelatedTo);
+        let edge = Edge {
+            edge_type: et,
+            target: crate::types::BankRef {
+                bank: to_bank_id,
+                entry: to_entry,
+            },
+            weight,
+            created_tick: tick,
+        };
+
+        let bank = match cluster.get_mut(bank_id) {
+            Some(b) => b,
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
+        };
+
+        match bank.add_edge(from_entry, edge) {
+            Ok(()) => FulfillResult::Ok,
+            Err(e) => FulfillResult::op_error("BankLinkAbsolute", e),
         }
     }
 
@@ -222,13 +463,13 @@ impl BankFulfiller {
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
 
         if source_data.len() < 2 {
-            return FulfillResult::Error(
+            return FulfillResult::error(DataBankError::InvalidOperand(
                 "BankTraverse: source must have [id_high, id_low]".into(),
-            );
+            ));
         }
         let entry_id = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
         let et = EdgeType::from_u8(edge_type).unwrap_or(EdgeType::RelatedTo);
@@ -239,22 +480,12 @@ impl BankFulfiller {
         };
         let refs = cluster.traverse(start, et, depth as usize);
 
-        // Convert BankRefs to (slot, EntryId) pairs using reverse slot lookup
-        let mut results: Vec<(u8, EntryId)> = Vec::new();
-        for bref in &refs {
-            // Find the slot for this BankId
-            let mut found_slot = None;
-            for s in 0..=255u8 {
-                if slot_map.resolve(s) == Some(bref.bank) {
-                    found_slot = Some(s);
-                    break;
-                }
-            }
-            if let Some(s) = found_slot {
-                results.push((s, bref.entry));
-            }
-            // Skip refs whose banks aren't in the slot map
-        }
+        // Convert BankRefs to (slot, EntryId) pairs using reverse slot lookup,
+        // skipping refs whose banks aren't in the slot map
+        let results: Vec<(u8, EntryId)> = refs
+            .iter()
+            .filter_map(|bref| Some((slot_map.slot_of(bref.bank)?, bref.entry)))
+            .collect();
 
         let packed = bridge::traverse_results_to_i32(&results);
         let len = packed.len();
@@ -265,6 +496,53 @@ impl BankFulfiller {
         }
     }
 
+    /// Fulfill BankTraverseDetailed: like [`traverse`](Self::traverse), but
+    /// each result also carries its hop distance and the weight of the edge
+    /// it was reached through, so firmware can tell a strong direct
+    /// association from a weak chain. Output layout is
+    /// [`bridge::traverse_results_detailed_to_i32`].
+    pub fn traverse_detailed(
+        cluster: &BankCluster,
+        slot_map: &BankSlotMap,
+        bank_slot: u8,
+        source_data: &[i32],
+        edge_type: u8,
+        depth: u8,
+    ) -> FulfillResult {
+        let bank_id = match slot_map.resolve(bank_slot) {
+            Some(id) => id,
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
+        };
+
+        if source_data.len() < 2 {
+            return FulfillResult::error(DataBankError::InvalidOperand(
+                "BankTraverseDetailed: source must have [id_high, id_low]".into(),
+            ));
+        }
+        let entry_id = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
+        let et = EdgeType::from_u8(edge_type).unwrap_or(EdgeType::RelatedTo);
+
+        let start = crate::types::BankRef {
+            bank: bank_id,
+            entry: entry_id,
+        };
+        let results: Vec<(u8, EntryId, u8, u8)> = cluster
+            .traverse_detailed(start, et, depth as usize)
+            .iter()
+            .filter_map(|&(bref, hop, weight)| {
+                Some((slot_map.slot_of(bref.bank)?, bref.entry, hop, weight))
+            })
+            .collect();
+
+        let packed = bridge::traverse_results_detailed_to_i32(&results);
+        let len = packed.len();
+        FulfillResult::WriteRegister {
+            register_index: 0,
+            data: packed,
+            shape: vec![len],
+        }
+    }
+
     /// Fulfill a BankTouch DomainOp.
     pub fn touch(
         cluster: &mut BankCluster,
@@ -275,49 +553,130 @@ impl BankFulfiller {
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let bank = match cluster.get_mut(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
 
         if source_data.len() < 2 {
-            return FulfillResult::Error("BankTouch: source must have [id_high, id_low]".into());
+            return FulfillResult::error(DataBankError::InvalidOperand(
+                "BankTouch: source must have [id_high, id_low]".into(),
+            ));
         }
         let entry_id = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
-        match bank.get_mut(entry_id) {
-            Some(entry) => {
-                entry.touch(tick);
-                FulfillResult::Ok
-            }
-            None => FulfillResult::Error(format!("Entry {:?} not found", entry_id)),
+        match bank.touch_entry(entry_id, tick) {
+            Ok(()) => FulfillResult::Ok,
+            Err(e) => FulfillResult::error(e),
         }
     }
 
-    /// Fulfill a BankDelete DomainOp.
+    /// Fulfill a BankDelete DomainOp on behalf of the calling interpreter.
+    /// Its own lease and expired leases do not block the delete; another
+    /// interpreter's unexpired lease is an `EntryLeased` error, see
+    /// [`DataBank::remove_as`](crate::bank::DataBank::remove_as).
+    /// source_data: [entry_id_high, entry_id_low]
     pub fn delete(
         cluster: &mut BankCluster,
         slot_map: &BankSlotMap,
         bank_slot: u8,
         source_data: &[i32],
+        interpreter_id: u32,
+        current_tick: u64,
+    ) -> FulfillResult {
+        let bank_id = match slot_map.resolve(bank_slot) {
+            Some(id) => id,
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
+        };
+        let bank = match cluster.get_mut(bank_id) {
+            Some(b) => b,
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
+        };
+
+        if source_data.len() < 2 {
+            return FulfillResult::error(DataBankError::InvalidOperand(
+                "BankDelete: source must have [id_high, id_low]".into(),
+            ));
+        }
+        let entry_id = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
+        match bank.remove_as(entry_id, interpreter_id, current_tick) {
+            Ok(_) => FulfillResult::Ok,
+            Err(e) => FulfillResult::error(e),
+        }
+    }
+
+    /// Fulfill BankLease: lease an entry to the calling interpreter for
+    /// `ttl_ticks`, so a load/modify/write-back across DomainOps cannot
+    /// lose the entry to eviction. The interpreter id is the lease owner.
+    /// source_data: [entry_id_high, entry_id_low]
+    pub fn lease(
+        cluster: &mut BankCluster,
+        slot_map: &BankSlotMap,
+        bank_slot: u8,
+        source_data: &[i32],
+        interpreter_id: u32,
+        ttl_ticks: u64,
+        current_tick: u64,
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let bank = match cluster.get_mut(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
+        if source_data.len() < 2 {
+            return FulfillResult::error(DataBankError::InvalidOperand(
+                "BankLease: source must have [id_high, id_low]".into(),
+            ));
+        }
+        let entry_id = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
+        match bank.lease(entry_id, interpreter_id, ttl_ticks, current_tick) {
+            Ok(_) => FulfillResult::Ok,
+            Err(e) => FulfillResult::op_error("BankLease", e),
+        }
+    }
 
+    /// Fulfill BankRelease: release the calling interpreter's lease.
+    /// Releasing an entry nobody holds is a no-op; one held by another
+    /// interpreter is an `EntryLeased` error.
+    /// source_data: [entry_id_high, entry_id_low]
+    pub fn release(
+        cluster: &mut BankCluster,
+        slot_map: &BankSlotMap,
+        bank_slot: u8,
+        source_data: &[i32],
+        interpreter_id: u32,
+    ) -> FulfillResult {
+        let bank_id = match slot_map.resolve(bank_slot) {
+            Some(id) => id,
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
+        };
+        let bank = match cluster.get_mut(bank_id) {
+            Some(b) => b,
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
+        };
         if source_data.len() < 2 {
-            return FulfillResult::Error("BankDelete: source must have [id_high, id_low]".into());
+            return FulfillResult::error(DataBankError::InvalidOperand(
+                "BankRelease: source must have [id_high, id_low]".into(),
+            ));
         }
         let entry_id = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
-        match bank.remove(entry_id) {
-            Some(_) => FulfillResult::Ok,
-            None => FulfillResult::Error(format!("Entry {:?} not found", entry_id)),
+        let token = LeaseToken {
+            entry: entry_id,
+            owner: interpreter_id,
+        };
+        if bank.release(token) {
+            return FulfillResult::Ok;
+        }
+        match bank.lease_holder(entry_id) {
+            Some((owner, _)) => FulfillResult::op_error(
+                "BankRelease",
+                DataBankError::EntryLeased { id: entry_id, owner },
+            ),
+            None => FulfillResult::Ok,
         }
     }
 
@@ -331,19 +690,21 @@ impl BankFulfiller {
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let bank = match cluster.get_mut(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
         if source_data.len() < 2 {
-            return FulfillResult::Error("BankPromote: source must have [id_high, id_low]".into());
+            return FulfillResult::error(DataBankError::InvalidOperand(
+                "BankPromote: source must have [id_high, id_low]".into(),
+            ));
         }
         let entry_id = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
         match bank.promote_entry(entry_id) {
             Ok(_) => FulfillResult::Ok,
-            Err(e) => FulfillResult::Error(format!("BankPromote failed: {}", e)),
+            Err(e) => FulfillResult::op_error("BankPromote", e),
         }
     }
 
@@ -357,19 +718,21 @@ impl BankFulfiller {
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let bank = match cluster.get_mut(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
         if source_data.len() < 2 {
-            return FulfillResult::Error("BankDemote: source must have [id_high, id_low]".into());
+            return FulfillResult::error(DataBankError::InvalidOperand(
+                "BankDemote: source must have [id_high, id_low]".into(),
+            ));
         }
         let entry_id = bridge::i32_pair_to_entry_id(source_data[0], source_data[1]);
         match bank.demote_entry(entry_id) {
             Ok(_) => FulfillResult::Ok,
-            Err(e) => FulfillResult::Error(format!("BankDemote failed: {}", e)),
+            Err(e) => FulfillResult::op_error("BankDemote", e),
         }
     }
 
@@ -383,14 +746,12 @@ impl BankFulfiller {
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
-        let bank = match cluster.get_mut(bank_id) {
-            Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
-        };
-        bank.evict_n(count as usize, current_tick);
-        FulfillResult::Ok
+        match cluster.evict(bank_id, count as usize, current_tick) {
+            Ok(_) => FulfillResult::Ok,
+            Err(e) => FulfillResult::op_error("BankEvict", e),
+        }
     }
 
     /// Fulfill BankCompact: compact bank after eviction.
@@ -401,11 +762,11 @@ impl BankFulfiller {
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let bank = match cluster.get_mut(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
         bank.compact();
         FulfillResult::Ok
@@ -419,11 +780,11 @@ impl BankFulfiller {
     ) -> FulfillResult {
         let bank_id = match slot_map.resolve(bank_slot) {
             Some(id) => id,
-            None => return FulfillResult::Error(format!("Bank slot {} not bound", bank_slot)),
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
         };
         let bank = match cluster.get(bank_id) {
             Some(b) => b,
-            None => return FulfillResult::Error(format!("Bank {:?} not found", bank_id)),
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
         };
 
         FulfillResult::WriteRegister {
@@ -432,11 +793,71 @@ impl BankFulfiller {
             shape: vec![1],
         }
     }
+
+    /// Fulfill a BankCountByTemperature operation.
+    ///
+    /// Returns `[total, hot, warm, cool, cold, capacity]`, capacity 0 for
+    /// unlimited banks. Consolidation firmware reads the hot lane to decide
+    /// when to sleep. `Set` entries count toward the total only; read them
+    /// with [`count_set`](Self::count_set).
+    pub fn count_by_temperature(
+        cluster: &BankCluster,
+        slot_map: &BankSlotMap,
+        bank_slot: u8,
+    ) -> FulfillResult {
+        let bank_id = match slot_map.resolve(bank_slot) {
+            Some(id) => id,
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
+        };
+        let bank = match cluster.get(bank_id) {
+            Some(b) => b,
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
+        };
+
+        let counts = bank.temperature_counts();
+        let lane = |t: Temperature| counts[t as usize] as i32;
+        FulfillResult::WriteRegister {
+            register_index: 0,
+            data: vec![
+                bank.len() as i32,
+                lane(Temperature::Hot),
+                lane(Temperature::Warm),
+                lane(Temperature::Cool),
+                lane(Temperature::Cold),
+                bank.config().capacity().unwrap_or(0) as i32,
+            ],
+            shape: vec![6],
+        }
+    }
+
+    /// Fulfill a BankCountSet operation: the number of `Set` entries, as a
+    /// one-lane register.
+    pub fn count_set(
+        cluster: &BankCluster,
+        slot_map: &BankSlotMap,
+        bank_slot: u8,
+    ) -> FulfillResult {
+        let bank_id = match slot_map.resolve(bank_slot) {
+            Some(id) => id,
+            None => return FulfillResult::error(DataBankError::SlotNotBound { slot: bank_slot }),
+        };
+        let bank = match cluster.get(bank_id) {
+            Some(b) => b,
+            None => return FulfillResult::error(DataBankError::BankNotFound { id: bank_id }),
+        };
+
+        FulfillResult::WriteRegister {
+            register_index: 0,
+            data: vec![bank.count_by_temperature(Temperature::Set) as i32],
+            shape: vec![1],
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::ErrorCode;
     use crate::types::BankConfig;
     use ternary_signal::Signal;
 
@@ -451,7 +872,7 @@ mod tests {
             vector_width: 4,
             ..BankConfig::default()
         };
-        cluster.get_or_create(bank_id, "test.semantic".to_string(), config);
+        cluster.get_or_create(bank_id, "test.semantic".to_string(), config).unwrap();
         let mut slot_map = BankSlotMap::new();
         slot_map.bind(0, bank_id);
         (cluster, slot_map, bank_id)
@@ -486,6 +907,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_count_by_temperature_lanes() {
+        let (mut cluster, slot_map, bank_id) = setup_cluster();
+        let bank = cluster.get_mut(bank_id).unwrap();
+        let temps = [
+            Temperature::Hot,
+            Temperature::Hot,
+            Temperature::Hot,
+            Temperature::Warm,
+            Temperature::Cool,
+            Temperature::Set,
+            Temperature::Cold,
+            Temperature::Cold,
+        ];
+        for (tick, t) in temps.into_iter().enumerate() {
+            bank.insert(vec![make_signal(1, 10, 1); 4], t, tick as u64).unwrap();
+        }
+        let capacity = bank.config().capacity().unwrap() as i32;
+
+        match BankFulfiller::count_by_temperature(&cluster, &slot_map, 0) {
+            FulfillResult::WriteRegister { data, shape, .. } => {
+                assert_eq!(data, [8, 3, 1, 1, 2, capacity]);
+                assert_eq!(shape, [6]);
+            }
+            other => panic!("Expected WriteRegister, got {:?}", other),
+        }
+        match BankFulfiller::count_set(&cluster, &slot_map, 0) {
+            FulfillResult::WriteRegister { data, shape, .. } => {
+                assert_eq!((data, shape), (vec![1], vec![1]));
+            }
+            other => panic!("Expected WriteRegister, got {:?}", other),
+        }
+        assert!(matches!(
+            BankFulfiller::count_by_temperature(&cluster, &slot_map, 9),
+            FulfillResult::Error { .. }
+        ));
+    }
+
     #[test]
     fn test_write_load_roundtrip() {
         let (mut cluster, slot_map, _) = setup_cluster();
@@ -547,6 +1006,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_query_contrastive() {
+        let (mut cluster, slot_map, _) = setup_cluster();
+        let on = make_signal(1, 100, 1);
+        let both = bridge::signals_to_i32(&[on, on, on, on]);
+        let cue_only = bridge::signals_to_i32(&[on, Signal::ZERO, Signal::ZERO, Signal::ZERO]);
+        BankFulfiller::write(&mut cluster, &slot_map, 0, &both, Temperature::Hot, 1);
+        BankFulfiller::write(&mut cluster, &slot_map, 0, &cue_only, Temperature::Hot, 1);
+
+        let cue = bridge::signals_to_i32(&[on, on, Signal::ZERO, Signal::ZERO]);
+        let inhibit = bridge::signals_to_i32(&[Signal::ZERO, Signal::ZERO, on, on]);
+        let mut source = vec![cue.len() as i32];
+        source.extend_from_slice(&cue);
+        source.extend_from_slice(&inhibit);
+
+        let result = BankFulfiller::query_contrastive(&cluster, &slot_map, 0, &source, 255, 2);
+        match result {
+            FulfillResult::WriteRegister { data, .. } => {
+                // [count, score_0, hi_0, lo_0, score_1, ...]: the cue-only
+                // entry outranks the fully suppressed one
+                assert_eq!(data[0], 2);
+                assert!(data[1] > 0);
+                assert_eq!(data[4], 0);
+            }
+            other => panic!("Expected WriteRegister, got {:?}", other),
+        }
+
+        let bad = BankFulfiller::query_contrastive(&cluster, &slot_map, 0, &[9, 1], 255, 2);
+        assert_eq!(bad.error_code(), Some(ErrorCode::InvalidOperand as u16));
+    }
+
     #[test]
     fn test_touch_and_delete() {
         let (mut cluster, slot_map, _) = setup_cluster();
@@ -569,7 +1059,7 @@ mod tests {
         assert!(matches!(touch_result, FulfillResult::Ok));
 
         // Delete
-        let del_result = BankFulfiller::delete(&mut cluster, &slot_map, 0, &entry_data);
+        let del_result = BankFulfiller::delete(&mut cluster, &slot_map, 0, &entry_data, 7, 10);
         assert!(matches!(del_result, FulfillResult::Ok));
 
         // Count should be 0
@@ -604,6 +1094,59 @@ mod tests {
         assert!(matches!(result, FulfillResult::Ok));
     }
 
+    #[test]
+    fn test_lease_and_release() {
+        let (mut cluster, slot_map, _) = setup_cluster();
+        let source = bridge::signals_to_i32(&[make_signal(1, 100, 1); 4]);
+        let entry_data =
+            match BankFulfiller::write(&mut cluster, &slot_map, 0, &source, Temperature::Hot, 1) {
+                FulfillResult::WriteRegister { data, .. } => data,
+                _ => panic!("write failed"),
+            };
+
+        let result = BankFulfiller::lease(&mut cluster, &slot_map, 0, &entry_data, 7, 50, 10);
+        assert!(matches!(result, FulfillResult::Ok));
+        // Another interpreter can neither lease nor delete it
+        let result = BankFulfiller::lease(&mut cluster, &slot_map, 0, &entry_data, 8, 50, 11);
+        assert_eq!(result.error_code(), Some(ErrorCode::EntryLeased as u16));
+        let result = BankFulfiller::delete(&mut cluster, &slot_map, 0, &entry_data, 8, 11);
+        assert_eq!(result.error_code(), Some(ErrorCode::EntryLeased as u16));
+        let result = BankFulfiller::release(&mut cluster, &slot_map, 0, &entry_data, 8);
+        assert_eq!(result.error_code(), Some(ErrorCode::EntryLeased as u16));
+
+        let result = BankFulfiller::release(&mut cluster, &slot_map, 0, &entry_data, 7);
+        assert!(matches!(result, FulfillResult::Ok));
+        let result = BankFulfiller::delete(&mut cluster, &slot_map, 0, &entry_data, 8, 12);
+        assert!(matches!(result, FulfillResult::Ok));
+    }
+
+    #[test]
+    fn test_delete_through_own_and_expired_leases() {
+        let (mut cluster, slot_map, _) = setup_cluster();
+        let mut write = |value| {
+            let source = bridge::signals_to_i32(&[make_signal(1, value, 1); 4]);
+            match BankFulfiller::write(&mut cluster, &slot_map, 0, &source, Temperature::Hot, 1) {
+                FulfillResult::WriteRegister { data, .. } => data,
+                _ => panic!("write failed"),
+            }
+        };
+        let (own, stale) = (write(100), write(50));
+
+        // The holder deletes through its own lease
+        BankFulfiller::lease(&mut cluster, &slot_map, 0, &own, 7, 50, 10);
+        let result = BankFulfiller::delete(&mut cluster, &slot_map, 0, &own, 7, 11);
+        assert!(matches!(result, FulfillResult::Ok));
+        let result = BankFulfiller::delete(&mut cluster, &slot_map, 0, &own, 7, 12);
+        assert_eq!(result.error_code(), Some(ErrorCode::EntryNotFound as u16));
+
+        // An expired lease blocks nobody
+        BankFulfiller::lease(&mut cluster, &slot_map, 0, &stale, 7, 5, 10);
+        let result = BankFulfiller::delete(&mut cluster, &slot_map, 0, &stale, 8, 14);
+        assert_eq!(result.error_code(), Some(ErrorCode::EntryLeased as u16));
+        let result = BankFulfiller::delete(&mut cluster, &slot_map, 0, &stale, 8, 15);
+        assert!(matches!(result, FulfillResult::Ok));
+    }
+
     #[test]
     fn test_evict_and_compact() {
         let (mut cluster, slot_map, _) = setup_cluster();
@@ -636,12 +1179,159 @@ mod tests {
         assert!(matches!(result, FulfillResult::Ok));
     }
 
+    #[test]
+    fn test_link_absolute_to_unslotted_bank() {
+        let (mut cluster, slot_map, bank_id) = setup_cluster();
+        let source = bridge::signals_to_i32(&[
+            make_signal(1, 100, 1),
+            Signal::ZERO,
+            Signal::ZERO,
+            make_signal(-1, 40, 1),
+        ]);
+        let from = match BankFulfiller::write(
+            &mut cluster,
+            &slot_map,
+            0,
+            &source,
+            Temperature::Hot,
+            1,
+        ) {
+            FulfillResult::WriteRegister { data, .. } => data,
+            other => panic!("Expected WriteRegister, got {:?}", other),
+        };
+
+        // A bank that exists nowhere in this cluster or slot map.
+        let remote = BankId::from_raw(0x0000_0001_00AB_CD07);
+        let remote_entry = EntryId(0x1234_5678_9ABC);
+        let (bank_hi, bank_lo) = bridge::bank_id_to_i32_pair(remote);
+        let (to_hi, to_lo) = bridge::entry_id_to_i32_pair(remote_entry);
+        let operands = [from[0], from[1], bank_hi, bank_lo, to_hi, to_lo, 180];
+
+        let result = BankFulfiller::link_absolute(
+            &mut cluster,
+            &slot_map,
+            0,
+            &operands,
+            EdgeType::IsA as u8,
+            2,
+        );
+        assert!(matches!(result, FulfillResult::Ok));
+
+        let from_id = bridge::i32_pair_to_entry_id(from[0], from[1]);
+        let edges = cluster.get(bank_id).unwrap().edges_from(from_id);
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].target.bank, remote);
+        assert_eq!(edges[0].target.entry, remote_entry);
+        assert_eq!(edges[0].weight, 180);
+
+        // Short operands are rejected rather than read out of bounds.
+        let short = BankFulfiller::link_absolute(&mut cluster, &slot_map, 0, &operands[..6], 0, 3);
+        assert!(matches!(short, FulfillResult::Error { .. }));
+    }
+
+    #[test]
+    fn test_traverse_detailed_reports_hops_and_weights() {
+        let mut cluster = BankCluster::new();
+        let mut slot_map = BankSlotMap::new();
+        let config = BankConfig {
+            vector_width: 4,
+            ..BankConfig::default()
+        };
+        // a -> b -> c, one entry per bank, each bank in its own slot
+        let refs: Vec<crate::types::BankRef> = ["a", "b", "c"]
+            .iter()
+            .enumerate()
+            .map(|(slot, name)| {
+                let bank = BankId::from_raw(slot as u64 + 1);
+                slot_map.bind(slot as u8, bank);
+                let entry = cluster
+                    .get_or_create(bank, (*name).into(), config.clone())
+                    .unwrap()
+                    .insert(vec![make_signal(1, 100, 1); 4], Temperature::Hot, 0)
+                    .unwrap();
+                crate::types::BankRef { bank, entry }
+            })
+            .collect();
+        cluster.link(refs[0], refs[1], EdgeType::RelatedTo, 200, 0).unwrap();
+        cluster.link(refs[1], refs[2], EdgeType::RelatedTo, 90, 0).unwrap();
+
+        let (hi, lo) = bridge::entry_id_to_i32_pair(refs[0].entry);
+        let related = EdgeType::RelatedTo as u8;
+        let result =
+            BankFulfiller::traverse_detailed(&cluster, &slot_map, 0, &[hi, lo], related, 3);
+        let data = match result {
+            FulfillResult::WriteRegister { data, .. } => data,
+            other => panic!("Expected WriteRegister, got {:?}", other),
+        };
+        let (b_hi, b_lo) = bridge::entry_id_to_i32_pair(refs[1].entry);
+        let (c_hi, c_lo) = bridge::entry_id_to_i32_pair(refs[2].entry);
+        assert_eq!(data, [2, 1, b_hi, b_lo, 1, 200, 2, c_hi, c_lo, 2, 90]);
+
+        // The plain op keeps its layout
+        match BankFulfiller::traverse(&cluster, &slot_map, 0, &[hi, lo], related, 3) {
+            FulfillResult::WriteRegister { data, .. } => {
+                assert_eq!(data, [2, 1, b_hi, b_lo, 2, c_hi, c_lo])
+            }
+            other => panic!("Expected WriteRegister, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_unbound_slot_error() {
         let cluster = BankCluster::new();
         let slot_map = BankSlotMap::new();
 
         let result = BankFulfiller::count(&cluster, &slot_map, 42);
-        assert!(matches!(result, FulfillResult::Error(_)));
+        assert!(matches!(result, FulfillResult::Error { .. }));
+        assert_eq!(result.error_code(), Some(ErrorCode::SlotNotBound as u16));
+        assert_eq!(result.severity(), Some(ErrorSeverity::Recoverable));
+    }
+
+    #[test]
+    fn atomic_slot_map_has_no_torn_reads() {
+        use std::sync::Arc;
+
+        // Every id written has equal high and low halves, so a torn read
+        // would show up as a mismatch.
+        fn id_for(n: u64) -> BankId {
+            BankId((n << 32) | n)
+        }
+
+        let map = Arc::new(AtomicBankSlotMap::new());
+        let writers: Vec<_> = (0..4u64)
+            .map(|w| {
+                let map = Arc::clone(&map);
+                std::thread::spawn(move || {
+                    for n in 0..10_000u64 {
+                        let slot = (n % 8) as u8;
+                        if n % 5 == 0 {
+                            map.unbind(slot);
+                        } else {
+                            map.bind(slot, id_for(w * 100_000 + n));
+                        }
+                    }
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let map = Arc::clone(&map);
+                std::thread::spawn(move || {
+                    for n in 0..40_000u32 {
+                        if let Some(id) = map.resolve((n % 8) as u8) {
+                            assert_eq!(id.0 >> 32, id.0 & 0xFFFF_FFFF, "torn read: {:#x}", id.0);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in writers.into_iter().chain(readers) {
+            handle.join().unwrap();
+        }
+
+        map.bind(3, id_for(7));
+        let snapshot = map.snapshot();
+        assert_eq!(snapshot.resolve(3), Some(id_for(7)));
+        assert_eq!(snapshot.resolve(200), None);
     }
 }
@@ -0,0 +1,469 @@
+//! Embedding Import
+//!
+//! Bootstraps a bank from precomputed f32 embeddings. Each component is
+//! quantized with [`bridge::f32_to_signal`], so `scale` is the magnitude a
+//! value of 1.0 maps to. Rows are validated and quantized a batch at a time
+//! before any of that batch is inserted.
+//!
+//! Two file layouts are understood: a raw little-endian dump
+//! (`u32 count, u32 dim, count x dim f32`) and, with the `npy` feature,
+//! NumPy `.npy` v1 files holding a 2-D `<f4` C-order array.
+
+use crate::bank::DataBank;
+use crate::bridge;
+use crate::error::{CodecError, CodecErrorKind, DataBankError, Result};
+use crate::prelude::*;
+use crate::types::{EntryId, Temperature};
+use ternary_signal::Signal;
+
+/// Rows quantized and validated per batch.
+pub const IMPORT_BATCH: usize = 256;
+
+/// A component is heavily clamped when its scaled magnitude is over twice
+/// the 255 ceiling, i.e. quantization threw away more than half of it.
+const HEAVY_CLAMP_MAGNITUDE: f32 = 2.0 * 255.0;
+
+/// Outcome of an import.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Inserted entry ids, in row order.
+    pub ids: Vec<EntryId>,
+    /// Rows with at least one heavily clamped component. Non-zero means
+    /// `scale` is too large for the data.
+    pub heavily_clamped: usize,
+}
+
+/// Quantize and insert `rows`, logging a warning if any were heavily
+/// clamped. See [`import_f32_rows_report`].
+pub fn import_f32_rows(
+    bank: &mut DataBank,
+    rows: impl Iterator<Item = Vec<f32>>,
+    scale: u8,
+    temperature: Temperature,
+    tick: u64,
+) -> Result<Vec<EntryId>> {
+    let report = import_f32_rows_report(bank, rows, scale, temperature, tick)?;
+    if report.heavily_clamped > 0 {
+        log::warn!(
+            "{}: {} of {} imported rows lost over half of a component to clamping (scale {})",
+            bank.label(),
+            report.heavily_clamped,
+            report.ids.len(),
+            scale
+        );
+    }
+    Ok(report.ids)
+}
+
+/// Quantize and insert `rows` into `bank` at `temperature` and `tick`.
+///
+/// Every row must have `vector_width` finite components. A bad row fails
+/// the import before anything from its batch is inserted; earlier batches
+/// stay in the bank.
+pub fn import_f32_rows_report(
+    bank: &mut DataBank,
+    mut rows: impl Iterator<Item = Vec<f32>>,
+    scale: u8,
+    temperature: Temperature,
+    tick: u64,
+) -> Result<ImportReport> {
+    if scale == 0 {
+        return Err(DataBankError::InvalidOperand(
+            "import scale must be non-zero".into(),
+        ));
+    }
+    let width = bank.config().vector_width as usize;
+    let mut report = ImportReport::default();
+    let mut batch: Vec<Vec<Signal>> = Vec::with_capacity(IMPORT_BATCH);
+    let mut row_index = 0usize;
+
+    loop {
+        batch.clear();
+        for row in rows.by_ref().take(IMPORT_BATCH) {
+            if row.len() != width {
+                return Err(DataBankError::VectorWidthMismatch {
+                    expected: width as u16,
+                    got: row.len().min(u16::MAX as usize) as u16,
+                });
+            }
+            if let Some(bad) = row.iter().position(|v| !v.is_finite()) {
+                return Err(DataBankError::InvalidOperand(format!(
+                    "row {row_index} component {bad} is not finite"
+                )));
+            }
+            if row
+                .iter()
+                .any(|v| v.abs() * scale as f32 > HEAVY_CLAMP_MAGNITUDE)
+            {
+                report.heavily_clamped += 1;
+            }
+            batch.push(
+                row.iter()
+                    .map(|&v| bridge::f32_to_signal(v, scale))
+                    .collect(),
+            );
+            row_index += 1;
+        }
+        if batch.is_empty() {
+            break;
+        }
+        report.ids.reserve(batch.len());
+        for vector in batch.drain(..) {
+            report.ids.push(bank.insert(vector, temperature, tick)?);
+        }
+    }
+    Ok(report)
+}
+
+/// A dense row-major f32 matrix read from an embedding file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct F32Rows {
+    dim: usize,
+    data: Vec<f32>,
+}
+
+impl F32Rows {
+    /// Components per row.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.data.len().checked_div(self.dim).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The rows, ready for [`import_f32_rows`].
+    pub fn rows(&self) -> impl Iterator<Item = Vec<f32>> + '_ {
+        self.data
+            .chunks_exact(self.dim.max(1))
+            .map(|row| row.to_vec())
+    }
+}
+
+fn format_error(kind: CodecErrorKind, offset: usize, context: impl Into<String>) -> DataBankError {
+    DataBankError::Codec(CodecError::new(kind, offset, context))
+}
+
+/// Read `count x dim` little-endian f32s starting at `offset`, which must
+/// run exactly to the end of `bytes`.
+fn read_f32_body(bytes: &[u8], offset: usize, count: usize, dim: usize) -> Result<F32Rows> {
+    if count > 0 && dim == 0 {
+        return Err(format_error(
+            CodecErrorKind::WidthMismatch,
+            offset,
+            "rows have zero dimensions",
+        ));
+    }
+    let body_len = count
+        .checked_mul(dim)
+        .and_then(|n| n.checked_mul(4))
+        .ok_or_else(|| {
+            format_error(
+                CodecErrorKind::SectionOverrun,
+                offset,
+                "matrix size overflows",
+            )
+        })?;
+    let body = &bytes[offset.min(bytes.len())..];
+    if body.len() < body_len {
+        return Err(format_error(
+            CodecErrorKind::Truncated,
+            bytes.len(),
+            format!(
+                "{count}x{dim} f32 matrix needs {body_len} bytes, found {}",
+                body.len()
+            ),
+        ));
+    }
+    if body.len() > body_len {
+        return Err(format_error(
+            CodecErrorKind::SectionOverrun,
+            offset + body_len,
+            format!("{} trailing bytes after the matrix", body.len() - body_len),
+        ));
+    }
+    let data = body
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Ok(F32Rows { dim, data })
+}
+
+/// Parse the raw layout: `u32 count`, `u32 dim`, then `count x dim` f32s,
+/// all little-endian.
+pub fn parse_f32_raw(bytes: &[u8]) -> Result<F32Rows> {
+    if bytes.len() < 8 {
+        return Err(format_error(
+            CodecErrorKind::Truncated,
+            bytes.len(),
+            "raw f32 header needs 8 bytes",
+        ));
+    }
+    let count = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+    let dim = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+    read_f32_body(bytes, 8, count, dim)
+}
+
+/// Read a raw f32 file. See [`parse_f32_raw`].
+#[cfg(feature = "std")]
+pub fn read_f32_raw(path: &std::path::Path) -> Result<F32Rows> {
+    parse_f32_raw(&std::fs::read(path)?)
+}
+
+/// Parse a `.npy` v1 file holding a 2-D little-endian f32 array in C order.
+#[cfg(feature = "npy")]
+pub fn parse_npy(bytes: &[u8]) -> Result<F32Rows> {
+    const MAGIC: &[u8] = b"\x93NUMPY";
+    if bytes.len() < 10 {
+        return Err(format_error(
+            CodecErrorKind::Truncated,
+            bytes.len(),
+            "npy preamble needs 10 bytes",
+        ));
+    }
+    if &bytes[..6] != MAGIC {
+        return Err(format_error(CodecErrorKind::BadMagic, 0, "not a .npy file"));
+    }
+    if bytes[6] != 1 {
+        return Err(format_error(
+            CodecErrorKind::UnsupportedVersion,
+            6,
+            format!("npy version {}.{} (only 1.x)", bytes[6], bytes[7]),
+        ));
+    }
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let header = bytes.get(10..10 + header_len).ok_or_else(|| {
+        format_error(
+            CodecErrorKind::Truncated,
+            bytes.len(),
+            "npy header cut short",
+        )
+    })?;
+    let header = core::str::from_utf8(header)
+        .map_err(|_| format_error(CodecErrorKind::BadString, 10, "npy header is not ASCII"))?;
+
+    let field = |key: &str| -> Option<&str> {
+        let start = header.find(key)? + key.len();
+        Some(
+            header[start..]
+                .trim_start()
+                .trim_start_matches(':')
+                .trim_start(),
+        )
+    };
+    let descr = field("'descr'").unwrap_or_default();
+    if !(descr.starts_with("'<f4'") || descr.starts_with("'float32'")) {
+        return Err(format_error(
+            CodecErrorKind::InvalidEnum,
+            10,
+            "npy dtype is not little-endian f32",
+        ));
+    }
+    if !field("'fortran_order'")
+        .unwrap_or_default()
+        .starts_with("False")
+    {
+        return Err(format_error(
+            CodecErrorKind::UnsupportedFlags,
+            10,
+            "npy array is Fortran-ordered",
+        ));
+    }
+    let shape = field("'shape'")
+        .and_then(|s| s.strip_prefix('('))
+        .and_then(|s| s.split(')').next())
+        .ok_or_else(|| {
+            format_error(
+                CodecErrorKind::SectionOverrun,
+                10,
+                "npy header has no shape",
+            )
+        })?;
+    let dims: Vec<usize> = shape
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse())
+        .collect::<core::result::Result<_, _>>()
+        .map_err(|_| {
+            format_error(
+                CodecErrorKind::SectionOverrun,
+                10,
+                format!("bad npy shape ({shape})"),
+            )
+        })?;
+    let [count, dim] = dims[..] else {
+        return Err(format_error(
+            CodecErrorKind::WidthMismatch,
+            10,
+            format!("npy array has {} dimensions, expected 2", dims.len()),
+        ));
+    };
+    read_f32_body(bytes, 10 + header_len, count, dim)
+}
+
+/// Read a `.npy` v1 file. See [`parse_npy`].
+#[cfg(all(feature = "npy", feature = "std"))]
+pub fn read_npy(path: &std::path::Path) -> Result<F32Rows> {
+    parse_npy(&std::fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BankConfig, BankId};
+
+    const WIDTH: usize = 16;
+
+    fn make_bank(max_entries: u32) -> DataBank {
+        let config = BankConfig {
+            vector_width: WIDTH as u16,
+            max_entries,
+            ..BankConfig::default()
+        };
+        DataBank::new(BankId::from_raw(7), "import.test".into(), config)
+    }
+
+    /// Deterministic unit-range embeddings.
+    fn synthetic_rows(count: usize) -> Vec<Vec<f32>> {
+        let mut state = 0x2545_F491u32;
+        (0..count)
+            .map(|_| {
+                (0..WIDTH)
+                    .map(|_| {
+                        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                        (state >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn raw_bytes(rows: &[Vec<f32>]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(rows.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(WIDTH as u32).to_le_bytes());
+        for v in rows.iter().flatten() {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn raw_file_round_trips_within_quantization_error() {
+        let rows = synthetic_rows(IMPORT_BATCH + 40);
+        let parsed = parse_f32_raw(&raw_bytes(&rows)).unwrap();
+        assert_eq!((parsed.len(), parsed.dim()), (rows.len(), WIDTH));
+
+        let mut bank = make_bank(0);
+        let scale = 200;
+        let report =
+            import_f32_rows_report(&mut bank, parsed.rows(), scale, Temperature::Warm, 3).unwrap();
+        assert_eq!(report.ids.len(), rows.len());
+        assert_eq!(report.heavily_clamped, 0);
+
+        for (row, &id) in rows.iter().zip(&report.ids) {
+            let entry = bank.get(id).unwrap();
+            for (&original, &signal) in row.iter().zip(&entry.vector) {
+                let restored = bridge::signal_to_f32(signal, scale);
+                assert!((original - restored).abs() <= 0.5 / scale as f32 + 1e-6);
+            }
+        }
+
+        // Each imported row is its own best match
+        let probe = &rows[17];
+        let query: Vec<Signal> = probe
+            .iter()
+            .map(|&v| bridge::f32_to_signal(v, scale))
+            .collect();
+        assert_eq!(bank.query_sparse(&query, 1).unwrap()[0].entry_id, report.ids[17]);
+    }
+
+    #[test]
+    fn heavy_clamping_is_counted() {
+        let mut rows = synthetic_rows(3);
+        rows[1][4] = 5.0;
+        let mut bank = make_bank(0);
+        let report =
+            import_f32_rows_report(&mut bank, rows.into_iter(), 255, Temperature::Hot, 1).unwrap();
+        assert_eq!(report.heavily_clamped, 1);
+        assert_eq!(bank.get(report.ids[1]).unwrap().vector[4].current(), 255);
+    }
+
+    #[test]
+    fn bad_rows_are_rejected_before_their_batch_inserts() {
+        let mut rows = synthetic_rows(4);
+        rows[2].pop();
+        let mut bank = make_bank(0);
+        let err = import_f32_rows(&mut bank, rows.into_iter(), 100, Temperature::Hot, 1);
+        assert!(matches!(
+            err,
+            Err(DataBankError::VectorWidthMismatch {
+                expected: 16,
+                got: 15
+            })
+        ));
+        assert!(bank.is_empty());
+
+        let mut rows = synthetic_rows(2);
+        rows[1][0] = f32::NAN;
+        assert!(import_f32_rows(&mut bank, rows.into_iter(), 100, Temperature::Hot, 1).is_err());
+        assert!(import_f32_rows(
+            &mut bank,
+            synthetic_rows(1).into_iter(),
+            0,
+            Temperature::Hot,
+            1
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn malformed_raw_files_are_rejected() {
+        let bytes = raw_bytes(&synthetic_rows(2));
+        let kind = |bytes: &[u8]| match parse_f32_raw(bytes) {
+            Err(DataBankError::Codec(e)) => e.kind,
+            other => panic!("expected a codec error, got {other:?}"),
+        };
+        assert_eq!(kind(&bytes[..5]), CodecErrorKind::Truncated);
+        assert_eq!(kind(&bytes[..bytes.len() - 1]), CodecErrorKind::Truncated);
+        let mut long = bytes.clone();
+        long.push(0);
+        assert_eq!(kind(&long), CodecErrorKind::SectionOverrun);
+        assert!(parse_f32_raw(&[0; 8]).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "npy")]
+    #[test]
+    fn npy_v1_parses_like_the_raw_layout() {
+        let rows = synthetic_rows(5);
+        let mut header = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+            rows.len(),
+            WIDTH
+        );
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(&raw_bytes(&rows)[8..]);
+
+        let parsed = parse_npy(&bytes).unwrap();
+        assert_eq!(parsed, parse_f32_raw(&raw_bytes(&rows)).unwrap());
+
+        let mut fortran = bytes.clone();
+        let at = fortran.windows(5).position(|w| w == b"False").unwrap();
+        fortran[at..at + 5].copy_from_slice(b"True ");
+        assert!(parse_npy(&fortran).is_err());
+        bytes[6] = 2;
+        assert!(parse_npy(&bytes).is_err());
+    }
+}
@@ -1,11 +1,123 @@
-use std::collections::HashMap;
+use core::cmp::Reverse;
+use serde::{Deserialize, Serialize};
 use ternary_signal::Signal;
 
 use crate::entry::BankEntry;
-use crate::similarity::{sparse_cosine_similarity, QueryResult};
-use crate::types::EntryId;
+use crate::prelude::*;
+use crate::rng::XorShift64Star;
+use crate::similarity::{Metric, QueryResult};
+use crate::store::EntrySource;
+use crate::types::{EntryId, Temperature};
+
+/// Which path served a query, as recorded in a [`QueryTrace`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryPath {
+    /// Linear scan of every entry.
+    #[default]
+    BruteForce,
+    /// IVF scan of the probed buckets.
+    Ivf,
+    /// IVF had no centroids yet and fell back to a linear scan.
+    IvfFallback,
+    /// Served from the exact-match content hash without scanning.
+    ExactMatch,
+}
+
+/// Instrumentation for one query, filled by
+/// [`DataBank::query_sparse_traced`](crate::bank::DataBank::query_sparse_traced).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryTrace {
+    /// Path that produced the results.
+    pub path: QueryPath,
+    /// Entries whose similarity was computed (or, for exact matches,
+    /// returned).
+    pub candidates_scanned: usize,
+    /// IVF buckets searched. Zero for every other path.
+    pub buckets_probed: usize,
+    /// Wall time of the query in whole microseconds. Always zero without
+    /// the `std` feature, which has no clock.
+    pub micros: u64,
+}
+
+impl QueryTrace {
+    /// Whether the index could not use its fast path and scanned everything.
+    pub fn is_fallback(&self) -> bool {
+        self.path == QueryPath::IvfFallback
+    }
+}
+
+/// Entry predicates applied before scoring, so filtered-out entries cost
+/// no similarity computation. An empty filter matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryFilter {
+    /// Only entries in this namespace. Checked first, as the cheapest test.
+    pub namespace: Option<u16>,
+    /// Only entries whose `created_tick` lies in `first..=last`. Both
+    /// bounds are inclusive.
+    pub created_range: Option<(u64, u64)>,
+    /// Only entries at one of these temperatures.
+    pub temperatures: Option<Vec<Temperature>>,
+    /// Only entries not yet expired at this tick (see
+    /// [`BankEntry::is_expired`]).
+    pub live_at: Option<u64>,
+}
+
+impl QueryFilter {
+    /// Entries created in `first..=last`.
+    pub fn created_between(first: u64, last: u64) -> Self {
+        Self {
+            created_range: Some((first, last)),
+            ..Self::default()
+        }
+    }
+
+    /// Entries in namespace `namespace`.
+    pub fn in_namespace(namespace: u16) -> Self {
+        Self {
+            namespace: Some(namespace),
+            ..Self::default()
+        }
+    }
+
+    /// Also require namespace `namespace`.
+    pub fn with_namespace(mut self, namespace: u16) -> Self {
+        self.namespace = Some(namespace);
+        self
+    }
+
+    /// Also require the entry to be unexpired at `tick`.
+    pub fn with_live_at(mut self, tick: u64) -> Self {
+        self.live_at = Some(tick);
+        self
+    }
+
+    /// Also require one of `temperatures`.
+    pub fn with_temperatures(mut self, temperatures: impl IntoIterator<Item = Temperature>) -> Self {
+        self.temperatures = Some(temperatures.into_iter().collect());
+        self
+    }
+
+    /// Whether `entry` passes every predicate.
+    pub fn matches(&self, entry: &BankEntry) -> bool {
+        self.namespace.is_none_or(|ns| ns == entry.namespace)
+            && self
+                .created_range
+                .is_none_or(|(first, last)| (first..=last).contains(&entry.created_tick))
+            && self
+                .temperatures
+                .as_ref()
+                .is_none_or(|temps| temps.contains(&entry.temperature))
+            && self.live_at.is_none_or(|tick| !entry.is_expired(tick))
+    }
+}
 
 /// Vector similarity index for fast recall.
+///
+/// The methods that read a bank's entries take `&dyn EntrySource`, which
+/// both [`EntryStore`](crate::EntryStore) and `HashMap<EntryId, BankEntry>`
+/// implement, so callers can pass either. Implementations written against
+/// `&HashMap<EntryId, BankEntry>` change only the parameter type; the
+/// source's `get`, `len` and `iter` read the same way.
 pub trait VectorIndex: Send + Sync {
     /// Record a new entry in the index.
     fn insert(&mut self, id: EntryId, vector: &[Signal]);
@@ -17,12 +129,81 @@ pub trait VectorIndex: Send + Sync {
     fn query(
         &self,
         query: &[Signal],
-        entries: &HashMap<EntryId, BankEntry>,
+        entries: &dyn EntrySource,
+        top_k: usize,
+    ) -> Vec<QueryResult> {
+        let mut out = Vec::new();
+        self.query_into(query, entries, top_k, &mut out);
+        out
+    }
+
+    /// Like [`query`](Self::query), but writes into `out` (cleared first) so
+    /// callers can reuse one buffer across queries.
+    fn query_into(
+        &self,
+        query: &[Signal],
+        entries: &dyn EntrySource,
+        top_k: usize,
+        out: &mut Vec<QueryResult>,
+    );
+
+    /// Like [`query_into`](Self::query_into), also recording the path taken
+    /// and the work done in `trace`. The default reports a linear scan of
+    /// every entry; indexes with a sub-linear path override it.
+    fn query_traced(
+        &self,
+        query: &[Signal],
+        entries: &dyn EntrySource,
         top_k: usize,
-    ) -> Vec<QueryResult>;
+        out: &mut Vec<QueryResult>,
+        trace: &mut QueryTrace,
+    ) {
+        self.query_into(query, entries, top_k, out);
+        trace.path = QueryPath::BruteForce;
+        trace.candidates_scanned = if top_k == 0 { 0 } else { entries.len() };
+    }
+
+    /// Like [`query_into`](Self::query_into), but only entries matching
+    /// `filter` are scored. The default is a filtered linear scan; indexes
+    /// with a sub-linear path override it to filter their candidates.
+    fn query_filtered_into(
+        &self,
+        query: &[Signal],
+        entries: &dyn EntrySource,
+        top_k: usize,
+        filter: &QueryFilter,
+        out: &mut Vec<QueryResult>,
+    ) {
+        out.clear();
+        if top_k == 0 {
+            return;
+        }
+        out.extend(
+            entries
+                .iter()
+                .filter(|(_, entry)| filter.matches(entry))
+                .map(|(&id, entry)| QueryResult {
+                    entry_id: id,
+                    score: self.metric().score(query, &entry.vector),
+                }),
+        );
+        out.sort_unstable_by_key(|r| Reverse(r.score));
+        out.truncate(top_k);
+    }
 
     /// Rebuild the index from scratch (e.g. after loading from disk).
-    fn rebuild(&mut self, entries: &HashMap<EntryId, BankEntry>);
+    fn rebuild(&mut self, entries: &dyn EntrySource);
+
+    /// How the index scores candidates.
+    fn metric(&self) -> Metric {
+        Metric::Cosine
+    }
+
+    /// Trained partition centroids as per-dimension currents, if the index
+    /// has any. Reused as a quantization codebook.
+    fn centroids(&self) -> &[Vec<i32>] {
+        &[]
+    }
 }
 
 /// Brute-force linear scan index. O(n) per query.
@@ -31,7 +212,16 @@ pub trait VectorIndex: Send + Sync {
 /// At 64-dimensional vectors with integer arithmetic, a full scan
 /// of 4096 entries takes <1ms on modern hardware.
 #[derive(Debug, Default)]
-pub struct BruteForceIndex;
+pub struct BruteForceIndex {
+    metric: Metric,
+}
+
+impl BruteForceIndex {
+    /// A linear scan scoring with `metric`.
+    pub fn new(metric: Metric) -> Self {
+        Self { metric }
+    }
+}
 
 impl VectorIndex for BruteForceIndex {
     fn insert(&mut self, _id: EntryId, _vector: &[Signal]) {
@@ -42,38 +232,107 @@ impl VectorIndex for BruteForceIndex {
         // No-op: brute force scans the entry map directly.
     }
 
-    fn query(
+    fn query_into(
         &self,
         query: &[Signal],
-        entries: &HashMap<EntryId, BankEntry>,
+        entries: &dyn EntrySource,
         top_k: usize,
-    ) -> Vec<QueryResult> {
+        out: &mut Vec<QueryResult>,
+    ) {
+        out.clear();
         if top_k == 0 || entries.is_empty() {
-            return Vec::new();
+            return;
         }
 
-        let mut results: Vec<QueryResult> = entries
-            .iter()
-            .map(|(&id, entry)| QueryResult {
-                entry_id: id,
-                score: sparse_cosine_similarity(query, &entry.vector),
-            })
-            .collect();
+        out.extend(entries.iter().map(|(&id, entry)| QueryResult {
+            entry_id: id,
+            score: self.metric.score(query, &entry.vector),
+        }));
 
         // Sort descending by score
-        results.sort_unstable_by(|a, b| b.score.cmp(&a.score));
-        results.truncate(top_k);
-        results
+        out.sort_unstable_by_key(|r| Reverse(r.score));
+        out.truncate(top_k);
     }
 
-    fn rebuild(&mut self, _entries: &HashMap<EntryId, BankEntry>) {
+    fn rebuild(&mut self, _entries: &dyn EntrySource) {
         // No-op: brute force doesn't maintain state.
     }
+
+    fn metric(&self) -> Metric {
+        self.metric
+    }
+}
+
+/// How closely an index's answers match an exact scan, from
+/// [`evaluate_recall`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecallReport {
+    /// Sampled queries run.
+    pub queries: usize,
+    /// Results an exact scan returns, summed over the queries.
+    pub expected: usize,
+    /// Index results scoring at least the exact scan's k-th best, summed
+    /// over the queries. Counting by score keeps ties at the cutoff from
+    /// depending on which tied entry either side happened to keep.
+    pub hits: usize,
+    /// Entries the index scored, summed over the queries.
+    pub candidates_scanned: usize,
+}
+
+impl RecallReport {
+    /// `hits / expected` in thousandths; 1000 when nothing was expected.
+    pub fn recall_permille(&self) -> u32 {
+        if self.expected == 0 {
+            return 1000;
+        }
+        (self.hits * 1000 / self.expected) as u32
+    }
+}
+
+/// Measure `index`'s top-`top_k` recall against an exact scan of
+/// `entries` under the index's metric, querying with the vectors of
+/// `samples` distinct entries drawn by a [`XorShift64Star`] seeded with
+/// `seed`. Entries are drawn in id order, so the same seed and entries give
+/// the same report.
+pub fn evaluate_recall(
+    index: &dyn VectorIndex,
+    entries: &dyn EntrySource,
+    samples: usize,
+    top_k: usize,
+    seed: u64,
+) -> RecallReport {
+    let mut report = RecallReport::default();
+    let mut sorted: Vec<&BankEntry> = entries.iter().map(|(_, entry)| entry).collect();
+    sorted.sort_unstable_by_key(|e| e.id);
+    let want = top_k.min(sorted.len());
+    if want == 0 {
+        return report;
+    }
+
+    let metric = index.metric();
+    let mut scores = Vec::with_capacity(sorted.len());
+    let mut out = Vec::with_capacity(top_k);
+    for pick in XorShift64Star::new(seed).sample_indices(sorted.len(), samples) {
+        let query = &sorted[pick].vector;
+        scores.clear();
+        scores.extend(sorted.iter().map(|e| metric.score(query, &e.vector)));
+        scores.sort_unstable_by_key(|&s| Reverse(s));
+        let cutoff = scores[want - 1];
+
+        let mut trace = QueryTrace::default();
+        index.query_traced(query, entries, top_k, &mut out, &mut trace);
+        report.queries += 1;
+        report.expected += want;
+        report.hits += out.iter().filter(|r| r.score >= cutoff).count().min(want);
+        report.candidates_scanned += trace.candidates_scanned;
+    }
+    report
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::store::EntryStore;
     use crate::types::{BankId, Temperature};
 
     fn sig(polarity: i8, magnitude: u8) -> Signal {
@@ -88,7 +347,7 @@ mod tests {
 
     #[test]
     fn brute_force_returns_top_k() {
-        let mut entries = HashMap::new();
+        let mut entries = EntryStore::default();
         // Entry 1: strongly matches query
         let (id1, e1) = make_entry(1, vec![sig(1, 200), sig(1, 100)]);
         entries.insert(id1, e1);
@@ -99,7 +358,7 @@ mod tests {
         let (id3, e3) = make_entry(3, vec![sig(-1, 200), sig(-1, 100)]);
         entries.insert(id3, e3);
 
-        let index = BruteForceIndex;
+        let index = BruteForceIndex::default();
         let query = vec![sig(1, 200), sig(1, 100)];
         let results = index.query(&query, &entries, 2);
 
@@ -111,19 +370,19 @@ mod tests {
 
     #[test]
     fn brute_force_empty_returns_empty() {
-        let entries = HashMap::new();
-        let index = BruteForceIndex;
+        let entries = EntryStore::default();
+        let index = BruteForceIndex::default();
         let query = vec![sig(1, 100)];
         assert!(index.query(&query, &entries, 5).is_empty());
     }
 
     #[test]
     fn brute_force_top_k_zero_returns_empty() {
-        let mut entries = HashMap::new();
+        let mut entries = EntryStore::default();
         let (id, entry) = make_entry(1, vec![sig(1, 100)]);
         entries.insert(id, entry);
 
-        let index = BruteForceIndex;
+        let index = BruteForceIndex::default();
         let query = vec![sig(1, 100)];
         assert!(index.query(&query, &entries, 0).is_empty());
     }
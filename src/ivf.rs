@@ -4,12 +4,15 @@
 //! its nearest centroid. Queries search only the `nprobe` nearest clusters
 //! instead of all entries, giving ~k/nprobe speedup.
 
-use std::collections::HashMap;
+use core::cmp::Reverse;
 use ternary_signal::Signal;
 
 use crate::entry::BankEntry;
-use crate::index::VectorIndex;
-use crate::similarity::{sparse_cosine_similarity, QueryResult};
+use crate::index::{QueryFilter, QueryPath, QueryTrace, VectorIndex};
+use crate::prelude::*;
+use crate::rng::XorShift64Star;
+use crate::similarity::{Metric, QueryResult};
+use crate::store::EntrySource;
 use crate::types::EntryId;
 
 /// Inverted File Index -- partitions vector space into clusters for
@@ -23,6 +26,37 @@ pub struct IvfIndex {
     nprobe: usize,
     /// Number of centroids.
     k: usize,
+    /// Seed for sampling initial centroids; `None` spaces them evenly.
+    seed: Option<u64>,
+    /// How candidates are scored. Centroid routing always uses the raw dot.
+    metric: Metric,
+}
+
+/// How to build an [`IvfIndex`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IvfBuildParams {
+    /// Number of centroids.
+    pub k: usize,
+    /// Number of clusters to search per query.
+    pub nprobe: usize,
+    /// k-means refinement passes after seeding; 0 keeps the seeds as
+    /// centroids.
+    pub max_iterations: usize,
+    /// With a seed, initial centroids are `k` entries sampled by
+    /// [`XorShift64Star`], reproducible for the same seed and entries.
+    /// Without one they are every (n/k)th entry in id order.
+    pub seed: Option<u64>,
+}
+
+impl Default for IvfBuildParams {
+    fn default() -> Self {
+        Self {
+            k: 64,
+            nprobe: 8,
+            max_iterations: 0,
+            seed: None,
+        }
+    }
 }
 
 impl IvfIndex {
@@ -36,9 +70,35 @@ impl IvfIndex {
             assignments: Vec::new(),
             nprobe: nprobe.max(1),
             k: k.max(1),
+            seed: None,
+            metric: Metric::Cosine,
         }
     }
 
+    /// Score candidates with `metric` instead of cosine.
+    pub fn with_metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// An empty index that samples its initial centroids as `params`
+    /// describes. `params.max_iterations` only applies through
+    /// [`build`](Self::build); trait rebuilds just reseed and reassign.
+    pub fn with_params(params: &IvfBuildParams) -> Self {
+        Self {
+            seed: params.seed,
+            ..Self::new(params.k, params.nprobe)
+        }
+    }
+
+    /// An index trained on `entries`: seeded, refined by up to
+    /// `params.max_iterations` k-means passes, and assigned.
+    pub fn build(params: &IvfBuildParams, entries: &dyn EntrySource) -> Self {
+        let mut index = Self::with_params(params);
+        index.rebuild_kmeans(entries, params.max_iterations);
+        index
+    }
+
     /// Find the nearest centroid index for a given vector.
     fn nearest_centroid(&self, vector: &[Signal]) -> usize {
         if self.centroids.is_empty() {
@@ -60,7 +120,7 @@ impl IvfIndex {
             .enumerate()
             .map(|(i, c)| (i, dot_i32(&i32_vec, c)))
             .collect();
-        scored.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+        scored.sort_unstable_by_key(|s| Reverse(s.1));
         scored
             .iter()
             .take(self.nprobe.min(scored.len()))
@@ -69,7 +129,7 @@ impl IvfIndex {
     }
 
     /// Initialize centroids by sampling from existing entries.
-    fn initialize_centroids(&mut self, entries: &HashMap<EntryId, BankEntry>) {
+    fn initialize_centroids(&mut self, entries: &dyn EntrySource) {
         if entries.is_empty() {
             self.centroids.clear();
             self.assignments.clear();
@@ -77,29 +137,34 @@ impl IvfIndex {
         }
 
         let k = self.k.min(entries.len());
-        let entry_list: Vec<&BankEntry> = entries.values().collect();
-
-        // Deterministic spacing: pick every (n/k)th entry
-        let step = entry_list.len() / k;
-        self.centroids = (0..k)
-            .map(|i| {
-                let idx = (i * step).min(entry_list.len() - 1);
-                signals_to_i32_vec(&entry_list[idx].vector)
-            })
+        let mut entry_list: Vec<&BankEntry> = entries.iter().map(|(_, entry)| entry).collect();
+        entry_list.sort_unstable_by_key(|e| e.id);
+
+        let picks: Vec<usize> = match self.seed {
+            Some(seed) => XorShift64Star::new(seed).sample_indices(entry_list.len(), k),
+            // Deterministic spacing: pick every (n/k)th entry in id order
+            None => {
+                let step = entry_list.len() / k;
+                (0..k).map(|i| (i * step).min(entry_list.len() - 1)).collect()
+            }
+        };
+        self.centroids = picks
+            .into_iter()
+            .map(|idx| signals_to_i32_vec(&entry_list[idx].vector))
             .collect();
 
         self.assignments = vec![Vec::new(); k];
     }
 
     /// Assign all entries to their nearest centroid.
-    fn assign_all(&mut self, entries: &HashMap<EntryId, BankEntry>) {
+    fn assign_all(&mut self, entries: &dyn EntrySource) {
         for bucket in &mut self.assignments {
             bucket.clear();
         }
         if self.centroids.is_empty() {
             return;
         }
-        for (&id, entry) in entries {
+        for (&id, entry) in entries.iter() {
             let ci = self.nearest_centroid(&entry.vector);
             if ci < self.assignments.len() {
                 self.assignments[ci].push(id);
@@ -140,47 +205,113 @@ impl VectorIndex for IvfIndex {
         }
     }
 
-    fn query(
+    fn query_into(
         &self,
         query: &[Signal],
-        entries: &HashMap<EntryId, BankEntry>,
+        entries: &dyn EntrySource,
         top_k: usize,
-    ) -> Vec<QueryResult> {
-        if top_k == 0 || entries.is_empty() || self.centroids.is_empty() {
+        out: &mut Vec<QueryResult>,
+    ) {
+        self.scan(query, entries, top_k, out, None, None);
+    }
+
+    fn query_traced(
+        &self,
+        query: &[Signal],
+        entries: &dyn EntrySource,
+        top_k: usize,
+        out: &mut Vec<QueryResult>,
+        trace: &mut QueryTrace,
+    ) {
+        self.scan(query, entries, top_k, out, Some(trace), None);
+    }
+
+    /// Filters the probed buckets' candidates, so a narrow filter can
+    /// return fewer than `top_k` even when more matching entries exist in
+    /// unprobed buckets.
+    fn query_filtered_into(
+        &self,
+        query: &[Signal],
+        entries: &dyn EntrySource,
+        top_k: usize,
+        filter: &QueryFilter,
+        out: &mut Vec<QueryResult>,
+    ) {
+        self.scan(query, entries, top_k, out, None, Some(filter));
+    }
+
+    fn rebuild(&mut self, entries: &dyn EntrySource) {
+        self.initialize_centroids(entries);
+        self.assign_all(entries);
+    }
+
+    fn centroids(&self) -> &[Vec<i32>] {
+        &self.centroids
+    }
+
+    fn metric(&self) -> Metric {
+        self.metric
+    }
+}
+
+impl IvfIndex {
+    /// Shared body of the query methods; trace bookkeeping is skipped
+    /// entirely when `trace` is None, and entries failing `filter` are
+    /// never scored.
+    fn scan(
+        &self,
+        query: &[Signal],
+        entries: &dyn EntrySource,
+        top_k: usize,
+        out: &mut Vec<QueryResult>,
+        mut trace: Option<&mut QueryTrace>,
+        filter: Option<&QueryFilter>,
+    ) {
+        out.clear();
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.path = QueryPath::Ivf;
+        }
+        if top_k == 0 || entries.is_empty() {
+            return;
+        }
+        if self.centroids.is_empty() {
             // Fallback to brute force if no centroids
-            return brute_force_query(query, entries, top_k);
+            if let Some(trace) = trace {
+                trace.path = QueryPath::IvfFallback;
+                trace.candidates_scanned = entries.len();
+            }
+            brute_force_query_into(query, entries, top_k, out, filter, self.metric);
+            return;
         }
 
         let probe_indices = self.nearest_centroids(query);
-        let mut results: Vec<QueryResult> = Vec::new();
 
         for ci in &probe_indices {
             if *ci >= self.assignments.len() {
                 continue;
             }
             for &id in &self.assignments[*ci] {
-                if let Some(entry) = entries.get(&id) {
-                    let score = sparse_cosine_similarity(query, &entry.vector);
-                    results.push(QueryResult {
+                if let Some(entry) = entries
+                    .get(&id)
+                    .filter(|entry| filter.is_none_or(|f| f.matches(entry)))
+                {
+                    let score = self.metric.score(query, &entry.vector);
+                    out.push(QueryResult {
                         entry_id: id,
                         score,
                     });
                 }
             }
         }
+        if let Some(trace) = trace {
+            trace.buckets_probed = probe_indices.len();
+            trace.candidates_scanned = out.len();
+        }
 
-        results.sort_unstable_by(|a, b| b.score.cmp(&a.score));
-        results.truncate(top_k);
-        results
-    }
-
-    fn rebuild(&mut self, entries: &HashMap<EntryId, BankEntry>) {
-        self.initialize_centroids(entries);
-        self.assign_all(entries);
+        out.sort_unstable_by_key(|r| Reverse(r.score));
+        out.truncate(top_k);
     }
-}
 
-impl IvfIndex {
     /// Rebuild with k-means clustering.
     ///
     /// Iteratively refines centroids by:
@@ -191,7 +322,7 @@ impl IvfIndex {
     /// Uses integer arithmetic only (ASTRO_004 compliant).
     pub fn rebuild_kmeans(
         &mut self,
-        entries: &HashMap<EntryId, BankEntry>,
+        entries: &dyn EntrySource,
         max_iterations: usize,
     ) {
         if entries.is_empty() {
@@ -200,7 +331,7 @@ impl IvfIndex {
             return;
         }
 
-        // Initialize centroids with deterministic spacing (same as before)
+        // Initialize centroids by even spacing or seeded sampling
         self.initialize_centroids(entries);
         if self.centroids.is_empty() {
             return;
@@ -222,13 +353,13 @@ impl IvfIndex {
 
             // Step 2: Recompute centroids as mean of assigned entries
             let mut changed = false;
-            for ci in 0..k {
-                if new_assignments[ci].is_empty() {
+            for (ci, members) in new_assignments.iter().enumerate() {
+                if members.is_empty() {
                     continue; // keep old centroid if no assignments
                 }
-                let n = new_assignments[ci].len() as i64;
+                let n = members.len() as i64;
                 let mut new_centroid = vec![0i64; width];
-                for &ei in &new_assignments[ci] {
+                for &ei in members {
                     for (j, &v) in entry_vecs[ei].1.iter().enumerate() {
                         if j < width {
                             new_centroid[j] += v as i64;
@@ -277,22 +408,26 @@ fn dot_i32(a: &[i32], b: &[i32]) -> i64 {
     sum
 }
 
-/// Brute-force fallback when IVF has no centroids.
-fn brute_force_query(
+/// Brute-force fallback when IVF has no centroids. Appends to `out`.
+fn brute_force_query_into(
     query: &[Signal],
-    entries: &HashMap<EntryId, BankEntry>,
+    entries: &dyn EntrySource,
     top_k: usize,
-) -> Vec<QueryResult> {
-    let mut results: Vec<QueryResult> = entries
-        .iter()
-        .map(|(&id, entry)| QueryResult {
-            entry_id: id,
-            score: sparse_cosine_similarity(query, &entry.vector),
-        })
-        .collect();
-    results.sort_unstable_by(|a, b| b.score.cmp(&a.score));
-    results.truncate(top_k);
-    results
+    out: &mut Vec<QueryResult>,
+    filter: Option<&QueryFilter>,
+    metric: Metric,
+) {
+    out.extend(
+        entries
+            .iter()
+            .filter(|(_, entry)| filter.is_none_or(|f| f.matches(entry)))
+            .map(|(&id, entry)| QueryResult {
+                entry_id: id,
+                score: metric.score(query, &entry.vector),
+            }),
+    );
+    out.sort_unstable_by_key(|r| Reverse(r.score));
+    out.truncate(top_k);
 }
 
 /// Index type selector for BankConfig.
@@ -313,6 +448,7 @@ impl Default for IndexType {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::store::EntryStore;
     use crate::types::{BankId, Temperature};
 
     fn sig(polarity: i8, magnitude: u8) -> Signal {
@@ -327,7 +463,7 @@ mod tests {
 
     #[test]
     fn ivf_rebuild_and_query() {
-        let mut entries = HashMap::new();
+        let mut entries = EntryStore::default();
         // Create 16 entries with distinct patterns
         for i in 0u64..16 {
             let v = vec![
@@ -356,9 +492,63 @@ mod tests {
         assert!(results[0].score > 0);
     }
 
+    #[test]
+    fn plain_map_and_store_give_the_same_answers() {
+        let map: HashMap<EntryId, BankEntry> = (0u64..16)
+            .map(|i| make_entry(i + 1, vec![sig(1, (i * 15 + 10) as u8), sig(1, 100)]))
+            .collect();
+        let store = EntryStore::from(map.clone());
+        let query = vec![sig(1, 40), sig(1, 100)];
+
+        let mut from_map = IvfIndex::new(4, 2);
+        from_map.rebuild_kmeans(&map, 5);
+        let mut from_store = IvfIndex::new(4, 2);
+        from_store.rebuild_kmeans(&store, 5);
+        assert_eq!(from_map.centroids, from_store.centroids);
+        let ranked = |results: Vec<QueryResult>| -> Vec<(EntryId, i32)> {
+            results.iter().map(|r| (r.entry_id, r.score)).collect()
+        };
+        assert_eq!(
+            ranked(from_map.query(&query, &map, 3)),
+            ranked(from_store.query(&query, &store, 3))
+        );
+    }
+
+    #[test]
+    fn ivf_trace_reports_probed_buckets_and_fallback() {
+        let mut entries = EntryStore::default();
+        for i in 0u64..16 {
+            let v = vec![sig(1, (i * 15 + 10) as u8), sig(if i < 8 { 1 } else { -1 }, 100)];
+            let (id, e) = make_entry(i + 1, v);
+            entries.insert(id, e);
+        }
+        let query = vec![sig(1, 10), sig(1, 100)];
+        let mut out = Vec::new();
+
+        let mut index = IvfIndex::new(4, 2);
+        let mut trace = QueryTrace::default();
+        index.query_traced(&query, &entries, 3, &mut out, &mut trace);
+        assert!(trace.is_fallback());
+        assert_eq!(trace.candidates_scanned, 16);
+
+        index.rebuild(&entries);
+        let mut trace = QueryTrace::default();
+        index.query_traced(&query, &entries, 3, &mut out, &mut trace);
+        let probed: usize = index
+            .nearest_centroids(&query)
+            .iter()
+            .map(|&ci| index.assignments[ci].len())
+            .sum();
+        assert_eq!(trace.path, QueryPath::Ivf);
+        assert_eq!(trace.buckets_probed, 2);
+        assert_eq!(trace.candidates_scanned, probed);
+        let untraced: Vec<_> = index.query(&query, &entries, 3).iter().map(|r| r.entry_id).collect();
+        assert_eq!(out.iter().map(|r| r.entry_id).collect::<Vec<_>>(), untraced);
+    }
+
     #[test]
     fn ivf_insert_and_remove() {
-        let mut entries = HashMap::new();
+        let mut entries = EntryStore::default();
         for i in 0u64..8 {
             let v = vec![sig(1, (i * 30 + 10).min(255) as u8), sig(1, 100)];
             let (id, e) = make_entry(i + 1, v);
@@ -386,7 +576,7 @@ mod tests {
 
     #[test]
     fn ivf_empty_entries_fallback() {
-        let entries = HashMap::new();
+        let entries = EntryStore::default();
         let index = IvfIndex::new(4, 2);
         let query = vec![sig(1, 100)];
         let results = index.query(&query, &entries, 5);
@@ -395,7 +585,7 @@ mod tests {
 
     #[test]
     fn ivf_accuracy_vs_brute_force() {
-        let mut entries = HashMap::new();
+        let mut entries = EntryStore::default();
         for i in 0u64..32 {
             let v = vec![
                 sig(1, ((i * 7 + 3) % 255 + 1) as u8),
@@ -409,7 +599,7 @@ mod tests {
 
         // Brute force baseline
         let query = vec![sig(1, 100), sig(1, 150), sig(1, 200), sig(1, 50)];
-        let bf_results = brute_force_query(&query, &entries, 5);
+        let bf_results = crate::index::BruteForceIndex::default().query(&query, &entries, 5);
 
         // IVF with full probe (nprobe = k) should match brute force
         let mut index = IvfIndex::new(4, 4); // nprobe = k, searches all clusters
@@ -424,7 +614,7 @@ mod tests {
 
     #[test]
     fn ivf_k_larger_than_entries() {
-        let mut entries = HashMap::new();
+        let mut entries = EntryStore::default();
         let (id, e) = make_entry(1, vec![sig(1, 100), sig(1, 200)]);
         entries.insert(id, e);
 
@@ -439,7 +629,7 @@ mod tests {
 
     #[test]
     fn kmeans_convergence() {
-        let mut entries = HashMap::new();
+        let mut entries = EntryStore::default();
         // Two clear clusters: positive and negative
         for i in 0u64..16 {
             let pol = if i < 8 { 1 } else { -1 };
@@ -474,7 +664,7 @@ mod tests {
 
     #[test]
     fn kmeans_vs_brute_force_accuracy() {
-        let mut entries = HashMap::new();
+        let mut entries = EntryStore::default();
         for i in 0u64..32 {
             let v = vec![
                 sig(1, ((i * 7 + 3) % 255 + 1) as u8),
@@ -487,7 +677,7 @@ mod tests {
         }
 
         let query = vec![sig(1, 100), sig(1, 150), sig(1, 200), sig(1, 50)];
-        let bf_results = brute_force_query(&query, &entries, 5);
+        let bf_results = crate::index::BruteForceIndex::default().query(&query, &entries, 5);
 
         // K-means with full probe should match
         let mut index = IvfIndex::new(4, 4);
@@ -499,6 +689,57 @@ mod tests {
         assert_eq!(bf_results[0].entry_id, km_results[0].entry_id);
     }
 
+    /// 64 entries inserted in `order`, so each store hashes differently.
+    fn spread_entries(order: impl Iterator<Item = u64>) -> EntryStore {
+        let mut entries = EntryStore::default();
+        for i in order {
+            let v = vec![
+                sig(if i % 2 == 0 { 1 } else { -1 }, ((i * 37 + 5) % 250 + 1) as u8),
+                sig(if i % 3 == 0 { -1 } else { 1 }, ((i * 53 + 11) % 250 + 1) as u8),
+                sig(1, ((i * 71 + 17) % 250 + 1) as u8),
+                sig(if i % 7 < 3 { -1 } else { 1 }, ((i * 89 + 23) % 250 + 1) as u8),
+            ];
+            let (id, e) = make_entry(i + 1, v);
+            entries.insert(id, e);
+        }
+        entries
+    }
+
+    #[test]
+    fn seeded_builds_are_reproducible() {
+        let forward = spread_entries(0..64);
+        let backward = spread_entries((0..64).rev());
+        let params = IvfBuildParams {
+            k: 6,
+            nprobe: 2,
+            max_iterations: 5,
+            seed: Some(0xDA7A),
+        };
+
+        let a = IvfIndex::build(&params, &forward);
+        let b = IvfIndex::build(&params, &backward);
+        assert_eq!(a.centroids, b.centroids);
+        let other = IvfIndex::build(&IvfBuildParams { seed: Some(7), ..params }, &forward);
+        assert_ne!(a.centroids, other.centroids);
+
+        // Trait rebuilds reseed from the same seed
+        let mut rebuilt = IvfIndex::with_params(&IvfBuildParams { max_iterations: 0, ..params });
+        rebuilt.rebuild(&forward);
+        let mut again = IvfIndex::with_params(&IvfBuildParams { max_iterations: 0, ..params });
+        again.rebuild(&backward);
+        assert_eq!(rebuilt.centroids, again.centroids);
+
+        let report = crate::index::evaluate_recall(&a, &forward, 16, 5, 99);
+        assert_eq!(report, crate::index::evaluate_recall(&b, &backward, 16, 5, 99));
+        assert_eq!(report.queries, 16);
+        assert_eq!(report.expected, 80);
+        assert!(report.candidates_scanned < 16 * 64);
+
+        let exhaustive = IvfIndex::build(&IvfBuildParams { nprobe: 6, ..params }, &forward);
+        let full = crate::index::evaluate_recall(&exhaustive, &forward, 16, 5, 99);
+        assert_eq!(full.recall_permille(), 1000);
+    }
+
     #[test]
     fn dot_i32_correctness() {
         assert_eq!(dot_i32(&[1, 2, 3], &[4, 5, 6]), 32);
@@ -7,21 +7,81 @@
 //! ## Binary Format (per entry)
 //!
 //! ```text
-//! [0]       Tag (u8): 0=Insert, 1=Remove, 2=Touch, 3=AddEdge, 4=SetTemperature
+//! [0]       Tag (u8): 0=Insert, 1=Remove, 2=Touch, 3=AddEdge, 4=SetTemperature,
+//!           5=Promote, 6=Demote, 7=BatchEvict, 8=Pin, 9=Unpin, 10=TouchMany,
+//!           11=Insert with entry checksum, 12=RemoveEdgesTo,
+//!           13=Insert with entry checksum and expiry tick,
+//!           14=Insert with entry checksum, expiry tick and namespace,
+//!           15=InsertCopy
 //! [1..9]    BankId (u64 LE)
 //! [9..17]   EntryId (u64 LE)
 //! [17..]    Payload (variable, depends on tag)
 //! [last 4]  CRC32 of all preceding bytes in this entry
 //! ```
+//!
+//! Tag 11 is tag 0's payload followed by the producer's entry checksum
+//! (u32 LE). The record CRC only proves the bytes survived the disk; the
+//! entry checksum also catches a vector damaged before it was journaled,
+//! and replay refuses such records instead of restoring wrong content.
+//!
+//! Tag 12 stores the target's EntryId in the EntryId slot, followed by the
+//! target's BankId (u64 LE); it names no source entry, since replay strips
+//! every edge in the bank that points at the target.
+//!
+//! Tag 13 is tag 11's payload followed by the entry's expiry tick (u64
+//! LE), so an entry inserted with a TTL replays with it. An `Insert`
+//! carrying an expiry is always written as tag 13; one without a producer
+//! checksum takes its vector's.
+//!
+//! Tag 14 is tag 13's payload followed by the entry's namespace (u16 LE),
+//! with an expiry tick of `u64::MAX` standing for none. An `Insert` into a
+//! namespace other than 0 is always written as tag 14, so replay puts the
+//! entry back into its partition.
+//!
+//! Tag 15 carries a whole entry, as copied in by
+//! [`BankCluster::migrate_entry`](crate::cluster::BankCluster::migrate_entry).
+//! After the tick (u64 LE), temperature (u8) and vector length (u16 LE) of
+//! an insert come the edge count (u16 LE), the signals, the edges (type
+//! u8, target BankId u64 LE, target EntryId u64 LE, weight u8, created
+//! tick u64 LE; 26 bytes each), then the entry checksum (u32 LE), origin
+//! BankId (u64 LE), expiry tick (u64 LE, `u64::MAX` for none), namespace
+//! (u16 LE), last-accessed tick (u64 LE), access count (u32 LE),
+//! confidence (u8) and pinned (u8). The EntryId slot holds the entry's id
+//! in the bank it was copied from. The debug tag is not carried.
+//!
+//! Bit 7 of the tag (`TAG_FLAG_EXTENDED_TEMPERATURE`) marks a record whose
+//! temperature byte is beyond the original four (`Temperature::Set`); only
+//! tags 0, 4, 5, 6, 11, 13, 14 and 15 may carry it. Readers that predate the flag
+//! stop at such a record as at any unknown tag, and current readers reject
+//! an extended temperature on a record without it.
+//!
+//! ## Compressed Blocks (`zstd` feature)
+//!
+//! A journal opened with [`JournalWriter::open_compressed`] buffers records
+//! until `flush`, then writes them as one block:
+//!
+//! ```text
+//! [0]       0xC0 (block marker, never a record tag)
+//! [1..5]    Compressed length (u32 LE)
+//! [5..]     zstd frame holding the concatenated records above
+//! ```
+//!
+//! Readers accept raw records and blocks interleaved. A truncated or
+//! undecodable block ends the read like a corrupt record; without the
+//! feature, every block is treated that way.
 
 use crate::cluster::BankCluster;
+use crate::entry::{vector_checksum, BankEntry, EdgeList};
+use crate::error::JournalError;
 use crate::types::{BankId, BankRef, Edge, EdgeType, EntryId, Temperature};
-use std::io::{self, BufWriter, Write};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
 use std::path::Path;
 use ternary_signal::Signal;
 
 /// A single journal entry: one mutation to a bank.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum JournalEntry {
     /// New entry inserted.
     Insert {
@@ -30,6 +90,18 @@ pub enum JournalEntry {
         vector: Vec<Signal>,
         temperature: Temperature,
         tick: u64,
+        /// The producer's [`BankEntry::checksum`](crate::BankEntry::checksum),
+        /// verified against `vector` on replay. None for records written
+        /// without one (tag 0).
+        checksum: Option<u32>,
+        /// The tick the entry expires at, see
+        /// [`DataBank::insert_ttl`](crate::bank::DataBank::insert_ttl).
+        /// None for entries without a TTL (tags 0 and 11).
+        expires_at_tick: Option<u64>,
+        /// The namespace the entry was inserted into, see
+        /// [`DataBank::insert_in_namespace`](crate::bank::DataBank::insert_in_namespace).
+        /// 0 for every tag but 14.
+        namespace: u16,
     },
     /// Entry removed.
     Remove {
@@ -71,8 +143,81 @@ pub enum JournalEntry {
         bank_id: BankId,
         entry_ids: Vec<EntryId>,
     },
+    /// Entry pinned (exempt from demotion and eviction).
+    Pin {
+        bank_id: BankId,
+        entry_id: EntryId,
+    },
+    /// Entry unpinned.
+    Unpin {
+        bank_id: BankId,
+        entry_id: EntryId,
+    },
+    /// `count` touches of one entry, the last at `tick`, collapsed into one
+    /// record by [`coalesce`].
+    TouchMany {
+        bank_id: BankId,
+        entry_id: EntryId,
+        tick: u64,
+        count: u32,
+    },
+    /// Every edge in the bank pointing at `target` removed, see
+    /// [`DataBank::remove_all_edges_to`](crate::DataBank::remove_all_edges_to).
+    RemoveEdgesTo {
+        bank_id: BankId,
+        target: BankRef,
+    },
+    /// A whole entry copied into the bank under a fresh id, with its edges
+    /// and metadata (but not its debug tag), see
+    /// [`BankCluster::migrate_entry`](crate::cluster::BankCluster::migrate_entry).
+    /// `entry.id()` is its id in the bank it came from.
+    InsertCopy {
+        bank_id: BankId,
+        entry: BankEntry,
+    },
+}
+
+impl JournalEntry {
+    /// An `Insert` record for `entry`, carrying its checksum, expiry and
+    /// namespace.
+    pub fn insert_of(bank_id: BankId, entry: &BankEntry) -> Self {
+        Self::Insert {
+            bank_id,
+            entry_id: entry.id(),
+            vector: entry.vector().to_vec(),
+            temperature: entry.temperature(),
+            tick: entry.created_tick(),
+            checksum: Some(entry.checksum()),
+            expires_at_tick: entry.expires_at_tick(),
+            namespace: entry.namespace(),
+        }
+    }
+
+    /// The bank this record mutates.
+    pub fn bank_id(&self) -> BankId {
+        match self {
+            Self::Insert { bank_id, .. }
+            | Self::Remove { bank_id, .. }
+            | Self::Touch { bank_id, .. }
+            | Self::AddEdge { bank_id, .. }
+            | Self::SetTemperature { bank_id, .. }
+            | Self::Promote { bank_id, .. }
+            | Self::Demote { bank_id, .. }
+            | Self::BatchEvict { bank_id, .. }
+            | Self::Pin { bank_id, .. }
+            | Self::Unpin { bank_id, .. }
+            | Self::TouchMany { bank_id, .. }
+            | Self::RemoveEdgesTo { bank_id, .. }
+            | Self::InsertCopy { bank_id, .. } => *bank_id,
+        }
+    }
 }
 
+/// Version of the record layout above. Journals carry no header, so it is
+/// not written to the file; it changes whenever an existing record's
+/// bytes would decode differently (new tags do not change it).
+pub const FORMAT_VERSION: u16 = 3;
+
 // Tag constants
 const TAG_INSERT: u8 = 0;
 const TAG_REMOVE: u8 = 1;
@@ -82,37 +227,259 @@ const TAG_SET_TEMP: u8 = 4;
 const TAG_PROMOTE: u8 = 5;
 const TAG_DEMOTE: u8 = 6;
 const TAG_BATCH_EVICT: u8 = 7;
+const TAG_PIN: u8 = 8;
+const TAG_UNPIN: u8 = 9;
+const TAG_TOUCH_MANY: u8 = 10;
+const TAG_INSERT_CHECKED: u8 = 11;
+const TAG_REMOVE_EDGES_TO: u8 = 12;
+const TAG_INSERT_EXPIRING: u8 = 13;
+const TAG_INSERT_NAMESPACED: u8 = 14;
+const TAG_INSERT_COPY: u8 = 15;
+const TAG_COMPRESSED_BLOCK: u8 = 0xC0;
+
+/// Tag bit set on records whose temperature is extended, see the module
+/// docs.
+const TAG_FLAG_EXTENDED_TEMPERATURE: u8 = 0x80;
+
+/// Where a [`JournalWriter`] sends its bytes: the journal file, or any
+/// other append-only store.
+pub trait JournalSink: Write + Send + Sync {
+    /// Bytes already written to the sink.
+    fn len_bytes(&self) -> io::Result<u64>;
+}
+
+impl JournalSink for std::fs::File {
+    fn len_bytes(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+}
 
 /// Append-only journal writer.
 pub struct JournalWriter {
-    writer: BufWriter<std::fs::File>,
+    writer: BufWriter<Box<dyn JournalSink>>,
+    /// Completed [`flush`](Self::flush) calls since open.
+    flushes: u64,
+    /// Records awaiting compression at the next flush. `None` for a raw
+    /// journal.
+    #[cfg(feature = "zstd")]
+    block: Option<Vec<u8>>,
+    /// Touches held back for coalescing, see
+    /// [`set_coalesce_touches`](Self::set_coalesce_touches). `None` when off.
+    touches: Option<Vec<JournalEntry>>,
 }
 
 impl JournalWriter {
     /// Open or create a journal file for appending.
-    pub fn open(path: &Path) -> io::Result<Self> {
+    pub fn open(path: &Path) -> crate::Result<Self> {
         let file = std::fs::OpenOptions::new()
             .create(true)
             .append(true)
-            .open(path)?;
-        Ok(Self {
-            writer: BufWriter::new(file),
-        })
+            .open(path)
+            .map_err(JournalError::OpenFailed)?;
+        Ok(Self::from_sink(Box::new(file)))
+    }
+
+    /// Raw journal writing to `sink` instead of a file.
+    pub fn from_sink(sink: Box<dyn JournalSink>) -> Self {
+        Self {
+            writer: BufWriter::new(sink),
+            flushes: 0,
+            #[cfg(feature = "zstd")]
+            block: None,
+            touches: None,
+        }
+    }
+
+    /// Hold back `Touch` records and write each run of them (up to the
+    /// next other record or flush) through [`coalesce`], so a hot entry
+    /// costs one record per flush instead of one per access. Turning it off
+    /// writes out any held touches first.
+    pub fn set_coalesce_touches(&mut self, on: bool) -> crate::Result<()> {
+        if !on {
+            self.write_touches()?;
+            self.touches = None;
+        } else if self.touches.is_none() {
+            self.touches = Some(Vec::new());
+        }
+        Ok(())
+    }
+
+    /// Open or create a journal whose records are written as zstd blocks,
+    /// one per [`flush`](Self::flush). Records appended since the last
+    /// flush are only in memory; dropping the writer flushes them.
+    #[cfg(feature = "zstd")]
+    pub fn open_compressed(path: &Path) -> crate::Result<Self> {
+        let mut writer = Self::open(path)?;
+        writer.block = Some(Vec::new());
+        Ok(writer)
     }
 
     /// Append a journal entry.
-    pub fn append(&mut self, entry: &JournalEntry) -> io::Result<()> {
+    pub fn append(&mut self, entry: &JournalEntry) -> crate::Result<()> {
+        if let Some(touches) = self.touches.as_mut() {
+            if matches!(entry, JournalEntry::Touch { .. } | JournalEntry::TouchMany { .. }) {
+                touches.push(entry.clone());
+                return Ok(());
+            }
+        }
+        self.write_touches()?;
+        self.write_record(entry)
+    }
+
+    fn write_record(&mut self, entry: &JournalEntry) -> crate::Result<()> {
         let bytes = encode_entry(entry);
-        self.writer.write_all(&bytes)?;
+        #[cfg(feature = "zstd")]
+        if let Some(block) = self.block.as_mut() {
+            block.extend_from_slice(&bytes);
+            return Ok(());
+        }
+        self.writer
+            .write_all(&bytes)
+            .map_err(JournalError::SyncFailed)?;
         Ok(())
     }
 
-    /// Flush buffered writes to disk.
-    pub fn flush(&mut self) -> io::Result<()> {
-        self.writer.flush()
+    /// Write out the touches held for coalescing.
+    fn write_touches(&mut self) -> crate::Result<()> {
+        let Some(touches) = self.touches.as_mut().filter(|t| !t.is_empty()) else {
+            return Ok(());
+        };
+        let run = coalesce(touches);
+        touches.clear();
+        for record in &run {
+            self.write_record(record)?;
+        }
+        Ok(())
+    }
+
+    /// Flush buffered writes to disk. Held touches are coalesced and
+    /// written first; a compressed journal then writes the pending records
+    /// as one block.
+    pub fn flush(&mut self) -> crate::Result<()> {
+        self.write_touches()?;
+        #[cfg(feature = "zstd")]
+        self.write_block()?;
+        self.writer.flush().map_err(JournalError::SyncFailed)?;
+        self.flushes += 1;
+        Ok(())
+    }
+
+    /// Reattach to `path` after the file was replaced (see
+    /// [`drop_banks_from_journal`]), keeping the raw or compressed mode.
+    /// Flush before the file is replaced; anything still buffered would go
+    /// to the old file.
+    pub fn reopen(&mut self, path: &Path) -> crate::Result<()> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(JournalError::OpenFailed)?;
+        self.writer = BufWriter::new(Box::new(file));
+        Ok(())
+    }
+
+    /// Number of completed flushes since the journal was opened.
+    pub fn flush_count(&self) -> u64 {
+        self.flushes
+    }
+
+    /// Bytes in the journal file plus bytes buffered for it. Records held
+    /// for the next compressed block or for coalescing are not counted
+    /// until written.
+    pub fn len_bytes(&self) -> io::Result<u64> {
+        let on_disk = self.writer.get_ref().len_bytes()?;
+        Ok(on_disk + self.writer.buffer().len() as u64)
+    }
+
+    #[cfg(feature = "zstd")]
+    fn write_block(&mut self) -> crate::Result<()> {
+        let Some(block) = self.block.as_mut().filter(|b| !b.is_empty()) else {
+            return Ok(());
+        };
+        let compressed = zstd::bulk::compress(block, 0).map_err(JournalError::SyncFailed)?;
+        let mut header = [TAG_COMPRESSED_BLOCK, 0, 0, 0, 0];
+        header[1..].copy_from_slice(&(compressed.len() as u32).to_le_bytes());
+        self.writer
+            .write_all(&header)
+            .and_then(|()| self.writer.write_all(&compressed))
+            .map_err(JournalError::SyncFailed)?;
+        block.clear();
+        Ok(())
     }
 }
 
+impl Drop for JournalWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.write_touches() {
+            log::error!("coalesced touches lost on drop: {e}");
+        }
+        #[cfg(feature = "zstd")]
+        if let Err(e) = self.write_block() {
+            log::error!("journal block lost on drop: {e}");
+        }
+    }
+}
+
+/// Collapse each run of consecutive `Touch`/`TouchMany` records into one
+/// record per entry, placed where the entry first appears in the run:
+/// `Touch` for a single access, otherwise `TouchMany` carrying the access
+/// count and the last tick. Other records pass through and end a run, so
+/// replaying the result leaves every entry as replaying `records` would.
+pub fn coalesce(records: &[JournalEntry]) -> Vec<JournalEntry> {
+    let mut out: Vec<JournalEntry> = Vec::with_capacity(records.len());
+    // Index in `out` where the current touch run starts
+    let mut run_start = 0;
+    for record in records {
+        let (bank_id, entry_id, tick, count) = match *record {
+            JournalEntry::Touch {
+                bank_id,
+                entry_id,
+                tick,
+            } => (bank_id, entry_id, tick, 1),
+            JournalEntry::TouchMany {
+                bank_id,
+                entry_id,
+                tick,
+                count,
+            } => (bank_id, entry_id, tick, count),
+            _ => {
+                out.push(record.clone());
+                run_start = out.len();
+                continue;
+            }
+        };
+        let earlier = out[run_start..].iter_mut().find(|r| match r {
+            JournalEntry::Touch {
+                bank_id: b,
+                entry_id: e,
+                ..
+            }
+            | JournalEntry::TouchMany {
+                bank_id: b,
+                entry_id: e,
+                ..
+            } => *b == bank_id && *e == entry_id,
+            _ => false,
+        });
+        match earlier {
+            Some(slot) => {
+                let seen = match slot {
+                    JournalEntry::TouchMany { count, .. } => *count,
+                    _ => 1,
+                };
+                *slot = JournalEntry::TouchMany {
+                    bank_id,
+                    entry_id,
+                    tick,
+                    count: seen.saturating_add(count),
+                };
+            }
+            None => out.push(record.clone()),
+        }
+    }
+    out
+}
+
 /// Journal reader for replay during crash recovery.
 pub struct JournalReader;
 
@@ -120,142 +487,400 @@ impl JournalReader {
     /// Read all valid entries from a journal file.
     /// Tolerates truncated final entry (crash mid-write).
     pub fn read_all(path: &Path) -> crate::Result<Vec<JournalEntry>> {
-        let data = match std::fs::read(path) {
-            Ok(d) => d,
-            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
-            Err(e) => return Err(crate::DataBankError::Io(e)),
-        };
-
-        let mut entries = Vec::new();
-        let mut cursor = 0;
-
-        while cursor < data.len() {
-            match decode_entry(&data[cursor..]) {
-                Some((entry, consumed)) => {
-                    entries.push(entry);
-                    cursor += consumed;
-                }
-                None => {
-                    // Truncated or corrupt entry -- stop here
-                    log::warn!(
-                        "Journal truncated at byte {}/{}, recovered {} entries",
-                        cursor,
-                        data.len(),
-                        entries.len()
-                    );
-                    break;
-                }
-            }
+        let (entries, corrupt) = Self::read_checked(path)?;
+        if let Some(e) = corrupt {
+            log::warn!("{e}, recovered {} entries", entries.len());
         }
-
         Ok(entries)
     }
 
+    /// Read all valid entries, also reporting where decoding stopped early.
+    ///
+    /// A missing file reads as empty. Failing to open an existing file is
+    /// `JournalError::OpenFailed`; a record that fails to decode ends the
+    /// read and is returned alongside the entries recovered before it as
+    /// `JournalError::CorruptRecord`.
+    pub fn read_checked(
+        path: &Path,
+    ) -> crate::Result<(Vec<JournalEntry>, Option<JournalError>)> {
+        let mut stream = Self::stream(path)?;
+        let entries = stream.by_ref().collect();
+        Ok((entries, stream.take_error()))
+    }
+
+    /// Iterate a journal's records without reading the whole file, for
+    /// replaying journals too large to hold in memory. Only the current
+    /// record (or compressed block) is buffered.
+    ///
+    /// A missing file yields no records. Failing to open an existing file
+    /// is `JournalError::OpenFailed`; where decoding stopped early is left
+    /// in [`JournalStream::take_error`] once the iterator is exhausted.
+    pub fn stream(path: &Path) -> crate::Result<JournalStream> {
+        let source = match File::open(path) {
+            Ok(file) => Some(BufReader::new(file)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(JournalError::OpenFailed(e).into()),
+        };
+        Ok(JournalStream {
+            source,
+            record: Vec::new(),
+            pending: VecDeque::new(),
+            offset: 0,
+            error: None,
+        })
+    }
+
     /// Replay journal entries onto an existing bank cluster.
     /// Returns count of entries replayed.
     pub fn replay(entries: &[JournalEntry], cluster: &mut BankCluster) -> crate::Result<usize> {
-        let mut count = 0;
+        let (applied, _skipped) = Self::replay_counted(entries, cluster);
+        Ok(applied)
+    }
+
+    /// Replay journal entries, returning `(applied, skipped)`.
+    ///
+    /// A record is skipped when its bank (or, for per-entry records, its
+    /// entry) is not in the cluster, or when it is an `Insert` whose vector
+    /// fails its entry checksum.
+    pub fn replay_counted(entries: &[JournalEntry], cluster: &mut BankCluster) -> (usize, usize) {
+        let counts = Self::replay_verified(entries, cluster);
+        (counts.applied, entries.len() - counts.applied)
+    }
+
+    /// Replay journal entries, verifying each `Insert` that carries an entry
+    /// checksum before applying it. A mismatching record is not applied and
+    /// is counted apart from records skipped for a missing target.
+    pub fn replay_verified(entries: &[JournalEntry], cluster: &mut BankCluster) -> ReplayCounts {
+        let mut counts = ReplayCounts::default();
         for entry in entries {
-            match entry {
-                JournalEntry::Insert {
-                    bank_id,
-                    vector,
-                    temperature,
-                    tick,
-                    ..
-                } => {
-                    if let Some(bank) = cluster.get_mut(*bank_id) {
-                        let _ = bank.insert(vector.clone(), *temperature, *tick);
-                        count += 1;
-                    }
-                }
-                JournalEntry::Remove {
-                    bank_id, entry_id, ..
-                } => {
-                    if let Some(bank) = cluster.get_mut(*bank_id) {
-                        bank.remove(*entry_id);
-                        count += 1;
-                    }
-                }
-                JournalEntry::Touch {
-                    bank_id,
-                    entry_id,
-                    tick,
-                } => {
-                    if let Some(bank) = cluster.get_mut(*bank_id) {
-                        if let Some(entry) = bank.get_mut(*entry_id) {
-                            entry.touch(*tick);
-                            count += 1;
-                        }
-                    }
-                }
-                JournalEntry::AddEdge {
-                    bank_id,
-                    entry_id,
-                    edge,
-                } => {
-                    if let Some(bank) = cluster.get_mut(*bank_id) {
-                        let _ = bank.add_edge(*entry_id, edge.clone());
-                        count += 1;
-                    }
-                }
-                JournalEntry::SetTemperature {
-                    bank_id,
-                    entry_id,
-                    temperature,
-                } => {
-                    if let Some(bank) = cluster.get_mut(*bank_id) {
-                        if let Some(entry) = bank.get_mut(*entry_id) {
-                            entry.temperature = *temperature;
-                            count += 1;
-                        }
-                    }
+            counts.replay(entry, cluster);
+        }
+        counts
+    }
+}
+
+/// Streaming journal reader returned by [`JournalReader::stream`].
+pub struct JournalStream {
+    /// None for a missing file, or once the end or a bad record is reached.
+    source: Option<BufReader<File>>,
+    /// Bytes of the record being decoded, reused between records.
+    record: Vec<u8>,
+    /// Records of the current compressed block not yet yielded.
+    pending: VecDeque<JournalEntry>,
+    offset: u64,
+    error: Option<JournalError>,
+}
+
+impl JournalStream {
+    /// Bytes of the file consumed so far (whole records and blocks only).
+    pub fn bytes_consumed(&self) -> u64 {
+        self.offset
+    }
+
+    /// Where decoding stopped early, as `JournalError::CorruptRecord`.
+    /// None while records remain or if the whole file decoded.
+    pub fn take_error(&mut self) -> Option<JournalError> {
+        self.error.take()
+    }
+
+    /// Grow `record` to `len` bytes from the file. False at end of file.
+    fn fill(&mut self, len: usize) -> bool {
+        let Some(source) = self.source.as_mut() else {
+            return false;
+        };
+        let want = len.saturating_sub(self.record.len()) as u64;
+        match source.by_ref().take(want).read_to_end(&mut self.record) {
+            Ok(_) => self.record.len() == len,
+            Err(e) => {
+                log::warn!("journal read failed at byte {}: {e}", self.offset);
+                false
+            }
+        }
+    }
+
+    /// Stop at the current record: truncated, corrupt, or unreadable.
+    fn stop(&mut self) -> Option<JournalEntry> {
+        self.source = None;
+        self.error = Some(JournalError::CorruptRecord {
+            offset: self.offset as usize,
+        });
+        None
+    }
+}
+
+impl Iterator for JournalStream {
+    type Item = JournalEntry;
+
+    fn next(&mut self) -> Option<JournalEntry> {
+        loop {
+            if let Some(entry) = self.pending.pop_front() {
+                return Some(entry);
+            }
+            self.source.as_ref()?;
+            self.record.clear();
+            if !self.fill(1) {
+                // Clean end of file
+                self.source = None;
+                return None;
+            }
+            let Some(header) = header_len(self.record[0]) else {
+                return self.stop();
+            };
+            if !self.fill(header) || !self.fill(record_len(&self.record)) {
+                return self.stop();
+            }
+
+            let len = self.record.len() as u64;
+            if self.record[0] == TAG_COMPRESSED_BLOCK {
+                let Some((entries, _)) = decode_block(&self.record) else {
+                    return self.stop();
+                };
+                self.pending.extend(entries);
+                self.offset += len;
+                continue;
+            }
+            let Some((entry, _)) = decode_entry(&self.record) else {
+                return self.stop();
+            };
+            self.offset += len;
+            return Some(entry);
+        }
+    }
+}
+
+/// What [`JournalReader::replay_verified`] did with each record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplayCounts {
+    pub applied: usize,
+    /// Records whose bank or entry is not in the cluster.
+    pub skipped: usize,
+    /// `Insert` records whose vector does not match the producer's checksum.
+    pub checksum_mismatches: usize,
+}
+
+impl ReplayCounts {
+    /// Verify and apply one record, counting what happened to it.
+    pub(crate) fn replay(&mut self, entry: &JournalEntry, cluster: &mut BankCluster) {
+        if let JournalEntry::Insert {
+            vector,
+            checksum: Some(checksum),
+            ..
+        } = entry
+        {
+            if vector_checksum(vector) != *checksum {
+                self.checksum_mismatches += 1;
+                return;
+            }
+        }
+        if let JournalEntry::InsertCopy { entry, .. } = entry {
+            if entry.compute_checksum() != entry.checksum {
+                self.checksum_mismatches += 1;
+                return;
+            }
+        }
+        if apply_entry(entry, cluster) {
+            self.applied += 1;
+        } else {
+            self.skipped += 1;
+        }
+    }
+}
+
+/// Apply one journal record. Returns false if its target was not found.
+pub(crate) fn apply_entry(entry: &JournalEntry, cluster: &mut BankCluster) -> bool {
+    match entry {
+        JournalEntry::Insert {
+            bank_id,
+            vector,
+            temperature,
+            tick,
+            expires_at_tick,
+            namespace,
+            ..
+        } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                let vector = vector.clone();
+                return bank
+                    .insert_entry(vector, *temperature, *tick, *namespace, *expires_at_tick)
+                    .is_ok();
+            }
+        }
+        JournalEntry::Remove {
+            bank_id, entry_id, ..
+        } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                bank.remove(*entry_id);
+                return true;
+            }
+        }
+        JournalEntry::Touch {
+            bank_id,
+            entry_id,
+            tick,
+        } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                return bank.touch_entry(*entry_id, *tick).is_ok();
+            }
+        }
+        JournalEntry::TouchMany {
+            bank_id,
+            entry_id,
+            tick,
+            count,
+        } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                if let Some(mut entry) = bank.get_mut(*entry_id) {
+                    entry.access_count = entry.access_count.saturating_add(*count);
+                    entry.last_accessed_tick = *tick;
+                    return true;
                 }
-                JournalEntry::Promote {
-                    bank_id,
-                    entry_id,
-                    new_temp,
-                } => {
-                    if let Some(bank) = cluster.get_mut(*bank_id) {
-                        if let Some(entry) = bank.get_mut(*entry_id) {
-                            entry.temperature = *new_temp;
-                            count += 1;
-                        }
-                    }
+            }
+        }
+        JournalEntry::AddEdge {
+            bank_id,
+            entry_id,
+            edge,
+        } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                let _ = bank.add_edge(*entry_id, *edge);
+                return true;
+            }
+        }
+        JournalEntry::SetTemperature {
+            bank_id,
+            entry_id,
+            temperature,
+        } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                if bank.set_temperature(*entry_id, *temperature).is_ok() {
+                    return true;
                 }
-                JournalEntry::Demote {
-                    bank_id,
-                    entry_id,
-                    new_temp,
-                } => {
-                    if let Some(bank) = cluster.get_mut(*bank_id) {
-                        if let Some(entry) = bank.get_mut(*entry_id) {
-                            entry.temperature = *new_temp;
-                            count += 1;
-                        }
-                    }
+            }
+        }
+        JournalEntry::Promote {
+            bank_id,
+            entry_id,
+            new_temp,
+        }
+        | JournalEntry::Demote {
+            bank_id,
+            entry_id,
+            new_temp,
+        } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                if bank.set_temperature(*entry_id, *new_temp).is_ok() {
+                    return true;
                 }
-                JournalEntry::BatchEvict {
-                    bank_id,
-                    entry_ids,
-                } => {
-                    if let Some(bank) = cluster.get_mut(*bank_id) {
-                        for eid in entry_ids {
-                            bank.remove(*eid);
-                        }
-                        count += 1;
-                    }
+            }
+        }
+        JournalEntry::BatchEvict {
+            bank_id,
+            entry_ids,
+        } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                for eid in entry_ids {
+                    bank.remove(*eid);
                 }
+                return true;
+            }
+        }
+        JournalEntry::Pin { bank_id, entry_id } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                return bank.pin(*entry_id).is_ok();
+            }
+        }
+        JournalEntry::Unpin { bank_id, entry_id } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                return bank.unpin(*entry_id).is_ok();
+            }
+        }
+        JournalEntry::RemoveEdgesTo { bank_id, target } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                bank.remove_all_edges_to(*target);
+                return true;
+            }
+        }
+        JournalEntry::InsertCopy { bank_id, entry } => {
+            if let Some(bank) = cluster.get_mut(*bank_id) {
+                return bank.insert_copy(entry).is_ok();
             }
         }
-        Ok(count)
     }
+    false
+}
+
+/// Whether [`apply_entry`] would change `cluster`: the target bank, and for
+/// per-entry records the target entry, exists, a `Remove` target is not
+/// under a lease (which [`DataBank::remove`] refuses), and an `Insert` fits
+/// the bank's width and either has room or an entry to evict for it. The
+/// insert hook and byte budget are only checked by the insert itself.
+///
+/// [`DataBank::remove`]: crate::bank::DataBank::remove
+pub(crate) fn entry_applies(entry: &JournalEntry, cluster: &BankCluster) -> bool {
+    let Some(bank) = cluster.get(entry.bank_id()) else {
+        return false;
+    };
+    match entry {
+        JournalEntry::Insert { vector, tick, .. } => bank.has_room_for(vector, *tick),
+        JournalEntry::InsertCopy { entry, .. } => {
+            let max = bank.config().max_edges_per_entry as usize;
+            (max == 0 || entry.edges().len() <= max)
+                && bank.has_room_for(entry.vector(), entry.created_tick())
+        }
+        JournalEntry::BatchEvict { .. } | JournalEntry::RemoveEdgesTo { .. } => true,
+        JournalEntry::Remove { entry_id, .. } => {
+            bank.get(*entry_id).is_some() && bank.lease_holder(*entry_id).is_none()
+        }
+        JournalEntry::Touch { entry_id, .. }
+        | JournalEntry::TouchMany { entry_id, .. }
+        | JournalEntry::AddEdge { entry_id, .. }
+        | JournalEntry::SetTemperature { entry_id, .. }
+        | JournalEntry::Promote { entry_id, .. }
+        | JournalEntry::Demote { entry_id, .. }
+        | JournalEntry::Pin { entry_id, .. }
+        | JournalEntry::Unpin { entry_id, .. } => bank.get(*entry_id).is_some(),
+    }
+}
+
+/// Rewrite a journal without the records for `banks`, after those banks
+/// were snapshotted on their own. Returns the number of records dropped.
+///
+/// Surviving records are written uncompressed to a temp file, synced, and
+/// renamed over `path`, so a crash leaves either the old or the new
+/// journal. A corrupt tail is dropped, as replay would. Flush any writer
+/// first and [`reopen`](JournalWriter::reopen) it afterwards.
+pub fn drop_banks_from_journal(path: &Path, banks: &[BankId]) -> crate::Result<usize> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let (entries, _) = JournalReader::read_checked(path)?;
+    let mut kept = Vec::new();
+    let mut dropped = 0;
+    for entry in &entries {
+        if banks.contains(&entry.bank_id()) {
+            dropped += 1;
+        } else {
+            kept.extend_from_slice(&encode_entry(entry));
+        }
+    }
+    if dropped == 0 {
+        return Ok(0);
+    }
+    let temp = path.with_extension("journal.tmp");
+    {
+        let mut file = std::fs::File::create(&temp).map_err(JournalError::SyncFailed)?;
+        file.write_all(&kept)
+            .and_then(|()| file.sync_all())
+            .map_err(JournalError::SyncFailed)?;
+    }
+    std::fs::rename(&temp, path).map_err(JournalError::SyncFailed)?;
+    Ok(dropped)
 }
 
 /// Truncate (reset) a journal file after a full snapshot completes.
 pub fn truncate_journal(path: &Path) -> io::Result<()> {
     if path.exists() {
-        std::fs::write(path, &[])?;
+        std::fs::write(path, [])?;
     }
     Ok(())
 }
@@ -273,8 +898,22 @@ fn encode_entry(entry: &JournalEntry) -> Vec<u8> {
             vector,
             temperature,
             tick,
+            checksum,
+            expires_at_tick,
+            namespace,
         } => {
-            buf.push(TAG_INSERT);
+            let namespaced = *namespace != 0;
+            let checksum = if namespaced || expires_at_tick.is_some() {
+                Some(checksum.unwrap_or_else(|| vector_checksum(vector)))
+            } else {
+                *checksum
+            };
+            buf.push(match (expires_at_tick, checksum) {
+                _ if namespaced => TAG_INSERT_NAMESPACED,
+                (Some(_), _) => TAG_INSERT_EXPIRING,
+                (None, Some(_)) => TAG_INSERT_CHECKED,
+                (None, None) => TAG_INSERT,
+            });
             buf.extend_from_slice(&bank_id.0.to_le_bytes());
             buf.extend_from_slice(&entry_id.0.to_le_bytes());
             buf.extend_from_slice(&(*tick).to_le_bytes());
@@ -286,6 +925,15 @@ fn encode_entry(entry: &JournalEntry) -> Vec<u8> {
                 buf.push(s.magnitude);
                 buf.push(s.multiplier);
             }
+            if let Some(checksum) = checksum {
+                buf.extend_from_slice(&checksum.to_le_bytes());
+            }
+            if namespaced {
+                buf.extend_from_slice(&expires_at_tick.unwrap_or(u64::MAX).to_le_bytes());
+                buf.extend_from_slice(&namespace.to_le_bytes());
+            } else if let Some(at) = expires_at_tick {
+                buf.extend_from_slice(&at.to_le_bytes());
+            }
         }
         JournalEntry::Remove { bank_id, entry_id } => {
             buf.push(TAG_REMOVE);
@@ -310,11 +958,7 @@ fn encode_entry(entry: &JournalEntry) -> Vec<u8> {
             buf.push(TAG_ADD_EDGE);
             buf.extend_from_slice(&bank_id.0.to_le_bytes());
             buf.extend_from_slice(&entry_id.0.to_le_bytes());
-            buf.push(edge.edge_type.as_u8());
-            buf.extend_from_slice(&edge.target.bank.0.to_le_bytes());
-            buf.extend_from_slice(&edge.target.entry.0.to_le_bytes());
-            buf.push(edge.weight);
-            buf.extend_from_slice(&edge.created_tick.to_le_bytes());
+            encode_edge(&mut buf, edge);
         }
         JournalEntry::SetTemperature {
             bank_id,
@@ -357,6 +1001,72 @@ fn encode_entry(entry: &JournalEntry) -> Vec<u8> {
                 buf.extend_from_slice(&eid.0.to_le_bytes());
             }
         }
+        JournalEntry::Pin { bank_id, entry_id } => {
+            buf.push(TAG_PIN);
+            buf.extend_from_slice(&bank_id.0.to_le_bytes());
+            buf.extend_from_slice(&entry_id.0.to_le_bytes());
+        }
+        JournalEntry::Unpin { bank_id, entry_id } => {
+            buf.push(TAG_UNPIN);
+            buf.extend_from_slice(&bank_id.0.to_le_bytes());
+            buf.extend_from_slice(&entry_id.0.to_le_bytes());
+        }
+        JournalEntry::TouchMany {
+            bank_id,
+            entry_id,
+            tick,
+            count,
+        } => {
+            buf.push(TAG_TOUCH_MANY);
+            buf.extend_from_slice(&bank_id.0.to_le_bytes());
+            buf.extend_from_slice(&entry_id.0.to_le_bytes());
+            buf.extend_from_slice(&tick.to_le_bytes());
+            buf.extend_from_slice(&count.to_le_bytes());
+        }
+        JournalEntry::RemoveEdgesTo { bank_id, target } => {
+            buf.push(TAG_REMOVE_EDGES_TO);
+            buf.extend_from_slice(&bank_id.0.to_le_bytes());
+            buf.extend_from_slice(&target.entry.0.to_le_bytes());
+            buf.extend_from_slice(&target.bank.0.to_le_bytes());
+        }
+        JournalEntry::InsertCopy { bank_id, entry } => {
+            buf.push(TAG_INSERT_COPY);
+            buf.extend_from_slice(&bank_id.0.to_le_bytes());
+            buf.extend_from_slice(&entry.id.0.to_le_bytes());
+            buf.extend_from_slice(&entry.created_tick.to_le_bytes());
+            buf.push(temperature_to_u8(entry.temperature));
+            buf.extend_from_slice(&(entry.vector.len() as u16).to_le_bytes());
+            buf.extend_from_slice(&(entry.edges.len() as u16).to_le_bytes());
+            for s in &entry.vector {
+                buf.push(s.polarity as u8);
+                buf.push(s.magnitude);
+                buf.push(s.multiplier);
+            }
+            for edge in entry.edges.iter() {
+                encode_edge(&mut buf, edge);
+            }
+            buf.extend_from_slice(&entry.checksum.to_le_bytes());
+            buf.extend_from_slice(&entry.origin.0.to_le_bytes());
+            buf.extend_from_slice(&entry.expires_at_tick.unwrap_or(u64::MAX).to_le_bytes());
+            buf.extend_from_slice(&entry.namespace.to_le_bytes());
+            buf.extend_from_slice(&entry.last_accessed_tick.to_le_bytes());
+            buf.extend_from_slice(&entry.access_count.to_le_bytes());
+            buf.push(entry.confidence);
+            buf.push(u8::from(entry.pinned));
+        }
+    }
+
+    let extended = match entry {
+        JournalEntry::Insert { temperature, .. }
+        | JournalEntry::SetTemperature { temperature, .. } => temperature.is_extended(),
+        JournalEntry::InsertCopy { entry, .. } => entry.temperature.is_extended(),
+        JournalEntry::Promote { new_temp, .. } | JournalEntry::Demote { new_temp, .. } => {
+            new_temp.is_extended()
+        }
+        _ => false,
+    };
+    if extended {
+        buf[0] |= TAG_FLAG_EXTENDED_TEMPERATURE;
     }
 
     // Append CRC32
@@ -365,27 +1075,150 @@ fn encode_entry(entry: &JournalEntry) -> Vec<u8> {
     buf
 }
 
+/// Decode one compressed block. Returns its records and the bytes consumed,
+/// or None if the block is truncated or any record inside fails to decode.
+fn decode_block(data: &[u8]) -> Option<(Vec<JournalEntry>, usize)> {
+    let len = u32::from_le_bytes(data.get(1..5)?.try_into().ok()?) as usize;
+    let frame = data.get(5..5 + len)?;
+    let records = decompress_block(frame)?;
+
+    let mut entries = Vec::new();
+    let mut cursor = 0;
+    while cursor < records.len() {
+        let (entry, consumed) = decode_entry(&records[cursor..])?;
+        entries.push(entry);
+        cursor += consumed;
+    }
+    Some((entries, 5 + len))
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_block(frame: &[u8]) -> Option<Vec<u8>> {
+    zstd::stream::decode_all(frame).ok()
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_block(_frame: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+/// Split a record tag into its base tag and whether
+/// [`TAG_FLAG_EXTENDED_TEMPERATURE`] is set. None if the flag is set on a
+/// tag without a temperature byte.
+fn split_tag(tag: u8) -> Option<(u8, bool)> {
+    if tag == TAG_COMPRESSED_BLOCK || tag & TAG_FLAG_EXTENDED_TEMPERATURE == 0 {
+        return Some((tag, false));
+    }
+    let base = tag & !TAG_FLAG_EXTENDED_TEMPERATURE;
+    matches!(
+        base,
+        TAG_INSERT
+            | TAG_INSERT_CHECKED
+            | TAG_INSERT_EXPIRING
+            | TAG_INSERT_NAMESPACED
+            | TAG_INSERT_COPY
+            | TAG_SET_TEMP
+            | TAG_PROMOTE
+            | TAG_DEMOTE
+    )
+    .then_some((base, true))
+}
+
+/// Bytes of a record (or block) needed before its length is known, by tag.
+/// None for an unknown tag.
+fn header_len(tag: u8) -> Option<usize> {
+    let (tag, _) = split_tag(tag)?;
+    match tag {
+        TAG_INSERT | TAG_INSERT_CHECKED | TAG_INSERT_EXPIRING | TAG_INSERT_NAMESPACED => {
+            Some(28)
+        }
+        TAG_INSERT_COPY => Some(30),
+        TAG_BATCH_EVICT => Some(11),
+        TAG_COMPRESSED_BLOCK => Some(5),
+        TAG_REMOVE | TAG_TOUCH | TAG_ADD_EDGE | TAG_SET_TEMP | TAG_PROMOTE | TAG_DEMOTE
+        | TAG_PIN | TAG_UNPIN | TAG_TOUCH_MANY | TAG_REMOVE_EDGES_TO => Some(1),
+        _ => None,
+    }
+}
+
+/// Full length of the record (or block) whose first
+/// [`header_len`] bytes are `header`, CRC included.
+fn record_len(header: &[u8]) -> usize {
+    let u16_at = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]) as usize;
+    let tag = split_tag(header[0]).map_or(header[0], |(tag, _)| tag);
+    match tag {
+        TAG_INSERT => 28 + u16_at(26) * 3 + 4,
+        TAG_INSERT_CHECKED => 28 + u16_at(26) * 3 + 8,
+        TAG_INSERT_EXPIRING => 28 + u16_at(26) * 3 + 16,
+        TAG_INSERT_NAMESPACED => 28 + u16_at(26) * 3 + 18,
+        TAG_INSERT_COPY => 30 + u16_at(26) * 3 + u16_at(28) * EDGE_LEN + 40,
+        TAG_BATCH_EVICT => 11 + u16_at(9) * 8 + 4,
+        TAG_COMPRESSED_BLOCK => {
+            5 + u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize
+        }
+        TAG_REMOVE | TAG_PIN | TAG_UNPIN => 21,
+        TAG_SET_TEMP | TAG_PROMOTE | TAG_DEMOTE => 22,
+        TAG_TOUCH | TAG_REMOVE_EDGES_TO => 29,
+        TAG_TOUCH_MANY => 33,
+        TAG_ADD_EDGE => 47,
+        _ => 0, // unknown tags are rejected by header_len
+    }
+}
+
 fn decode_entry(data: &[u8]) -> Option<(JournalEntry, usize)> {
     if data.is_empty() {
         return None;
     }
-    let tag = data[0];
+    let (tag, extended) = split_tag(data[0])?;
 
     match tag {
-        TAG_INSERT => decode_insert(data),
+        TAG_INSERT | TAG_INSERT_CHECKED | TAG_INSERT_EXPIRING | TAG_INSERT_NAMESPACED => {
+            decode_insert(data, extended)
+        }
         TAG_REMOVE => decode_remove(data),
         TAG_TOUCH => decode_touch(data),
         TAG_ADD_EDGE => decode_add_edge(data),
-        TAG_SET_TEMP => decode_set_temp(data),
-        TAG_PROMOTE => decode_promote(data),
-        TAG_DEMOTE => decode_demote(data),
+        TAG_SET_TEMP => decode_set_temp(data, extended),
+        TAG_PROMOTE => decode_promote(data, extended),
+        TAG_DEMOTE => decode_demote(data, extended),
         TAG_BATCH_EVICT => decode_batch_evict(data),
+        TAG_PIN | TAG_UNPIN => decode_pin(data),
+        TAG_TOUCH_MANY => decode_touch_many(data),
+        TAG_REMOVE_EDGES_TO => decode_remove_edges_to(data),
+        TAG_INSERT_COPY => decode_insert_copy(data, extended),
         _ => None,
     }
 }
 
-fn decode_insert(data: &[u8]) -> Option<(JournalEntry, usize)> {
-    // tag(1) + bank_id(8) + entry_id(8) + tick(8) + temp(1) + vec_len(2) + signals(N*3) + crc(4)
+/// Encoded size of one edge: type, target bank, target entry, weight,
+/// created tick.
+const EDGE_LEN: usize = 26;
+
+fn encode_edge(buf: &mut Vec<u8>, edge: &Edge) {
+    buf.push(edge.edge_type.as_u8());
+    buf.extend_from_slice(&edge.target.bank.0.to_le_bytes());
+    buf.extend_from_slice(&edge.target.entry.0.to_le_bytes());
+    buf.push(edge.weight);
+    buf.extend_from_slice(&edge.created_tick.to_le_bytes());
+}
+
+/// Decode an edge from the first [`EDGE_LEN`] bytes of `data`.
+fn decode_edge(data: &[u8]) -> Option<Edge> {
+    Some(Edge {
+        edge_type: EdgeType::from_u8(data[0]).unwrap_or(EdgeType::RelatedTo),
+        target: BankRef {
+            bank: BankId(u64::from_le_bytes(data.get(1..9)?.try_into().ok()?)),
+            entry: EntryId(u64::from_le_bytes(data.get(9..17)?.try_into().ok()?)),
+        },
+        weight: data[17],
+        created_tick: u64::from_le_bytes(data.get(18..26)?.try_into().ok()?),
+    })
+}
+
+fn decode_insert(data: &[u8], extended: bool) -> Option<(JournalEntry, usize)> {
+    // tag(1) + bank_id(8) + entry_id(8) + tick(8) + temp(1) + vec_len(2) + signals(N*3)
+    //   + [checksum(4), tags 11, 13 and 14] + [expires_at_tick(8), tags 13 and 14]
+    //   + [namespace(2), tag 14 only] + crc(4)
     let min_len = 1 + 8 + 8 + 8 + 1 + 2 + 4;
     if data.len() < min_len {
         return None;
@@ -393,10 +1226,17 @@ fn decode_insert(data: &[u8]) -> Option<(JournalEntry, usize)> {
     let bank_id = BankId(u64::from_le_bytes(data[1..9].try_into().ok()?));
     let entry_id = EntryId(u64::from_le_bytes(data[9..17].try_into().ok()?));
     let tick = u64::from_le_bytes(data[17..25].try_into().ok()?);
-    let temperature = u8_to_temperature(data[25])?;
+    let temperature = u8_to_temperature(data[25], extended)?;
     let vec_len = u16::from_le_bytes(data[26..28].try_into().ok()?) as usize;
 
-    let body_len = 28 + vec_len * 3; // 3 bytes per Signal
+    let signals_end = 28 + vec_len * 3; // 3 bytes per Signal
+    let tag = data[0] & !TAG_FLAG_EXTENDED_TEMPERATURE;
+    let namespaced = tag == TAG_INSERT_NAMESPACED;
+    let expiring = namespaced || tag == TAG_INSERT_EXPIRING;
+    let checked = expiring || tag == TAG_INSERT_CHECKED;
+    let checksum_end = signals_end + if checked { 4 } else { 0 };
+    let expiry_end = checksum_end + if expiring { 8 } else { 0 };
+    let body_len = expiry_end + if namespaced { 2 } else { 0 };
     let total = body_len + 4; // + crc
     if data.len() < total {
         return None;
@@ -417,6 +1257,22 @@ fn decode_insert(data: &[u8]) -> Option<(JournalEntry, usize)> {
         let multiplier = data[offset + 2];
         vector.push(Signal::new_raw(polarity, magnitude, multiplier));
     }
+    let checksum = if checked {
+        Some(u32::from_le_bytes(data[signals_end..checksum_end].try_into().ok()?))
+    } else {
+        None
+    };
+    let expires_at_tick = if expiring {
+        Some(u64::from_le_bytes(data[checksum_end..expiry_end].try_into().ok()?))
+            .filter(|&at| !namespaced || at != u64::MAX)
+    } else {
+        None
+    };
+    let namespace = if namespaced {
+        u16::from_le_bytes(data[expiry_end..body_len].try_into().ok()?)
+    } else {
+        0
+    };
 
     Some((
         JournalEntry::Insert {
@@ -425,6 +1281,9 @@ fn decode_insert(data: &[u8]) -> Option<(JournalEntry, usize)> {
             vector,
             temperature,
             tick,
+            checksum,
+            expires_at_tick,
+            namespace,
         },
         total,
     ))
@@ -447,6 +1306,26 @@ fn decode_remove(data: &[u8]) -> Option<(JournalEntry, usize)> {
     Some((JournalEntry::Remove { bank_id, entry_id }, 21))
 }
 
+fn decode_pin(data: &[u8]) -> Option<(JournalEntry, usize)> {
+    // Same layout as Remove: tag(1) + bank_id(8) + entry_id(8) + crc(4) = 21
+    if data.len() < 21 {
+        return None;
+    }
+    let body_len = 17;
+    let stored_crc = u32::from_le_bytes(data[body_len..21].try_into().ok()?);
+    if stored_crc != crc32(&data[..body_len]) {
+        return None;
+    }
+    let bank_id = BankId(u64::from_le_bytes(data[1..9].try_into().ok()?));
+    let entry_id = EntryId(u64::from_le_bytes(data[9..17].try_into().ok()?));
+    let entry = if data[0] == TAG_PIN {
+        JournalEntry::Pin { bank_id, entry_id }
+    } else {
+        JournalEntry::Unpin { bank_id, entry_id }
+    };
+    Some((entry, 21))
+}
+
 fn decode_touch(data: &[u8]) -> Option<(JournalEntry, usize)> {
     // tag(1) + bank_id(8) + entry_id(8) + tick(8) + crc(4) = 29
     if data.len() < 29 {
@@ -472,6 +1351,57 @@ fn decode_touch(data: &[u8]) -> Option<(JournalEntry, usize)> {
     ))
 }
 
+fn decode_touch_many(data: &[u8]) -> Option<(JournalEntry, usize)> {
+    // tag(1) + bank_id(8) + entry_id(8) + tick(8) + count(4) + crc(4) = 33
+    if data.len() < 33 {
+        return None;
+    }
+    let body_len = 29;
+    let stored_crc = u32::from_le_bytes(data[body_len..33].try_into().ok()?);
+    if stored_crc != crc32(&data[..body_len]) {
+        return None;
+    }
+
+    let bank_id = BankId(u64::from_le_bytes(data[1..9].try_into().ok()?));
+    let entry_id = EntryId(u64::from_le_bytes(data[9..17].try_into().ok()?));
+    let tick = u64::from_le_bytes(data[17..25].try_into().ok()?);
+    let count = u32::from_le_bytes(data[25..29].try_into().ok()?);
+
+    Some((
+        JournalEntry::TouchMany {
+            bank_id,
+            entry_id,
+            tick,
+            count,
+        },
+        33,
+    ))
+}
+
+fn decode_remove_edges_to(data: &[u8]) -> Option<(JournalEntry, usize)> {
+    // tag(1) + bank_id(8) + target_entry(8) + target_bank(8) + crc(4) = 29
+    if data.len() < 29 {
+        return None;
+    }
+    let body_len = 25;
+    let stored_crc = u32::from_le_bytes(data[body_len..29].try_into().ok()?);
+    if stored_crc != crc32(&data[..body_len]) {
+        return None;
+    }
+
+    let bank_id = BankId(u64::from_le_bytes(data[1..9].try_into().ok()?));
+    let entry = EntryId(u64::from_le_bytes(data[9..17].try_into().ok()?));
+    let bank = BankId(u64::from_le_bytes(data[17..25].try_into().ok()?));
+
+    Some((
+        JournalEntry::RemoveEdgesTo {
+            bank_id,
+            target: BankRef { bank, entry },
+        },
+        29,
+    ))
+}
+
 fn decode_add_edge(data: &[u8]) -> Option<(JournalEntry, usize)> {
     // tag(1) + bank_id(8) + entry_id(8) + edge_type(1) + target_bank(8) + target_entry(8) + weight(1) + tick(8) + crc(4) = 47
     if data.len() < 47 {
@@ -485,31 +1415,68 @@ fn decode_add_edge(data: &[u8]) -> Option<(JournalEntry, usize)> {
 
     let bank_id = BankId(u64::from_le_bytes(data[1..9].try_into().ok()?));
     let entry_id = EntryId(u64::from_le_bytes(data[9..17].try_into().ok()?));
-    let edge_type = EdgeType::from_u8(data[17]).unwrap_or(EdgeType::RelatedTo);
-    let target_bank = BankId(u64::from_le_bytes(data[18..26].try_into().ok()?));
-    let target_entry = EntryId(u64::from_le_bytes(data[26..34].try_into().ok()?));
-    let weight = data[34];
-    let created_tick = u64::from_le_bytes(data[35..43].try_into().ok()?);
+    let edge = decode_edge(&data[17..43])?;
 
     Some((
         JournalEntry::AddEdge {
             bank_id,
             entry_id,
-            edge: Edge {
-                edge_type,
-                target: BankRef {
-                    bank: target_bank,
-                    entry: target_entry,
-                },
-                weight,
-                created_tick,
-            },
+            edge,
         },
         47,
     ))
 }
 
-fn decode_set_temp(data: &[u8]) -> Option<(JournalEntry, usize)> {
+fn decode_insert_copy(data: &[u8], extended: bool) -> Option<(JournalEntry, usize)> {
+    // tag(1) + bank_id(8) + entry_id(8) + tick(8) + temp(1) + vec_len(2) + edge_count(2)
+    //   + signals(N*3) + edges(E*26) + checksum(4) + origin(8) + expires_at_tick(8)
+    //   + namespace(2) + last_accessed_tick(8) + access_count(4) + confidence(1)
+    //   + pinned(1) + crc(4)
+    if data.len() < 30 {
+        return None;
+    }
+    let total = record_len(data);
+    let body_len = total - 4;
+    if data.len() < total {
+        return None;
+    }
+    let stored_crc = u32::from_le_bytes(data[body_len..total].try_into().ok()?);
+    if stored_crc != crc32(&data[..body_len]) {
+        return None;
+    }
+
+    let bank_id = BankId(u64::from_le_bytes(data[1..9].try_into().ok()?));
+    let id = EntryId(u64::from_le_bytes(data[9..17].try_into().ok()?));
+    let created_tick = u64::from_le_bytes(data[17..25].try_into().ok()?);
+    let temperature = u8_to_temperature(data[25], extended)?;
+    let vec_len = u16::from_le_bytes(data[26..28].try_into().ok()?) as usize;
+    let edge_count = u16::from_le_bytes(data[28..30].try_into().ok()?) as usize;
+    let vector = data[30..30 + vec_len * 3]
+        .chunks_exact(3)
+        .map(|s| Signal::new_raw(s[0] as i8, s[1], s[2]))
+        .collect();
+    let edges_start = 30 + vec_len * 3;
+    let mut edges = EdgeList::new();
+    for chunk in data[edges_start..edges_start + edge_count * EDGE_LEN].chunks_exact(EDGE_LEN) {
+        edges.push(decode_edge(chunk)?);
+    }
+
+    let meta = &data[edges_start + edge_count * EDGE_LEN..body_len];
+    let u64_at = |at: usize| meta[at..at + 8].try_into().ok().map(u64::from_le_bytes);
+    let mut entry = BankEntry::new(id, vector, BankId(u64_at(4)?), temperature, created_tick);
+    entry.edges = edges;
+    entry.checksum = u32::from_le_bytes(meta[0..4].try_into().ok()?);
+    entry.expires_at_tick = u64_at(12).filter(|&at| at != u64::MAX);
+    entry.namespace = u16::from_le_bytes(meta[20..22].try_into().ok()?);
+    entry.last_accessed_tick = u64_at(22)?;
+    entry.access_count = u32::from_le_bytes(meta[30..34].try_into().ok()?);
+    entry.confidence = meta[34];
+    entry.pinned = meta[35] != 0;
+    entry.refresh_meta_checksum();
+    Some((JournalEntry::InsertCopy { bank_id, entry }, total))
+}
+
+fn decode_set_temp(data: &[u8], extended: bool) -> Option<(JournalEntry, usize)> {
     // tag(1) + bank_id(8) + entry_id(8) + temp(1) + crc(4) = 22
     if data.len() < 22 {
         return None;
@@ -522,7 +1489,7 @@ fn decode_set_temp(data: &[u8]) -> Option<(JournalEntry, usize)> {
 
     let bank_id = BankId(u64::from_le_bytes(data[1..9].try_into().ok()?));
     let entry_id = EntryId(u64::from_le_bytes(data[9..17].try_into().ok()?));
-    let temperature = u8_to_temperature(data[17])?;
+    let temperature = u8_to_temperature(data[17], extended)?;
 
     Some((
         JournalEntry::SetTemperature {
@@ -534,7 +1501,7 @@ fn decode_set_temp(data: &[u8]) -> Option<(JournalEntry, usize)> {
     ))
 }
 
-fn decode_promote(data: &[u8]) -> Option<(JournalEntry, usize)> {
+fn decode_promote(data: &[u8], extended: bool) -> Option<(JournalEntry, usize)> {
     // Same layout as SetTemperature: tag(1) + bank_id(8) + entry_id(8) + temp(1) + crc(4) = 22
     if data.len() < 22 {
         return None;
@@ -546,11 +1513,11 @@ fn decode_promote(data: &[u8]) -> Option<(JournalEntry, usize)> {
     }
     let bank_id = BankId(u64::from_le_bytes(data[1..9].try_into().ok()?));
     let entry_id = EntryId(u64::from_le_bytes(data[9..17].try_into().ok()?));
-    let new_temp = u8_to_temperature(data[17])?;
+    let new_temp = u8_to_temperature(data[17], extended)?;
     Some((JournalEntry::Promote { bank_id, entry_id, new_temp }, 22))
 }
 
-fn decode_demote(data: &[u8]) -> Option<(JournalEntry, usize)> {
+fn decode_demote(data: &[u8], extended: bool) -> Option<(JournalEntry, usize)> {
     if data.len() < 22 {
         return None;
     }
@@ -561,7 +1528,7 @@ fn decode_demote(data: &[u8]) -> Option<(JournalEntry, usize)> {
     }
     let bank_id = BankId(u64::from_le_bytes(data[1..9].try_into().ok()?));
     let entry_id = EntryId(u64::from_le_bytes(data[9..17].try_into().ok()?));
-    let new_temp = u8_to_temperature(data[17])?;
+    let new_temp = u8_to_temperature(data[17], extended)?;
     Some((JournalEntry::Demote { bank_id, entry_id, new_temp }, 22))
 }
 
@@ -596,22 +1563,13 @@ fn decode_batch_evict(data: &[u8]) -> Option<(JournalEntry, usize)> {
 // =============================================================================
 
 fn temperature_to_u8(t: Temperature) -> u8 {
-    match t {
-        Temperature::Hot => 0,
-        Temperature::Warm => 1,
-        Temperature::Cool => 2,
-        Temperature::Cold => 3,
-    }
+    t.as_u8()
 }
 
-fn u8_to_temperature(v: u8) -> Option<Temperature> {
-    match v {
-        0 => Some(Temperature::Hot),
-        1 => Some(Temperature::Warm),
-        2 => Some(Temperature::Cool),
-        3 => Some(Temperature::Cold),
-        _ => None,
-    }
+/// Extended discriminants (`Temperature::Set`) are only accepted from a
+/// record tagged with [`TAG_FLAG_EXTENDED_TEMPERATURE`].
+fn u8_to_temperature(v: u8, extended: bool) -> Option<Temperature> {
+    Temperature::from_u8(v).filter(|t| extended || !t.is_extended())
 }
 
 /// Simple CRC32 (IEEE polynomial).
@@ -638,6 +1596,52 @@ mod tests {
         Signal::new_raw(pol, mag, 1)
     }
 
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compressed_journal_round_trips_and_tolerates_truncated_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bank.journal");
+        let insert = |i: u64| JournalEntry::Insert {
+            bank_id: BankId(1),
+            entry_id: EntryId(i),
+            vector: vec![make_signal(1, 100); 64],
+            temperature: Temperature::Hot,
+            tick: i,
+            checksum: None,
+            expires_at_tick: None,
+            namespace: 0,
+        };
+
+        let mut writer = JournalWriter::open_compressed(&path).unwrap();
+        for i in 0..20 {
+            writer.append(&insert(i)).unwrap();
+        }
+        writer.flush().unwrap();
+        let first_block_len = std::fs::metadata(&path).unwrap().len() as usize;
+        for i in 20..30 {
+            writer.append(&insert(i)).unwrap();
+        }
+        drop(writer); // pending records are flushed as a second block
+
+        let raw_len: usize = (0..30).map(|i| encode_entry(&insert(i)).len()).sum();
+        let data = std::fs::read(&path).unwrap();
+        assert!(data.len() < raw_len / 4, "{} vs {}", data.len(), raw_len);
+
+        let (entries, corrupt) = JournalReader::read_checked(&path).unwrap();
+        assert!(corrupt.is_none());
+        assert_eq!(entries.len(), 30);
+        assert!(matches!(entries[29], JournalEntry::Insert { tick: 29, .. }));
+
+        // Crash mid-write of the second block
+        std::fs::write(&path, &data[..data.len() - 3]).unwrap();
+        let (entries, corrupt) = JournalReader::read_checked(&path).unwrap();
+        assert_eq!(entries.len(), 20);
+        assert!(matches!(
+            corrupt,
+            Some(JournalError::CorruptRecord { offset }) if offset == first_block_len
+        ));
+    }
+
     #[test]
     fn test_insert_roundtrip() {
         let entry = JournalEntry::Insert {
@@ -646,6 +1650,9 @@ mod tests {
             vector: vec![make_signal(1, 100), make_signal(-1, 200)],
             temperature: Temperature::Warm,
             tick: 42,
+            checksum: None,
+            expires_at_tick: None,
+            namespace: 0,
         };
         let bytes = encode_entry(&entry);
         let (decoded, consumed) = decode_entry(&bytes).expect("should decode");
@@ -657,8 +1664,14 @@ mod tests {
                 vector,
                 temperature,
                 tick,
+                checksum,
+                expires_at_tick,
+                namespace,
             } => {
                 assert_eq!(bank_id, BankId(12345));
+                assert_eq!(checksum, None);
+                assert_eq!(expires_at_tick, None);
+                assert_eq!(namespace, 0);
                 assert_eq!(entry_id, EntryId(67890));
                 assert_eq!(vector.len(), 2);
                 assert_eq!(vector[0].polarity, 1);
@@ -758,6 +1771,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_set_temperature_tier_roundtrip() {
+        let entry = JournalEntry::Promote {
+            bank_id: BankId(501),
+            entry_id: EntryId(601),
+            new_temp: Temperature::Set,
+        };
+        let bytes = encode_entry(&entry);
+        assert_eq!(bytes[0], TAG_PROMOTE | TAG_FLAG_EXTENDED_TEMPERATURE);
+        let (decoded, _) = decode_entry(&bytes).expect("should decode");
+        match decoded {
+            JournalEntry::Promote { new_temp, .. } => {
+                assert_eq!(new_temp, Temperature::Set);
+            }
+            _ => panic!("Expected Promote"),
+        }
+
+        // Original tiers leave the tag as it was
+        let cold = JournalEntry::Promote {
+            bank_id: BankId(501),
+            entry_id: EntryId(601),
+            new_temp: Temperature::Cold,
+        };
+        assert_eq!(encode_entry(&cold)[0], TAG_PROMOTE);
+
+        // An extended temperature without the flag is rejected, even with a valid CRC
+        let mut unflagged = bytes[..18].to_vec();
+        unflagged[0] = TAG_PROMOTE;
+        let crc = crc32(&unflagged);
+        unflagged.extend_from_slice(&crc.to_le_bytes());
+        assert!(decode_entry(&unflagged).is_none());
+
+        // The flag is refused on records without a temperature
+        let mut flagged_remove = encode_entry(&JournalEntry::Remove {
+            bank_id: BankId(1),
+            entry_id: EntryId(2),
+        });
+        flagged_remove[0] |= TAG_FLAG_EXTENDED_TEMPERATURE;
+        assert_eq!(header_len(flagged_remove[0]), None);
+    }
+
     #[test]
     fn test_multiple_entries_sequential() {
         let entries = vec![
@@ -895,6 +1949,283 @@ mod tests {
         }
     }
 
+    #[test]
+    fn replay_refuses_inserts_failing_their_entry_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bank.journal");
+        let bank_id = BankId(3);
+        let vector = vec![make_signal(1, 100), make_signal(-1, 40)];
+        let source = BankEntry::new(EntryId(1), vector, bank_id, Temperature::Warm, 5);
+        let good = JournalEntry::insert_of(bank_id, &source);
+        // The producer damaged the vector after taking its checksum; the
+        // record CRC is computed over the damaged bytes and still passes
+        let mut bad = good.clone();
+        if let JournalEntry::Insert { vector, .. } = &mut bad {
+            vector[1] = make_signal(1, 40);
+        }
+        let legacy = JournalEntry::Insert {
+            bank_id,
+            entry_id: EntryId(2),
+            vector: vec![make_signal(1, 1); 2],
+            temperature: Temperature::Hot,
+            tick: 6,
+            checksum: None,
+            expires_at_tick: None,
+            namespace: 0,
+        };
+
+        let bytes = encode_entry(&good);
+        assert_eq!(bytes[0], TAG_INSERT_CHECKED);
+        assert_eq!(decode_entry(&bytes).unwrap(), (good.clone(), bytes.len()));
+        assert_eq!(encode_entry(&legacy)[0], TAG_INSERT);
+
+        let mut writer = JournalWriter::open(&path).unwrap();
+        for record in [&good, &bad, &legacy] {
+            writer.append(record).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let mut cluster = BankCluster::new();
+        let config = crate::types::BankConfig {
+            vector_width: 2,
+            ..Default::default()
+        };
+        cluster.get_or_create(bank_id, "test.crc".into(), config).unwrap();
+        let records = JournalReader::read_all(&path).unwrap();
+        assert_eq!(records, vec![good, bad, legacy]);
+        let counts = JournalReader::replay_verified(&records, &mut BankCluster::new());
+        assert_eq!((counts.applied, counts.skipped, counts.checksum_mismatches), (0, 2, 1));
+
+        let (applied, issues) = cluster.replay_journal_checked(&path).unwrap();
+        assert_eq!(applied, 2);
+        assert!(matches!(issues[..], [JournalError::ReplayChecksumMismatch { count: 1 }]));
+        let bank = cluster.get(bank_id).unwrap();
+        assert_eq!(bank.len(), 2);
+        assert!(bank.entries().all(|(_, e)| e.vector()[1] != make_signal(1, 40)));
+    }
+
+    #[test]
+    fn test_insert_with_expiry_roundtrips_and_replays_its_ttl() {
+        let bank_id = BankId(3);
+        let mut source = BankCluster::new();
+        let config = crate::types::BankConfig {
+            vector_width: 2,
+            ..Default::default()
+        };
+        let bank = source.get_or_create(bank_id, "test.ttl".into(), config.clone()).unwrap();
+        let vector = vec![make_signal(1, 100), make_signal(-1, 40)];
+        let id = bank.insert_ttl(vector.clone(), Temperature::Warm, 5, 20).unwrap();
+        let record = JournalEntry::insert_of(bank_id, bank.get(id).unwrap());
+
+        let bytes = encode_entry(&record);
+        assert_eq!(bytes[0], TAG_INSERT_EXPIRING);
+        assert_eq!(decode_entry(&bytes).unwrap(), (record.clone(), bytes.len()));
+
+        // Without a producer checksum the vector's is written
+        let unchecked = JournalEntry::Insert {
+            bank_id,
+            entry_id: EntryId(9),
+            vector: vector.clone(),
+            temperature: Temperature::Hot,
+            tick: 5,
+            checksum: None,
+            expires_at_tick: Some(25),
+            namespace: 0,
+        };
+        let (decoded, _) = decode_entry(&encode_entry(&unchecked)).unwrap();
+        assert!(matches!(
+            decoded,
+            JournalEntry::Insert { checksum: Some(c), expires_at_tick: Some(25), .. }
+                if c == vector_checksum(&vector)
+        ));
+
+        let mut replica = BankCluster::new();
+        replica.get_or_create(bank_id, "test.ttl".into(), config).unwrap();
+        assert!(apply_entry(&record, &mut replica));
+        let (_, replayed) = replica.get(bank_id).unwrap().entries().next().unwrap();
+        assert_eq!(replayed.expires_at_tick(), Some(25));
+    }
+
+    #[test]
+    fn test_insert_in_namespace_roundtrips_and_replays_its_namespace() {
+        let bank_id = BankId(4);
+        let mut source = BankCluster::new();
+        let config = crate::types::BankConfig {
+            vector_width: 2,
+            ..Default::default()
+        };
+        let bank = source.get_or_create(bank_id, "test.ns".into(), config.clone()).unwrap();
+        let vector = vec![make_signal(1, 100), make_signal(-1, 40)];
+        let plain = bank.insert_in_namespace(vector.clone(), Temperature::Warm, 5, 7).unwrap();
+        let expiring = bank.insert_entry(vector, Temperature::Set, 6, 9, Some(30)).unwrap();
+        let records =
+            [plain, expiring].map(|id| JournalEntry::insert_of(bank_id, bank.get(id).unwrap()));
+
+        for record in &records {
+            let bytes = encode_entry(record);
+            assert_eq!(bytes[0] & !TAG_FLAG_EXTENDED_TEMPERATURE, TAG_INSERT_NAMESPACED);
+            assert_eq!(record_len(&bytes), bytes.len());
+            assert_eq!(decode_entry(&bytes).unwrap(), (record.clone(), bytes.len()));
+        }
+
+        let mut replica = BankCluster::new();
+        replica.get_or_create(bank_id, "test.ns".into(), config).unwrap();
+        assert_eq!(JournalReader::replay_counted(&records, &mut replica), (2, 0));
+        let bank = replica.get(bank_id).unwrap();
+        let mut replayed: Vec<_> = bank
+            .entries()
+            .map(|(_, e)| (e.namespace(), e.expires_at_tick(), e.temperature()))
+            .collect();
+        replayed.sort_unstable_by_key(|&(namespace, ..)| namespace);
+        assert_eq!(
+            replayed,
+            vec![(7, None, Temperature::Warm), (9, Some(30), Temperature::Set)]
+        );
+    }
+
+    #[test]
+    fn test_pin_unpin_roundtrip_and_replay() {
+        for entry in [
+            JournalEntry::Pin {
+                bank_id: BankId(7),
+                entry_id: EntryId(8),
+            },
+            JournalEntry::Unpin {
+                bank_id: BankId(7),
+                entry_id: EntryId(8),
+            },
+        ] {
+            let bytes = encode_entry(&entry);
+            let (decoded, consumed) = decode_entry(&bytes).expect("should decode");
+            assert_eq!(consumed, 21);
+            assert_eq!(format!("{decoded:?}"), format!("{entry:?}"));
+        }
+
+        let mut cluster = BankCluster::new();
+        let bank_id = BankId::new("test.pin", 0);
+        let bank = cluster.get_or_create(
+            bank_id,
+            "test.pin".into(),
+            crate::types::BankConfig {
+                vector_width: 2,
+                ..Default::default()
+            },
+        ).unwrap();
+        let entry_id = bank
+            .insert(vec![Signal::new_raw(1, 10, 1); 2], Temperature::Hot, 0)
+            .unwrap();
+        let pin = JournalEntry::Pin { bank_id, entry_id };
+        assert_eq!(JournalReader::replay(&[pin], &mut cluster).unwrap(), 1);
+        assert!(cluster.get(bank_id).unwrap().get(entry_id).unwrap().pinned);
+
+        let unpin = JournalEntry::Unpin { bank_id, entry_id };
+        let missing = JournalEntry::Pin {
+            bank_id,
+            entry_id: EntryId(u64::MAX),
+        };
+        assert_eq!(JournalReader::replay_counted(&[unpin, missing], &mut cluster), (1, 1));
+        assert!(!cluster.get(bank_id).unwrap().get(entry_id).unwrap().pinned);
+    }
+
+    #[test]
+    fn failed_inserts_replay_as_skipped() {
+        let mut cluster = BankCluster::new();
+        let bank_id = BankId::new("test.full", 0);
+        let config = crate::types::BankConfig {
+            vector_width: 2,
+            max_entries: 1,
+            ..Default::default()
+        };
+        let bank = cluster.get_or_create(bank_id, "test.full".into(), config).unwrap();
+        let kept = bank.insert(vec![Signal::new_raw(1, 10, 1); 2], Temperature::Hot, 0).unwrap();
+        bank.pin(kept).unwrap();
+
+        let insert = |width: usize| JournalEntry::Insert {
+            bank_id,
+            entry_id: EntryId(1),
+            vector: vec![Signal::new_raw(1, 20, 1); width],
+            temperature: Temperature::Hot,
+            tick: 1,
+            checksum: None,
+            expires_at_tick: None,
+            namespace: 0,
+        };
+        // Wrong width, and a full bank with nothing evictable
+        for record in [insert(3), insert(2)] {
+            assert!(!entry_applies(&record, &cluster));
+            assert_eq!(JournalReader::replay_counted(&[record], &mut cluster), (0, 1));
+        }
+        assert_eq!(cluster.get(bank_id).unwrap().len(), 1);
+
+        cluster.get_mut(bank_id).unwrap().unpin(kept).unwrap();
+        assert!(entry_applies(&insert(2), &cluster));
+        assert_eq!(JournalReader::replay_counted(&[insert(2)], &mut cluster), (1, 0));
+        assert!(cluster.get(bank_id).unwrap().get(kept).is_none());
+    }
+
+    #[test]
+    fn coalesced_touches_replay_to_the_same_state() {
+        let config = crate::types::BankConfig {
+            vector_width: 2,
+            ..Default::default()
+        };
+        let bank_id = BankId::new("test.touch", 0);
+        let mut bank = crate::bank::DataBank::new(bank_id, "test.touch".into(), config);
+        let a = bank.insert(vec![Signal::new_raw(1, 10, 1); 2], Temperature::Hot, 0).unwrap();
+        let b = bank.insert(vec![Signal::new_raw(-1, 10, 1); 2], Temperature::Hot, 0).unwrap();
+        let snapshot = crate::codec::encode(&bank).unwrap();
+
+        let touch = |entry_id, tick| JournalEntry::Touch {
+            bank_id,
+            entry_id,
+            tick,
+        };
+        let mut records: Vec<JournalEntry> = (1..=50)
+            .map(|tick| touch(if tick % 10 == 0 { b } else { a }, tick))
+            .collect();
+        records.push(JournalEntry::SetTemperature {
+            bank_id,
+            entry_id: a,
+            temperature: Temperature::Cold,
+        });
+        records.extend((51..54).map(|tick| touch(a, tick)));
+
+        let dir = tempfile::tempdir().unwrap();
+        let (raw_path, coalesced_path) = (dir.path().join("raw"), dir.path().join("coalesced"));
+        let mut raw = JournalWriter::open(&raw_path).unwrap();
+        let mut coalesced = JournalWriter::open(&coalesced_path).unwrap();
+        coalesced.set_coalesce_touches(true).unwrap();
+        for record in &records {
+            raw.append(record).unwrap();
+            coalesced.append(record).unwrap();
+        }
+        raw.flush().unwrap();
+        drop(coalesced); // held touches are written on drop
+
+        let raw_records = JournalReader::read_all(&raw_path).unwrap();
+        let coalesced_records = JournalReader::read_all(&coalesced_path).unwrap();
+        assert_eq!(raw_records.len(), 54);
+        assert_eq!(coalesced_records.len(), 4);
+        assert!(matches!(
+            coalesced_records[0],
+            JournalEntry::TouchMany { count: 45, tick: 49, .. }
+        ));
+        assert_eq!(coalesce(&raw_records).len(), 4);
+
+        let replayed = |records: &[JournalEntry]| {
+            let mut cluster = BankCluster::new();
+            cluster.add(crate::codec::decode(&snapshot).unwrap()).unwrap();
+            JournalReader::replay(records, &mut cluster).unwrap();
+            let bank = cluster.get(bank_id).unwrap();
+            [a, b].map(|id| {
+                let e = bank.get(id).unwrap();
+                (e.access_count, e.last_accessed_tick, e.temperature)
+            })
+        };
+        assert_eq!(replayed(&raw_records), replayed(&coalesced_records));
+        assert_eq!(replayed(&raw_records)[0], (48, 53, Temperature::Cold));
+    }
+
     #[test]
     fn test_file_roundtrip() {
         let dir = tempfile::tempdir().unwrap();
@@ -930,4 +2261,42 @@ mod tests {
         let after = JournalReader::read_all(&path).unwrap();
         assert_eq!(after.len(), 0);
     }
+
+    #[test]
+    fn test_read_checked_reports_corrupt_record_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.journal");
+
+        let first = encode_entry(&JournalEntry::Remove {
+            bank_id: BankId(1),
+            entry_id: EntryId(2),
+        });
+        let mut second = encode_entry(&JournalEntry::Touch {
+            bank_id: BankId(3),
+            entry_id: EntryId(4),
+            tick: 10,
+        });
+        let last = second.len() - 1;
+        second[last] ^= 0xFF; // break the CRC
+        std::fs::write(&path, [first.as_slice(), second.as_slice()].concat()).unwrap();
+
+        let (entries, corrupt) = JournalReader::read_checked(&path).unwrap();
+        assert_eq!(entries.len(), 1);
+        match corrupt {
+            Some(JournalError::CorruptRecord { offset }) => assert_eq!(offset, first.len()),
+            other => panic!("expected CorruptRecord, got {other:?}"),
+        }
+
+        // The tolerant reader still recovers the good prefix
+        assert_eq!(JournalReader::read_all(&path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_read_checked_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let (entries, corrupt) =
+            JournalReader::read_checked(&dir.path().join("absent.journal")).unwrap();
+        assert!(entries.is_empty());
+        assert!(corrupt.is_none());
+    }
 }
@@ -4,6 +4,36 @@
 //! temperature lifecycle, sparse pattern completion, and binary `.bank`
 //! persistence. Thermograms store the connectome (HOW neurons wire);
 //! databanks store the engrams (WHAT neurons represent).
+//!
+//! The default `std` feature adds file persistence, the delta journal,
+//! background flushing, and wall-clock ids. Built with
+//! `--no-default-features --features alloc`, the in-memory core (entries,
+//! banks, the cluster's query and graph operations, similarity, indexes,
+//! the `.bank` byte codec, and the register bridge) needs only `alloc`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+compile_error!("databank-rs needs the `std` feature or, for no_std targets, `alloc`");
+
+extern crate alloc;
+
+/// What the core modules would otherwise take from the std prelude.
+/// Std builds keep std's maps so public signatures are unchanged; no_std
+/// builds use hashbrown's.
+#[allow(unused_imports)]
+mod prelude {
+    pub use alloc::borrow::ToOwned;
+    pub use alloc::boxed::Box;
+    pub use alloc::string::{String, ToString};
+    pub use alloc::vec::Vec;
+    pub use alloc::{format, vec};
+
+    #[cfg(not(feature = "std"))]
+    pub use hashbrown::{HashMap, HashSet};
+    #[cfg(feature = "std")]
+    pub use std::collections::{HashMap, HashSet};
+}
 
 #[cfg(feature = "ternsig")]
 pub mod access;
@@ -11,27 +41,118 @@ pub mod bank;
 pub mod bridge;
 pub mod cluster;
 pub mod codec;
+pub mod dot;
 pub mod entry;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod fulfiller;
+pub mod import;
 pub mod index;
 pub mod ivf;
+#[cfg(feature = "std")]
 pub mod journal;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod observer;
+pub mod quantize;
+pub mod rng;
+#[cfg(feature = "std")]
+pub mod shared;
 pub mod similarity;
+pub mod snapshot;
+pub mod store;
 pub mod types;
 
 #[cfg(feature = "ternsig")]
 pub use access::ClusterBankAccess;
-pub use bank::DataBank;
+pub use bank::{
+    BankStats, DataBank, EdgeStats, EntryMut, EvictionReason, EvictionRecord, InsertHook,
+    LeaseToken, PersistenceState,
+};
 pub use bridge::{
-    entry_id_to_i32_pair, i32_pair_to_entry_id, i32_to_signals,
-    query_results_to_i32, signals_to_i32, traverse_results_to_i32,
+    entry_id_to_i32_pair, f32_to_signal, i32_pair_to_entry_id, i32_to_signals,
+    query_results_to_i32, signal_to_f32, signals_to_i32, traverse_results_to_i32,
+};
+pub use cluster::{BankCluster, ClusterQueryResult, FlushSchedule, QueryCursor};
+#[cfg(feature = "std")]
+pub use cluster::{
+    BankLoadRecord, FlushCompletion, FlushJob, FlushReport, FlushWorker, LoadReport,
+    ReplaySummary, REPLAY_PROGRESS_INTERVAL,
 };
-pub use cluster::{BankCluster, ClusterQueryResult};
-pub use entry::BankEntry;
-pub use error::{DataBankError, Result};
-pub use fulfiller::{BankFulfiller, BankSlotMap, FulfillResult};
-pub use ivf::{IndexType, IvfIndex};
-pub use journal::{JournalEntry, JournalReader, JournalWriter};
-pub use similarity::QueryResult;
-pub use types::{BankConfig, BankId, BankRef, Edge, EdgeType, EntryId, Temperature};
+pub use codec::{BankDiff, ConfigChange, EncodeOptions};
+pub use dot::GraphFilter;
+pub use entry::{BankEntry, EdgeList, RawEntryMut};
+pub use error::{CodecError, CodecErrorKind, DataBankError, ErrorCode, ErrorSeverity, Result};
+#[cfg(feature = "std")]
+pub use error::JournalError;
+pub use fulfiller::{AtomicBankSlotMap, BankFulfiller, BankSlotMap, FulfillResult};
+pub use import::{import_f32_rows, F32Rows, ImportReport};
+pub use index::{evaluate_recall, QueryFilter, QueryPath, QueryTrace, RecallReport};
+pub use ivf::{IndexType, IvfBuildParams, IvfIndex};
+#[cfg(feature = "std")]
+pub use journal::{
+    JournalEntry, JournalReader, JournalSink, JournalStream, JournalWriter, ReplayCounts,
+};
+#[cfg(feature = "metrics")]
+pub use metrics::BankMetrics;
+pub use observer::{BankObserver, ClusterObserver};
+pub use quantize::{Codebook, QuantizedVector};
+pub use rng::XorShift64Star;
+#[cfg(feature = "std")]
+pub use shared::SharedBankCluster;
+pub use similarity::{
+    explain_similarity, prenormalized_dot, score_stream, sparse_cosine_similarity,
+    sparse_cosine_similarity_detailed, sparse_cosine_similarity_weighted, subtract, superpose,
+    DimContribution, MatchExplanation, Metric, QueryResult,
+};
+pub use snapshot::BankSnapshot;
+pub use store::{EntrySource, EntryStore};
+pub use types::{
+    BankConfig, BankId, BankRef, Edge, EdgeOverflow, EdgeType, EntryId, EntryStorage,
+    EvictionMode, QueryWidth, Temperature,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    /// Banks and clusters move to flush workers and loader threads; a field
+    /// that drops Send or Sync must fail here, not in downstream builds.
+    #[test]
+    fn core_types_are_send_and_sync() {
+        assert_send::<DataBank>();
+        assert_sync::<DataBank>();
+        assert_send::<BankCluster>();
+        assert_sync::<BankCluster>();
+        assert_send::<BankEntry>();
+        assert_sync::<BankEntry>();
+        assert_send::<BankSnapshot>();
+        assert_sync::<BankSnapshot>();
+        assert_send::<JournalWriter>();
+        assert_sync::<JournalWriter>();
+        assert_send::<FlushWorker>();
+        assert_sync::<FlushWorker>();
+        assert_send::<Box<dyn index::VectorIndex>>();
+        assert_sync::<Box<dyn index::VectorIndex>>();
+        assert_send::<Box<dyn ClusterObserver>>();
+        assert_sync::<Box<dyn ClusterObserver>>();
+        assert_send::<std::sync::Arc<dyn BankObserver>>();
+        assert_sync::<std::sync::Arc<dyn BankObserver>>();
+    }
+
+    #[test]
+    fn fulfiller_types_are_send_and_sync() {
+        assert_send::<BankFulfiller>();
+        assert_sync::<BankFulfiller>();
+        assert_send::<BankSlotMap>();
+        assert_sync::<BankSlotMap>();
+        assert_send::<AtomicBankSlotMap>();
+        assert_sync::<AtomicBankSlotMap>();
+        assert_send::<FulfillResult>();
+        assert_sync::<FulfillResult>();
+    }
+}
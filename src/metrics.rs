@@ -0,0 +1,128 @@
+//! Runtime Metrics
+//!
+//! Per-bank counters and a query latency histogram, compiled in only with
+//! the `metrics` feature. Without it, this module, the recorder fields, and
+//! every call site are cfg'd out, so the default build pays nothing.
+//!
+//! Counters are relaxed atomics so queries (which take `&self`) can record
+//! without locking. The kernel scrapes [`BankMetrics`] snapshots through
+//! [`BankCluster::metrics_snapshot`](crate::cluster::BankCluster::metrics_snapshot).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Upper bounds (inclusive, microseconds) of the query latency buckets.
+/// Queries slower than the last bound land in a final overflow bucket.
+pub const LATENCY_BUCKETS_MICROS: [u64; 8] = [10, 50, 100, 500, 1_000, 5_000, 10_000, 50_000];
+
+/// Number of histogram slots: one per bound plus the overflow bucket.
+pub const LATENCY_SLOTS: usize = LATENCY_BUCKETS_MICROS.len() + 1;
+
+/// Point-in-time copy of one bank's counters.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BankMetrics {
+    /// Queries served (`query_sparse` and friends).
+    pub queries: u64,
+    /// Entries inserted.
+    pub inserts: u64,
+    /// Entries removed explicitly.
+    pub removes: u64,
+    /// Entries evicted for capacity or by `evict_n`.
+    pub evictions: u64,
+    /// Journal records written for this bank.
+    pub journal_records: u64,
+    /// Times the bank was marked persisted.
+    pub flushes: u64,
+    /// Cumulative microseconds spent encoding and writing this bank on the
+    /// flushing thread. Background writes only count the encode.
+    pub flush_micros: u64,
+    /// Query counts per latency bucket, see [`LATENCY_BUCKETS_MICROS`].
+    pub query_latency: [u64; LATENCY_SLOTS],
+}
+
+/// Live counters owned by a `DataBank`.
+#[derive(Debug, Default)]
+pub(crate) struct MetricsRecorder {
+    queries: AtomicU64,
+    inserts: AtomicU64,
+    removes: AtomicU64,
+    evictions: AtomicU64,
+    journal_records: AtomicU64,
+    flushes: AtomicU64,
+    flush_micros: AtomicU64,
+    query_latency: [AtomicU64; LATENCY_SLOTS],
+}
+
+impl MetricsRecorder {
+    pub(crate) fn record_query(&self, elapsed: Duration) {
+        let micros = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        let slot = LATENCY_BUCKETS_MICROS
+            .iter()
+            .position(|&bound| micros <= bound)
+            .unwrap_or(LATENCY_SLOTS - 1);
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        self.query_latency[slot].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_insert(&self) {
+        self.inserts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_remove(&self) {
+        self.removes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_evictions(&self, count: u64) {
+        self.evictions.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_journal_record(&self) {
+        self.journal_records.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_flush(&self) {
+        self.flushes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_flush_time(&self, elapsed: Duration) {
+        let micros = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        self.flush_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> BankMetrics {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        BankMetrics {
+            queries: load(&self.queries),
+            inserts: load(&self.inserts),
+            removes: load(&self.removes),
+            evictions: load(&self.evictions),
+            journal_records: load(&self.journal_records),
+            flushes: load(&self.flushes),
+            flush_micros: load(&self.flush_micros),
+            query_latency: std::array::from_fn(|i| load(&self.query_latency[i])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_latency_lands_in_inclusive_buckets() {
+        let recorder = MetricsRecorder::default();
+        recorder.record_query(Duration::from_micros(0));
+        recorder.record_query(Duration::from_micros(10));
+        recorder.record_query(Duration::from_micros(11));
+        recorder.record_query(Duration::from_secs(1));
+
+        let metrics = recorder.snapshot();
+        assert_eq!(metrics.queries, 4);
+        assert_eq!(metrics.query_latency[0], 2);
+        assert_eq!(metrics.query_latency[1], 1);
+        assert_eq!(metrics.query_latency[LATENCY_SLOTS - 1], 1);
+        assert_eq!(metrics.query_latency.iter().sum::<u64>(), 4);
+    }
+}
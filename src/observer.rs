@@ -0,0 +1,72 @@
+//! Cluster and Bank Event Hooks
+//!
+//! Optional callbacks that `BankCluster` invokes on cluster-level events
+//! (bank created, flushed, evicted, journal replayed). Integrators implement
+//! `ClusterObserver` to wire metrics without scraping logs. When no observer
+//! is registered the cluster skips notification entirely.
+//!
+//! `BankObserver` is the per-entry counterpart: a `DataBank` reports
+//! inserts, removals, evictions, temperature changes, and new edges.
+
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::types::{BankId, Edge, EntryId, Temperature};
+
+/// Receives cluster-level events. All methods default to no-ops so an
+/// observer only overrides what it cares about.
+///
+/// Callbacks take `&self`; observers that count or aggregate should use
+/// interior mutability (atomics, mutexes).
+pub trait ClusterObserver: Send + Sync {
+    /// A bank was created in, or added to, the cluster.
+    fn on_bank_created(&self, _bank_id: BankId, _name: &str) {}
+
+    /// A bank was saved to `path` during a flush.
+    #[cfg(feature = "std")]
+    fn on_flush(&self, _bank_id: BankId, _path: &Path) {}
+
+    /// `count` entries were evicted from a bank by a cluster call:
+    /// `evict`, `expire_entries`, or `migrate_entry` making room in its target.
+    /// Evictions a bank makes on its own to fit an insert made directly on
+    /// it never pass through the cluster; a [`BankObserver`] installed with
+    /// `set_bank_observer` sees those as `on_evict`.
+    fn on_eviction(&self, _bank_id: BankId, _count: usize) {}
+
+    /// `count` journal entries were replayed onto the cluster by
+    /// `replay_journal`. The `load_with_journal` family replays before an
+    /// observer can be installed, so it reports its count in the returned
+    /// `LoadReport` instead.
+    fn on_replay(&self, _count: usize) {}
+}
+
+/// Receives per-entry events from a single `DataBank`. All methods default
+/// to no-ops.
+///
+/// Installed with [`DataBank::set_observer`](crate::bank::DataBank::set_observer),
+/// or on every bank at once with
+/// [`BankCluster::set_bank_observer`](crate::cluster::BankCluster::set_bank_observer).
+/// The observer is held behind an `Arc` so one instance can watch many banks.
+///
+/// Callbacks run synchronously on the mutating thread, after the bank's
+/// state is consistent. Observers must not panic: panics are not caught and
+/// unwind through the bank call that triggered them.
+pub trait BankObserver: Send + Sync {
+    /// An entry was inserted.
+    fn on_insert(&self, _bank_id: BankId, _entry_id: EntryId) {}
+
+    /// An entry was removed explicitly via `DataBank::remove`.
+    fn on_remove(&self, _bank_id: BankId, _entry_id: EntryId) {}
+
+    /// An entry was evicted, either to make room or by `evict_n`.
+    fn on_evict(&self, _bank_id: BankId, _entry_id: EntryId) {}
+
+    /// An entry was promoted to the colder temperature `to`.
+    fn on_promote(&self, _bank_id: BankId, _entry_id: EntryId, _to: Temperature) {}
+
+    /// An entry was demoted to the hotter temperature `to`.
+    fn on_demote(&self, _bank_id: BankId, _entry_id: EntryId, _to: Temperature) {}
+
+    /// An edge was added to entry `from`.
+    fn on_edge_added(&self, _bank_id: BankId, _from: EntryId, _edge: &Edge) {}
+}
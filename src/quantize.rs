@@ -0,0 +1,229 @@
+//! Vector Quantization Codebooks
+//!
+//! Banks holding many vectors clustered around a few prototypes waste most
+//! of their storage repeating the prototypes. A [`Codebook`] holds those
+//! prototypes; each vector is stored as the index of its best prototype
+//! plus the dimensions where it differs from it by more than a tolerance.
+//!
+//! Dimensions within the tolerance take the prototype's value, so
+//! reconstruction is lossy but bounded: every dimension's current is within
+//! `tolerance` of the original. A tolerance of 0 is lossless: it corrects
+//! every signal that differs from the prototype's, even when the currents
+//! agree (e.g. magnitude 80 x 1 against 40 x 2).
+//!
+//! The codebook reuses the bank's IVF centroids when the index is trained,
+//! and otherwise runs the IVF k-means over the bank's entries. The `.bank`
+//! codec uses it when `EncodeOptions::quantize` is set, taking the bank's
+//! kept codebook ([`DataBank::train_codebook`], or the one a quantized file
+//! was loaded with) so repeated saves do not retrain.
+//!
+//! Quantization is a storage format only. Decoding reconstructs every
+//! vector at full width, so it saves disk space and I/O, not memory.
+//! Keeping entries resident as prototype plus corrections is not
+//! implemented: [`BankEntry::vector`](crate::entry::BankEntry::vector)
+//! lends a full-width slice, and every scan and index reads one, so it
+//! needs an entry layout change of its own rather than a codec flag.
+
+use ternary_signal::Signal;
+
+use crate::bank::DataBank;
+use crate::error::{DataBankError, Result};
+use crate::ivf::IvfIndex;
+use crate::prelude::*;
+
+/// Prototypes trained when the bank's index has no centroids to reuse.
+pub const CODEBOOK_SIZE: usize = 64;
+
+/// k-means refinement passes when training a codebook.
+const KMEANS_ITERATIONS: usize = 10;
+
+/// Prototype vectors that quantized vectors are stored against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Codebook {
+    width: usize,
+    prototypes: Vec<Vec<Signal>>,
+}
+
+/// A vector stored as a prototype index plus per-dimension corrections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantizedVector {
+    /// Index of the prototype in the codebook.
+    pub prototype: u16,
+    /// `(dimension, signal)` for each dimension stored exactly, in
+    /// ascending dimension order.
+    pub corrections: Vec<(u16, Signal)>,
+}
+
+impl Codebook {
+    /// Build a codebook from explicit prototypes, each `width` signals long.
+    /// At most `u16::MAX` prototypes are allowed.
+    pub fn new(width: usize, prototypes: Vec<Vec<Signal>>) -> Result<Self> {
+        if prototypes.len() > u16::MAX as usize {
+            return Err(DataBankError::InvalidConfig(format!(
+                "codebook has {} prototypes, at most {} allowed",
+                prototypes.len(),
+                u16::MAX
+            )));
+        }
+        if let Some(bad) = prototypes.iter().find(|p| p.len() != width) {
+            return Err(DataBankError::VectorWidthMismatch {
+                expected: width as u16,
+                got: bad.len() as u16,
+            });
+        }
+        Ok(Self { width, prototypes })
+    }
+
+    /// Codebook from centroids given as per-dimension currents, as the IVF
+    /// index stores them.
+    pub fn from_centroids(width: usize, centroids: &[Vec<i32>]) -> Self {
+        let prototypes = centroids
+            .iter()
+            .take(u16::MAX as usize)
+            .map(|c| {
+                let mut prototype: Vec<Signal> =
+                    c.iter().map(|&v| Signal::from_current(v)).collect();
+                prototype.resize(width, Signal::ZERO);
+                prototype
+            })
+            .collect();
+        Self { width, prototypes }
+    }
+
+    /// Codebook for `bank`: its IVF centroids if the index is trained,
+    /// otherwise k-means centroids of its entries, about sqrt(n) of them and
+    /// at most [`CODEBOOK_SIZE`]. Empty for an empty bank.
+    pub fn train(bank: &DataBank) -> Self {
+        let width = bank.config().vector_width as usize;
+        let centroids = bank.index_centroids();
+        if !centroids.is_empty() {
+            return Self::from_centroids(width, centroids);
+        }
+        let mut k = 1;
+        while k < CODEBOOK_SIZE && (k + 1) * (k + 1) <= bank.len() {
+            k += 1;
+        }
+        let mut index = IvfIndex::new(k, 1);
+        index.rebuild_kmeans(bank.entry_map(), KMEANS_ITERATIONS);
+        Self::from_centroids(width, crate::index::VectorIndex::centroids(&index))
+    }
+
+    /// Vector width of every prototype.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of prototypes.
+    pub fn len(&self) -> usize {
+        self.prototypes.len()
+    }
+
+    /// Whether the codebook has no prototypes.
+    pub fn is_empty(&self) -> bool {
+        self.prototypes.is_empty()
+    }
+
+    /// The prototypes, in index order.
+    pub fn prototypes(&self) -> &[Vec<Signal>] {
+        &self.prototypes
+    }
+
+    /// Quantize `vector` against the prototype needing the fewest
+    /// corrections (lowest index on ties). A dimension is corrected when its
+    /// current differs from the prototype's by more than `tolerance`, or,
+    /// with `tolerance` 0, when its signal differs at all.
+    ///
+    /// Panics if the codebook is empty.
+    pub fn quantize(&self, vector: &[Signal], tolerance: u16) -> QuantizedVector {
+        assert!(!self.is_empty(), "cannot quantize against an empty codebook");
+        let off = |prototype: &[Signal], i: usize| {
+            let original = vector.get(i).copied().unwrap_or(Signal::ZERO);
+            if tolerance == 0 {
+                original != prototype[i]
+            } else {
+                original.current().abs_diff(prototype[i].current()) > tolerance as u32
+            }
+        };
+        let best = self
+            .prototypes
+            .iter()
+            .enumerate()
+            .min_by_key(|(index, prototype)| {
+                ((0..self.width).filter(|&i| off(prototype, i)).count(), *index)
+            })
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        let corrections = (0..self.width)
+            .filter(|&i| off(&self.prototypes[best], i))
+            .map(|i| (i as u16, vector.get(i).copied().unwrap_or(Signal::ZERO)))
+            .collect();
+        QuantizedVector {
+            prototype: best as u16,
+            corrections,
+        }
+    }
+
+    /// Rebuild a vector. None if the prototype index or a correction's
+    /// dimension is out of range.
+    pub fn reconstruct(&self, quantized: &QuantizedVector) -> Option<Vec<Signal>> {
+        let mut vector = self.prototypes.get(quantized.prototype as usize)?.clone();
+        for &(dim, signal) in &quantized.corrections {
+            *vector.get_mut(dim as usize)? = signal;
+        }
+        Some(vector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BankConfig, BankId, Temperature};
+
+    fn sig(current: i32) -> Signal {
+        Signal::from_current(current)
+    }
+
+    #[test]
+    fn quantize_respects_tolerance_and_picks_closest_prototype() {
+        let codebook = Codebook::new(
+            4,
+            vec![vec![sig(100); 4], vec![sig(-100), sig(-100), sig(100), sig(100)]],
+        )
+        .unwrap();
+        let vector = vec![sig(-96), sig(-110), sig(104), sig(40)];
+
+        let loose = codebook.quantize(&vector, 10);
+        assert_eq!(loose.prototype, 1);
+        assert_eq!(loose.corrections, vec![(3, sig(40))]);
+        let rebuilt = codebook.reconstruct(&loose).unwrap();
+        for (a, b) in rebuilt.iter().zip(&vector) {
+            assert!(a.current().abs_diff(b.current()) <= 10);
+        }
+
+        let exact = codebook.quantize(&vector, 0);
+        assert_eq!(codebook.reconstruct(&exact).unwrap(), vector);
+
+        let out_of_range = QuantizedVector {
+            prototype: 2,
+            corrections: Vec::new(),
+        };
+        assert!(codebook.reconstruct(&out_of_range).is_none());
+        assert!(Codebook::new(4, vec![vec![sig(1); 3]]).is_err());
+    }
+
+    #[test]
+    fn train_falls_back_to_kmeans_on_untrained_index() {
+        let config = BankConfig {
+            vector_width: 4,
+            ..BankConfig::default()
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "vq".into(), config);
+        assert!(Codebook::train(&bank).is_empty());
+        for i in 0..10 {
+            bank.insert(vec![sig(50 + i); 4], Temperature::Hot, 0).unwrap();
+        }
+        let codebook = Codebook::train(&bank);
+        assert_eq!(codebook.len(), 3);
+        assert_eq!(codebook.width(), 4);
+    }
+}
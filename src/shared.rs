@@ -0,0 +1,414 @@
+//! Thread-Safe Shared Cluster
+//!
+//! `SharedBankCluster` lets region interpreters on a thread pool work on
+//! different banks at the same time. Each bank sits behind its own
+//! `RwLock`; the bank map sits behind an outer `RwLock` that is only taken
+//! for writing when banks are added or removed. Queries on different banks
+//! never contend, and queries on the same bank share a read lock.
+//!
+//! Lock order: outer map, then bank locks in ascending `BankId` order.
+//! Every method that holds more than one bank lock follows it, so
+//! concurrent cross-bank links cannot deadlock.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use ternary_signal::Signal;
+
+use crate::bank::DataBank;
+use crate::cluster::{fuse_results, BankCluster, ClusterQueryResult};
+use crate::codec;
+use crate::error::{DataBankError, Result};
+use crate::types::{BankId, BankRef, Edge, EdgeType};
+
+/// A bank cluster safe to share across threads (`Arc<SharedBankCluster>`).
+///
+/// Access goes through closures (`read_bank` / `write_bank`) so a lock is
+/// never held past the operation that needed it. The observer and journal
+/// of a plain `BankCluster` are not carried over.
+#[derive(Default)]
+pub struct SharedBankCluster {
+    banks: RwLock<HashMap<BankId, RwLock<DataBank>>>,
+}
+
+impl SharedBankCluster {
+    /// Create an empty shared cluster.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a bank, returning the bank it replaced (if any).
+    pub fn insert(&self, bank: DataBank) -> Option<DataBank> {
+        write(&self.banks)
+            .insert(bank.id, RwLock::new(bank))
+            .map(|old| old.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Remove a bank from the cluster.
+    pub fn remove(&self, id: BankId) -> Option<DataBank> {
+        write(&self.banks)
+            .remove(&id)
+            .map(|old| old.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Whether a bank with this id exists.
+    pub fn contains(&self, id: BankId) -> bool {
+        read(&self.banks).contains_key(&id)
+    }
+
+    /// All bank IDs in the cluster.
+    pub fn bank_ids(&self) -> Vec<BankId> {
+        read(&self.banks).keys().copied().collect()
+    }
+
+    /// Number of banks in the cluster.
+    pub fn len(&self) -> usize {
+        read(&self.banks).len()
+    }
+
+    /// Whether the cluster has no banks.
+    pub fn is_empty(&self) -> bool {
+        read(&self.banks).is_empty()
+    }
+
+    /// Run `f` with a shared lock on one bank.
+    pub fn read_bank<R>(&self, id: BankId, f: impl FnOnce(&DataBank) -> R) -> Result<R> {
+        let banks = read(&self.banks);
+        let bank = banks.get(&id).ok_or(DataBankError::BankNotFound { id })?;
+        let guard = read(bank);
+        Ok(f(&guard))
+    }
+
+    /// Run `f` with an exclusive lock on one bank.
+    pub fn write_bank<R>(&self, id: BankId, f: impl FnOnce(&mut DataBank) -> R) -> Result<R> {
+        let banks = read(&self.banks);
+        let bank = banks.get(&id).ok_or(DataBankError::BankNotFound { id })?;
+        let mut guard = write(bank);
+        Ok(f(&mut guard))
+    }
+
+    /// Query across banks, taking each bank's read lock only while that
+    /// bank is queried. Same ranking as [`BankCluster::query_all`].
+    pub fn query_all(
+        &self,
+        query_per_bank: &HashMap<BankId, Vec<Signal>>,
+        top_k: usize,
+    ) -> Vec<ClusterQueryResult> {
+        let banks = read(&self.banks);
+        let mut per_bank = Vec::new();
+
+        for (bank_id, query) in query_per_bank {
+            let Some(bank) = banks.get(bank_id) else {
+                continue;
+            };
+            let bank = read(bank);
+            per_bank.push((*bank_id, bank.name.clone(), bank.query_lenient(query, top_k)));
+        }
+
+        let mut all_results = fuse_results(per_bank);
+        all_results.sort_by_key(|r| Reverse(r.normalized_score));
+        all_results.truncate(top_k);
+        all_results
+    }
+
+    /// Create a typed edge between two entries (possibly in different banks).
+    ///
+    /// Both banks are write-locked in ascending `BankId` order. Unlike
+    /// [`BankCluster::link`], the target entry must exist: holding its lock
+    /// is what makes that check meaningful.
+    pub fn link(
+        &self,
+        from: BankRef,
+        to: BankRef,
+        edge_type: EdgeType,
+        weight: u8,
+        tick: u64,
+    ) -> Result<()> {
+        let banks = read(&self.banks);
+        let source = banks
+            .get(&from.bank)
+            .ok_or(DataBankError::BankNotFound { id: from.bank })?;
+        let target = banks
+            .get(&to.bank)
+            .ok_or(DataBankError::BankNotFound { id: to.bank })?;
+
+        let edge = Edge {
+            edge_type,
+            target: to,
+            weight,
+            created_tick: tick,
+        };
+
+        if from.bank == to.bank {
+            let mut bank = write(source);
+            if bank.get(to.entry).is_none() {
+                return Err(DataBankError::EntryNotFound { id: to.entry });
+            }
+            return bank.add_edge(from.entry, edge);
+        }
+
+        // Canonical order: lower BankId first
+        let (mut source_guard, target_guard) = if from.bank < to.bank {
+            let s = write(source);
+            let t = write(target);
+            (s, t)
+        } else {
+            let t = write(target);
+            let s = write(source);
+            (s, t)
+        };
+        if target_guard.get(to.entry).is_none() {
+            return Err(DataBankError::EntryNotFound { id: to.entry });
+        }
+        source_guard.add_edge(from.entry, edge)
+    }
+
+    /// Flush all dirty banks that have exceeded their persistence threshold.
+    ///
+    /// Banks are locked one at a time, so queries on other banks keep
+    /// running during the flush. A bank is marked persisted only after the
+    /// directory sync succeeds, and stays dirty if it was mutated after its
+    /// write. Returns the number of banks flushed.
+    pub fn flush_dirty(&self, dir: &Path, current_tick: u64) -> Result<usize> {
+        let banks = read(&self.banks);
+        let mut written = Vec::new();
+        let mut buf = Vec::new();
+
+        for (&id, bank) in banks.iter() {
+            let bank = read(bank);
+            if !bank.should_persist(current_tick) {
+                continue;
+            }
+            #[cfg(feature = "metrics")]
+            let started = std::time::Instant::now();
+            let path = dir.join(format!("{}.bank", bank.name));
+            codec::encode_into(&bank, &codec::EncodeOptions::default(), &mut buf)?;
+            codec::write_atomic(&buf, &path)?;
+            #[cfg(feature = "metrics")]
+            bank.metrics_recorder().record_flush_time(started.elapsed());
+            written.push((id, bank.generation(), bank.mutations_since_persist()));
+        }
+
+        if written.is_empty() {
+            return Ok(0);
+        }
+        codec::sync_dir(dir)?;
+        for (id, generation, mutations) in &written {
+            write(&banks[id]).mark_generation_persisted(current_tick, *generation, *mutations);
+        }
+        log::info!("flushed {} dirty banks to {:?}", written.len(), dir);
+
+        Ok(written.len())
+    }
+}
+
+impl From<BankCluster> for SharedBankCluster {
+    fn from(cluster: BankCluster) -> Self {
+        let banks = cluster
+            .into_banks()
+            .map(|bank| (bank.id, RwLock::new(bank)))
+            .collect();
+        Self {
+            banks: RwLock::new(banks),
+        }
+    }
+}
+
+// A panic inside a caller's closure poisons the lock, but the bank itself
+// is only mutated through its own methods, so keep serving it.
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BankConfig, EntryId, Temperature};
+    use std::sync::mpsc;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn make_config(width: u16) -> BankConfig {
+        BankConfig {
+            vector_width: width,
+            max_entries: 100,
+            max_edges_per_entry: 512,
+            persist_after_mutations: 1,
+            persist_after_ticks: 0,
+            ..BankConfig::default()
+        }
+    }
+
+    fn make_vector(width: u16) -> Vec<Signal> {
+        (0..width)
+            .map(|i| Signal::new_raw(1, (i % 255) as u8 + 1, 1))
+            .collect()
+    }
+
+    fn make_shared() -> (Arc<SharedBankCluster>, BankRef, BankRef) {
+        let shared = SharedBankCluster::new();
+        let mut refs = Vec::new();
+        for (raw, name) in [(1, "a"), (2, "b")] {
+            let id = BankId::from_raw(raw);
+            let mut bank = DataBank::new(id, name.into(), make_config(4));
+            let entry = bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+            shared.insert(bank);
+            refs.push(BankRef { bank: id, entry });
+        }
+        (Arc::new(shared), refs[0], refs[1])
+    }
+
+    #[test]
+    fn shared_cluster_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedBankCluster>();
+    }
+
+    #[test]
+    fn queries_on_other_banks_proceed_while_one_is_write_locked() {
+        let (shared, a, b) = make_shared();
+        let (entered_tx, entered_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+
+        // Hold bank a's write lock until told to release it
+        let holder = {
+            let shared = Arc::clone(&shared);
+            std::thread::spawn(move || {
+                shared
+                    .write_bank(a.bank, |_| {
+                        entered_tx.send(()).unwrap();
+                        release_rx.recv().unwrap();
+                    })
+                    .unwrap();
+            })
+        };
+        entered_rx.recv().unwrap();
+
+        // Query bank b from another thread; it must not wait for bank a
+        let (done_tx, done_rx) = mpsc::channel();
+        let reader = {
+            let shared = Arc::clone(&shared);
+            std::thread::spawn(move || {
+                let mut query = HashMap::new();
+                query.insert(b.bank, make_vector(4));
+                let results = shared.query_all(&query, 5);
+                let len = shared.read_bank(b.bank, |bank| bank.len()).unwrap();
+                done_tx.send((results.len(), len)).unwrap();
+            })
+        };
+        let (found, len) = done_rx
+            .recv_timeout(Duration::from_secs(5))
+            .expect("query on bank b blocked behind bank a's write lock");
+        assert_eq!(found, 1);
+        assert_eq!(len, 1);
+
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+        reader.join().unwrap();
+    }
+
+    #[test]
+    fn writes_to_different_banks_run_concurrently() {
+        let (shared, a, b) = make_shared();
+        // Each writer waits inside its bank's write lock for the other; a
+        // lock shared between the banks would leave both stuck here.
+        let both_inside = Arc::new(std::sync::Barrier::new(2));
+        let (done_tx, done_rx) = mpsc::channel();
+        let handles: Vec<_> = [a.bank, b.bank]
+            .into_iter()
+            .map(|id| {
+                let shared = Arc::clone(&shared);
+                let both_inside = Arc::clone(&both_inside);
+                let done_tx = done_tx.clone();
+                std::thread::spawn(move || {
+                    shared
+                        .write_bank(id, |bank| {
+                            both_inside.wait();
+                            bank.insert(make_vector(4), Temperature::Hot, 1).unwrap();
+                        })
+                        .unwrap();
+                    done_tx.send(()).unwrap();
+                })
+            })
+            .collect();
+        for _ in 0..2 {
+            done_rx
+                .recv_timeout(Duration::from_secs(5))
+                .expect("writers on different banks were serialized");
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        for id in [a.bank, b.bank] {
+            assert_eq!(shared.read_bank(id, |bank| bank.len()).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn opposing_cross_bank_links_do_not_deadlock() {
+        let (shared, a, b) = make_shared();
+        let (done_tx, done_rx) = mpsc::channel();
+        let handles: Vec<_> = [(a, b), (b, a)]
+            .into_iter()
+            .map(|(from, to)| {
+                let shared = Arc::clone(&shared);
+                let done_tx = done_tx.clone();
+                std::thread::spawn(move || {
+                    for tick in 0..200 {
+                        shared.link(from, to, EdgeType::RelatedTo, 100, tick).unwrap();
+                    }
+                    done_tx.send(()).unwrap();
+                })
+            })
+            .collect();
+        for _ in 0..2 {
+            done_rx
+                .recv_timeout(Duration::from_secs(10))
+                .expect("cross-bank links deadlocked");
+        }
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        let edges = shared.read_bank(a.bank, |bank| bank.edges_from(a.entry).len()).unwrap();
+        assert_eq!(edges, 200);
+    }
+
+    #[test]
+    fn link_requires_existing_target_entry() {
+        let (shared, a, b) = make_shared();
+        let missing = BankRef {
+            bank: b.bank,
+            entry: EntryId::from_raw(12345),
+        };
+        assert!(matches!(
+            shared.link(a, missing, EdgeType::IsA, 10, 0),
+            Err(DataBankError::EntryNotFound { .. })
+        ));
+        assert!(shared.link(a, b, EdgeType::IsA, 10, 0).is_ok());
+        assert!(shared.link(a, a, EdgeType::IsA, 10, 0).is_ok());
+    }
+
+    #[test]
+    fn flush_dirty_and_conversion_from_cluster() {
+        let mut cluster = BankCluster::new();
+        let id = BankId::from_raw(7);
+        let bank = cluster.get_or_create(id, "converted".into(), make_config(4)).unwrap();
+        bank.insert(make_vector(4), Temperature::Hot, 0).unwrap();
+
+        let shared = SharedBankCluster::from(cluster);
+        assert_eq!(shared.len(), 1);
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(shared.flush_dirty(dir.path(), 10).unwrap(), 1);
+        assert!(dir.path().join("converted.bank").exists());
+        // Clean now
+        assert_eq!(shared.flush_dirty(dir.path(), 20).unwrap(), 0);
+        assert!(shared.remove(id).is_some());
+        assert!(shared.is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use ternary_signal::Signal;
 
+use crate::prelude::*;
 use crate::types::EntryId;
 
 /// Result of a similarity query: entry ID + score.
@@ -12,6 +13,45 @@ pub struct QueryResult {
     pub score: i32,
 }
 
+/// How a bank scores stored vectors against a query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Metric {
+    /// [`sparse_cosine_similarity`].
+    #[default]
+    Cosine,
+    /// [`prenormalized_dot`]: cosine without the norms, for banks whose
+    /// vectors all have squared norm (in currents) `norm_sq`. Callers must
+    /// normalize every vector to that norm before inserting it, and cues
+    /// before querying; other vectors get meaningless scores.
+    PrenormalizedDot { norm_sq: u64 },
+}
+
+impl Metric {
+    /// Score `stored` against `query`, x256.
+    pub fn score(self, query: &[Signal], stored: &[Signal]) -> i32 {
+        match self {
+            Metric::Cosine => sparse_cosine_similarity(query, stored),
+            Metric::PrenormalizedDot { norm_sq } => prenormalized_dot(query, stored, norm_sq),
+        }
+    }
+}
+
+/// `dot * 256 / norm_sq`, clamped to [-256, 256]. For two vectors of
+/// squared norm `norm_sq` this is their cosine, with no norm or square root
+/// computed per candidate. Returns 0 when `norm_sq` is 0.
+pub fn prenormalized_dot(query: &[Signal], stored: &[Signal], norm_sq: u64) -> i32 {
+    if norm_sq == 0 {
+        return 0;
+    }
+    let dot: i64 = query
+        .iter()
+        .zip(stored)
+        .map(|(q, s)| q.current() as i64 * s.current() as i64)
+        .sum();
+    let norm_sq = norm_sq.min(i64::MAX as u64) as i64;
+    (dot.saturating_mul(256) / norm_sq).clamp(-256, 256) as i32
+}
+
 /// Sparse cosine similarity using only integer arithmetic.
 ///
 /// Uses the full ternary equation s = p x m x k via `Signal::current()`.
@@ -23,11 +63,27 @@ pub struct QueryResult {
 ///
 /// Compliant with ASTRO_004: no floating point. Integer-only arithmetic.
 pub fn sparse_cosine_similarity(query: &[Signal], stored: &[Signal]) -> i32 {
+    sparse_cosine_similarity_detailed(query, stored).0
+}
+
+/// [`sparse_cosine_similarity`] plus the overlap behind the score:
+/// `(score, active_dims, agreeing_dims)`.
+///
+/// `active_dims` counts the non-zero query dimensions that were compared;
+/// `agreeing_dims` counts those where the stored signal is non-zero with
+/// the same sign. A high score from few agreeing dimensions is a lucky
+/// match on a thin cue, not strong evidence.
+pub fn sparse_cosine_similarity_detailed(
+    query: &[Signal],
+    stored: &[Signal],
+) -> (i32, usize, usize) {
     let len = query.len().min(stored.len());
 
     let mut dot: i64 = 0;
     let mut norm_q: i64 = 0;
     let mut norm_s: i64 = 0;
+    let mut active = 0;
+    let mut agreeing = 0;
 
     for i in 0..len {
         let q = query[i];
@@ -40,25 +96,218 @@ pub fn sparse_cosine_similarity(query: &[Signal], stored: &[Signal]) -> i32 {
         let q_val = q.current() as i64;
         let s_val = stored[i].current() as i64;
 
+        active += 1;
+        if q_val.signum() == s_val.signum() {
+            agreeing += 1;
+        }
         dot += q_val * s_val;
         norm_q += q_val * q_val;
         norm_s += s_val * s_val;
     }
 
     if norm_q == 0 || norm_s == 0 {
-        return 0;
+        return (0, active, agreeing);
     }
 
     // cosine = dot / sqrt(norm_q * norm_s)
     // scaled = dot * 256 / sqrt(norm_q * norm_s)
     let denom = isqrt(norm_q * norm_s);
     if denom == 0 {
-        return 0;
+        return (0, active, agreeing);
     }
 
+    (((dot * 256) / denom) as i32, active, agreeing)
+}
+
+/// One active query dimension's part in a [`MatchExplanation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DimContribution {
+    pub dim: usize,
+    /// Query current on this dimension (never 0).
+    pub query: i32,
+    /// Stored current on this dimension.
+    pub stored: i32,
+    /// `query * stored`: this dimension's term in the dot product. Positive
+    /// supports the match, negative conflicts with it.
+    pub product: i64,
+}
+
+/// Why a stored vector scored what it did against a query, from
+/// [`explain_similarity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchExplanation {
+    /// The [`sparse_cosine_similarity`] score.
+    pub score: i32,
+    /// Sum of every contribution's `product`.
+    pub dot: i64,
+    /// Squared norm of the query over its active dimensions.
+    pub query_norm_sq: i64,
+    /// Squared norm of the stored vector over the query's active dimensions.
+    pub stored_norm_sq: i64,
+    /// Active query dimensions compared.
+    pub active_dims: usize,
+    /// Active dimensions where the stored signal has the query's sign.
+    pub agreeing_dims: usize,
+    /// One per active query dimension, in dimension order.
+    pub contributions: Vec<DimContribution>,
+}
+
+impl MatchExplanation {
+    /// Dimensions that raised the score.
+    pub fn supporting(&self) -> impl Iterator<Item = &DimContribution> {
+        self.contributions.iter().filter(|c| c.product > 0)
+    }
+
+    /// Dimensions that lowered the score.
+    pub fn conflicting(&self) -> impl Iterator<Item = &DimContribution> {
+        self.contributions.iter().filter(|c| c.product < 0)
+    }
+}
+
+/// Break [`sparse_cosine_similarity_detailed`] down per dimension. A
+/// diagnostic for "why did this recall?", not for hot paths.
+pub fn explain_similarity(query: &[Signal], stored: &[Signal]) -> MatchExplanation {
+    let (score, active_dims, agreeing_dims) = sparse_cosine_similarity_detailed(query, stored);
+    let contributions: Vec<DimContribution> = query
+        .iter()
+        .zip(stored)
+        .enumerate()
+        .filter(|(_, (q, _))| q.current() != 0)
+        .map(|(dim, (q, s))| DimContribution {
+            dim,
+            query: q.current(),
+            stored: s.current(),
+            product: q.current() as i64 * s.current() as i64,
+        })
+        .collect();
+    let sum_sq = |value: fn(&DimContribution) -> i32| -> i64 {
+        contributions.iter().map(|c| (value(c) as i64).pow(2)).sum()
+    };
+    MatchExplanation {
+        score,
+        dot: contributions.iter().map(|c| c.product).sum(),
+        query_norm_sq: sum_sq(|c| c.query),
+        stored_norm_sq: sum_sq(|c| c.stored),
+        active_dims,
+        agreeing_dims,
+        contributions,
+    }
+}
+
+/// [`sparse_cosine_similarity`] with each dimension's terms in the dot
+/// product and both norms multiplied by `weights[i]`: attention over the
+/// features of one representation.
+///
+/// Dimensions past the end of `weights` weigh 1; negative weights count as
+/// 0, which drops the dimension. All-ones weights give exactly the
+/// unweighted score. Accumulates in i128, so large weights on wide vectors
+/// cannot overflow.
+pub fn sparse_cosine_similarity_weighted(
+    query: &[Signal],
+    stored: &[Signal],
+    weights: &[i32],
+) -> i32 {
+    let len = query.len().min(stored.len());
+
+    let mut dot: i128 = 0;
+    let mut norm_q: i128 = 0;
+    let mut norm_s: i128 = 0;
+
+    for i in 0..len {
+        let q_val = query[i].current() as i128;
+        if q_val == 0 {
+            continue;
+        }
+        let s_val = stored[i].current() as i128;
+        let w = weights.get(i).map_or(1, |&w| w.max(0)) as i128;
+
+        dot += w * q_val * s_val;
+        norm_q += w * q_val * q_val;
+        norm_s += w * s_val * s_val;
+    }
+
+    // Exact like the unweighted path unless the product itself overflows
+    let denom = match norm_q.checked_mul(norm_s) {
+        Some(product) => isqrt_wide(product),
+        None => isqrt_wide(norm_q) * isqrt_wide(norm_s),
+    };
+    if denom == 0 {
+        return 0;
+    }
     ((dot * 256) / denom) as i32
 }
 
+/// Largest |current| a single Signal can carry: magnitude 255 x multiplier 255.
+const MAX_CURRENT: i64 = 255 * 255;
+
+/// Superpose cues: the element-wise signed sum of their currents, clamped to
+/// the representable range and re-split into polarity/magnitude/multiplier.
+///
+/// Cues shorter than the widest one contribute zero in the missing
+/// dimensions. The result scores high against every source pattern, which
+/// is how vector-symbolic queries combine concepts ("jar plus metal").
+pub fn superpose(cues: &[&[Signal]]) -> Vec<Signal> {
+    let width = cues.iter().map(|c| c.len()).max().unwrap_or(0);
+    let mut sums = vec![0i64; width];
+    for cue in cues {
+        for (sum, s) in sums.iter_mut().zip(cue.iter()) {
+            *sum += s.current() as i64;
+        }
+    }
+    sums.into_iter().map(clamp_to_signal).collect()
+}
+
+/// Element-wise `a - b` on signal currents, clamped and re-split like
+/// [`superpose`]. The result is `max(a.len(), b.len())` wide.
+pub fn subtract(a: &[Signal], b: &[Signal]) -> Vec<Signal> {
+    let width = a.len().max(b.len());
+    (0..width)
+        .map(|i| {
+            let x = a.get(i).map_or(0, |s| s.current() as i64);
+            let y = b.get(i).map_or(0, |s| s.current() as i64);
+            clamp_to_signal(x - y)
+        })
+        .collect()
+}
+
+/// Top `top_k` candidates by [`sparse_cosine_similarity`] against `query`,
+/// highest score first, with equal scores in ascending id order.
+///
+/// Candidates can come from anywhere (an mmap, a lazy decoder, a filtered
+/// scan), not just an [`EntryStore`](crate::EntryStore). A bounded
+/// min-heap keeps memory at `top_k` results however long the stream is.
+pub fn score_stream<'a, I>(query: &[Signal], candidates: I, top_k: usize) -> Vec<QueryResult>
+where
+    I: Iterator<Item = (EntryId, &'a [Signal])>,
+{
+    use alloc::collections::BinaryHeap;
+    use core::cmp::Reverse;
+
+    if top_k == 0 {
+        return Vec::new();
+    }
+    // Smallest (score, Reverse(id)) on top, so it is the one displaced.
+    let mut heap: BinaryHeap<Reverse<(i32, Reverse<EntryId>)>> =
+        BinaryHeap::with_capacity(top_k + 1);
+    for (id, vector) in candidates {
+        let key = (sparse_cosine_similarity(query, vector), Reverse(id));
+        if heap.len() < top_k {
+            heap.push(Reverse(key));
+        } else if heap.peek().is_some_and(|Reverse(min)| key > *min) {
+            heap.pop();
+            heap.push(Reverse(key));
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((score, Reverse(entry_id)))| QueryResult { entry_id, score })
+        .collect()
+}
+
+fn clamp_to_signal(current: i64) -> Signal {
+    Signal::from_current(current.clamp(-MAX_CURRENT, MAX_CURRENT) as i32)
+}
+
 /// Integer square root via Newton's method. 5 iterations is sufficient
 /// for the full i64 range. Returns floor(sqrt(n)).
 fn isqrt(n: i64) -> i64 {
@@ -70,7 +319,7 @@ fn isqrt(n: i64) -> i64 {
     }
 
     // Initial guess: overestimate so Newton converges downward
-    let mut x = 1i64 << (((64 - n.leading_zeros()) + 1) / 2);
+    let mut x = 1i64 << (64 - n.leading_zeros()).div_ceil(2);
 
     for _ in 0..8 {
         let next = (x + n / x) / 2;
@@ -83,6 +332,21 @@ fn isqrt(n: i64) -> i64 {
     x
 }
 
+/// Floor square root of a non-negative i128, by Newton's method.
+pub(crate) fn isqrt_wide(n: i128) -> i128 {
+    if n <= 1 {
+        return n.max(0);
+    }
+    let mut x = 1i128 << (128 - n.leading_zeros()).div_ceil(2);
+    loop {
+        let next = (x + n / x) / 2;
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,6 +359,19 @@ mod tests {
         Signal::ZERO
     }
 
+    #[test]
+    fn detailed_counts_active_and_agreeing_dims_of_partial_cue() {
+        let stored = vec![sig(1, 100), sig(-1, 80), sig(1, 60), zero(), sig(-1, 40)];
+        // Cue names four dimensions: two agree, one opposes, one hits a zero
+        let cue = vec![sig(1, 90), sig(-1, 70), sig(-1, 50), sig(1, 30), zero()];
+
+        let (score, active, agreeing) = sparse_cosine_similarity_detailed(&cue, &stored);
+        assert_eq!(active, 4);
+        assert_eq!(agreeing, 2);
+        assert_eq!(score, sparse_cosine_similarity(&cue, &stored));
+        assert!(score > 0);
+    }
+
     #[test]
     fn identical_vectors_max_similarity() {
         let a = vec![sig(1, 100), sig(-1, 50), sig(1, 200)];
@@ -175,4 +452,125 @@ mod tests {
         assert_eq!(isqrt(10000), 100);
         assert_eq!(isqrt(1_000_000), 1000);
     }
+
+    #[test]
+    fn superposition_matches_both_sources() {
+        let jar = vec![sig(1, 100), sig(1, 100), zero(), zero()];
+        let metal = vec![zero(), zero(), sig(-1, 100), sig(1, 100)];
+        let combined = superpose(&[jar.as_slice(), metal.as_slice()]);
+        assert_eq!(combined.len(), 4);
+
+        let to_jar = sparse_cosine_similarity(&combined, &jar);
+        let to_metal = sparse_cosine_similarity(&combined, &metal);
+        assert!(to_jar > 150, "expected high score vs jar, got {to_jar}");
+        assert!(to_metal > 150, "expected high score vs metal, got {to_metal}");
+
+        let unrelated = vec![sig(-1, 100), sig(-1, 100), sig(1, 100), sig(-1, 100)];
+        assert!(sparse_cosine_similarity(&combined, &unrelated) < 0);
+    }
+
+    #[test]
+    fn subtract_undoes_superposition() {
+        let jar = vec![sig(1, 100), sig(-1, 40), sig(1, 60)];
+        let glass = vec![sig(1, 20), sig(1, 80), sig(-1, 10)];
+        let combined = superpose(&[jar.as_slice(), glass.as_slice()]);
+        let recovered = subtract(&combined, &glass);
+        let score = sparse_cosine_similarity(&recovered, &jar);
+        assert!(score >= 250, "expected ~256, got {score}");
+    }
+
+    #[test]
+    fn superpose_clamps_and_handles_ragged_cues() {
+        let loud = vec![Signal::new_raw(1, 255, 255); 2];
+        let short = vec![Signal::new_raw(1, 255, 255)];
+        let combined = superpose(&[loud.as_slice(), loud.as_slice(), short.as_slice()]);
+        assert_eq!(combined.len(), 2);
+        assert!(combined[0].current() as i64 <= MAX_CURRENT);
+        assert!(combined[0].current() > 0);
+        assert!(superpose(&[]).is_empty());
+    }
+
+    #[test]
+    fn weighted_similarity_reweighs_dimensions() {
+        let stored = vec![sig(1, 100), sig(-1, 80), sig(1, 60), zero()];
+        let cue = vec![sig(1, 90), sig(1, 70), sig(1, 50), sig(1, 30)];
+        let plain = sparse_cosine_similarity(&cue, &stored);
+        assert_eq!(sparse_cosine_similarity_weighted(&cue, &stored, &[]), plain);
+        assert_eq!(sparse_cosine_similarity_weighted(&cue, &stored, &[1, 1, 1, 1, 9]), plain);
+
+        // Dropping the disagreeing dimension raises the score
+        let muted = sparse_cosine_similarity_weighted(&cue, &stored, &[1, 0]);
+        assert!(muted > plain, "{muted} <= {plain}");
+        assert_eq!(sparse_cosine_similarity_weighted(&cue, &stored, &[1, -5]), muted);
+        // Leaning on it lowers the score
+        assert!(sparse_cosine_similarity_weighted(&cue, &stored, &[1, 50]) < plain);
+        assert_eq!(sparse_cosine_similarity_weighted(&cue, &stored, &[0; 4]), 0);
+
+        let loud = vec![Signal::new_raw(1, 255, 255); 64];
+        let score = sparse_cosine_similarity_weighted(&loud, &loud, &[i32::MAX; 64]);
+        assert_eq!(score, 256);
+    }
+
+    #[test]
+    fn explanation_accounts_for_the_score() {
+        let stored = vec![sig(1, 100), sig(-1, 80), sig(1, 60), zero(), sig(-1, 40)];
+        let cue = vec![sig(1, 90), sig(-1, 70), sig(-1, 50), sig(1, 30), zero()];
+        let explained = explain_similarity(&cue, &stored);
+
+        assert_eq!(explained.score, sparse_cosine_similarity(&cue, &stored));
+        assert_eq!((explained.active_dims, explained.agreeing_dims), (4, 2));
+        let dims: Vec<usize> = explained.contributions.iter().map(|c| c.dim).collect();
+        assert_eq!(dims, [0, 1, 2, 3]);
+        assert_eq!(explained.supporting().map(|c| c.dim).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(explained.conflicting().map(|c| c.dim).collect::<Vec<_>>(), [2]);
+        assert_eq!(explained.dot, 90 * 100 + 70 * 80 - 50 * 60);
+        let denom = isqrt(explained.query_norm_sq * explained.stored_norm_sq);
+        assert_eq!((explained.dot * 256 / denom) as i32, explained.score);
+    }
+
+    #[test]
+    fn score_stream_matches_bank_scan() {
+        use crate::bank::DataBank;
+        use crate::ivf::IndexType;
+        use crate::rng::XorShift64Star;
+        use crate::types::{BankConfig, BankId, Temperature};
+
+        let config = BankConfig {
+            vector_width: 16,
+            max_entries: 0,
+            index_type: IndexType::BruteForce,
+            ..BankConfig::default()
+        };
+        let mut bank = DataBank::new(BankId::from_raw(1), "stream".into(), config);
+        let mut rng = XorShift64Star::new(0x9E37_79B9);
+        for _ in 0..200 {
+            let v: Vec<Signal> = (0..16)
+                .map(|_| {
+                    let r = rng.next_u64();
+                    sig(if r & 1 == 0 { 1 } else { -1 }, (r >> 8) as u8 | 1)
+                })
+                .collect();
+            bank.insert(v, Temperature::Hot, 0).unwrap();
+        }
+        let stream: Vec<(EntryId, Vec<Signal>)> =
+            bank.entries().map(|(id, e)| (*id, e.vector().to_vec())).collect();
+        let query: Vec<Signal> = (0..16).map(|i| sig(1, 10 + i as u8 * 9)).collect();
+
+        let expected = bank.query_sparse(&query, 10).unwrap();
+        let streamed =
+            score_stream(&query, stream.iter().rev().map(|(id, v)| (*id, v.as_slice())), 10);
+        let scores = |r: &[QueryResult]| r.iter().map(|r| r.score).collect::<Vec<_>>();
+        assert_eq!(scores(&streamed), scores(&expected));
+        for r in &streamed {
+            let stored = bank.get(r.entry_id).unwrap().vector();
+            assert_eq!(r.score, sparse_cosine_similarity(&query, stored));
+        }
+
+        // Ties resolve to the smaller id, whatever the stream order.
+        let same = vec![sig(1, 50); 4];
+        let tied = [(EntryId(9), &same[..]), (EntryId(3), &same[..]), (EntryId(5), &same[..])];
+        let top = score_stream(&same, tied.into_iter(), 2);
+        assert_eq!(top.iter().map(|r| r.entry_id).collect::<Vec<_>>(), [EntryId(3), EntryId(5)]);
+        assert!(score_stream(&same, tied.into_iter(), 0).is_empty());
+    }
 }
@@ -0,0 +1,157 @@
+//! Immutable Bank Snapshots
+//!
+//! A `BankSnapshot` is a frozen, read-only view of a bank's entries taken
+//! at a single point in time. The kernel takes one at a phase boundary and
+//! serves reads from it while mutations continue on the live bank.
+//!
+//! Each entry is held behind its own `Arc`, and the entry list behind
+//! another, so cloning a snapshot or handing an entry to a reader never
+//! copies vectors. Taking the snapshot copies each entry once.
+
+use alloc::sync::Arc;
+use core::cmp::Reverse;
+use ternary_signal::Signal;
+
+use crate::entry::BankEntry;
+use crate::prelude::*;
+use crate::similarity::{Metric, QueryResult};
+use crate::types::{BankId, EntryId};
+
+/// Point-in-time, cheaply-cloneable view of a bank's entries.
+///
+/// Queries use a linear scan with the same metric as the live bank. The
+/// live bank's index is not carried over, so results match a `BruteForce`
+/// bank.
+#[derive(Debug, Clone)]
+pub struct BankSnapshot {
+    /// Identity of the bank this snapshot was taken from.
+    pub bank_id: BankId,
+    /// Entries sorted by EntryId.
+    entries: Arc<Vec<(EntryId, Arc<BankEntry>)>>,
+    /// The live bank's metric when the snapshot was taken.
+    metric: Metric,
+}
+
+impl BankSnapshot {
+    /// Build a snapshot from a bank's entries. Called by
+    /// [`DataBank::snapshot`](crate::bank::DataBank::snapshot).
+    pub(crate) fn from_entries<'a>(
+        bank_id: BankId,
+        metric: Metric,
+        entries: impl Iterator<Item = (&'a EntryId, &'a BankEntry)>,
+    ) -> Self {
+        let mut list: Vec<(EntryId, Arc<BankEntry>)> = entries
+            .map(|(&id, entry)| (id, Arc::new(entry.clone())))
+            .collect();
+        list.sort_unstable_by_key(|(id, _)| *id);
+        Self {
+            bank_id,
+            entries: Arc::new(list),
+            metric,
+        }
+    }
+
+    /// Get a reference to an entry by ID.
+    pub fn get(&self, id: EntryId) -> Option<&BankEntry> {
+        self.position(id).map(|i| &*self.entries[i].1)
+    }
+
+    /// Get a shared handle to an entry by ID, for readers that outlive the
+    /// snapshot borrow.
+    pub fn entry(&self, id: EntryId) -> Option<Arc<BankEntry>> {
+        self.position(id).map(|i| Arc::clone(&self.entries[i].1))
+    }
+
+    /// Query the snapshot for the entries most similar to `query`.
+    pub fn query_sparse(&self, query: &[Signal], top_k: usize) -> Vec<QueryResult> {
+        if top_k == 0 || self.entries.is_empty() {
+            return Vec::new();
+        }
+
+        let mut results: Vec<QueryResult> = self
+            .entries
+            .iter()
+            .map(|(id, entry)| QueryResult {
+                entry_id: *id,
+                score: self.metric.score(query, &entry.vector),
+            })
+            .collect();
+
+        results.sort_unstable_by_key(|r| Reverse(r.score));
+        results.truncate(top_k);
+        results
+    }
+
+    /// Iterate all entries in EntryId order.
+    pub fn entries(&self) -> impl Iterator<Item = (EntryId, &BankEntry)> {
+        self.entries.iter().map(|(id, entry)| (*id, &**entry))
+    }
+
+    /// Number of entries in the snapshot.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the snapshot holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn position(&self, id: EntryId) -> Option<usize> {
+        self.entries.binary_search_by_key(&id, |(eid, _)| *eid).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bank::DataBank;
+    use crate::ivf::IndexType;
+    use crate::similarity::QueryResult;
+    use crate::types::{BankConfig, BankId, EntryId, Temperature};
+    use ternary_signal::Signal;
+
+    fn sig(polarity: i8, magnitude: u8) -> Signal {
+        Signal::new_raw(polarity, magnitude, 1)
+    }
+
+    fn make_bank() -> DataBank {
+        let config = BankConfig {
+            vector_width: 2,
+            max_entries: 16,
+            index_type: IndexType::BruteForce,
+            ..BankConfig::default()
+        };
+        DataBank::new(BankId::from_raw(1), "snap".into(), config)
+    }
+
+    fn ranked(results: &[QueryResult]) -> Vec<(EntryId, i32)> {
+        results.iter().map(|r| (r.entry_id, r.score)).collect()
+    }
+
+    #[test]
+    fn snapshot_is_unaffected_by_live_mutations() {
+        let mut bank = make_bank();
+        let a = bank.insert(vec![sig(1, 200), sig(1, 100)], Temperature::Hot, 0).unwrap();
+        let b = bank.insert(vec![sig(-1, 200), sig(1, 50)], Temperature::Hot, 0).unwrap();
+
+        let snapshot = bank.snapshot();
+        let query = vec![sig(1, 200), sig(1, 100)];
+        let before = ranked(&snapshot.query_sparse(&query, 2));
+        let shared = snapshot.clone();
+
+        bank.remove(a);
+        bank.set_vector(b, vec![sig(1, 200), sig(1, 100)]).unwrap();
+        bank.insert(vec![sig(1, 100), sig(1, 50)], Temperature::Hot, 1).unwrap();
+
+        assert_eq!(snapshot.len(), 2);
+        assert!(snapshot.get(a).is_some());
+        assert_eq!(snapshot.get(b).unwrap().vector, vec![sig(-1, 200), sig(1, 50)]);
+        assert_eq!(ranked(&snapshot.query_sparse(&query, 2)), before);
+        assert_eq!(before[0].0, a);
+        assert_eq!(ranked(&shared.query_sparse(&query, 2)), before);
+        assert!(std::sync::Arc::ptr_eq(
+            &snapshot.entry(a).unwrap(),
+            &shared.entry(a).unwrap()
+        ));
+    }
+}
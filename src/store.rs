@@ -0,0 +1,354 @@
+//! Entry Storage Backends
+//!
+//! A bank's entries live in an [`EntryStore`], keyed by [`EntryId`]. The
+//! default backend is a hash map. The slab backend keeps entries in one
+//! contiguous `Vec` with an id -> slot map beside it, so brute-force scans
+//! walk memory in order instead of chasing hash buckets; removal swaps the
+//! last slot into the hole, so iteration order changes as entries leave.
+//!
+//! Both backends present the same map-like API; the bank, indexes and codec
+//! never see which one is in use.
+
+use core::ops::Index;
+use core::slice;
+
+#[cfg(not(feature = "std"))]
+use hashbrown::hash_map;
+#[cfg(feature = "std")]
+use std::collections::hash_map;
+
+use crate::entry::BankEntry;
+use crate::prelude::*;
+use crate::types::{EntryId, EntryStorage};
+
+/// A bank's entries, keyed by id, in the backend chosen by
+/// [`BankConfig::storage`](crate::types::BankConfig::storage).
+#[derive(Debug, Clone)]
+pub struct EntryStore {
+    inner: Inner,
+}
+
+#[derive(Debug, Clone)]
+enum Inner {
+    Map(HashMap<EntryId, BankEntry>),
+    Slab {
+        slots: Vec<(EntryId, BankEntry)>,
+        slot_of: HashMap<EntryId, usize>,
+    },
+}
+
+impl Default for EntryStore {
+    fn default() -> Self {
+        Self::new(EntryStorage::default())
+    }
+}
+
+impl EntryStore {
+    /// An empty store using `storage`.
+    pub fn new(storage: EntryStorage) -> Self {
+        let inner = match storage {
+            EntryStorage::HashMap => Inner::Map(HashMap::new()),
+            EntryStorage::Slab => Inner::Slab {
+                slots: Vec::new(),
+                slot_of: HashMap::new(),
+            },
+        };
+        Self { inner }
+    }
+
+    /// The backend in use.
+    pub fn storage(&self) -> EntryStorage {
+        match self.inner {
+            Inner::Map(_) => EntryStorage::HashMap,
+            Inner::Slab { .. } => EntryStorage::Slab,
+        }
+    }
+
+    /// The same entries in `storage`, moved rather than cloned.
+    pub fn into_storage(self, storage: EntryStorage) -> Self {
+        if self.storage() == storage {
+            return self;
+        }
+        let entries: Vec<(EntryId, BankEntry)> = match self.inner {
+            Inner::Map(map) => map.into_iter().collect(),
+            Inner::Slab { slots, .. } => slots,
+        };
+        let mut store = Self::new(storage);
+        for (id, entry) in entries {
+            store.insert(id, entry);
+        }
+        store
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.inner {
+            Inner::Map(map) => map.len(),
+            Inner::Slab { slots, .. } => slots.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, id: &EntryId) -> Option<&BankEntry> {
+        match &self.inner {
+            Inner::Map(map) => map.get(id),
+            Inner::Slab { slots, slot_of } => slot_of.get(id).map(|&i| &slots[i].1),
+        }
+    }
+
+    pub fn get_mut(&mut self, id: &EntryId) -> Option<&mut BankEntry> {
+        match &mut self.inner {
+            Inner::Map(map) => map.get_mut(id),
+            Inner::Slab { slots, slot_of } => slot_of.get(id).map(|&i| &mut slots[i].1),
+        }
+    }
+
+    pub fn contains_key(&self, id: &EntryId) -> bool {
+        match &self.inner {
+            Inner::Map(map) => map.contains_key(id),
+            Inner::Slab { slot_of, .. } => slot_of.contains_key(id),
+        }
+    }
+
+    /// Insert or replace the entry under `id`, returning the old one.
+    pub fn insert(&mut self, id: EntryId, entry: BankEntry) -> Option<BankEntry> {
+        match &mut self.inner {
+            Inner::Map(map) => map.insert(id, entry),
+            Inner::Slab { slots, slot_of } => match slot_of.get(&id) {
+                Some(&i) => Some(core::mem::replace(&mut slots[i].1, entry)),
+                None => {
+                    slot_of.insert(id, slots.len());
+                    slots.push((id, entry));
+                    None
+                }
+            },
+        }
+    }
+
+    /// Remove and return the entry under `id`. The slab backend moves its
+    /// last entry into the freed slot.
+    pub fn remove(&mut self, id: &EntryId) -> Option<BankEntry> {
+        match &mut self.inner {
+            Inner::Map(map) => map.remove(id),
+            Inner::Slab { slots, slot_of } => {
+                let i = slot_of.remove(id)?;
+                let (_, entry) = slots.swap_remove(i);
+                if let Some((moved, _)) = slots.get(i) {
+                    slot_of.insert(*moved, i);
+                }
+                Some(entry)
+            }
+        }
+    }
+
+    /// Every `(id, entry)`, in no particular order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(match &self.inner {
+            Inner::Map(map) => IterInner::Map(map.iter()),
+            Inner::Slab { slots, .. } => IterInner::Slab(slots.iter()),
+        })
+    }
+
+    /// Every `(id, entry)` with the entry mutable, in no particular order.
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(match &mut self.inner {
+            Inner::Map(map) => IterMutInner::Map(map.iter_mut()),
+            Inner::Slab { slots, .. } => IterMutInner::Slab(slots.iter_mut()),
+        })
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &EntryId> + '_ {
+        self.iter().map(|(id, _)| id)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &BankEntry> + '_ {
+        self.iter().map(|(_, entry)| entry)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut BankEntry> + '_ {
+        self.iter_mut().map(|(_, entry)| entry)
+    }
+}
+
+/// Read-only access to entries by id, as [`VectorIndex`] needs it.
+///
+/// Implemented for [`EntryStore`] and for a plain
+/// `HashMap<EntryId, BankEntry>`, so index code can be handed either.
+///
+/// [`VectorIndex`]: crate::index::VectorIndex
+pub trait EntrySource {
+    /// Number of entries.
+    fn len(&self) -> usize;
+
+    /// Whether there are no entries.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The entry with `id`, if present.
+    fn get(&self, id: &EntryId) -> Option<&BankEntry>;
+
+    /// Every `(id, entry)`, in no particular order.
+    fn iter(&self) -> Iter<'_>;
+}
+
+impl EntrySource for EntryStore {
+    fn len(&self) -> usize {
+        EntryStore::len(self)
+    }
+
+    fn get(&self, id: &EntryId) -> Option<&BankEntry> {
+        EntryStore::get(self, id)
+    }
+
+    fn iter(&self) -> Iter<'_> {
+        EntryStore::iter(self)
+    }
+}
+
+impl EntrySource for HashMap<EntryId, BankEntry> {
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn get(&self, id: &EntryId) -> Option<&BankEntry> {
+        HashMap::get(self, id)
+    }
+
+    fn iter(&self) -> Iter<'_> {
+        Iter(IterInner::Map(HashMap::iter(self)))
+    }
+}
+
+impl Index<&EntryId> for EntryStore {
+    type Output = BankEntry;
+
+    fn index(&self, id: &EntryId) -> &BankEntry {
+        self.get(id).expect("no entry for id")
+    }
+}
+
+impl FromIterator<(EntryId, BankEntry)> for EntryStore {
+    /// Collects into the default (hash map) backend.
+    fn from_iter<I: IntoIterator<Item = (EntryId, BankEntry)>>(iter: I) -> Self {
+        Self {
+            inner: Inner::Map(iter.into_iter().collect()),
+        }
+    }
+}
+
+impl From<HashMap<EntryId, BankEntry>> for EntryStore {
+    fn from(map: HashMap<EntryId, BankEntry>) -> Self {
+        Self {
+            inner: Inner::Map(map),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a EntryStore {
+    type Item = (&'a EntryId, &'a BankEntry);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over an [`EntrySource`]'s `(id, entry)` pairs.
+pub struct Iter<'a>(IterInner<'a>);
+
+enum IterInner<'a> {
+    Map(hash_map::Iter<'a, EntryId, BankEntry>),
+    Slab(slice::Iter<'a, (EntryId, BankEntry)>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a EntryId, &'a BankEntry);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterInner::Map(it) => it.next(),
+            IterInner::Slab(it) => it.next().map(|(id, entry)| (id, entry)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterInner::Map(it) => it.size_hint(),
+            IterInner::Slab(it) => it.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// Iterator over an [`EntryStore`]'s `(id, entry)` pairs, entries mutable.
+pub struct IterMut<'a>(IterMutInner<'a>);
+
+enum IterMutInner<'a> {
+    Map(hash_map::IterMut<'a, EntryId, BankEntry>),
+    Slab(slice::IterMut<'a, (EntryId, BankEntry)>),
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a EntryId, &'a mut BankEntry);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterMutInner::Map(it) => it.next(),
+            IterMutInner::Slab(it) => it.next().map(|(id, entry)| (&*id, entry)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterMutInner::Map(it) => it.size_hint(),
+            IterMutInner::Slab(it) => it.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BankId, Temperature};
+    use ternary_signal::Signal;
+
+    fn entry(raw: u64) -> (EntryId, BankEntry) {
+        let id = EntryId::from_raw(raw);
+        let vector = vec![Signal::new_raw(1, raw as u8, 1); 4];
+        (id, BankEntry::new(id, vector, BankId::from_raw(1), Temperature::Hot, raw))
+    }
+
+    #[test]
+    fn slab_remove_keeps_slot_map_consistent() {
+        let mut store = EntryStore::new(EntryStorage::Slab);
+        for raw in 1..=5 {
+            let (id, e) = entry(raw);
+            assert!(store.insert(id, e).is_none());
+        }
+        // Removing from the middle moves the last entry into the hole
+        assert!(store.remove(&EntryId::from_raw(2)).is_some());
+        assert!(store.remove(&EntryId::from_raw(2)).is_none());
+        assert_eq!(store.len(), 4);
+        for raw in [1, 3, 4, 5] {
+            let id = EntryId::from_raw(raw);
+            assert_eq!(store.get(&id).unwrap().id, id);
+            assert_eq!(store[&id].created_tick, raw);
+        }
+        let (id, mut replacement) = entry(5);
+        replacement.confidence = 1;
+        assert_eq!(store.insert(id, replacement).unwrap().confidence, 128);
+        assert_eq!(store.len(), 4);
+
+        let map = store.clone().into_storage(EntryStorage::HashMap);
+        assert_eq!(map.storage(), EntryStorage::HashMap);
+        let mut ids: Vec<u64> = map.keys().map(|id| id.0).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, [1, 3, 4, 5]);
+    }
+}
@@ -1,6 +1,11 @@
+use core::str::FromStr;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::error::DataBankError;
+use crate::prelude::*;
+
 // ---------------------------------------------------------------------------
 // BankId — 64-bit temporally sortable bank identity
 // Layout: [timestamp_s:32][region_tag:24][seq:8]
@@ -20,10 +25,17 @@ impl BankId {
     ///
     /// The region name is hashed to a 24-bit tag via FNV-1a.
     /// The timestamp is captured at call time.
+    #[cfg(feature = "std")]
     pub fn new(region_name: &str, seq: u8) -> Self {
-        let timestamp = unix_timestamp_secs() as u64;
+        Self::new_at(region_name, seq, unix_timestamp_secs())
+    }
+
+    /// Create a BankId with an explicit timestamp (Unix seconds, or any
+    /// monotonic counter the caller owns). Deterministic, and the only
+    /// constructor besides `from_raw` on no_std builds.
+    pub fn new_at(region_name: &str, seq: u8, timestamp_secs: u32) -> Self {
         let region_tag = fnv1a_24(region_name) as u64;
-        Self((timestamp << 32) | (region_tag << 8) | seq as u64)
+        Self(((timestamp_secs as u64) << 32) | (region_tag << 8) | seq as u64)
     }
 
     /// Create a BankId from a raw u64 value (e.g. loaded from disk).
@@ -47,8 +59,8 @@ impl BankId {
     }
 }
 
-impl std::fmt::Display for BankId {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for BankId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "BankId(t={}, tag={:#08x}, seq={})",
@@ -74,9 +86,16 @@ pub struct EntryId(pub u64);
 
 impl EntryId {
     /// Create a new EntryId with the current timestamp and a sequence number.
+    #[cfg(feature = "std")]
     pub fn new(seq: u32) -> Self {
-        let ms = unix_timestamp_ms();
-        Self((ms << 22) | (seq as u64 & 0x003F_FFFF))
+        Self::new_at(unix_timestamp_ms(), seq)
+    }
+
+    /// Create an EntryId with an explicit millisecond timestamp. Only the
+    /// low 42 bits of `timestamp_ms` are kept. Deterministic, and the only
+    /// constructor besides `from_raw` on no_std builds.
+    pub fn new_at(timestamp_ms: u64, seq: u32) -> Self {
+        Self((timestamp_ms << 22) | (seq as u64 & 0x003F_FFFF))
     }
 
     /// Create an EntryId from a raw u64 value (e.g. loaded from disk).
@@ -95,8 +114,8 @@ impl EntryId {
     }
 }
 
-impl std::fmt::Display for EntryId {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for EntryId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "EntryId(ms={}, seq={})", self.timestamp_ms(), self.seq())
     }
 }
@@ -117,6 +136,37 @@ pub struct BankRef {
     pub entry: EntryId,
 }
 
+impl BankRef {
+    /// Parse the compact `bank:0x…/entry:0x…` form produced by `Display`.
+    pub fn parse(s: &str) -> Result<Self, DataBankError> {
+        let err = || DataBankError::Parse {
+            what: "BankRef",
+            input: s.to_string(),
+        };
+        let (bank, entry) = s.trim().split_once('/').ok_or_else(err)?;
+        let bank = bank.strip_prefix("bank:").ok_or_else(err)?;
+        let entry = entry.strip_prefix("entry:").ok_or_else(err)?;
+        Ok(Self {
+            bank: BankId(parse_hex_u64(bank).ok_or_else(err)?),
+            entry: EntryId(parse_hex_u64(entry).ok_or_else(err)?),
+        })
+    }
+}
+
+impl core::fmt::Display for BankRef {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "bank:{:#06x}/entry:{:#06x}", self.bank.0, self.entry.0)
+    }
+}
+
+impl FromStr for BankRef {
+    type Err = DataBankError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // EdgeType — typed relationships between entries
 // ---------------------------------------------------------------------------
@@ -150,6 +200,23 @@ pub enum EdgeType {
 }
 
 impl EdgeType {
+    /// Every EdgeType variant, in discriminant order.
+    pub const ALL: [Self; 13] = [
+        Self::IsA,
+        Self::HasA,
+        Self::PartOf,
+        Self::RelatedTo,
+        Self::SimilarTo,
+        Self::Causes,
+        Self::Precedes,
+        Self::LooksLike,
+        Self::SoundsLike,
+        Self::FeelsLike,
+        Self::CoOccurred,
+        Self::FollowedBy,
+        Self::Custom,
+    ];
+
     /// Convert a raw u8 to an EdgeType, if valid.
     pub fn from_u8(v: u8) -> Option<Self> {
         match v {
@@ -174,6 +241,47 @@ impl EdgeType {
     pub fn as_u8(self) -> u8 {
         self as u8
     }
+
+    /// Lowercase snake_case name, as used in config files and logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::IsA => "is_a",
+            Self::HasA => "has_a",
+            Self::PartOf => "part_of",
+            Self::RelatedTo => "related_to",
+            Self::SimilarTo => "similar_to",
+            Self::Causes => "causes",
+            Self::Precedes => "precedes",
+            Self::LooksLike => "looks_like",
+            Self::SoundsLike => "sounds_like",
+            Self::FeelsLike => "feels_like",
+            Self::CoOccurred => "co_occurred",
+            Self::FollowedBy => "followed_by",
+            Self::Custom => "custom",
+        }
+    }
+}
+
+impl core::fmt::Display for EdgeType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for EdgeType {
+    type Err = DataBankError;
+
+    /// Case-insensitive parse of the snake_case name (e.g. "Related_To").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|t| t.name() == lower)
+            .ok_or_else(|| DataBankError::Parse {
+                what: "EdgeType",
+                input: s.to_string(),
+            })
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -199,18 +307,26 @@ pub struct Edge {
 /// Temperature lifecycle for bank entries.
 ///
 /// Matches the thermogram lifecycle: Hot (active learning) → Warm (session
-/// patterns) → Cool (proven) → Cold (frozen priors). Eviction scoring
-/// uses temperature as a major factor.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+/// patterns) → Cool (proven) → Set (proven this session, not yet frozen) →
+/// Cold (frozen priors). Eviction scoring uses temperature as a major factor.
+///
+/// The on-disk discriminants of the original four tiers are unchanged; `Set`
+/// was added later and takes discriminant 4. Ordering follows the lifecycle
+/// (see [`Temperature::level`]), not the discriminant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Temperature {
     Hot = 0,
     Warm = 1,
     Cool = 2,
     Cold = 3,
+    Set = 4,
 }
 
 impl Temperature {
+    /// Every Temperature variant, in lifecycle order.
+    pub const ALL: [Self; 5] = [Self::Hot, Self::Warm, Self::Cool, Self::Set, Self::Cold];
+
     /// Convert a raw u8 to a Temperature, if valid.
     pub fn from_u8(v: u8) -> Option<Self> {
         match v {
@@ -218,6 +334,7 @@ impl Temperature {
             1 => Some(Self::Warm),
             2 => Some(Self::Cool),
             3 => Some(Self::Cold),
+            4 => Some(Self::Set),
             _ => None,
         }
     }
@@ -226,23 +343,147 @@ impl Temperature {
     pub fn as_u8(self) -> u8 {
         self as u8
     }
-}
 
-impl std::fmt::Display for Temperature {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    /// Lifecycle position: Hot=0, Warm=1, Cool=2, Set=3, Cold=4.
+    pub fn level(self) -> u8 {
+        match self {
+            Self::Hot => 0,
+            Self::Warm => 1,
+            Self::Cool => 2,
+            Self::Set => 3,
+            Self::Cold => 4,
+        }
+    }
+
+    /// The tier one promotion step colder, or None if already Cold.
+    pub fn promoted(self) -> Option<Self> {
+        match self {
+            Self::Hot => Some(Self::Warm),
+            Self::Warm => Some(Self::Cool),
+            Self::Cool => Some(Self::Set),
+            Self::Set => Some(Self::Cold),
+            Self::Cold => None,
+        }
+    }
+
+    /// The tier one demotion step hotter, or None if already Hot.
+    pub fn demoted(self) -> Option<Self> {
+        match self {
+            Self::Cold => Some(Self::Set),
+            Self::Set => Some(Self::Cool),
+            Self::Cool => Some(Self::Warm),
+            Self::Warm => Some(Self::Hot),
+            Self::Hot => None,
+        }
+    }
+
+    /// Whether this tier requires the extended-temperature format flag
+    /// when persisted (readers predating `Set` cannot decode it).
+    pub fn is_extended(self) -> bool {
+        matches!(self, Self::Set)
+    }
+
+    /// Lowercase name, as used in config files and logs.
+    pub fn name(self) -> &'static str {
         match self {
-            Self::Hot => write!(f, "HOT"),
-            Self::Warm => write!(f, "WARM"),
-            Self::Cool => write!(f, "COOL"),
-            Self::Cold => write!(f, "COLD"),
+            Self::Hot => "hot",
+            Self::Warm => "warm",
+            Self::Cool => "cool",
+            Self::Set => "set",
+            Self::Cold => "cold",
         }
     }
 }
 
+impl PartialOrd for Temperature {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Temperature {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.level().cmp(&other.level())
+    }
+}
+
+impl core::fmt::Display for Temperature {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Temperature {
+    type Err = DataBankError;
+
+    /// Case-insensitive parse of the temperature name (e.g. "WARM").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|t| t.name() == lower)
+            .ok_or_else(|| DataBankError::Parse {
+                what: "Temperature",
+                input: s.to_string(),
+            })
+    }
+}
+
 // ---------------------------------------------------------------------------
 // BankConfig — per-region bank configuration
 // ---------------------------------------------------------------------------
 
+/// Policy applied when an entry is already at `max_edges_per_entry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum EdgeOverflow {
+    /// Fail with `EdgeLimitReached`.
+    #[default]
+    Reject,
+    /// Drop the lowest-weight edge (oldest on ties) to make room. A new edge
+    /// weaker than every existing edge is refused with `EdgeLimitReached`
+    /// instead, so the entry always keeps its strongest edges.
+    EvictWeakest,
+    /// Drop the edge with the smallest `created_tick` to make room.
+    EvictOldest,
+}
+
+/// How a full bank picks the entry to evict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum EvictionMode {
+    /// The lowest [`eviction_score`](crate::entry::BankEntry::eviction_score).
+    #[default]
+    Score,
+    /// Among the lowest-scoring entries, first evict one whose nearest other
+    /// entry scores at least `min_similarity` (0-256), so a unique memory
+    /// outlives redundant copies of a common one. Falls back to the score
+    /// when none of them has such a neighbor.
+    NearDuplicatesFirst { min_similarity: i32 },
+}
+
+/// How [`DataBank::query_sparse_with`] treats a query whose length differs
+/// from the bank's vector width.
+///
+/// [`DataBank::query_sparse_with`]: crate::bank::DataBank::query_sparse_with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum QueryWidth {
+    /// Reject the query with `VectorWidthMismatch`.
+    #[default]
+    Strict,
+    /// Compare over the shorter of the two lengths, ignoring the rest.
+    Lenient,
+}
+
+/// Where a bank keeps its entries, see [`crate::store`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum EntryStorage {
+    /// A hash map from id to entry.
+    #[default]
+    HashMap,
+    /// One contiguous `Vec` of entries plus an id -> slot map, for better
+    /// locality in brute-force scans of large banks.
+    Slab,
+}
+
 /// Configuration for a single DataBank.
 ///
 /// Each region sets its own persistence frequency, capacity, and vector
@@ -252,23 +493,155 @@ pub struct BankConfig {
     /// Flush to disk after this many mutations. Default: 100.
     pub persist_after_mutations: u32,
     /// Flush to disk after this many ticks since last flush. Default: 10_000.
+    ///
+    /// This is a hard ceiling: it applies even inside the coalescing window
+    /// set by `min_ticks_between_flushes`.
     pub persist_after_ticks: u64,
-    /// Maximum number of entries in the bank. Default: 4096.
+    /// Minimum ticks between mutation-triggered flushes. Bursts that cross
+    /// `persist_after_mutations` inside the window are coalesced into one
+    /// flush at the end of it. 0 disables coalescing. Not stored in `.bank`
+    /// files. Default: 0.
+    #[serde(default)]
+    pub min_ticks_between_flushes: u64,
+    /// Maximum number of entries in the bank. 0 means unlimited: the bank
+    /// never evicts on insert and never reports BankFull. Default: 4096.
     pub max_entries: u32,
+    /// Estimated memory budget in bytes (see [`DataBank::approx_bytes`]).
+    /// Inserts evict lowest-scoring entries until the new entry fits, or
+    /// fail with BankOverBudget. Not stored in `.bank` files. Default: None.
+    ///
+    /// [`DataBank::approx_bytes`]: crate::bank::DataBank::approx_bytes
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
     /// Fixed signal vector width for all entries. Set at creation.
     pub vector_width: u16,
     /// Maximum edges per entry. Default: 32.
     pub max_edges_per_entry: u16,
+    /// What `add_edge` does when an entry already has `max_edges_per_entry`
+    /// edges. Not stored in `.bank` files. Default: Reject.
+    #[serde(default)]
+    pub edge_overflow: EdgeOverflow,
     /// Index type for similarity search. Default: IVF (k=64, nprobe=8).
     #[serde(skip)]
     pub index_type: crate::ivf::IndexType,
+    /// Maintain a content-hash map so queries byte-identical to at least
+    /// `top_k` stored vectors return them at score 256 without scanning.
+    /// Costs one hash per insert. Runtime-only (not persisted). Default:
+    /// false.
+    #[serde(skip)]
+    pub exact_match_fast_path: bool,
+    /// Keep a record of the last N evictions, readable through
+    /// [`DataBank::eviction_log`]. 0 disables the log entirely. Not stored
+    /// in `.bank` files. Default: 0.
+    ///
+    /// [`DataBank::eviction_log`]: crate::bank::DataBank::eviction_log
+    #[serde(default)]
+    pub audit_evictions: usize,
+    /// How inserts into a full bank choose what to evict. Not stored in
+    /// `.bank` files. Default: Score.
+    #[serde(default)]
+    pub eviction_mode: EvictionMode,
+    /// Entry storage backend. Not stored in `.bank` files. Default: HashMap.
+    #[serde(default)]
+    pub storage: EntryStorage,
+    /// How queries score entries. Not stored in `.bank` files. Default:
+    /// Cosine.
+    #[serde(default)]
+    pub metric: crate::similarity::Metric,
+    /// Write cluster flushes as deltas appended to the bank's file (see
+    /// [`save_incremental`]), rewriting it in full once the deltas reach
+    /// this percentage of the base snapshot's size. None rewrites the whole
+    /// file on every flush. Not stored in `.bank` files. Default: None.
+    ///
+    /// [`save_incremental`]: crate::codec::save_incremental
+    #[serde(default)]
+    pub incremental_flush: Option<u8>,
+    /// Compact automatically once removals since the last
+    /// [`compact`](crate::bank::DataBank::compact) reach this percentage of
+    /// `max_entries`. The compaction runs at the start of the next insert
+    /// or edge add, never inside an eviction loop. 0 disables it, as does
+    /// an unlimited bank. Not stored in `.bank` files. Default: 10.
+    #[serde(default = "default_auto_compact_percent")]
+    pub auto_compact_percent: u8,
+}
+
+fn default_auto_compact_percent() -> u8 {
+    10
 }
 
 impl BankConfig {
+    /// Entry capacity, or None if the bank is unlimited (`max_entries == 0`).
+    pub fn capacity(&self) -> Option<u32> {
+        if self.max_entries == 0 {
+            None
+        } else {
+            Some(self.max_entries)
+        }
+    }
+
+    /// Check the configuration for values the bank cannot operate with.
+    ///
+    /// - `vector_width` must be non-zero.
+    /// - `max_entries == 0` is valid and means unlimited capacity: inserts
+    ///   never evict, `BankFull` never occurs, and only explicit
+    ///   `evict_n` calls remove entries.
+    /// - `max_bytes`, when set, must be non-zero.
+    /// - A `PrenormalizedDot` metric needs a non-zero `norm_sq`.
+    /// - `incremental_flush`, when set, must be non-zero.
+    pub fn validate(&self) -> Result<(), DataBankError> {
+        if self.vector_width == 0 {
+            return Err(DataBankError::InvalidConfig(
+                "vector_width must be non-zero".into(),
+            ));
+        }
+        if self.max_bytes == Some(0) {
+            return Err(DataBankError::InvalidConfig(
+                "max_bytes must be non-zero when set".into(),
+            ));
+        }
+        if self.metric == (crate::similarity::Metric::PrenormalizedDot { norm_sq: 0 }) {
+            return Err(DataBankError::InvalidConfig(
+                "prenormalized dot metric needs a non-zero norm_sq".into(),
+            ));
+        }
+        if self.incremental_flush == Some(0) {
+            return Err(DataBankError::InvalidConfig(
+                "incremental_flush must be non-zero when set".into(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Check whether the bank should be flushed to disk.
+    ///
+    /// The mutation threshold only fires once `min_ticks_between_flushes`
+    /// have passed; the tick threshold always fires.
     pub fn should_persist(&self, mutations_since: u32, ticks_since: u64) -> bool {
+        if ticks_since >= self.persist_after_ticks {
+            return true;
+        }
         mutations_since >= self.persist_after_mutations
-            || ticks_since >= self.persist_after_ticks
+            && ticks_since >= self.min_ticks_between_flushes
+    }
+
+    /// Removals that trigger an automatic compaction, or None when
+    /// `auto_compact_percent` is 0 or the bank is unlimited.
+    pub fn auto_compact_threshold(&self) -> Option<u32> {
+        if self.auto_compact_percent == 0 {
+            return None;
+        }
+        let capacity = self.capacity()? as u64;
+        Some((capacity * self.auto_compact_percent as u64 / 100).max(1) as u32)
+    }
+
+    /// Whether [`should_persist`](Self::should_persist) can ever fire.
+    ///
+    /// `u32::MAX` mutations and `u64::MAX` ticks are unreachable in
+    /// practice, so a config with both thresholds at their maximum never
+    /// auto-persists. Zero thresholds are not degenerate: they persist on
+    /// every check.
+    pub fn can_ever_persist(&self) -> bool {
+        self.persist_after_ticks != u64::MAX || self.persist_after_mutations != u32::MAX
     }
 }
 
@@ -277,10 +650,20 @@ impl Default for BankConfig {
         Self {
             persist_after_mutations: 100,
             persist_after_ticks: 10_000,
+            min_ticks_between_flushes: 0,
             max_entries: 4096,
+            max_bytes: None,
             vector_width: 64,
             max_edges_per_entry: 32,
+            edge_overflow: EdgeOverflow::default(),
             index_type: crate::ivf::IndexType::default(),
+            exact_match_fast_path: false,
+            audit_evictions: 0,
+            eviction_mode: EvictionMode::default(),
+            storage: EntryStorage::default(),
+            metric: crate::similarity::Metric::default(),
+            incremental_flush: None,
+            auto_compact_percent: default_auto_compact_percent(),
         }
     }
 }
@@ -299,7 +682,14 @@ fn fnv1a_24(s: &str) -> u32 {
     hash & 0x00FF_FFFF
 }
 
+/// Parse a u64 written in hex with an optional `0x` prefix.
+fn parse_hex_u64(s: &str) -> Option<u64> {
+    let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    u64::from_str_radix(digits, 16).ok()
+}
+
 /// Current Unix timestamp in seconds.
+#[cfg(feature = "std")]
 fn unix_timestamp_secs() -> u32 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -308,6 +698,7 @@ fn unix_timestamp_secs() -> u32 {
 }
 
 /// Current Unix timestamp in milliseconds.
+#[cfg(feature = "std")]
 fn unix_timestamp_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -366,6 +757,20 @@ mod tests {
         assert!(b >= a);
     }
 
+    #[test]
+    fn explicit_time_ids_are_deterministic() {
+        let id = EntryId::new_at(1_700_000_000_123, 7);
+        assert_eq!(id, EntryId::new_at(1_700_000_000_123, 7));
+        assert_eq!(id.timestamp_ms(), 1_700_000_000_123);
+        assert_eq!(id.seq(), 7);
+        assert!(EntryId::new_at(5, 0) > EntryId::new_at(4, 9));
+
+        let bank = BankId::new_at("temporal.semantic", 3, 1_700_000_000);
+        assert_eq!(bank.timestamp_secs(), 1_700_000_000);
+        assert_eq!(bank.seq(), 3);
+        assert_eq!(bank.region_tag(), BankId::new("temporal.semantic", 0).region_tag());
+    }
+
     #[test]
     fn edge_type_round_trip() {
         for v in [0u8, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 255] {
@@ -378,18 +783,65 @@ mod tests {
 
     #[test]
     fn temperature_round_trip() {
-        for v in 0..=3u8 {
+        for v in 0..=4u8 {
             let t = Temperature::from_u8(v).expect("valid temperature");
             assert_eq!(t.as_u8(), v);
         }
-        assert!(Temperature::from_u8(4).is_none());
+        assert!(Temperature::from_u8(5).is_none());
     }
 
     #[test]
     fn temperature_ordering() {
         assert!(Temperature::Hot < Temperature::Warm);
         assert!(Temperature::Warm < Temperature::Cool);
-        assert!(Temperature::Cool < Temperature::Cold);
+        assert!(Temperature::Cool < Temperature::Set);
+        assert!(Temperature::Set < Temperature::Cold);
+        assert_eq!(Temperature::Set.as_u8(), 4);
+    }
+
+    #[test]
+    fn temperature_text_round_trip() {
+        for t in Temperature::ALL {
+            let text = t.to_string();
+            assert_eq!(text, text.to_lowercase());
+            assert_eq!(text.parse::<Temperature>().unwrap(), t);
+            assert_eq!(text.to_uppercase().parse::<Temperature>().unwrap(), t);
+        }
+        assert_eq!(Temperature::Warm.to_string(), "warm");
+        assert!("lukewarm".parse::<Temperature>().is_err());
+    }
+
+    #[test]
+    fn edge_type_text_round_trip() {
+        for et in EdgeType::ALL {
+            let text = et.to_string();
+            assert_eq!(text, text.to_lowercase());
+            assert_eq!(text.parse::<EdgeType>().unwrap(), et);
+            assert_eq!(text.to_uppercase().parse::<EdgeType>().unwrap(), et);
+        }
+        assert_eq!(EdgeType::RelatedTo.to_string(), "related_to");
+        assert_eq!("Custom".parse::<EdgeType>().unwrap(), EdgeType::Custom);
+        assert!("unrelated".parse::<EdgeType>().is_err());
+    }
+
+    #[test]
+    fn bank_ref_text_round_trip() {
+        let r = BankRef {
+            bank: BankId::from_raw(0x0123),
+            entry: EntryId::from_raw(0x0456),
+        };
+        assert_eq!(r.to_string(), "bank:0x0123/entry:0x0456");
+        assert_eq!(BankRef::parse(&r.to_string()).unwrap(), r);
+
+        let big = BankRef {
+            bank: BankId::new("temporal.semantic", 7),
+            entry: EntryId::from_raw(u64::MAX),
+        };
+        assert_eq!(big.to_string().parse::<BankRef>().unwrap(), big);
+
+        assert!(BankRef::parse("bank:0x1").is_err());
+        assert!(BankRef::parse("bank:zz/entry:0x1").is_err());
+        assert!(BankRef::parse("entry:0x1/bank:0x2").is_err());
     }
 
     #[test]
@@ -400,6 +852,50 @@ mod tests {
         assert!(cfg.should_persist(0, 10_000));
         assert!(cfg.should_persist(100, 10_000));
         assert!(!cfg.should_persist(99, 9_999));
+
+        let coalesced = BankConfig {
+            min_ticks_between_flushes: 500,
+            ..BankConfig::default()
+        };
+        assert!(!coalesced.should_persist(1_000, 499));
+        assert!(coalesced.should_persist(100, 500));
+        assert!(coalesced.should_persist(0, 10_000));
+    }
+
+    #[test]
+    fn bank_config_capacity_and_validate() {
+        let cfg = BankConfig::default();
+        assert_eq!(cfg.capacity(), Some(4096));
+        assert!(cfg.validate().is_ok());
+
+        let unlimited = BankConfig { max_entries: 0, ..BankConfig::default() };
+        assert_eq!(unlimited.capacity(), None);
+        assert!(unlimited.validate().is_ok());
+
+        let zero_width = BankConfig { vector_width: 0, ..BankConfig::default() };
+        assert!(zero_width.validate().is_err());
+    }
+
+    #[test]
+    fn bank_config_flags_never_persist() {
+        assert!(BankConfig::default().can_ever_persist());
+        let eager = BankConfig {
+            persist_after_mutations: 0,
+            persist_after_ticks: 0,
+            ..BankConfig::default()
+        };
+        assert!(eager.can_ever_persist());
+        assert!(eager.should_persist(0, 0));
+
+        let never = BankConfig {
+            persist_after_mutations: u32::MAX,
+            persist_after_ticks: u64::MAX,
+            ..BankConfig::default()
+        };
+        assert!(!never.can_ever_persist());
+        assert!(!never.should_persist(u32::MAX - 1, u64::MAX - 1));
+        let ticks_only = BankConfig { persist_after_mutations: u32::MAX, ..BankConfig::default() };
+        assert!(ticks_only.can_ever_persist());
     }
 
     #[test]
@@ -0,0 +1,68 @@
+//! Format compatibility: the committed fixtures in `tests/fixtures/` must
+//! keep decoding to exactly what `codec::testing` builds. See that module
+//! for how (and when) to regenerate them.
+
+use std::path::{Path, PathBuf};
+
+use databank_rs::codec::{self, testing};
+use databank_rs::journal::{self, JournalReader};
+use databank_rs::{BankEntry, DataBank};
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn sorted_entries(bank: &DataBank) -> Vec<&BankEntry> {
+    let mut entries: Vec<&BankEntry> = bank.entries().map(|(_, e)| e).collect();
+    entries.sort_by_key(|e| e.id());
+    entries
+}
+
+#[test]
+fn format_versions_are_exposed() {
+    assert_eq!(codec::FORMAT_VERSION, 3);
+    assert_eq!(journal::FORMAT_VERSION, 3);
+}
+
+#[test]
+fn bank_fixtures_decode_to_expected_banks() {
+    for fixture in testing::bank_fixtures() {
+        let path = fixtures_dir().join(fixture.file_name);
+        let bytes = std::fs::read(&path).unwrap_or_else(|e| panic!("{path:?}: {e}"));
+        assert_eq!(
+            u16::from_le_bytes([bytes[4], bytes[5]]),
+            codec::FORMAT_VERSION,
+            "{}",
+            fixture.file_name
+        );
+
+        let decoded = codec::decode(&bytes).unwrap_or_else(|e| panic!("{path:?}: {e}"));
+        let expected = &fixture.bank;
+        assert_eq!(decoded.id, expected.id, "{}", fixture.file_name);
+        assert_eq!(decoded.name, expected.name, "{}", fixture.file_name);
+        assert_eq!(
+            format!("{:?}", decoded.config()),
+            format!("{:?}", expected.config()),
+            "{}",
+            fixture.file_name
+        );
+        assert_eq!(sorted_entries(&decoded), sorted_entries(expected), "{}", fixture.file_name);
+        assert_eq!(decoded.persistence_state(), expected.persistence_state());
+        assert!(codec::diff(expected, &decoded).is_empty(), "{}", fixture.file_name);
+    }
+}
+
+#[test]
+fn journal_fixture_decodes_every_tag() {
+    let path = fixtures_dir().join(testing::JOURNAL_FIXTURE);
+    let records = JournalReader::read_all(&path).unwrap();
+    assert_eq!(records, testing::journal_fixture());
+}
+
+/// Rewrites the committed fixtures. Run only after a deliberate format
+/// change: `cargo test --test format_fixtures -- --ignored regenerate_fixtures`.
+#[test]
+#[ignore = "rewrites tests/fixtures; run explicitly after a format change"]
+fn regenerate_fixtures() {
+    testing::regenerate_fixtures(&fixtures_dir()).unwrap();
+}
@@ -53,10 +53,10 @@ fn jar_expression_vector() -> Vec<Signal> {
     v
 }
 
-#[test]
-fn jar_distributed_concept_full_lifecycle() {
-    let dir = tempfile::tempdir().unwrap();
-
+/// Phases 1-2 of the jar test: four banks holding one "jar" fragment each,
+/// linked semantic -> {visual, spatial, expression} and visual -> spatial.
+/// Returns the cluster and the refs in that bank order.
+fn build_jar_cluster() -> (BankCluster, [BankRef; 4]) {
     // =========================================================================
     // Phase 1: Create banks and encode the "jar" concept
     // =========================================================================
@@ -72,28 +72,28 @@ fn jar_distributed_concept_full_lifecycle() {
         id_semantic,
         "temporal.semantic".into(),
         BankConfig { vector_width: 64, persist_after_mutations: 1, persist_after_ticks: 0, ..BankConfig::default() },
-    );
+    ).unwrap();
     let eid_semantic = semantic.insert(jar_semantic_vector(), Temperature::Hot, 0).unwrap();
 
     let visual = cluster.get_or_create(
         id_visual,
         "occipital.v4".into(),
         BankConfig { vector_width: 128, persist_after_mutations: 1, persist_after_ticks: 0, ..BankConfig::default() },
-    );
+    ).unwrap();
     let eid_visual = visual.insert(jar_visual_vector(), Temperature::Hot, 0).unwrap();
 
     let spatial = cluster.get_or_create(
         id_spatial,
         "parietal.spatial".into(),
         BankConfig { vector_width: 32, persist_after_mutations: 1, persist_after_ticks: 0, ..BankConfig::default() },
-    );
+    ).unwrap();
     let eid_spatial = spatial.insert(jar_spatial_vector(), Temperature::Hot, 0).unwrap();
 
     let expression = cluster.get_or_create(
         id_expression,
         "frontal.expression".into(),
         BankConfig { vector_width: 64, persist_after_mutations: 1, persist_after_ticks: 0, ..BankConfig::default() },
-    );
+    ).unwrap();
     let eid_expression = expression.insert(jar_expression_vector(), Temperature::Hot, 0).unwrap();
 
     // =========================================================================
@@ -110,6 +110,18 @@ fn jar_distributed_concept_full_lifecycle() {
     cluster.link(ref_semantic, ref_expression, EdgeType::RelatedTo, 160, 0).unwrap();
     cluster.link(ref_visual, ref_spatial, EdgeType::CoOccurred, 140, 0).unwrap();
 
+    (cluster, [ref_semantic, ref_visual, ref_spatial, ref_expression])
+}
+
+#[test]
+fn jar_distributed_concept_full_lifecycle() {
+    let dir = tempfile::tempdir().unwrap();
+    let (mut cluster, [ref_semantic, ref_visual, ref_spatial, ref_expression]) =
+        build_jar_cluster();
+    let (id_semantic, eid_semantic) = (ref_semantic.bank, ref_semantic.entry);
+    let id_visual = ref_visual.bank;
+    let id_expression = ref_expression.bank;
+
     // =========================================================================
     // Phase 3: Persist to disk
     // =========================================================================
@@ -133,7 +145,7 @@ fn jar_distributed_concept_full_lifecycle() {
     let loaded_semantic = loaded.get(id_semantic).unwrap();
     assert_eq!(loaded_semantic.len(), 1);
     let entry = loaded_semantic.get(eid_semantic).unwrap();
-    assert_eq!(entry.vector[0].current(), sig(1, 200).current());
+    assert_eq!(entry.vector()[0].current(), sig(1, 200).current());
 
     // =========================================================================
     // Phase 5: Recall via sparse query
@@ -142,7 +154,7 @@ fn jar_distributed_concept_full_lifecycle() {
     let mut partial_cue = vec![Signal::ZERO; 64];
     partial_cue[0] = sig(1, 200); // container
     partial_cue[1] = sig(1, 180); // glass
-    let results = loaded_semantic.query_sparse(&partial_cue, 1);
+    let results = loaded_semantic.query_sparse(&partial_cue, 1).unwrap();
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].entry_id, eid_semantic);
     assert!(results[0].score > 200, "Partial cue should strongly match. Score: {}", results[0].score);
@@ -183,12 +195,12 @@ fn jar_distributed_concept_full_lifecycle() {
 
     let bank = cluster2.get_mut(id_semantic).unwrap();
     assert!(bank.promote_entry(eid_semantic).unwrap());
-    assert_eq!(bank.get(eid_semantic).unwrap().temperature, Temperature::Warm);
+    assert_eq!(bank.get(eid_semantic).unwrap().temperature(), Temperature::Warm);
 
     cluster2.flush_dirty(dir.path(), 200).unwrap();
     let loaded2 = BankCluster::load_all(dir.path()).unwrap();
     let entry = loaded2.get(id_semantic).unwrap().get(eid_semantic).unwrap();
-    assert_eq!(entry.temperature, Temperature::Warm, "Promotion should survive save/load");
+    assert_eq!(entry.temperature(), Temperature::Warm, "Promotion should survive save/load");
 
     let mut cluster3 = BankCluster::load_all(dir.path()).unwrap();
     let expr_bank = cluster3.get_mut(id_expression).unwrap();
@@ -200,3 +212,173 @@ fn jar_distributed_concept_full_lifecycle() {
     let loaded3 = BankCluster::load_all(dir.path()).unwrap();
     assert_eq!(loaded3.get(id_expression).unwrap().len(), 0, "Evicted entry should be gone after reload");
 }
+
+/// `fragment` with every feature past the first two moved up one slot:
+/// same norm and same overlap with a two-feature cue, but no edges.
+fn distractor(fragment: &[Signal]) -> Vec<Signal> {
+    let mut v = vec![Signal::ZERO; fragment.len()];
+    v[..2].copy_from_slice(&fragment[..2]);
+    for (i, s) in fragment.iter().enumerate().skip(2) {
+        if s.magnitude > 0 {
+            v[i + 1] = *s;
+        }
+    }
+    v
+}
+
+/// A cue holding only the fragment's first two features.
+fn partial(fragment: &[Signal]) -> Vec<Signal> {
+    let mut v = vec![Signal::ZERO; fragment.len()];
+    v[..2].copy_from_slice(&fragment[..2]);
+    v
+}
+
+#[test]
+fn jar_partial_cue_association_lifts_linked_fragments() {
+    let (mut cluster, [ref_semantic, ref_visual, ref_spatial, _]) = build_jar_cluster();
+    let fragments = [
+        (ref_semantic, jar_semantic_vector()),
+        (ref_visual, jar_visual_vector()),
+        (ref_spatial, jar_spatial_vector()),
+    ];
+
+    let mut query_map = HashMap::new();
+    let mut distractors = Vec::new();
+    for (jar_ref, fragment) in &fragments {
+        let bank = cluster.get_mut(jar_ref.bank).unwrap();
+        let entry = bank.insert(distractor(fragment), Temperature::Hot, 0).unwrap();
+        distractors.push(BankRef { bank: jar_ref.bank, entry });
+        query_map.insert(jar_ref.bank, partial(fragment));
+    }
+
+    // Raw similarity cannot tell the fragments from the distractors
+    let plain = cluster.query_all(&query_map, 6);
+    assert_eq!(plain.len(), 6);
+    for (jar_ref, _) in &fragments {
+        let scores: Vec<i32> = plain
+            .iter()
+            .filter(|r| r.bank_id == jar_ref.bank)
+            .map(|r| r.score)
+            .collect();
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[0], scores[1], "distractor should tie the fragment");
+    }
+
+    let associated = cluster.query_with_association(&query_map, 6, 64, 1);
+    let top: Vec<BankRef> = associated[..3]
+        .iter()
+        .map(|r| BankRef { bank: r.bank_id, entry: r.entry_id })
+        .collect();
+    for (jar_ref, _) in &fragments {
+        assert!(top.contains(jar_ref), "linked fragment {:?} should rank first", jar_ref);
+    }
+    for d in &distractors {
+        let r = associated
+            .iter()
+            .find(|r| r.bank_id == d.bank && r.entry_id == d.entry)
+            .unwrap();
+        assert!(associated[2].normalized_score > r.normalized_score);
+    }
+
+    // Without hops the ranking is query_all's
+    let flat = cluster.query_with_association(&query_map, 6, 64, 0);
+    let key = |r: &ClusterQueryResult| (r.bank_id, r.entry_id, r.normalized_score);
+    let mut expected: Vec<_> = plain.iter().map(key).collect();
+    let mut actual: Vec<_> = flat.iter().map(key).collect();
+    expected.sort_unstable();
+    actual.sort_unstable();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn jar_graph_exports_stable_dot() {
+    let (mut cluster, [ref_semantic, ref_visual, _, _]) = build_jar_cluster();
+    cluster
+        .get_mut(ref_semantic.bank)
+        .unwrap()
+        .set_debug_tag(ref_semantic.entry, Some("jar".into()))
+        .unwrap();
+
+    assert_eq!(
+        cluster.export_dot(None),
+        "digraph databank {\n\
+         \x20 \"frontal.expression/0\" [label=\"frontal.expression/0\"];\n\
+         \x20 \"occipital.v4/0\" [label=\"occipital.v4/0\"];\n\
+         \x20 \"parietal.spatial/0\" [label=\"parietal.spatial/0\"];\n\
+         \x20 \"temporal.semantic/0\" [label=\"temporal.semantic/jar\"];\n\
+         \x20 \"occipital.v4/0\" -> \"parietal.spatial/0\" [label=\"CoOccurred 140\"];\n\
+         \x20 \"temporal.semantic/0\" -> \"occipital.v4/0\" [label=\"IsA 200\"];\n\
+         \x20 \"temporal.semantic/0\" -> \"parietal.spatial/0\" [label=\"HasA 180\"];\n\
+         \x20 \"temporal.semantic/0\" -> \"frontal.expression/0\" [label=\"RelatedTo 160\"];\n\
+         }\n"
+    );
+
+    let neighborhood = GraphFilter {
+        neighborhood: Some((ref_visual, 1)),
+        ..GraphFilter::default()
+    };
+    assert_eq!(
+        cluster.export_dot(Some(&neighborhood)),
+        "digraph databank {\n\
+         \x20 \"occipital.v4/0\" [label=\"occipital.v4/0\"];\n\
+         \x20 \"parietal.spatial/0\" [label=\"parietal.spatial/0\"];\n\
+         \x20 \"occipital.v4/0\" -> \"parietal.spatial/0\" [label=\"CoOccurred 140\"];\n\
+         }\n"
+    );
+
+    let semantic_is_a = GraphFilter {
+        edge_types: Some([EdgeType::IsA].into_iter().collect()),
+        neighborhood: Some((ref_semantic, 2)),
+        ..GraphFilter::default()
+    };
+    assert_eq!(
+        cluster.export_dot(Some(&semantic_is_a)),
+        "digraph databank {\n\
+         \x20 \"occipital.v4/0\" [label=\"occipital.v4/0\"];\n\
+         \x20 \"temporal.semantic/0\" [label=\"temporal.semantic/jar\"];\n\
+         \x20 \"temporal.semantic/0\" -> \"occipital.v4/0\" [label=\"IsA 200\"];\n\
+         }\n"
+    );
+
+    let prefix = GraphFilter {
+        name_prefix: Some("temporal.".into()),
+        ..GraphFilter::default()
+    };
+    assert_eq!(
+        cluster.export_dot(Some(&prefix)),
+        "digraph databank {\n\
+         \x20 \"temporal.semantic/0\" [label=\"temporal.semantic/jar\"];\n\
+         }\n"
+    );
+}
+
+#[test]
+fn jar_assembles_all_fragments_from_semantic_seed() {
+    let (cluster, [ref_semantic, ref_visual, ref_spatial, ref_expression]) = build_jar_cluster();
+
+    let edge_types = [EdgeType::IsA, EdgeType::HasA, EdgeType::RelatedTo];
+    let assembled = cluster.assemble(ref_semantic, &edge_types, 1);
+    let banks: Vec<BankId> = assembled.iter().map(|(bank, _)| *bank).collect();
+    assert_eq!(
+        banks,
+        vec![ref_semantic.bank, ref_visual.bank, ref_spatial.bank, ref_expression.bank]
+    );
+    let expected = [
+        jar_semantic_vector(),
+        jar_visual_vector(),
+        jar_spatial_vector(),
+        jar_expression_vector(),
+    ];
+    for ((_, vector), fragment) in assembled.iter().zip(&expected) {
+        assert_eq!(vector, fragment);
+    }
+    let total: usize = assembled.iter().map(|(_, v)| v.len()).sum();
+    assert_eq!(total, 64 + 128 + 32 + 64);
+
+    // IsA only: semantic plus visual; visual's CoOccurred edge is not followed.
+    let isa_only = cluster.assemble(ref_semantic, &[EdgeType::IsA], 2);
+    assert_eq!(isa_only.len(), 2);
+
+    let missing = BankRef { bank: BankId::from_raw(99), entry: ref_semantic.entry };
+    assert!(cluster.assemble(missing, &edge_types, 1).is_empty());
+}
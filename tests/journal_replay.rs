@@ -0,0 +1,109 @@
+//! Journal replay memory bound.
+//!
+//! Replays a synthetic 100k-record journal through `load_with_journal` and
+//! checks that peak heap growth stays far below the size of the records,
+//! i.e. replay streams rather than collecting the journal.
+
+use databank_rs::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use ternary_signal::Signal;
+
+/// Tracks live heap bytes and their high-water mark.
+struct PeakAlloc;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(live, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PeakAlloc = PeakAlloc;
+
+const RECORDS: usize = 100_000;
+const WIDTH: usize = 64;
+
+#[test]
+fn replay_streams_large_journals_in_bounded_memory() {
+    let dir = tempfile::tempdir().unwrap();
+    let bank_id = BankId::from_raw(1);
+    let config = BankConfig {
+        vector_width: WIDTH as u16,
+        persist_after_mutations: 1,
+        persist_after_ticks: 0,
+        ..BankConfig::default()
+    };
+
+    let mut cluster = BankCluster::new();
+    let bank = cluster.get_or_create(bank_id, "replay".into(), config).unwrap();
+    let vector = vec![Signal::new_raw(1, 90, 1); WIDTH];
+    let entry_id = bank.insert(vector.clone(), Temperature::Hot, 0).unwrap();
+    assert_eq!(cluster.flush_dirty(dir.path(), 0).unwrap(), 1);
+    drop(cluster);
+
+    // Inserts for a bank that is not on disk are decoded and skipped, so the
+    // cluster itself does not grow; touches hit the one real entry.
+    let mut writer = JournalWriter::open(&dir.path().join("databank.journal")).unwrap();
+    for i in 0..RECORDS {
+        let record = if i % 2 == 0 {
+            JournalEntry::Insert {
+                bank_id: BankId::from_raw(2),
+                entry_id: EntryId::from_raw(i as u64),
+                vector: vector.clone(),
+                temperature: Temperature::Hot,
+                tick: i as u64,
+                checksum: None,
+                expires_at_tick: None,
+                namespace: 0,
+            }
+        } else {
+            JournalEntry::Touch {
+                bank_id,
+                entry_id,
+                tick: i as u64,
+            }
+        };
+        writer.append(&record).unwrap();
+    }
+    writer.flush().unwrap();
+    drop(writer);
+    let journal_bytes = std::fs::metadata(dir.path().join("databank.journal")).unwrap().len();
+
+    let mut calls = Vec::new();
+    let baseline = LIVE.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let (loaded, issues) =
+        BankCluster::load_with_journal_progress(dir.path(), |applied, bytes| {
+            calls.push((applied, bytes))
+        })
+        .unwrap();
+    let growth = PEAK.load(Ordering::Relaxed) - baseline;
+
+    assert_eq!(issues.len(), 1, "{issues:?}");
+    assert!(matches!(
+        issues[0],
+        JournalError::ReplaySkipped { count } if count == RECORDS / 2
+    ));
+    let entry = loaded.get(bank_id).unwrap().get(entry_id).unwrap();
+    assert_eq!(entry.access_count(), (RECORDS / 2) as u32);
+
+    assert_eq!(calls.len(), RECORDS / REPLAY_PROGRESS_INTERVAL + 1);
+    assert!(calls.windows(2).all(|w| w[0].0 <= w[1].0 && w[0].1 < w[1].1));
+    assert_eq!(*calls.last().unwrap(), (RECORDS / 2, journal_bytes));
+
+    // Collecting the records would hold every insert's vector at once
+    let collected = RECORDS / 2 * WIDTH * std::mem::size_of::<Signal>();
+    assert!(growth < 1 << 20, "replay grew the heap by {growth} bytes");
+    assert!(growth * 8 < collected);
+}